@@ -1,9 +1,8 @@
 use crate::{
     child::Child, player_direction::PlayerDirection, player_state::PlayerState, position::Position,
-    resources::Resources, row::Row, splat::Splat, HEIGHT, WIDTH,
+    resources::Resources, row::Row, settings::play_effect, splat::Splat, HEIGHT, WIDTH,
 };
 use macroquad::{
-    audio::play_sound_once,
     color::colors::WHITE,
     experimental::collections::storage,
     input::KeyCode,
@@ -97,10 +96,10 @@ impl Bunner {
                                     Position::new(self.position.x, y_offset),
                                 )),
                             );
-                            play_sound_once(&storage::get::<Resources>().splat_sound);
+                            play_effect(&storage::get::<Resources>().splat_sound);
                         }
                         PlayerState::Splash => {
-                            play_sound_once(&storage::get::<Resources>().splash_sound);
+                            play_effect(&storage::get::<Resources>().splash_sound);
                             self.timer = 100;
                         }
                         _ => self.timer = 100,
@@ -108,7 +107,7 @@ impl Bunner {
                 } else if self.position.y > scroll_pos + HEIGHT + 80 {
                     self.state = PlayerState::Eagle(self.position.x);
                     self.timer = 150;
-                    play_sound_once(&storage::get::<Resources>().eagle_sound);
+                    play_effect(&storage::get::<Resources>().eagle_sound);
                 }
 
                 // Limit x position
@@ -173,7 +172,7 @@ impl Bunner {
                     ) {
                         self.direction = direction;
                         self.timer = Bunner::MOVE_DISTANCE;
-                        play_sound_once(&storage::get::<Resources>().jump_sound);
+                        play_effect(&storage::get::<Resources>().jump_sound);
                     }
                     break;
                 }
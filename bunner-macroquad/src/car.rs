@@ -1,6 +1,7 @@
-use crate::{actor::Actor, mover::Mover, position::Position, resources::Resources};
+use crate::{
+    actor::Actor, mover::Mover, position::Position, resources::Resources, settings::play_effect,
+};
 use macroquad::{
-    audio::play_sound_once,
     prelude::{collections::storage, draw_texture, WHITE},
     rand::{self, ChooseRandom},
 };
@@ -72,11 +73,11 @@ impl Car {
             match sound {
                 CarSound::Zoom => {
                     let rnd = rand::gen_range::<usize>(0, 6);
-                    play_sound_once(&storage::get::<Resources>().zoom_sounds[rnd]);
+                    play_effect(&storage::get::<Resources>().zoom_sounds[rnd]);
                 }
                 CarSound::Honk => {
                     let rnd = rand::gen_range::<usize>(0, 4);
-                    play_sound_once(&storage::get::<Resources>().honk_sounds[rnd]);
+                    play_effect(&storage::get::<Resources>().honk_sounds[rnd]);
                 }
             }
         }
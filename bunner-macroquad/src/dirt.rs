@@ -1,9 +1,8 @@
 use crate::{
-    child::Child, position::Position, resources::Resources, road::Road, row::Row, water::Water,
-    ROW_HEIGHT,
+    child::Child, position::Position, resources::Resources, road::Road, row::Row,
+    settings::play_effect, water::Water, ROW_HEIGHT,
 };
 use macroquad::{
-    audio::play_sound_once,
     prelude::collections::storage,
     rand::{self},
     texture::Texture2D,
@@ -41,7 +40,7 @@ impl Row for Dirt {
     }
 
     fn play_sound(&self) {
-        play_sound_once(&storage::get::<Resources>().dirt_sound);
+        play_effect(&storage::get::<Resources>().dirt_sound);
     }
 
     fn next(&self) -> Box<dyn Row> {
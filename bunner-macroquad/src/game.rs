@@ -1,6 +1,7 @@
 use crate::{
     actor::Actor, bunner::Bunner, eagle::Eagle, grass::Grass, player_state::PlayerState,
-    position::Position, resources::Resources, row::Row, row::RowSound, HEIGHT, ROW_HEIGHT,
+    position::Position, resources::Resources, row::Row, row::RowSound, settings::Settings, HEIGHT,
+    ROW_HEIGHT,
 };
 use macroquad::{
     audio::{play_sound, set_sound_volume, stop_sound, PlaySoundParams},
@@ -80,8 +81,9 @@ impl Game {
                     sounds.insert(sound_name, volume);
                 }
             }
+            let effects_volume = storage::get::<Settings>().effects_volume;
             for (sound, volume) in sounds.drain() {
-                self.loop_sound(sound, volume - 0.2);
+                self.loop_sound(sound, (volume - 0.2) * effects_volume);
             }
         }
     }
@@ -116,6 +118,20 @@ impl Game {
         }
     }
 
+    /// Stops the river and traffic sounds; the ones still needed are restarted by the next update.
+    pub fn stop_looped_sounds(&mut self) {
+        let resources = storage::get::<Resources>();
+        for row_sound in self.looped_sounds.drain() {
+            let sounds = match row_sound {
+                RowSound::River => &resources.river_sounds,
+                RowSound::Traffic => &resources.traffic_sounds,
+            };
+            for sound in sounds {
+                stop_sound(sound);
+            }
+        }
+    }
+
     fn loop_sound(&mut self, row_sound: RowSound, volume: f32) {
         let resources = storage::get::<Resources>();
         let sound = match row_sound {
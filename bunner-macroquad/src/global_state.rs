@@ -2,8 +2,10 @@ use crate::{
     bunner::Bunner,
     drawing::{display_number, NumberAlign, NumberColor},
     game::Game,
+    pause_menu::{PauseAction, PauseMenu},
     position::Position,
     resources::Resources,
+    settings::Settings,
     state::State,
     HEIGHT, WIDTH,
 };
@@ -20,12 +22,16 @@ use std::collections::VecDeque;
 #[cfg(not(target_arch = "wasm32"))]
 use std::fs;
 
+const MENU_MUSIC_VOLUME: f32 = 1.0;
+const PLAY_MUSIC_VOLUME: f32 = 0.3;
+
 pub struct GlobalState {
     state: State,
     game: Game,
     high_score: u32,
     music: Sound,
     input_queue: VecDeque<KeyCode>,
+    pause_menu: PauseMenu,
 }
 
 impl miniquad::EventHandler for GlobalState {
@@ -67,16 +73,18 @@ impl GlobalState {
             high_score: 0,
             music,
             input_queue: VecDeque::new(),
+            pause_menu: PauseMenu::default(),
         }
     }
 
     pub fn init(&mut self) {
         rand::srand(macroquad::miniquad::date::now() as u64);
+        storage::store(Settings::load());
         play_sound(
             &self.music,
             PlaySoundParams {
                 looped: true,
-                volume: 1.,
+                volume: MENU_MUSIC_VOLUME * storage::get::<Settings>().music_volume,
             },
         );
         #[cfg(not(target_arch = "wasm32"))]
@@ -91,11 +99,7 @@ impl GlobalState {
         match self.state {
             State::Menu => {
                 if self.input_queue.contains(&KeyCode::Space) {
-                    // Switch to play state, and create a new Game object, passing it a new Player object to use
-                    self.state = State::Play;
-                    self.game = Game::new(Some(Bunner::new(Position::new(240, -320))));
-                    self.input_queue.clear();
-                    set_sound_volume(&self.music, 0.3);
+                    self.start_game();
                 } else {
                     self.game.update(self.input_queue.drain(..).collect());
                 }
@@ -109,22 +113,69 @@ impl GlobalState {
                     }
 
                     self.state = State::GameOver;
+                } else if self.input_queue.contains(&KeyCode::Escape)
+                    || self.input_queue.contains(&KeyCode::P)
+                {
+                    self.input_queue.clear();
+                    self.game.stop_looped_sounds();
+                    self.pause_menu = PauseMenu::default();
+                    self.state = State::Paused;
                 } else {
                     self.game.update(self.input_queue.drain(..).collect());
                 }
             }
+            State::Paused => {
+                let action = self.pause_menu.update(self.input_queue.drain(..));
+                self.set_music_volume(PLAY_MUSIC_VOLUME);
+
+                if let Some(action) = action {
+                    storage::get::<Settings>().save();
+
+                    match action {
+                        PauseAction::Resume => self.state = State::Play,
+                        PauseAction::Restart => self.start_game(),
+                        PauseAction::QuitToMenu => self.quit_to_menu(),
+                    }
+                }
+            }
             State::GameOver => {
                 if self.input_queue.contains(&KeyCode::Space) {
-                    // Switch to menu state, and create a new game object
-                    self.state = State::Menu;
-                    self.game = Game::new(None);
-                    self.input_queue.clear();
-                    set_sound_volume(&self.music, 1.0);
+                    self.quit_to_menu();
                 }
             }
         }
     }
 
+    /// The process can be quit only from the title and game over screens; while playing, Escape
+    /// pauses the game.
+    pub fn can_quit(&self) -> bool {
+        matches!(self.state, State::Menu | State::GameOver)
+    }
+
+    fn start_game(&mut self) {
+        // Switch to play state, and create a new Game object, passing it a new Player object to use
+        self.state = State::Play;
+        self.game = Game::new(Some(Bunner::new(Position::new(240, -320))));
+        self.input_queue.clear();
+        self.set_music_volume(PLAY_MUSIC_VOLUME);
+    }
+
+    fn quit_to_menu(&mut self) {
+        // Switch to menu state, and create a new game object
+        self.state = State::Menu;
+        self.game = Game::new(None);
+        self.input_queue.clear();
+        self.set_music_volume(MENU_MUSIC_VOLUME);
+    }
+
+    /// The volume is scaled by the music volume setting.
+    fn set_music_volume(&self, volume: f32) {
+        set_sound_volume(
+            &self.music,
+            volume * storage::get::<Settings>().music_volume,
+        );
+    }
+
     pub fn draw(&mut self) {
         let resources = storage::get::<Resources>();
 
@@ -144,7 +195,7 @@ impl GlobalState {
                     );
                 };
             }
-            State::Play => {
+            State::Play | State::Paused => {
                 // Display score and high score
                 display_number(self.game.score(), NumberColor::Blue, 0, NumberAlign::Left);
                 display_number(
@@ -153,6 +204,10 @@ impl GlobalState {
                     WIDTH - 10,
                     NumberAlign::Right,
                 );
+
+                if self.state == State::Paused {
+                    self.pause_menu.draw();
+                }
             }
             State::GameOver => {
                 // Display "Game Over" image
@@ -1,10 +1,9 @@
 use crate::{
     child::Child, hedge::Hedge, hedge_mask::HedgeMask, hedge_row::HedgeRow, hedge_tile::HedgeTile,
-    position::Position, resources::Resources, road::Road, row::Row, water::Water, ROW_HEIGHT,
-    WIDTH,
+    position::Position, resources::Resources, road::Road, row::Row, settings::play_effect,
+    water::Water, ROW_HEIGHT, WIDTH,
 };
 use macroquad::{
-    audio::play_sound_once,
     prelude::collections::storage,
     rand::{self},
     texture::Texture2D,
@@ -42,7 +41,7 @@ impl Row for Grass {
     }
 
     fn play_sound(&self) {
-        play_sound_once(&storage::get::<Resources>().grass_sound);
+        play_effect(&storage::get::<Resources>().grass_sound);
     }
 
     fn next(&self) -> Box<dyn Row> {
@@ -15,6 +15,7 @@ pub mod hedge_row;
 pub mod hedge_tile;
 pub mod log;
 pub mod mover;
+pub mod pause_menu;
 pub mod pavement;
 pub mod player_direction;
 pub mod player_state;
@@ -23,6 +24,7 @@ pub mod rail;
 pub mod resources;
 pub mod road;
 pub mod row;
+pub mod settings;
 pub mod splat;
 pub mod state;
 pub mod train;
@@ -36,7 +36,9 @@ async fn main() -> Result<(), Box<dyn error::Error>> {
     let input_subscriber = register_input_subscriber();
     let mut frame_time: f32 = 0.;
     loop {
-        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Q) {
+        if global_state.can_quit()
+            && (is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Q))
+        {
             std::process::exit(0);
         }
         repeat_all_miniquad_input(&mut global_state, input_subscriber);
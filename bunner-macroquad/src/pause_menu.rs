@@ -0,0 +1,144 @@
+use crate::{settings::Settings, HEIGHT, WIDTH};
+use macroquad::{
+    color::{
+        colors::{WHITE, YELLOW},
+        Color,
+    },
+    experimental::collections::storage,
+    input::KeyCode,
+    shapes::{draw_rectangle, draw_rectangle_lines},
+    text::{draw_text, measure_text},
+};
+
+const FONT_SIZE: u16 = 40;
+const FIRST_ITEM_Y: f32 = 300.;
+const ITEM_SPACING: f32 = 60.;
+const SLIDER_WIDTH: f32 = 160.;
+const SLIDER_HEIGHT: f32 = 20.;
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum PauseItem {
+    Resume,
+    Restart,
+    QuitToMenu,
+    MusicVolume,
+    EffectsVolume,
+}
+
+const ITEMS: [PauseItem; 5] = [
+    PauseItem::Resume,
+    PauseItem::Restart,
+    PauseItem::QuitToMenu,
+    PauseItem::MusicVolume,
+    PauseItem::EffectsVolume,
+];
+
+pub enum PauseAction {
+    Resume,
+    Restart,
+    QuitToMenu,
+}
+
+/// Overlay shown while the game is paused. The volumes are changed in place (see `Settings`);
+/// saving them is up to the caller.
+#[derive(Default)]
+pub struct PauseMenu {
+    selected: usize,
+}
+
+impl PauseMenu {
+    pub fn update(
+        &mut self,
+        input_queue: impl IntoIterator<Item = KeyCode>,
+    ) -> Option<PauseAction> {
+        for key in input_queue {
+            let item = ITEMS[self.selected];
+
+            match key {
+                KeyCode::Escape | KeyCode::P => return Some(PauseAction::Resume),
+                KeyCode::Up => self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len(),
+                KeyCode::Down => self.selected = (self.selected + 1) % ITEMS.len(),
+                KeyCode::Left => change_volume(item, -VOLUME_STEP),
+                KeyCode::Right => change_volume(item, VOLUME_STEP),
+                KeyCode::Space | KeyCode::Enter => match item {
+                    PauseItem::Resume => return Some(PauseAction::Resume),
+                    PauseItem::Restart => return Some(PauseAction::Restart),
+                    PauseItem::QuitToMenu => return Some(PauseAction::QuitToMenu),
+                    PauseItem::MusicVolume | PauseItem::EffectsVolume => {}
+                },
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    pub fn draw(&self) {
+        let settings = *storage::get::<Settings>();
+
+        draw_rectangle(
+            0.,
+            0.,
+            WIDTH as f32,
+            HEIGHT as f32,
+            Color::new(0., 0., 0., 0.6),
+        );
+        draw_centered("PAUSED", FIRST_ITEM_Y - 2. * ITEM_SPACING, WHITE);
+
+        for (i, item) in ITEMS.iter().enumerate() {
+            let y = FIRST_ITEM_Y + i as f32 * ITEM_SPACING;
+            let color = if i == self.selected { YELLOW } else { WHITE };
+
+            match item {
+                PauseItem::Resume => draw_centered("Resume", y, color),
+                PauseItem::Restart => draw_centered("Restart", y, color),
+                PauseItem::QuitToMenu => draw_centered("Quit to menu", y, color),
+                PauseItem::MusicVolume => draw_slider("Music", settings.music_volume, y, color),
+                PauseItem::EffectsVolume => {
+                    draw_slider("Effects", settings.effects_volume, y, color)
+                }
+            }
+        }
+    }
+}
+
+fn change_volume(item: PauseItem, delta: f32) {
+    let mut settings = storage::get_mut::<Settings>();
+    let volume = match item {
+        PauseItem::MusicVolume => &mut settings.music_volume,
+        PauseItem::EffectsVolume => &mut settings.effects_volume,
+        _ => return,
+    };
+
+    // Rounded to the step, so that repeated changes don't accumulate errors.
+    *volume = ((*volume + delta) / VOLUME_STEP).round() * VOLUME_STEP;
+    *volume = volume.clamp(0., 1.);
+}
+
+fn draw_centered(text: &str, y: f32, color: Color) {
+    let dimensions = measure_text(text, None, FONT_SIZE, 1.);
+    draw_text(
+        text,
+        (WIDTH as f32 - dimensions.width) / 2.,
+        y,
+        FONT_SIZE as f32,
+        color,
+    );
+}
+
+/// Label on the left half, bar on the right half.
+fn draw_slider(label: &str, volume: f32, y: f32, color: Color) {
+    let slider_x = WIDTH as f32 / 2. + 20.;
+    let slider_y = y - SLIDER_HEIGHT;
+
+    draw_text(label, 60., y, FONT_SIZE as f32, color);
+    draw_rectangle(
+        slider_x,
+        slider_y,
+        SLIDER_WIDTH * volume,
+        SLIDER_HEIGHT,
+        color,
+    );
+    draw_rectangle_lines(slider_x, slider_y, SLIDER_WIDTH, SLIDER_HEIGHT, 2., color);
+}
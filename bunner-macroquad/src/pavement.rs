@@ -1,5 +1,7 @@
-use crate::{child::Child, resources::Resources, road::Road, row::Row, ROW_HEIGHT};
-use macroquad::{audio::play_sound_once, prelude::collections::storage, texture::Texture2D};
+use crate::{
+    child::Child, resources::Resources, road::Road, row::Row, settings::play_effect, ROW_HEIGHT,
+};
+use macroquad::{prelude::collections::storage, texture::Texture2D};
 
 #[derive(Clone)]
 pub struct Pavement {
@@ -31,7 +33,7 @@ impl Row for Pavement {
     }
 
     fn play_sound(&self) {
-        play_sound_once(&storage::get::<Resources>().sidewalk_sound);
+        play_effect(&storage::get::<Resources>().sidewalk_sound);
     }
 
     fn next(&self) -> Box<dyn Row> {
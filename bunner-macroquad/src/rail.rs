@@ -1,10 +1,9 @@
 use crate::{
     child::Child, player_state::PlayerState, position::Position, resources::Resources, road::Road,
-    row::Row, train::Train, water::Water, HEIGHT, ROW_HEIGHT, WIDTH,
+    row::Row, settings::play_effect, train::Train, water::Water, HEIGHT, ROW_HEIGHT, WIDTH,
 };
 
 use macroquad::{
-    audio::play_sound_once,
     prelude::collections::storage,
     rand::{self, ChooseRandom},
     texture::Texture2D,
@@ -47,12 +46,12 @@ impl Row for Rail {
                 };
                 self.children.push(Child::Train(Train::new(dx, position)));
                 let resources = storage::get::<Resources>();
-                play_sound_once(&resources.bell_sound);
+                play_effect(&resources.bell_sound);
                 let train_sound = resources
                     .train_sounds
                     .get(rand::gen_range::<usize>(0, 2))
                     .unwrap();
-                play_sound_once(train_sound);
+                play_effect(train_sound);
             }
         }
     }
@@ -67,7 +66,7 @@ impl Row for Rail {
     }
 
     fn play_sound(&self) {
-        play_sound_once(&storage::get::<Resources>().grass_sound);
+        play_effect(&storage::get::<Resources>().grass_sound);
     }
 
     fn next(&self) -> Box<dyn Row> {
@@ -1,11 +1,11 @@
 use crate::{
     active_row::ActiveRow, actor::Actor, car::Car, car::CarSound, car::TrafficSound, child::Child,
     grass::Grass, mover::Mover, pavement::Pavement, player_state::PlayerState, position::Position,
-    rail::Rail, resources::Resources, row::Row, row::RowSound, ROW_HEIGHT, WIDTH,
+    rail::Rail, resources::Resources, row::Row, row::RowSound, settings::play_effect, ROW_HEIGHT,
+    WIDTH,
 };
 
 use macroquad::{
-    audio::play_sound_once,
     prelude::collections::storage,
     rand::{self, ChooseRandom},
     texture::Texture2D,
@@ -79,7 +79,7 @@ impl Row for Road {
     }
 
     fn play_sound(&self) {
-        play_sound_once(&storage::get::<Resources>().road_sound);
+        play_effect(&storage::get::<Resources>().road_sound);
     }
 
     fn next(&self) -> Box<dyn Row> {
@@ -0,0 +1,79 @@
+use macroquad::{
+    audio::{play_sound, PlaySoundParams, Sound},
+    experimental::collections::storage,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+/// The volumes go from 0 to 1, and scale the ones chosen by the game. They're kept in the
+/// macroquad storage, like the resources, since the sound effects are played from everywhere.
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub music_volume: f32,
+    pub effects_volume: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 1.,
+            effects_volume: 1.,
+        }
+    }
+}
+
+impl Settings {
+    /// The file has one `name=value` line per setting; missing or invalid values are left to the
+    /// default.
+    pub fn load() -> Self {
+        #[allow(unused_mut)]
+        let mut settings = Self::default();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(contents) = fs::read_to_string(SETTINGS_FILE) {
+            for line in contents.lines() {
+                let Some((name, value)) = line.split_once('=') else {
+                    continue;
+                };
+                let Ok(value) = value.trim().parse::<f32>() else {
+                    continue;
+                };
+                let value = value.clamp(0., 1.);
+
+                match name.trim() {
+                    "music_volume" => settings.music_volume = value,
+                    "effects_volume" => settings.effects_volume = value,
+                    _ => {}
+                }
+            }
+        }
+
+        settings
+    }
+
+    pub fn save(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let contents = format!(
+                "music_volume={}\neffects_volume={}\n",
+                self.music_volume, self.effects_volume
+            );
+            fs::write(SETTINGS_FILE, contents).ok();
+        }
+    }
+}
+
+/// Plays a sound effect once, at the effects volume.
+pub fn play_effect(sound: &Sound) {
+    let volume = storage::get::<Settings>().effects_volume;
+
+    play_sound(
+        sound,
+        PlaySoundParams {
+            looped: false,
+            volume,
+        },
+    );
+}
@@ -2,5 +2,6 @@
 pub enum State {
     Menu,
     Play,
+    Paused,
     GameOver,
 }
@@ -1,9 +1,9 @@
 use crate::{
     active_row::ActiveRow, child::Child, dirt::Dirt, log::Log, player_state::PlayerState,
-    position::Position, resources::Resources, row::Row, row::RowSound, ROW_HEIGHT, WIDTH,
+    position::Position, resources::Resources, row::Row, row::RowSound, settings::play_effect,
+    ROW_HEIGHT, WIDTH,
 };
 use macroquad::{
-    audio::play_sound_once,
     prelude::collections::storage,
     rand::{self},
     texture::Texture2D,
@@ -52,7 +52,7 @@ impl Row for Water {
     }
 
     fn play_sound(&self) {
-        play_sound_once(&storage::get::<Resources>().log_sound);
+        play_effect(&storage::get::<Resources>().log_sound);
     }
 
     fn next(&self) -> Box<dyn Row> {
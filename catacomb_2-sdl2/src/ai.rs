@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+
+use crate::{
+    class_type::classtype::*, demo_enum::demoenum::*, dir_type::dirtype::*,
+    global_state::GlobalState, pcrlib_c_state::PcrlibCState,
+};
+
+// Rust port: Optional monster AI, not in the original (see the control panel). Chasing monsters
+// follow the shortest path to the player, instead of heading greedily toward them, and shooters
+// only fire when the player is in the line of fire.
+//
+// Both only look at the level map (`background`), so other objects don't block paths or shots; when
+// the chosen step is blocked, the classic logic takes over. Demos (recording included) always use
+// the classic AI, since they only play back correctly if the monsters behave exactly like when they
+// were recorded.
+
+const MAP_WIDTH: i32 = 86;
+const MAP_HEIGHT: i32 = 87;
+
+const STEPS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+pub fn enhancedai(gs: &GlobalState, pcs: &PcrlibCState) -> bool {
+    pcs.enhancedai && gs.indemo == notdemo
+}
+
+/// Tiles that monsters (and their shots) move through; see walkthrough().
+fn passable(tile: i32) -> bool {
+    tile == 128 || (162..=164).contains(&tile) || tile == 167 || (29..=31).contains(&tile)
+}
+
+fn squarefits(x: i32, y: i32, size: i32, gs: &GlobalState) -> bool {
+    if x < 0 || y < 0 || x + size > MAP_WIDTH || y + size > MAP_HEIGHT {
+        return false;
+    }
+
+    (y..y + size)
+        .all(|ty| (x..x + size).all(|tx| passable(gs.background[ty as usize][tx as usize])))
+}
+
+fn touchesplayer(x: i32, y: i32, size: i32, gs: &GlobalState) -> bool {
+    let px = gs.o[0].x as i32;
+    let py = gs.o[0].y as i32;
+    let psize = gs.objdef[player as usize].size as i32;
+
+    x < px + psize && px < x + size && y < py + psize && py < y + size
+}
+
+/// Breadth-first search from `obj` to the player; returns the direction of the first step of the
+/// shortest path, if there is one.
+pub fn pathdir(gs: &GlobalState) -> Option<u16> {
+    let size = gs.obj.size as i32;
+    let start = (gs.obj.x as i32, gs.obj.y as i32);
+
+    // First step direction of the path to each location (+1; 0 = not visited).
+    let mut firststep = vec![0_u8; (MAP_WIDTH * MAP_HEIGHT) as usize];
+    let mut queue = VecDeque::new();
+
+    firststep[(start.1 * MAP_WIDTH + start.0) as usize] = nodir as u8;
+    queue.push_back(start);
+
+    while let Some((x, y)) = queue.pop_front() {
+        for (dir, (dx, dy)) in STEPS.iter().enumerate() {
+            let (newx, newy) = (x + dx, y + dy);
+
+            if newx < 0 || newy < 0 || newx >= MAP_WIDTH || newy >= MAP_HEIGHT {
+                continue;
+            }
+
+            let index = (newy * MAP_WIDTH + newx) as usize;
+
+            if firststep[index] != 0 {
+                continue;
+            }
+
+            let dir = if (x, y) == start {
+                dir as u8
+            } else {
+                firststep[(y * MAP_WIDTH + x) as usize] - 1
+            };
+
+            if touchesplayer(newx, newy, size, gs) {
+                return Some(dir as u16);
+            }
+
+            if squarefits(newx, newy, size, gs) {
+                firststep[index] = dir + 1;
+                queue.push_back((newx, newy));
+            }
+        }
+    }
+
+    None
+}
+
+/// Whether a shot of the given size, starting at (x, y) and moving in `dir`, would reach the player
+/// before hitting a wall.
+pub fn lineofsight(x: i32, y: i32, size: i32, dir: u16, gs: &GlobalState) -> bool {
+    let (dx, dy) = match STEPS.get(dir as usize) {
+        Some(&step) => step,
+        None => return false,
+    };
+    let (mut x, mut y) = (x, y);
+
+    loop {
+        if touchesplayer(x, y, size, gs) {
+            return true;
+        }
+        if !squarefits(x, y, size, gs) {
+            return false;
+        }
+        x += dx;
+        y += dy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a level from a map, surrounded by walls (the default tiles); `M` is the monster and `P` the player (both
+    /// a single tile wide), `#` a wall and `.` the floor.
+    fn level(map: &[&str]) -> GlobalState {
+        let mut gs = GlobalState::default();
+
+        gs.objdef[player as usize].size = 1;
+        gs.obj.size = 1;
+
+        for (y, row) in map.iter().enumerate() {
+            for (x, tile) in row.chars().enumerate() {
+                let (x, y) = (x + 1, y + 1);
+
+                gs.background[y][x] = if tile == '#' { 0 } else { 128 };
+
+                match tile {
+                    'M' => {
+                        gs.obj.x = x as u8;
+                        gs.obj.y = y as u8;
+                    }
+                    'P' => {
+                        gs.o[0].x = x as u8;
+                        gs.o[0].y = y as u8;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        gs
+    }
+
+    #[test]
+    fn straight_path_heads_to_the_player() {
+        let gs = level(&["M....P"]);
+        assert_eq!(pathdir(&gs), Some(east as u16));
+
+        let gs = level(&["P", ".", "M"]);
+        assert_eq!(pathdir(&gs), Some(north as u16));
+    }
+
+    #[test]
+    fn path_goes_around_walls() {
+        // Going east is shorter as the crow flies, but the wall is only open at the bottom.
+        let gs = level(&[
+            "...#..", //
+            "..M#.P", //
+            "...#..", //
+            "......", //
+        ]);
+        assert_eq!(pathdir(&gs), Some(south as u16));
+
+        // Here, the opening is at the top.
+        let gs = level(&[
+            "......", //
+            "...#..", //
+            "..M#.P", //
+            "...#..", //
+        ]);
+        assert_eq!(pathdir(&gs), Some(north as u16));
+    }
+
+    #[test]
+    fn unreachable_player_has_no_path() {
+        let gs = level(&[
+            "..#...", //
+            "M.#..P", //
+            "..#...", //
+        ]);
+        assert_eq!(pathdir(&gs), None);
+    }
+
+    #[test]
+    fn walls_block_the_line_of_sight() {
+        let gs = level(&["M....P"]);
+        let (x, y) = (gs.obj.x as i32, gs.obj.y as i32);
+        assert!(lineofsight(x, y, 1, east as u16, &gs));
+        assert!(!lineofsight(x, y, 1, west as u16, &gs));
+        assert!(!lineofsight(x, y, 1, nodir as u16, &gs));
+
+        let gs = level(&["M..#.P"]);
+        let (x, y) = (gs.obj.x as i32, gs.obj.y as i32);
+        assert!(!lineofsight(x, y, 1, east as u16, &gs));
+    }
+}
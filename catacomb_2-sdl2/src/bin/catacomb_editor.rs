@@ -0,0 +1,379 @@
+// Level editor for the `LEVELn.CA2` files.
+//
+// Usage:
+//
+//     catacomb_editor <level file>             # opens the level (or creates it, if not existing)
+//     catacomb_editor --validate <level files> # checks the levels, and exits
+//
+// It must be run from the game data directory, since it uses the EGA tile graphics
+// (`EGACHARS.CA2`).
+//
+// Controls:
+//
+// - left mouse button (map): paint the selected tile/object
+// - right mouse button (map): pick the tile/object under the cursor
+// - left mouse button (palette): select a tile/object
+// - Ctrl+S: validate and save
+// - Esc: quit (press twice if there are unsaved changes)
+
+use std::{
+    convert::TryFrom,
+    env,
+    path::{Path, PathBuf},
+    process,
+};
+
+use catacomb::{
+    class_type::classtype,
+    level::{token_class, Level, FIRST_TOKEN, FLOOR_TILE, LEVEL_SIZE},
+    obj_def_type::objdeftype,
+    objects::initobjects,
+    pcrlib_a::EGA,
+    pcrlib_c::EGAPalette,
+};
+use sdl2::{
+    event::Event,
+    keyboard::{Keycode, Mod},
+    mouse::MouseButton,
+    pixels::{Color, PixelFormatEnum},
+    rect::Rect,
+};
+
+const TILE_PX: usize = 8;
+const MAP_PX: usize = LEVEL_SIZE * TILE_PX;
+
+// The palette shows all the tile values; the ones from FIRST_TOKEN onwards are the object tokens.
+const PALETTE_COLUMNS: usize = 16;
+const PALETTE_ROWS: usize = 256 / PALETTE_COLUMNS;
+const PALETTE_X: usize = MAP_PX + TILE_PX;
+
+const SCREEN_W: usize = PALETTE_X + PALETTE_COLUMNS * TILE_PX;
+const SCREEN_H: usize = MAP_PX;
+
+const VALIDATE_PARAM: &str = "--validate";
+
+struct Editor {
+    level: Level,
+    path: PathBuf,
+    pics: Vec<u8>,
+    objdef: [objdeftype; 23],
+    selected: u8,
+    modified: bool,
+    quit_requested: bool,
+    status: String,
+}
+
+impl Editor {
+    fn new(path: PathBuf, pics: Vec<u8>) -> Self {
+        let (level, status) = if path.exists() {
+            match Level::load(&path) {
+                Ok(level) => (level, "Loaded".to_string()),
+                Err(error) => {
+                    eprintln!("Can't open {}: {}", path.display(), error);
+                    process::exit(1);
+                }
+            }
+        } else {
+            (Level::new(), "New level".to_string())
+        };
+
+        let mut objdef = [objdeftype::default(); 23];
+        initobjects(&mut objdef);
+
+        Self {
+            level,
+            path,
+            pics,
+            objdef,
+            selected: FLOOR_TILE,
+            modified: false,
+            quit_requested: false,
+            status,
+        }
+    }
+
+    fn title(&self) -> String {
+        let selected = match token_class(self.selected) {
+            Some(class) => format!("{} ({})", self.selected, class_name(class)),
+            None if self.selected >= FIRST_TOKEN => format!("{} (invalid)", self.selected),
+            None => self.selected.to_string(),
+        };
+
+        format!(
+            "Catacomb level editor - {}{} - tile: {} - {}",
+            self.path.display(),
+            if self.modified { " *" } else { "" },
+            selected,
+            self.status
+        )
+    }
+
+    fn paint(&mut self, x: usize, y: usize) {
+        if self.level.tile(x, y) == self.selected {
+            return;
+        }
+
+        if self.selected == FIRST_TOKEN {
+            self.level.set_player_start(x, y);
+        } else {
+            self.level.set_tile(x, y, self.selected);
+        }
+
+        self.modified = true;
+        self.quit_requested = false;
+        self.status = String::new();
+    }
+
+    fn save(&mut self) {
+        self.status = match self.level.save(&self.path) {
+            Ok(()) => {
+                self.modified = false;
+                "Saved".to_string()
+            }
+            Err(error) => format!("Not saved: {error}"),
+        };
+    }
+
+    /// Draws a tile from the EGA graphics; same layout as drawegachartile() (4 planes of 8 bytes).
+    /// The pixels right of `right` are clipped, so that the objects at the map edge don't spill
+    /// into the palette.
+    fn draw_tile(&self, buffer: &mut [u32], x: usize, y: usize, tile: usize, right: usize) {
+        let src = &self.pics[tile * 32..tile * 32 + 32];
+
+        for row in 0..TILE_PX {
+            let chan = [src[row], src[row + 8], src[row + 16], src[row + 24]];
+
+            for col in 0..TILE_PX {
+                if x + col < right && y + row < SCREEN_H {
+                    buffer[(y + row) * SCREEN_W + x + col] =
+                        EGAPalette[EGA(&chan, 7 - col as u8) as usize];
+                }
+            }
+        }
+    }
+
+    /// Draws the first frame of an object, as drawobj() would, with the upper left corner in the
+    /// given location.
+    fn draw_object(&self, buffer: &mut [u32], x: usize, y: usize, class: classtype) {
+        let objdef = &self.objdef[class as usize];
+        let mut tilenum = objdef.firstchar as usize;
+
+        for obj_y in 0..objdef.size as usize {
+            for obj_x in 0..objdef.size as usize {
+                let (tile_x, tile_y) = (x + obj_x * TILE_PX, y + obj_y * TILE_PX);
+                self.draw_tile(buffer, tile_x, tile_y, tilenum, MAP_PX);
+                tilenum += 1;
+            }
+        }
+    }
+
+    fn render(&self, buffer: &mut [u32]) {
+        buffer.fill(0);
+
+        // Objects are drawn after the map, since they're larger than a tile.
+        for y in 0..LEVEL_SIZE {
+            for x in 0..LEVEL_SIZE {
+                let tile = self.level.tile(x, y);
+                let tile = if tile >= FIRST_TOKEN {
+                    FLOOR_TILE
+                } else {
+                    tile
+                };
+                self.draw_tile(buffer, x * TILE_PX, y * TILE_PX, tile as usize, MAP_PX);
+            }
+        }
+        for y in 0..LEVEL_SIZE {
+            for x in 0..LEVEL_SIZE {
+                if let Some(class) = self.level.token(x, y) {
+                    self.draw_object(buffer, x * TILE_PX, y * TILE_PX, class);
+                }
+            }
+        }
+
+        for tile in 0..=255_usize {
+            let x = PALETTE_X + (tile % PALETTE_COLUMNS) * TILE_PX;
+            let y = (tile / PALETTE_COLUMNS) * TILE_PX;
+
+            if tile < FIRST_TOKEN as usize {
+                self.draw_tile(buffer, x, y, tile, SCREEN_W);
+            } else if let Some(class) = token_class(tile as u8) {
+                // Only the upper left tile of the object fits in the palette.
+                let tilenum = self.objdef[class as usize].firstchar as usize;
+                self.draw_tile(buffer, x, y, tilenum, SCREEN_W);
+            }
+        }
+    }
+
+    /// Returns the map or palette cell under the (logical) mouse coordinates.
+    fn cell_at(x: i32, y: i32) -> Option<Cell> {
+        let (x, y) = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+
+        if x < MAP_PX && y < MAP_PX {
+            Some(Cell::Map(x / TILE_PX, y / TILE_PX))
+        } else if (PALETTE_X..SCREEN_W).contains(&x) && y < PALETTE_ROWS * TILE_PX {
+            let tile = (y / TILE_PX) * PALETTE_COLUMNS + (x - PALETTE_X) / TILE_PX;
+            Some(Cell::Palette(tile as u8))
+        } else {
+            None
+        }
+    }
+}
+
+enum Cell {
+    Map(usize, usize),
+    Palette(u8),
+}
+
+fn class_name(class: classtype) -> &'static str {
+    match class {
+        classtype::player => "player start",
+        classtype::teleporter => "teleporter",
+        classtype::goblin => "goblin",
+        classtype::skeleton => "skeleton",
+        classtype::ogre => "ogre",
+        classtype::gargoyle => "gargoyle",
+        classtype::dragon => "dragon",
+        classtype::turbogre => "turbo ogre",
+        classtype::guns => "gun (south)",
+        classtype::gune => "gun (east)",
+        classtype::secretgate => "secret gate",
+        _ => "object",
+    }
+}
+
+fn validate_files(paths: &[String]) -> bool {
+    let mut all_valid = true;
+
+    for path in paths {
+        match Level::load(Path::new(path)).and_then(|level| level.validate()) {
+            Ok(()) => println!("{path}: OK"),
+            Err(error) => {
+                println!("{path}: {error}");
+                all_valid = false;
+            }
+        }
+    }
+
+    all_valid
+}
+
+pub fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    match args.first().map(String::as_str) {
+        Some(VALIDATE_PARAM) => {
+            let all_valid = validate_files(&args[1..]);
+            process::exit(if all_valid { 0 } else { 1 });
+        }
+        Some(_) if args.len() == 1 => {}
+        _ => {
+            eprintln!("Usage: catacomb_editor <level file> | {VALIDATE_PARAM} <level files>");
+            process::exit(1);
+        }
+    }
+
+    let pics = std::fs::read("EGACHARS.CA2")
+        .expect("EGACHARS.CA2 not found; the editor must be run from the game directory");
+
+    let mut editor = Editor::new(PathBuf::from(&args[0]), pics);
+
+    let sdl = sdl2::init().expect("Failed to initialize SDL");
+    let window = sdl
+        .video()
+        .unwrap()
+        .window(
+            &editor.title(),
+            SCREEN_W as u32 * 3 / 2,
+            SCREEN_H as u32 * 3 / 2,
+        )
+        .position_centered()
+        .resizable()
+        .build()
+        .expect("Failed to create SDL window");
+    let mut canvas = window
+        .into_canvas()
+        .present_vsync()
+        .build()
+        .expect("Failed to create SDL renderer");
+    canvas
+        .set_logical_size(SCREEN_W as u32, SCREEN_H as u32)
+        .unwrap();
+    let texture_creator = canvas.texture_creator();
+    let mut texture = texture_creator
+        .create_texture_streaming(PixelFormatEnum::ARGB8888, SCREEN_W as u32, SCREEN_H as u32)
+        .expect("Could not create video buffer");
+    let mut event_pump = sdl.event_pump().unwrap();
+
+    let mut buffer = vec![0_u32; SCREEN_W * SCREEN_H];
+
+    loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => {
+                    if !editor.modified || editor.quit_requested {
+                        return;
+                    }
+                    editor.quit_requested = true;
+                    editor.status = "Unsaved changes; press Esc again to quit".to_string();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::S),
+                    keymod,
+                    ..
+                } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                    editor.save();
+                }
+                Event::MouseButtonDown {
+                    mouse_btn, x, y, ..
+                } => match (Editor::cell_at(x, y), mouse_btn) {
+                    (Some(Cell::Map(x, y)), MouseButton::Left) => editor.paint(x, y),
+                    (Some(Cell::Map(x, y)), MouseButton::Right) => {
+                        editor.selected = editor.level.tile(x, y);
+                    }
+                    (Some(Cell::Palette(tile)), MouseButton::Left) => editor.selected = tile,
+                    _ => {}
+                },
+                // Drag painting; the player start is excluded, since it can't be repeated.
+                Event::MouseMotion {
+                    mousestate, x, y, ..
+                } if mousestate.left() && editor.selected != FIRST_TOKEN => {
+                    if let Some(Cell::Map(x, y)) = Editor::cell_at(x, y) {
+                        editor.paint(x, y);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        editor.render(&mut buffer);
+
+        let pixel_bytes = buffer
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        texture.update(None, &pixel_bytes, SCREEN_W * 4).unwrap();
+
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.clear();
+        canvas.copy(&texture, None, None).unwrap();
+
+        let selected_x = PALETTE_X + (editor.selected as usize % PALETTE_COLUMNS) * TILE_PX;
+        let selected_y = (editor.selected as usize / PALETTE_COLUMNS) * TILE_PX;
+        canvas.set_draw_color(Color::RGB(255, 255, 85));
+        canvas
+            .draw_rect(Rect::new(
+                selected_x as i32,
+                selected_y as i32,
+                TILE_PX as u32,
+                TILE_PX as u32,
+            ))
+            .unwrap();
+
+        canvas.window_mut().set_title(&editor.title()).ok();
+        canvas.present();
+    }
+}
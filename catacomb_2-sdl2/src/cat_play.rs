@@ -1,24 +1,24 @@
 use crate::{
     active_obj::activeobj,
+    ai::{enhancedai, lineofsight, pathdir},
     catacomb::{clearold, dofkeys, loadlevel, refresh, restore},
-    catasm::{doall, drawobj, eraseobj},
-    class_type::classtype::*,
+    catasm::{drawobj, eraseobj},
+    class_type::classtype::{self, *},
     control_struct::ControlStruct,
-    cpanel_state::CpanelState,
     demo_enum::demoenum::*,
     dir_type::dirtype::{self, *},
+    engine::{Overlay, Prompt},
     exit_type::exittype::*,
     extra_constants::maxobj,
     global_state::GlobalState,
-    pcrlib_a::{drawchar, initrndt, rndt, PlaySound, WaitEndSound, WaitVBL},
+    pcrlib_a::{drawchar, initrndt, rndt, PlaySound, WaitVBL},
     pcrlib_a_state::PcrlibAState,
     pcrlib_c::{
-        centerwindow, get, ControlPlayer, UpdateScreen, _inputint, bioskey, clearkeys, print,
-        print_str, RecordDemo, SaveDemo,
+        _inputint, bioskey, centerwindow, clearkeys, print, print_str, tryget, ControlPlayer,
+        LineInput, RecordDemo, RumbleJoystick, SaveDemo, UpdateScreen,
     },
     pcrlib_c_state::PcrlibCState,
     scan_codes::*,
-    sdl_manager::SdlManager,
     tag_type::tagtype::*,
 };
 
@@ -69,7 +69,7 @@ const opposite: [dirtype; 9] = [
 /*				       */
 /*=====================================*/
 
-fn newobject(gs: &mut GlobalState) -> i32 {
+pub fn newobject(gs: &mut GlobalState) -> i32 {
     let mut found_i = None;
 
     for i in 1..=gs.numobj {
@@ -205,7 +205,7 @@ fn levelcleared(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
     }
 }
 
-fn givekey(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
+pub fn givekey(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
     let mut i: i32 = 0;
     i = gs.items[1] as i32 + 1;
     gs.items[1] = i as i16;
@@ -459,6 +459,9 @@ fn tagobject(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCStat
             gs.altobj.hp = gs.o[0].hp;
             printbody(gs, pcs);
             PlaySound(10, pas);
+            if gs.indemo == notdemo {
+                RumbleJoystick(1, 0xffff, 1000, pcs);
+            }
             gs.playdone = true;
             gs.gamexit = killed;
         } else {
@@ -481,6 +484,9 @@ fn tagobject(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCStat
             gs.o[0].delay = 2;
             printbody(gs, pcs);
             PlaySound(8, pas);
+            if gs.indemo == notdemo {
+                RumbleJoystick(1, 0x8000, 200, pcs);
+            }
         } else {
             gs.o[gs.altnum as usize].delay = 4;
             PlaySound(7, pas);
@@ -730,20 +736,14 @@ fn walk(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) ->
     true
 }
 
-fn playercmdthink(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
+fn playercmdthink(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     let mut olddir: dirtype = north;
     let mut c: ControlStruct = ControlStruct {
         dir: north,
         button1: false,
         button2: false,
     };
-    c = ControlPlayer(1, gs, pcs, pas, sdl);
+    c = ControlPlayer(1, gs, pcs, pas);
     gs.obj.stage = (gs.obj.stage as i32 & 1) as u8;
     if c.button1 as i32 != 0
         && c.button2 as i32 != 0
@@ -875,7 +875,7 @@ fn playercmdthink(
             pcs.keydown[SDL_SCANCODE_RETURN as usize] = false;
         }
     }
-    dofkeys(gs, cps, pas, pcs, sdl);
+    dofkeys(gs, pas, pcs);
     if gs.resetgame {
         gs.resetgame = false;
         gs.playdone = true;
@@ -887,44 +887,25 @@ fn playercmdthink(
                 && pcs.keydown[SDL_SCANCODE_T as usize] as i32 != 0
                 && pcs.keydown[SDL_SCANCODE_SPACE as usize] as i32 != 0
             {
-                centerwindow(16, 2, gs, pcs);
-                print_str("warp to which\nlevel (1-99)?", gs, pcs);
-                clearkeys(pcs, pas, sdl);
-                pcs.level = _inputint(gs, pcs, pas, sdl) as i16;
-                if (pcs.level as i32) < 1 {
-                    pcs.level = 1;
-                }
-                if pcs.level as i32 > 30 {
-                    pcs.level = 30;
-                }
-                restore(gs, pcs);
-                gs.leveldone = true;
+                // Rust port: The level is entered in a prompt (see `PlayPrompt::Warp`), opened at the
+                // end of the frame.
+                clearkeys(pcs, pas);
+                gs.overlay = Some(Overlay::Prompt(Prompt::Play(PlayPrompt::Warp(
+                    LineInput::new(17),
+                ))));
             }
             if pcs.keydown[SDL_SCANCODE_C as usize] as i32 != 0
                 && pcs.keydown[SDL_SCANCODE_T as usize] as i32 != 0
                 && pcs.keydown[SDL_SCANCODE_TAB as usize] as i32 != 0
             {
-                if gs.GODMODE {
-                    centerwindow(13, 1, gs, pcs);
-                    print_str("God Mode Off", gs, pcs);
-                    gs.GODMODE = false;
-                } else {
-                    centerwindow(12, 1, gs, pcs);
-                    print_str("God Mode On", gs, pcs);
-                    gs.GODMODE = true;
-                }
-                UpdateScreen(gs, pcs);
-                clearkeys(pcs, pas, sdl);
-                while bioskey(0, pcs, pas, sdl) == 0 {
-                    WaitVBL();
-                }
-                restore(gs, pcs);
-                clearkeys(pcs, pas, sdl);
+                gs.GODMODE = !gs.GODMODE;
+                clearkeys(pcs, pas);
+                gs.overlay = Some(Overlay::Prompt(Prompt::Play(PlayPrompt::GodMode)));
             }
         }
         demoplay => {
             gs.indemo = notdemo;
-            gs.ctrl = ControlPlayer(1, gs, pcs, pas, sdl);
+            gs.ctrl = ControlPlayer(1, gs, pcs, pas);
             if gs.ctrl.button1 as i32 != 0
                 || gs.ctrl.button2 as i32 != 0
                 || pcs.keydown[SDL_SCANCODE_SPACE as usize] as i32 != 0
@@ -956,6 +937,16 @@ fn chasethink(
     gs.obj.stage = (gs.obj.stage as i32 & 1) as u8;
     olddir = gs.obj.dir as i32;
     turnaround = opposite[olddir as usize] as i32;
+    // Rust port: See `ai`; if the path is blocked (by another object), the classic logic is used.
+    if enhancedai(gs, pcs) {
+        if let Some(dir) = pathdir(gs) {
+            gs.obj.dir = dir;
+            if walk(gs, pas, pcs) as i32 != 0 || gs.obj.stage as i32 == 3 {
+                return;
+            }
+            gs.obj.dir = olddir as u16;
+        }
+    }
     deltax = gs.o[0].x as i32 - gs.obj.x as i32;
     deltay = gs.o[0].y as i32 - gs.obj.y as i32;
     d[1] = nodir;
@@ -1041,9 +1032,31 @@ fn chasethink(
     walk(gs, pas, pcs);
 }
 
+// Rust port: Not in the original; see `ai`. The shot location is the one of gargthink() and
+// dragonthink().
+fn inlineoffire(class: classtype, gs: &GlobalState) -> bool {
+    let x = gs.obj.x as i32;
+    let y = gs.obj.y as i32;
+    let (shotx, shoty) = match gs.obj.dir as i32 {
+        0 => (x + 1 + gs.side, y),
+        1 => (x + 3, y + 1 + gs.side),
+        2 => (x + 1 + gs.side, y + 3),
+        3 => (x, y + 1 + gs.side),
+        _ => return false,
+    };
+
+    lineofsight(
+        shotx,
+        shoty,
+        gs.objdef[class as usize].size as i32,
+        gs.obj.dir,
+        gs,
+    )
+}
+
 fn gargthink(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     let mut n: i32 = 0;
-    if rndt(pas) > 220 {
+    if rndt(pas) > 220 && (!enhancedai(gs, pcs) || inlineoffire(rock, gs)) {
         gs.obj.stage = 2;
         gs.obj.delay = 6;
         PlaySound(5, pas);
@@ -1079,7 +1092,7 @@ fn gargthink(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCStat
 
 fn dragonthink(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     let mut n: i32 = 0;
-    if rndt(pas) > 220 {
+    if rndt(pas) > 220 && (!enhancedai(gs, pcs) || inlineoffire(bigshot, gs)) {
         gs.obj.stage = 2;
         gs.obj.delay = 6;
         PlaySound(5, pas);
@@ -1113,8 +1126,20 @@ fn dragonthink(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCSt
     };
 }
 
-fn gunthink(dir: i32, gs: &mut GlobalState, pas: &mut PcrlibAState) {
+fn gunthink(dir: i32, gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     let mut n: i32 = 0;
+    // Rust port: See `ai`.
+    if enhancedai(gs, pcs)
+        && !lineofsight(
+            gs.obj.x as i32,
+            gs.obj.y as i32,
+            gs.objdef[bigshot as usize].size as i32,
+            dir as u16,
+            gs,
+        )
+    {
+        return;
+    }
     PlaySound(5, pas);
     gs.obj.stage = 0;
     n = newobject(gs);
@@ -1191,19 +1216,13 @@ fn explodethink(gs: &mut GlobalState, pas: &mut PcrlibAState) {
     }
 }
 
-fn think(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
+fn think(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     if gs.obj.delay as i32 > 0 {
         gs.obj.delay = (gs.obj.delay).wrapping_sub(1);
     } else if rndt(pas) < gs.obj.speed as i32 {
         match gs.obj.think as i32 {
             0 => {
-                playercmdthink(gs, cps, pas, pcs, sdl);
+                playercmdthink(gs, pas, pcs);
             }
             3 => {
                 chasethink(false, gs, pas, pcs);
@@ -1230,23 +1249,17 @@ fn think(
                 explodethink(gs, pas);
             }
             9 => {
-                gunthink(west as i32, gs, pas);
+                gunthink(west as i32, gs, pas, pcs);
             }
             10 => {
-                gunthink(north as i32, gs, pas);
+                gunthink(north as i32, gs, pas, pcs);
             }
             _ => {}
         }
     }
 }
 
-pub fn doactive(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
+pub fn doactive(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     if gs.obj.class as i32 != dead1 as i32
         && ((gs.obj.x as i32) < gs.origin.x - 10
             || gs.obj.x as i32 > gs.origin.x + 34
@@ -1255,7 +1268,7 @@ pub fn doactive(
     {
         gs.o[gs.objecton as usize].active = false;
     } else {
-        think(gs, cps, pas, pcs, sdl);
+        think(gs, pas, pcs);
         eraseobj(gs);
         if gs.playdone {
             return;
@@ -1279,73 +1292,164 @@ pub fn doinactive(gs: &mut GlobalState) {
     }
 }
 
-pub fn playloop(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
+// Rust port: playloop() ran all the levels in a loop; it's been split in steps, which are driven
+// by the `Engine` (see `PlayPhase`). The level loop was:
+//
+// - enterlevel(), then wait for the sound to end (only when not in a demo)
+// - startlevel()
+// - doframe(), until it returns false
+// - endlevel(); if it returns false, repeat
+//
+// The prompts which used to wait for the keys in the level loop (the warp and god mode cheats, and
+// recording demos) are opened by the `Engine` at the end of the frame (see `PlayPrompt`).
+
+pub fn enterlevel(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     gs.screencenter.x = 11;
-    loop {
-        if gs.indemo == notdemo {
-            centerwindow(11, 2, gs, pcs);
-            print_str(" Entering\nlevel ", gs, pcs);
-            print_str(&pcs.level.to_string(), gs, pcs);
-            print_str("...", gs, pcs);
-            PlaySound(17, pas);
-            WaitEndSound(gs, pas, pcs);
-        }
-        clearold(&mut gs.oldtiles);
-        loadlevel(gs, pas, pcs);
-        gs.leveldone = false;
-        if pcs.keydown[SDL_SCANCODE_F7 as usize] as i32 != 0
-            && pcs.keydown[SDL_SCANCODE_D as usize] as i32 != 0
-        {
-            clearold(&mut gs.oldtiles);
-            refresh(gs, pcs);
-            refresh(gs, pcs);
-            clearkeys(pcs, pas, sdl);
-            centerwindow(12, 1, gs, pcs);
-            print_str("RECORD DEMO", gs, pcs);
-            loop {
-                let ch = get(gs, pcs, pas, sdl) as i8;
-                if !(ch != 13) {
-                    break;
-                }
+    if gs.indemo == notdemo {
+        centerwindow(11, 2, gs, pcs);
+        print_str(" Entering\nlevel ", gs, pcs);
+        print_str(&pcs.level.to_string(), gs, pcs);
+        print_str("...", gs, pcs);
+        PlaySound(17, pas);
+        UpdateScreen(gs, pcs);
+    }
+}
+
+pub fn startlevel(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
+    clearold(&mut gs.oldtiles);
+    loadlevel(gs, pas, pcs);
+    gs.leveldone = false;
+    if pcs.keydown[SDL_SCANCODE_F7 as usize] as i32 != 0
+        && pcs.keydown[SDL_SCANCODE_D as usize] as i32 != 0
+    {
+        clearkeys(pcs, pas);
+        gs.overlay = Some(Overlay::Prompt(Prompt::Play(PlayPrompt::RecordDemo)));
+    }
+    gs.playdone = false;
+    gs.frameon = 0;
+    gs.boltsleft = 0;
+    gs.shotpower = 0;
+    initrndt(false, pas);
+    printshotpower(gs, pcs);
+}
+
+/// Rust port: Extracted from endlevel(); when recording, prompts for the demo number (see
+/// `PlayPrompt::SaveDemo`).
+pub fn savedemo(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
+    if gs.indemo == recording {
+        clearkeys(pcs, pas);
+        gs.overlay = Some(Overlay::Prompt(Prompt::Play(PlayPrompt::SaveDemo)));
+    }
+}
+
+/// Returns true if the play is over, false if the next level must be entered.
+pub fn endlevel(gs: &mut GlobalState) -> bool {
+    if gs.indemo != notdemo {
+        gs.playdone = true;
+    }
+    gs.playdone
+}
+
+// Rust port: The prompts of the level loop, which used to wait for the keys with get()/bioskey();
+// they take a key per frame, and repaint the screen themselves.
+pub enum PlayPrompt {
+    /// The level number, up to 17 characters (see _inputint()).
+    Warp(LineInput),
+    GodMode,
+    RecordDemo,
+    SaveDemo,
+}
+
+impl PlayPrompt {
+    pub fn open(self, gs: &mut GlobalState, pcs: &mut PcrlibCState) -> Self {
+        match self {
+            PlayPrompt::Warp(_) => {
+                centerwindow(16, 2, gs, pcs);
+                print_str("warp to which\nlevel (1-99)?", gs, pcs);
             }
-            RecordDemo(gs, pcs);
-            clearold(&mut gs.oldtiles);
-            clearkeys(pcs, pas, sdl);
-        }
-        gs.playdone = false;
-        gs.frameon = 0;
-        gs.boltsleft = 0;
-        gs.shotpower = 0;
-        initrndt(false, pas);
-        printshotpower(gs, pcs);
-        doall(gs, cps, pas, pcs, sdl);
-        if gs.indemo == recording {
-            clearkeys(pcs, pas, sdl);
-            centerwindow(15, 1, gs, pcs);
-            print_str("SAVE AS DEMO#:", gs, pcs);
-            let mut ch;
-            loop {
-                ch = get(gs, pcs, pas, sdl) as i8;
-                if !(ch < '0' as i8 || ch > '9' as i8) {
-                    break;
+            PlayPrompt::GodMode => {
+                if gs.GODMODE {
+                    centerwindow(12, 1, gs, pcs);
+                    print_str("God Mode On", gs, pcs);
+                } else {
+                    centerwindow(13, 1, gs, pcs);
+                    print_str("God Mode Off", gs, pcs);
                 }
+                UpdateScreen(gs, pcs);
+            }
+            PlayPrompt::RecordDemo => {
+                clearold(&mut gs.oldtiles);
+                refresh(gs, pcs);
+                refresh(gs, pcs);
+                centerwindow(12, 1, gs, pcs);
+                print_str("RECORD DEMO", gs, pcs);
+            }
+            PlayPrompt::SaveDemo => {
+                centerwindow(15, 1, gs, pcs);
+                print_str("SAVE AS DEMO#:", gs, pcs);
             }
-            SaveDemo((ch - '0' as i8) as u8, gs, pcs);
-            clearold(&mut gs.oldtiles);
-            refresh(gs, pcs);
-            refresh(gs, pcs);
         }
-        if gs.indemo != notdemo {
-            gs.playdone = true;
+        self
+    }
+
+    /// Returns None once the prompt is done.
+    pub fn step(
+        self,
+        cycle: &mut i32,
+        gs: &mut GlobalState,
+        pas: &mut PcrlibAState,
+        pcs: &mut PcrlibCState,
+    ) -> Option<Self> {
+        if let PlayPrompt::GodMode = self {
+            if bioskey(0, pcs, pas) == 0 {
+                WaitVBL(pcs);
+                return Some(self);
+            }
+            restore(gs, pcs);
+            clearkeys(pcs, pas);
+            return None;
         }
-        if gs.playdone {
-            break;
+
+        let key = match tryget(cycle, gs, pcs, pas) {
+            Some(key) => key,
+            None => return Some(self),
+        };
+        let ch = key as i8;
+
+        match self {
+            PlayPrompt::Warp(mut input) => {
+                if input.key(key, gs, pcs).is_none() {
+                    return Some(PlayPrompt::Warp(input));
+                }
+                pcs.level = _inputint(&input) as i16;
+                if (pcs.level as i32) < 1 {
+                    pcs.level = 1;
+                }
+                if pcs.level as i32 > 30 {
+                    pcs.level = 30;
+                }
+                restore(gs, pcs);
+                gs.leveldone = true;
+            }
+            PlayPrompt::GodMode => unreachable!(),
+            PlayPrompt::RecordDemo => {
+                if ch != 13 {
+                    return Some(self);
+                }
+                RecordDemo(gs, pcs);
+                clearold(&mut gs.oldtiles);
+                clearkeys(pcs, pas);
+            }
+            PlayPrompt::SaveDemo => {
+                if ch < '0' as i8 || ch > '9' as i8 {
+                    return Some(self);
+                }
+                SaveDemo((ch - '0' as i8) as u8, gs, pcs);
+                clearold(&mut gs.oldtiles);
+                refresh(gs, pcs);
+                refresh(gs, pcs);
+            }
         }
+        None
     }
 }
@@ -1,34 +1,29 @@
-use std::fs::File;
-
-use serdine::{Deserialize, Serialize};
-
 use crate::{
     cat_play::{
-        givebolt, givenuke, givepotion, playloop, printbody, printhighscore, printscore,
-        printshotpower,
+        givebolt, givenuke, givepotion, printbody, printhighscore, printscore, printshotpower,
     },
     catasm::{cgarefresh, drawchartile, egarefresh},
     class_type::classtype::{self, *},
-    cpanel::{controlpanel, installgrfile},
+    cpanel::installgrfile,
     cpanel_state::CpanelState,
     demo_enum::demoenum::*,
     dir_type::dirtype::{self, *},
-    exit_type::exittype::*,
-    extra_constants::{
-        blankfloor, leftoff, maxpics, numlevels, solidwall, tile2s, topoff, NUM_DEMOS,
-    },
+    engine::{Engine, Overlay, Prompt},
+    extra_constants::{blankfloor, leftoff, maxpics, solidwall, tile2s, topoff},
+    frontend::Frontend,
     global_state::GlobalState,
     gr_type::grtype::*,
-    objects::initobjects,
-    pcrlib_a::{drawchar, drawpic, rnd, rndt, PlaySound, WaitEndSound, WaitVBL},
+    level::{level_path, take_level_pack_arg, tokens, validate_level_pack, LEVEL_PACK_PARAM},
+    objects::{initobjects, loadobjects, objdefs_path},
+    pcrlib_a::{drawchar, drawpic, rndt, WaitVBL},
     pcrlib_a_state::PcrlibAState,
     pcrlib_c::{
-        ControlPlayer, LoadDemo, UpdateScreen, _Verify, _checkhighscore, _quit, _setupgame,
-        _showhighscores, bar, bioskey, bloadin, centerwindow, clearkeys, drawwindow, expwin, get,
-        loadFile, print_str, printchartile,
+        _quit, bar, bioskey, bloadin, centerwindow, clearkeys, drawwindow, expwin, loadFile,
+        print_str, printchartile, tryget, LineInput,
     },
     pcrlib_c_state::PcrlibCState,
     rleasm::RLEExpand,
+    save_game::{format_timestamp, list_saves, SaveGame, SaveSlot},
     scan_codes::*,
     sdl_manager::SdlManager,
     state_type::statetype,
@@ -75,7 +70,7 @@ pub fn refresh(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
         }
     }
 
-    WaitVBL();
+    WaitVBL(pcs);
     if pcs.grmode == CGAgr {
         cgarefresh(gs, pcs);
     } else {
@@ -92,11 +87,11 @@ pub fn refresh(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
         }
     }
 
-    WaitVBL();
+    WaitVBL(pcs);
 }
 
 fn simplerefresh(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
-    WaitVBL();
+    WaitVBL(pcs);
     if pcs.grmode as u32 == CGAgr as i32 as u32 {
         cgarefresh(gs, pcs);
     } else {
@@ -142,22 +137,25 @@ pub fn restore(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
     simplerefresh(gs, pcs);
 }
 
-fn wantmore(
-    gs: &mut GlobalState,
-    pcs: &mut PcrlibCState,
-    pas: &mut PcrlibAState,
-    sdl: &mut SdlManager,
-) -> bool {
+// Rust port: wantmore() waited for the key with get(); the callers print the prompt, and take the key
+// a frame at a time (see tryget()). A key other than Esc means more.
+//
+// fn wantmore(gs: &mut GlobalState, pcs: &mut PcrlibCState) -> bool {
+//     wantmoreprompt(gs, pcs);
+//     let ch = get(gs, pcs) as i8;
+//     if ch == 27 {
+//         return false;
+//     }
+//     true
+// }
+
+/// Prints the wantmore() prompt, and places the cursor for tryget().
+fn wantmoreprompt(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
     pcs.sx = 2;
     pcs.sy = 20;
     print_str("(space for more/esc)", gs, pcs);
     pcs.sx = 12;
     pcs.sy = 21;
-    let ch = get(gs, pcs, pas, sdl) as i8;
-    if ch == 27 {
-        return false;
-    }
-    true
 }
 
 fn charpic(
@@ -194,167 +192,157 @@ fn charpic(
     }
 }
 
-fn help(
-    gs: &mut GlobalState,
-    pcs: &mut PcrlibCState,
-    pas: &mut PcrlibAState,
-    sdl: &mut SdlManager,
-) {
+// Rust port: help() showed the pages in sequence, each waiting for a key (see wantmore()); now the
+// pages are drawn individually, so that the `Engine` can wait for the keys without blocking.
+pub const HELP_PAGES: usize = 6;
+
+pub fn drawhelppage(page: usize, gs: &mut GlobalState, pcs: &mut PcrlibCState) {
     let mut x: i32 = 0;
     let mut y: i32 = 0;
-    centerwindow(20, 20, gs, pcs);
-    print_str("  C A T A C O M B   \n", gs, pcs);
-    print_str("   - - - - - - -    \n", gs, pcs);
-    print_str(" by John Carmack    \n", gs, pcs);
-    print_str("                    \n", gs, pcs);
-    print_str("\n", gs, pcs);
-    print_str("f1 = help           \n", gs, pcs);
-    print_str("f2 = control panel  \n", gs, pcs);
-    print_str("f3 = game reset     \n", gs, pcs);
-    print_str("f4 = save game      \n", gs, pcs);
-    print_str("f5 = load saved game\n", gs, pcs);
-    print_str("f9 = pause          \n", gs, pcs);
-    print_str("f10 / ESC = quit    \n", gs, pcs);
-    print_str("\n", gs, pcs);
-    print_str("hit fire at the demo\n", gs, pcs);
-    print_str("to begin playing.   \n", gs, pcs);
-    if !wantmore(gs, pcs, pas, sdl) {
-        return;
-    }
-    centerwindow(20, 20, gs, pcs);
-    print_str("\nKeyboard controls:  \n\n", gs, pcs);
-    print_str("move    : arrows    \n", gs, pcs);
-    print_str("button1 : ctrl      \n", gs, pcs);
-    print_str("button2 : alt       \n", gs, pcs);
-    print_str("\nTo switch to mouse \n", gs, pcs);
-    print_str("or joystick control,\n", gs, pcs);
-    print_str("hit f2             \n", gs, pcs);
-    if !wantmore(gs, pcs, pas, sdl) {
-        return;
-    }
-    centerwindow(20, 20, gs, pcs);
-    print_str("Button 1 / ctrl key:\n", gs, pcs);
-    print_str("Builds shot power.  \n", gs, pcs);
-    print_str("If the shot power   \n", gs, pcs);
-    print_str("meter is full when  \n", gs, pcs);
-    print_str("the button is       \n", gs, pcs);
-    print_str("released, a super   \n", gs, pcs);
-    print_str("shot will be        \n", gs, pcs);
-    print_str("launched.           \n", gs, pcs);
-    print_str("\n", gs, pcs);
-    y = 11;
-    while y <= 18 {
-        x = 3;
-        while x <= 20 {
-            drawchartile(x, y, 128, gs, pcs);
-            x += 1;
+    match page {
+        0 => {
+            centerwindow(20, 20, gs, pcs);
+            print_str("  C A T A C O M B   \n", gs, pcs);
+            print_str("   - - - - - - -    \n", gs, pcs);
+            print_str(" by John Carmack    \n", gs, pcs);
+            print_str("                    \n", gs, pcs);
+            print_str("\n", gs, pcs);
+            print_str("f1 = help           \n", gs, pcs);
+            print_str("f2 = control panel  \n", gs, pcs);
+            print_str("f3 = game reset     \n", gs, pcs);
+            print_str("f4 = save game      \n", gs, pcs);
+            print_str("f5 = load saved game\n", gs, pcs);
+            print_str("f9 = pause          \n", gs, pcs);
+            print_str("f10 / ESC = quit    \n", gs, pcs);
+            print_str("\n", gs, pcs);
+            print_str("hit fire at the demo\n", gs, pcs);
+            print_str("to begin playing.   \n", gs, pcs);
         }
-        y += 1;
-    }
-    charpic(4, 14, player, east, 2, gs, pcs);
-    charpic(19, 15, shot, east, 1, gs, pcs);
-    charpic(17, 14, shot, east, 0, gs, pcs);
-    charpic(15, 15, shot, east, 1, gs, pcs);
-    charpic(8, 14, bigshot, east, 0, gs, pcs);
-    if !wantmore(gs, pcs, pas, sdl) {
-        return;
-    }
-    centerwindow(20, 20, gs, pcs);
-    print_str("Button 2 / alt key:\n", gs, pcs);
-    print_str("Allows you to move  \n", gs, pcs);
-    print_str("without changing the\n", gs, pcs);
-    print_str("direction you are   \n", gs, pcs);
-    print_str("facing.  Good for   \n", gs, pcs);
-    print_str("searching walls and \n", gs, pcs);
-    print_str("fighting retreats.  \n", gs, pcs);
-    y = 11;
-    while y <= 18 {
-        x = 3;
-        while x <= 20 {
-            if y == 15 {
-                drawchartile(x, y, 129, gs, pcs);
-            } else if y == 16 {
-                drawchartile(x, y, 131, gs, pcs);
-            } else {
-                drawchartile(x, y, 128, gs, pcs);
+        1 => {
+            centerwindow(20, 20, gs, pcs);
+            print_str("\nKeyboard controls:  \n\n", gs, pcs);
+            print_str("move    : arrows    \n", gs, pcs);
+            print_str("button1 : ctrl      \n", gs, pcs);
+            print_str("button2 : alt       \n", gs, pcs);
+            print_str("\nTo switch to mouse \n", gs, pcs);
+            print_str("or joystick control,\n", gs, pcs);
+            print_str("hit f2             \n", gs, pcs);
+        }
+        2 => {
+            centerwindow(20, 20, gs, pcs);
+            print_str("Button 1 / ctrl key:\n", gs, pcs);
+            print_str("Builds shot power.  \n", gs, pcs);
+            print_str("If the shot power   \n", gs, pcs);
+            print_str("meter is full when  \n", gs, pcs);
+            print_str("the button is       \n", gs, pcs);
+            print_str("released, a super   \n", gs, pcs);
+            print_str("shot will be        \n", gs, pcs);
+            print_str("launched.           \n", gs, pcs);
+            print_str("\n", gs, pcs);
+            y = 11;
+            while y <= 18 {
+                x = 3;
+                while x <= 20 {
+                    drawchartile(x, y, 128, gs, pcs);
+                    x += 1;
+                }
+                y += 1;
             }
-            x += 1;
+            charpic(4, 14, player, east, 2, gs, pcs);
+            charpic(19, 15, shot, east, 1, gs, pcs);
+            charpic(17, 14, shot, east, 0, gs, pcs);
+            charpic(15, 15, shot, east, 1, gs, pcs);
+            charpic(8, 14, bigshot, east, 0, gs, pcs);
         }
-        y += 1;
-    }
-    charpic(6, 13, player, south, 2, gs, pcs);
-    pcs.sx = 6;
-    pcs.sy = 15;
-    print_str("\x1D\x1D\x1E\x1E\x1F\x1F", gs, pcs);
-    if !wantmore(gs, pcs, pas, sdl) {
-        return;
-    }
-    centerwindow(20, 20, gs, pcs);
-    print_str("\"P\" or \"space\" will \n", gs, pcs);
-    print_str("take a healing      \n", gs, pcs);
-    print_str("potion if you have  \n", gs, pcs);
-    print_str("one.  This restores \n", gs, pcs);
-    print_str("the body meter to   \n", gs, pcs);
-    print_str("full strength.  Keep\n", gs, pcs);
-    print_str("a sharp eye on the  \n", gs, pcs);
-    print_str("meter, because when \n", gs, pcs);
-    print_str("it runs out, you are\n", gs, pcs);
-    print_str("dead!               \n\n", gs, pcs);
-    print_str("\"B\" will cast a bolt\n", gs, pcs);
-    print_str("spell if you have   \n", gs, pcs);
-    print_str("any.  You can mow   \n", gs, pcs);
-    print_str("down a lot of       \n", gs, pcs);
-    print_str("monsters with a bit \n", gs, pcs);
-    print_str("of skill.           \n", gs, pcs);
-    if !wantmore(gs, pcs, pas, sdl) {
-        return;
+        3 => {
+            centerwindow(20, 20, gs, pcs);
+            print_str("Button 2 / alt key:\n", gs, pcs);
+            print_str("Allows you to move  \n", gs, pcs);
+            print_str("without changing the\n", gs, pcs);
+            print_str("direction you are   \n", gs, pcs);
+            print_str("facing.  Good for   \n", gs, pcs);
+            print_str("searching walls and \n", gs, pcs);
+            print_str("fighting retreats.  \n", gs, pcs);
+            y = 11;
+            while y <= 18 {
+                x = 3;
+                while x <= 20 {
+                    if y == 15 {
+                        drawchartile(x, y, 129, gs, pcs);
+                    } else if y == 16 {
+                        drawchartile(x, y, 131, gs, pcs);
+                    } else {
+                        drawchartile(x, y, 128, gs, pcs);
+                    }
+                    x += 1;
+                }
+                y += 1;
+            }
+            charpic(6, 13, player, south, 2, gs, pcs);
+            pcs.sx = 6;
+            pcs.sy = 15;
+            print_str("\x1D\x1D\x1E\x1E\x1F\x1F", gs, pcs);
+        }
+        4 => {
+            centerwindow(20, 20, gs, pcs);
+            print_str("\"P\" or \"space\" will \n", gs, pcs);
+            print_str("take a healing      \n", gs, pcs);
+            print_str("potion if you have  \n", gs, pcs);
+            print_str("one.  This restores \n", gs, pcs);
+            print_str("the body meter to   \n", gs, pcs);
+            print_str("full strength.  Keep\n", gs, pcs);
+            print_str("a sharp eye on the  \n", gs, pcs);
+            print_str("meter, because when \n", gs, pcs);
+            print_str("it runs out, you are\n", gs, pcs);
+            print_str("dead!               \n\n", gs, pcs);
+            print_str("\"B\" will cast a bolt\n", gs, pcs);
+            print_str("spell if you have   \n", gs, pcs);
+            print_str("any.  You can mow   \n", gs, pcs);
+            print_str("down a lot of       \n", gs, pcs);
+            print_str("monsters with a bit \n", gs, pcs);
+            print_str("of skill.           \n", gs, pcs);
+        }
+        5 => {
+            centerwindow(20, 20, gs, pcs);
+            print_str("\"N\" or \"enter\" will \n", gs, pcs);
+            print_str("cast a nuke spell.  \n", gs, pcs);
+            print_str("This usually wipes  \n", gs, pcs);
+            print_str("out all the monsters\n", gs, pcs);
+            print_str("near you.  Consider \n", gs, pcs);
+            print_str("it a panic button   \n", gs, pcs);
+            print_str("when you are being  \n", gs, pcs);
+            print_str("mobbed by monsters! \n\n", gs, pcs);
+            printchartile(b"               \x80\x80\x80\n\0", gs, pcs);
+            printchartile(b"POTIONS:       \x80\xA2\x80\n\0", gs, pcs);
+            printchartile(b"               \x80\x80\x80\n\0", gs, pcs);
+            printchartile(b"SCROLLS:       \x80\xA3\x80\n\0", gs, pcs);
+            printchartile(b" (BOLTS/NUKES) \x80\x80\x80\n\0", gs, pcs);
+            printchartile(b"TREASURE:      \x80\xA7\x80\n\0", gs, pcs);
+            printchartile(b" (POINTS)      \x80\x80\x80\n\0", gs, pcs);
+            printchartile(b"               \x80\x80\x80\n\0", gs, pcs);
+        }
+        _ => unreachable!(),
     }
-    centerwindow(20, 20, gs, pcs);
-    print_str("\"N\" or \"enter\" will \n", gs, pcs);
-    print_str("cast a nuke spell.  \n", gs, pcs);
-    print_str("This usually wipes  \n", gs, pcs);
-    print_str("out all the monsters\n", gs, pcs);
-    print_str("near you.  Consider \n", gs, pcs);
-    print_str("it a panic button   \n", gs, pcs);
-    print_str("when you are being  \n", gs, pcs);
-    print_str("mobbed by monsters! \n\n", gs, pcs);
-    printchartile(b"               \x80\x80\x80\n\0", gs, pcs);
-    printchartile(b"POTIONS:       \x80\xA2\x80\n\0", gs, pcs);
-    printchartile(b"               \x80\x80\x80\n\0", gs, pcs);
-    printchartile(b"SCROLLS:       \x80\xA3\x80\n\0", gs, pcs);
-    printchartile(b" (BOLTS/NUKES) \x80\x80\x80\n\0", gs, pcs);
-    printchartile(b"TREASURE:      \x80\xA7\x80\n\0", gs, pcs);
-    printchartile(b" (POINTS)      \x80\x80\x80\n\0", gs, pcs);
-    printchartile(b"               \x80\x80\x80\n\0", gs, pcs);
-    wantmore(gs, pcs, pas, sdl);
+    wantmoreprompt(gs, pcs);
 }
 
 /*       */
 /* reset */
 /*       */
-#[allow(dead_code)]
-fn reset(
-    gs: &mut GlobalState,
-    pcs: &mut PcrlibCState,
-    pas: &mut PcrlibAState,
-    sdl: &mut SdlManager,
-) {
-    centerwindow(18, 1, gs, pcs);
-    print_str("reset game (y/n)?", gs, pcs);
-    let ch = get(gs, pcs, pas, sdl) as i8;
-    if ch == 'y' as i8 {
-        gs.gamexit = killed;
-        gs.playdone = true;
-    }
-}
+// Rust port: unused (F3 is handled by dofkeys()).
+//
+// fn reset(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
+//     centerwindow(18, 1, gs, pcs);
+//     print_str("reset game (y/n)?", gs, pcs);
+//     let ch = get(gs, pcs) as i8;
+//     if ch == 'y' as i8 {
+//         gs.gamexit = killed;
+//         gs.playdone = true;
+//     }
+// }
 
 pub fn loadlevel(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     let mut i: i32 = 0;
-    let tokens: [classtype; 26] = [
-        player, teleporter, goblin, skeleton, ogre, gargoyle, dragon, turbogre, guns, gune,
-        secretgate, nothing, nothing, nothing, nothing, nothing, nothing, nothing, nothing,
-        nothing, nothing, nothing, nothing, nothing, nothing, nothing,
-    ];
     let mut x: i32 = 0;
     let mut y: i32 = 0;
     let mut xx: i32 = 0;
@@ -362,8 +350,11 @@ pub fn loadlevel(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibC
     let mut btile: u8 = 0;
     let mut sm = vec![];
     let mut rle = [0; 4096];
-    let filename = format!("LEVEL{}.CA2", pcs.level);
-    let filesize = loadFile(&filename, &mut rle);
+    // Rust port: Not in the original; see selectobjdefs().
+    selectobjdefs(gs);
+
+    let filename = level_path(pcs.level, gs.levelpack.as_deref());
+    let filesize = loadFile(&filename.to_string_lossy(), &mut rle);
     RLEExpand(&rle[4..], filesize, &mut sm);
     gs.numobj = 0;
     gs.o[0].x = 13;
@@ -431,6 +422,14 @@ pub fn loadlevel(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibC
     }
     gs.savescore = pcs.score;
     gs.saveo[0] = gs.o[0];
+
+    // Rust port: Autosave at each level start. Like SaveFile(), errors are swallowed, since there's
+    // nothing sensible to do with them in the middle of the game.
+    if gs.indemo == notdemo {
+        SaveGame::new(pcs.level, gs.savescore, gs.saveitems, gs.saveo[0])
+            .write(SaveSlot::Auto, &gs.savedir)
+            .ok();
+    }
 }
 
 fn drawside(gs: &mut GlobalState, cps: &mut CpanelState, pcs: &mut PcrlibCState) {
@@ -478,7 +477,7 @@ fn drawside(gs: &mut GlobalState, cps: &mut CpanelState, pcs: &mut PcrlibCState)
     }
 }
 
-fn playsetup(gs: &mut GlobalState, cps: &mut CpanelState, pcs: &mut PcrlibCState) {
+pub fn playsetup(gs: &mut GlobalState, cps: &mut CpanelState, pcs: &mut PcrlibCState) {
     let mut i: i32 = 0;
     gs.shotpower = 0;
     bar(0, 0, 23, 23, 0, gs, pcs);
@@ -490,6 +489,7 @@ fn playsetup(gs: &mut GlobalState, cps: &mut CpanelState, pcs: &mut PcrlibCState
         }
         pcs.score = 0;
         pcs.level = 1;
+        pcs.playframes = 0;
         gs.o[0].active = true;
         gs.o[0].class = player;
         gs.o[0].hp = 13;
@@ -535,6 +535,251 @@ pub fn repaintscreen(gs: &mut GlobalState, cps: &mut CpanelState, pcs: &mut Pcrl
     };
 }
 
+// Rust port: The save slots are unlimited, so they're listed (one page at a time) in a large window,
+// whose bottom rows are used for the F4/F5 prompts.
+const SAVES_PER_PAGE: usize = 12;
+
+/// Shows a page of the save list; returns whether more pages follow, in which case the wantmore()
+/// prompt is printed.
+fn listsaves(page: usize, gs: &mut GlobalState, pcs: &mut PcrlibCState) -> bool {
+    let saves = list_saves(&gs.savedir);
+    let pages = saves.chunks(SAVES_PER_PAGE).collect::<Vec<_>>();
+
+    drawwindow(1, 2, 38, 21, gs, pcs);
+
+    if saves.is_empty() {
+        print_str("\n        No saved games yet.", gs, pcs);
+        return false;
+    }
+
+    print_str("             SAVED GAMES\n\n", gs, pcs);
+    print_str("   # LEVEL   SCORE  SAVED ON\n", gs, pcs);
+    for (slot, save) in pages[page].iter() {
+        let line = match save {
+            Ok(save) => format!(
+                "{:>4} {:>5} {:>7}  {}\n",
+                slot.label(),
+                save.level,
+                save.score,
+                format_timestamp(save.timestamp)
+            ),
+            Err(error) => format!("{:>4}  {}\n", slot.label(), error),
+        };
+        print_str(&line, gs, pcs);
+    }
+
+    let more = page < pages.len() - 1;
+    if more {
+        wantmoreprompt(gs, pcs);
+    }
+    more
+}
+
+/// Prints a message in the prompt rows of the save list window.
+fn saveprompt(message: &str, gs: &mut GlobalState, pcs: &mut PcrlibCState) {
+    pcs.sx = pcs.leftedge;
+    pcs.sy = 19;
+    print_str("                                    \n", gs, pcs);
+    print_str("                                    ", gs, pcs);
+    pcs.sx = pcs.leftedge;
+    pcs.sy = 19;
+    print_str(message, gs, pcs);
+}
+
+/// Parses a slot number, or `A` for the autosave; returns None if not valid.
+fn inputslot(input: &LineInput) -> Option<SaveSlot> {
+    let input = String::from_utf8_lossy(input.string());
+    let input = input.trim_end_matches('\0').trim();
+
+    if input == "A" {
+        Some(SaveSlot::Auto)
+    } else {
+        input.parse().ok().map(SaveSlot::from_number)
+    }
+}
+
+// Rust port: The dialogs of dofkeys(), which used to wait for the keys with get(); they're opened by
+// the `Engine` at the end of the frame, and take a key per frame. Once they're done, the screen is
+// repainted, as at the end of dofkeys().
+pub enum FKeyPrompt {
+    Reset,
+    CantSave,
+    /// A page of the save list, for loading (F5) or saving (F4).
+    SaveList {
+        load: bool,
+        page: usize,
+    },
+    /// The slot number, up to 9 characters.
+    Slot {
+        load: bool,
+        input: LineInput,
+    },
+    Overwrite(SaveSlot),
+    /// A message printed in the save list window, until a key is pressed.
+    Message,
+    Pause,
+    Quit,
+}
+
+impl FKeyPrompt {
+    pub fn open(
+        self,
+        gs: &mut GlobalState,
+        pas: &mut PcrlibAState,
+        pcs: &mut PcrlibCState,
+    ) -> Self {
+        match self {
+            FKeyPrompt::Reset => {
+                expwin(18, 1, gs, pas, pcs);
+                print_str("RESET GAME (Y/N)?", gs, pcs);
+            }
+            FKeyPrompt::CantSave => {
+                expwin(22, 4, gs, pas, pcs);
+                print_str("Can't save game here!", gs, pcs);
+            }
+            FKeyPrompt::SaveList { load, page } => return savelistpage(load, page, gs, pcs),
+            FKeyPrompt::Pause => {
+                expwin(7, 1, gs, pas, pcs);
+                print_str("PAUSED", gs, pcs);
+            }
+            FKeyPrompt::Quit => {
+                expwin(12, 1, gs, pas, pcs);
+                print_str("QUIT (Y/N)?", gs, pcs);
+            }
+            FKeyPrompt::Slot { .. } | FKeyPrompt::Overwrite(_) | FKeyPrompt::Message => {}
+        }
+        self
+    }
+
+    /// Returns None once the prompt is done.
+    pub fn step(
+        self,
+        cycle: &mut i32,
+        gs: &mut GlobalState,
+        cps: &mut CpanelState,
+        pas: &mut PcrlibAState,
+        pcs: &mut PcrlibCState,
+    ) -> Option<Self> {
+        let key = match tryget(cycle, gs, pcs, pas) {
+            Some(key) => key,
+            None => return Some(self),
+        };
+        let ch = (key as u8).to_ascii_uppercase();
+
+        match self {
+            FKeyPrompt::Reset => {
+                if ch == b'Y' {
+                    gs.resetgame = true;
+                }
+                None
+            }
+            FKeyPrompt::SaveList { load, page } => {
+                if ch == 27 {
+                    None
+                } else {
+                    Some(savelistpage(load, page + 1, gs, pcs))
+                }
+            }
+            FKeyPrompt::Slot { load, mut input } => match input.key(key, gs, pcs) {
+                None => Some(FKeyPrompt::Slot { load, input }),
+                Some(false) => None,
+                Some(true) => match inputslot(&input) {
+                    Some(slot) if load => loadgame(slot, gs, cps, pcs),
+                    Some(slot @ SaveSlot::Numbered(_)) => {
+                        if slot.exists(&gs.savedir) {
+                            saveprompt("Game exists, overwrite (Y/N)?", gs, pcs);
+                            Some(FKeyPrompt::Overwrite(slot))
+                        } else {
+                            Some(savegame(slot, gs, pcs))
+                        }
+                    }
+                    _ => None,
+                },
+            },
+            FKeyPrompt::Overwrite(slot) => {
+                if ch == b'Y' {
+                    Some(savegame(slot, gs, pcs))
+                } else {
+                    None
+                }
+            }
+            FKeyPrompt::Quit => {
+                if ch == b'Y' {
+                    _quit(None, pas, pcs);
+                }
+                None
+            }
+            FKeyPrompt::CantSave | FKeyPrompt::Message | FKeyPrompt::Pause => None,
+        }
+    }
+}
+
+/// Shows a page of the save list; on the last one, prompts for the slot.
+fn savelistpage(
+    load: bool,
+    page: usize,
+    gs: &mut GlobalState,
+    pcs: &mut PcrlibCState,
+) -> FKeyPrompt {
+    if listsaves(page, gs, pcs) {
+        return FKeyPrompt::SaveList { load, page };
+    }
+
+    if load {
+        saveprompt("Load game #(A = autosave):", gs, pcs);
+    } else {
+        saveprompt("Save as game #:", gs, pcs);
+    }
+
+    FKeyPrompt::Slot {
+        load,
+        input: LineInput::new(9),
+    }
+}
+
+fn savegame(slot: SaveSlot, gs: &mut GlobalState, pcs: &mut PcrlibCState) -> FKeyPrompt {
+    //
+    // save game
+    //
+    // Rust port: As in the original, the state saved is the one at the start of the level.
+    let save = SaveGame::new(pcs.level, gs.savescore, gs.saveitems, gs.saveo[0]);
+    match save.write(slot, &gs.savedir) {
+        Ok(()) => saveprompt("Game saved.  Hit F5 to restart it.", gs, pcs),
+        Err(error) => saveprompt(&error.to_string(), gs, pcs),
+    }
+    FKeyPrompt::Message
+}
+
+fn loadgame(
+    slot: SaveSlot,
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pcs: &mut PcrlibCState,
+) -> Option<FKeyPrompt> {
+    //
+    // load game
+    //
+    match SaveGame::read(slot, &gs.savedir) {
+        Ok(save) => {
+            gs.items = save.items;
+            pcs.score = save.score;
+            pcs.level = save.level;
+            gs.o[0] = save.player;
+            gs.exitdemo = true;
+            if gs.indemo != notdemo {
+                gs.playdone = true;
+            }
+            drawside(gs, cps, pcs); // draw score, icons, etc
+            gs.leveldone = true;
+            None
+        }
+        Err(error) => {
+            saveprompt(&error.to_string(), gs, pcs);
+            Some(FKeyPrompt::Message)
+        }
+    }
+}
+
 /*
 =============
 =
@@ -545,14 +790,10 @@ pub fn repaintscreen(gs: &mut GlobalState, cps: &mut CpanelState, pcs: &mut Pcrl
 =============
 */
 
-pub fn dofkeys(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
-    let mut key = bioskey(1, pcs, pas, sdl);
+// Rust port: The screens and dialogs opened by the keys are overlays of the `Engine`, which opens
+// them at the end of the frame; the screen is repainted when they're closed.
+pub fn dofkeys(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
+    let mut key = bioskey(1, pcs, pas);
     // make ESC into F10
     if key == SDL_SCANCODE_ESCAPE {
         key = SDL_SCANCODE_F10;
@@ -560,303 +801,151 @@ pub fn dofkeys(
     if key < SDL_SCANCODE_F1 || key > SDL_SCANCODE_F10 {
         return;
     }
-    match key {
+    let overlay = match key {
         // F1
-        SDL_SCANCODE_F1 => {
-            clearkeys(pcs, pas, sdl);
-            help(gs, pcs, pas, sdl);
-        }
+        SDL_SCANCODE_F1 => Overlay::Help,
         // F2
-        SDL_SCANCODE_F2 => {
-            clearkeys(pcs, pas, sdl);
-            controlpanel(gs, cps, pas, pcs, sdl);
-        }
+        SDL_SCANCODE_F2 => Overlay::ControlPanel,
         // F3
-        SDL_SCANCODE_F3 => {
-            clearkeys(pcs, pas, sdl);
-            expwin(18, 1, gs, pas, pcs);
-            print_str("RESET GAME (Y/N)?", gs, pcs);
-            let ch = (get(gs, pcs, pas, sdl) as u8).to_ascii_uppercase() as i8;
-            if ch as i32 == 'Y' as i32 {
-                gs.resetgame = true;
-            }
-        }
+        SDL_SCANCODE_F3 => Overlay::Prompt(Prompt::FKey(FKeyPrompt::Reset)),
         // F4
         SDL_SCANCODE_F4 => {
-            clearkeys(pcs, pas, sdl);
-            expwin(22, 4, gs, pas, pcs);
             if gs.indemo != notdemo {
-                print_str("Can't save game here!", gs, pcs);
-                get(gs, pcs, pas, sdl);
+                Overlay::Prompt(Prompt::FKey(FKeyPrompt::CantSave))
             } else {
-                print_str("Save as game #(1-9):", gs, pcs);
-                // Rust port: The upper casing is in the original, and it's redundant.
-                let ch1 = (get(gs, pcs, pas, sdl) as u8).to_ascii_uppercase();
-                drawchar(pcs.sx, pcs.sy, ch1 as i32, gs, pcs);
-                if ch1 >= b'1' && ch1 <= b'9' {
-                    let mut save_game = true;
-                    //
-                    // save game
-                    //
-                    // Rust port: Very easy to miss the subtraction!
-                    let str = format!("GAME{}.CA2", ch1 - b'0');
-                    if _Verify(&str) != 0 {
-                        print_str("\nGame exists,\noverwrite (Y/N)?", gs, pcs);
-                        let ch2 = get(gs, pcs, pas, sdl) as u8;
-                        if ch2 != b'Y' && ch2 != b'y' {
-                            save_game = false;
-                        } else {
-                            pcs.sx = pcs.leftedge;
-                            print_str("                    ", gs, pcs);
-                            pcs.sy -= 1;
-                            pcs.sx = pcs.leftedge;
-                            print_str("                    ", gs, pcs);
-                            pcs.sx = pcs.leftedge;
-                            pcs.sy -= 1;
-                        }
-                    }
-                    if save_game {
-                        // Rust port: Former flags: (O_WRONLY | O_BINARY | O_CREAT | O_TRUNC,
-                        // S_IREAD | S_IWRITE).
-                        if let Ok(mut file) = File::create(str) {
-                            gs.saveitems.serialize(&mut file).unwrap();
-                            gs.savescore.serialize(&mut file).unwrap();
-                            pcs.level.serialize(&mut file).unwrap();
-                            gs.saveo.serialize(&mut file).unwrap();
-
-                            print_str("\nGame saved.  Hit F5\n", gs, pcs);
-                            print_str("when you wish to\n", gs, pcs);
-                            print_str("restart the game.", gs, pcs);
-                            get(gs, pcs, pas, sdl);
-                        } else {
-                            return;
-                        }
-                    }
-                }
+                Overlay::Prompt(Prompt::FKey(FKeyPrompt::SaveList {
+                    load: false,
+                    page: 0,
+                }))
             }
         }
         // F5
-        SDL_SCANCODE_F5 => {
-            clearkeys(pcs, pas, sdl);
-            expwin(22, 4, gs, pas, pcs);
-            print_str("Load game #(1-9):", gs, pcs);
-            // Rust port: The upper casing is in the original, and it's redundant.
-            let ch = (get(gs, pcs, pas, sdl) as u8).to_ascii_uppercase();
-            drawchar(pcs.sx, pcs.sy, ch as i32, gs, pcs);
-            if ch >= b'1' && ch <= b'9' {
-                //
-                // load game
-                //
-                // Rust port: Very easy to miss the subtraction!
-                let str = format!("GAME{}.CA2", ch - b'0');
-                // Rust port: Flags in the original port = (O_RDONLY | O_BINARY, S_IWRITE | S_IREAD);
-                // oddly, O_RDONLY == O_BINARY == 0.
-                if let Ok(mut file) = File::open(str) {
-                    gs.items = Deserialize::deserialize(&mut file).unwrap();
-                    pcs.score = Deserialize::deserialize(&mut file).unwrap();
-                    pcs.level = Deserialize::deserialize(&mut file).unwrap();
-                    gs.o[0] = Deserialize::deserialize(&mut file).unwrap();
-                    gs.exitdemo = true;
-                    if gs.indemo != notdemo {
-                        gs.playdone = true;
-                    }
-                    drawside(gs, cps, pcs); // draw score, icons, etc
-                    gs.leveldone = true;
-                } else {
-                    print_str("\nGame not found.", gs, pcs);
-                    get(gs, pcs, pas, sdl);
-                }
-            }
+        SDL_SCANCODE_F5 => Overlay::Prompt(Prompt::FKey(FKeyPrompt::SaveList {
+            load: true,
+            page: 0,
+        })),
+        // F6
+        // Rust port: Not in the original; opens the high scores viewer.
+        SDL_SCANCODE_F6 => Overlay::HighScores,
+        // F8
+        // Rust port: Not in the original; opens the debug console (see `debug_console`), only
+        // while playing, and only in debug builds, since it's a cheat.
+        SDL_SCANCODE_F8
+            if cfg!(debug_assertions)
+                && gs.indemo == notdemo
+                && matches!(gs.gamestate, statetype::ingame) =>
+        {
+            Overlay::DebugConsole
         }
-        // F9
-        SDL_SCANCODE_F9 => {
-            clearkeys(pcs, pas, sdl);
-            expwin(7, 1, gs, pas, pcs);
-            print_str("PAUSED", gs, pcs);
-            get(gs, pcs, pas, sdl);
+        SDL_SCANCODE_F8 => {
+            clearkeys(pcs, pas);
+            return;
         }
+        // F9
+        SDL_SCANCODE_F9 => Overlay::Prompt(Prompt::FKey(FKeyPrompt::Pause)),
         // F10
-        SDL_SCANCODE_F10 => {
-            clearkeys(pcs, pas, sdl);
-            expwin(12, 1, gs, pas, pcs);
-            print_str("QUIT (Y/N)?", gs, pcs);
-            let ch = (get(gs, pcs, pas, sdl) as u8).to_ascii_uppercase() as i8;
-            if ch == 'Y' as i8 {
-                _quit(None, pas, pcs, sdl);
-            }
-        }
+        SDL_SCANCODE_F10 => Overlay::Prompt(Prompt::FKey(FKeyPrompt::Quit)),
         _ => return,
-    }
+    };
 
-    clearold(&mut gs.oldtiles);
-    clearkeys(pcs, pas, sdl);
-    repaintscreen(gs, cps, pcs);
+    clearkeys(pcs, pas);
+    gs.overlay = Some(overlay);
 }
 
-fn dotitlepage(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
-    let mut i: i32 = 0;
-    drawpic(0, 0, 14, gs, cps, pcs);
-    UpdateScreen(gs, pcs);
-    gs.gamestate = statetype::intitle;
-    i = 0;
-    while i < 300 {
-        WaitVBL();
-        gs.indemo = notdemo;
-        gs.ctrl = ControlPlayer(1, gs, pcs, pas, sdl);
-        if gs.ctrl.button1 as i32 != 0
-            || gs.ctrl.button2 as i32 != 0
-            || pcs.keydown[SDL_SCANCODE_SPACE as usize] as i32 != 0
-        {
-            pcs.level = 0;
-            gs.exitdemo = true;
-            break;
-        } else {
-            gs.indemo = demoplay;
-            if bioskey(1, pcs, pas, sdl) != 0 {
-                dofkeys(gs, cps, pas, pcs, sdl);
-                UpdateScreen(gs, pcs);
-            }
-            if gs.exitdemo {
-                break;
-            }
-            i += 1;
-        }
+// Rust port: Extracted from main(); sets up the tables that don't depend on the graphic mode. It
+// must be called after _setupgame(), which sets the level pack.
+pub fn initglobals(gs: &mut GlobalState) -> Result<(), String> {
+    initobjects(&mut gs.objdef);
+
+    // Rust port: Not in the original; see `objects`. The custom definitions are kept aside, since
+    // they're only used in games (see selectobjdefs()).
+    if objdefs_path(gs.levelpack.as_deref()).is_some() {
+        let mut customobjdef = gs.objdef;
+        loadobjects(gs.levelpack.as_deref(), &mut customobjdef)?;
+        gs.customobjdef = Some(customobjdef);
     }
-    gs.gamestate = statetype::ingame;
-}
 
-fn doendpage(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
-    WaitEndSound(gs, pas, pcs);
-    drawpic(0, 0, 15, gs, cps, pcs);
-    PlaySound(3, pas);
-    WaitEndSound(gs, pas, pcs);
-    PlaySound(3, pas);
-    WaitEndSound(gs, pas, pcs);
-    PlaySound(3, pas);
-    WaitEndSound(gs, pas, pcs);
-    PlaySound(3, pas);
-    WaitEndSound(gs, pas, pcs);
-    drawwindow(0, 0, 17, 9, gs, pcs);
-    print_str("Congratulation! \n", gs, pcs);
-    print_str("One as skilled  \n", gs, pcs);
-    print_str("as yourself     \n", gs, pcs);
-    print_str("deserves the    \n", gs, pcs);
-    print_str("10,000,000 gold \n", gs, pcs);
-    print_str("you pulled out  \n", gs, pcs);
-    print_str("of the palace! ", gs, pcs);
-    clearkeys(pcs, pas, sdl);
-    get(gs, pcs, pas, sdl);
-    drawwindow(0, 0, 17, 9, gs, pcs);
-    print_str("Let us know what\n", gs, pcs);
-    print_str("you enjoyed     \n", gs, pcs);
-    print_str("about this game,\n", gs, pcs);
-    print_str("so we can give  \n", gs, pcs);
-    print_str("you more of it. \n", gs, pcs);
-    print_str("Thank you for   \n", gs, pcs);
-    print_str("playing!", gs, pcs);
-    get(gs, pcs, pas, sdl);
-}
+    initpriority(gs);
 
-fn dodemo(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
-    let mut i: i32 = 0;
-    while !gs.exitdemo {
-        dotitlepage(gs, cps, pas, pcs, sdl);
-        if gs.exitdemo {
-            break;
-        }
-        i = rnd(NUM_DEMOS - 1, pas) + 1;
-        LoadDemo(i, gs, pcs);
-        pcs.level = 0;
-        playsetup(gs, cps, pcs);
-        playloop(gs, cps, pas, pcs, sdl);
-        if gs.exitdemo {
-            break;
+    gs.side = 0;
+
+    for x in 0..=85 {
+        for y in 0..=(topoff - 1) {
+            gs.view[x][y] = solidwall;
+            gs.view[x][(85 - y)] = solidwall;
+            gs.background[x][y] = solidwall;
+            gs.background[x][(85 - y)] = solidwall;
         }
-        pcs.level = 0;
-        gs.gamestate = statetype::inscores;
-        gs.indemo = demoplay;
-        _showhighscores(gs, pcs);
-        UpdateScreen(gs, pcs);
-        i = 0;
-        while i < 500 {
-            WaitVBL();
-            gs.indemo = notdemo;
-            gs.ctrl = ControlPlayer(1, gs, pcs, pas, sdl);
-            if gs.ctrl.button1 as i32 != 0
-                || gs.ctrl.button2 as i32 != 0
-                || pcs.keydown[SDL_SCANCODE_SPACE as usize] as i32 != 0
-            {
-                gs.exitdemo = true;
-                break;
-            } else {
-                if bioskey(1, pcs, pas, sdl) != 0 {
-                    dofkeys(gs, cps, pas, pcs, sdl);
-                }
-                if gs.exitdemo {
-                    break;
-                }
-                i += 1;
-            }
+        gs.view[86][x] = solidwall;
+    }
+    for y in 11..=74 {
+        for x in 0..=(leftoff - 1) {
+            gs.view[x][y] = solidwall;
+            gs.view[(85 - x)][y] = solidwall;
+            gs.background[x][y] = solidwall;
+            gs.background[(85 - x)][y] = solidwall;
         }
     }
+
+    Ok(())
 }
 
-fn gameover(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
-    let mut i: i32 = 0;
-    expwin(11, 4, gs, pas, pcs);
-    print_str("\n GAME OVER\n     ", gs, pcs);
-    UpdateScreen(gs, pcs);
-    WaitEndSound(gs, pas, pcs);
-    i = 0;
-    while i < 120 {
-        WaitVBL();
-        i += 1;
+// Rust port: Extracted from initglobals(); the priorities of the object tiles depend on the object
+// definitions.
+fn initpriority(gs: &mut GlobalState) {
+    gs.priority.fill(99);
+
+    gs.priority[blankfloor] = 0;
+    for i in
+        gs.objdef[teleporter as usize].firstchar..=gs.objdef[teleporter as usize].firstchar + 20
+    {
+        gs.priority[i as usize] = 0;
     }
-    gs.gamestate = statetype::inscores;
-    _checkhighscore(gs, pas, pcs, sdl);
-    pcs.level = 0;
-    i = 0;
-    while i < 500 {
-        WaitVBL();
-        gs.ctrl = ControlPlayer(1, gs, pcs, pas, sdl);
-        if gs.ctrl.button1 as i32 != 0
-            || gs.ctrl.button2 as i32 != 0
-            || pcs.keydown[SDL_SCANCODE_SPACE as usize] as i32 != 0
+    for clvar in (dead2 as usize)..=(dead5 as usize) {
+        for i in gs.objdef[clvar].firstchar
+            ..=(gs.objdef[clvar].firstchar
+                + gs.objdef[clvar].size as u16 * gs.objdef[clvar].size as u16)
         {
-            break;
+            gs.priority[i as usize] = 0; /*deadthing*/
         }
-        if bioskey(1, pcs, pas, sdl) != 0 {
-            dofkeys(gs, cps, pas, pcs, sdl);
+    }
+    for i in 152..=161 {
+        gs.priority[i] = 2; /*shots*/
+    }
+    for i in gs.objdef[bigshot as usize].firstchar..=(gs.objdef[bigshot as usize].firstchar + 31) {
+        gs.priority[i as usize] = 2; /*bigshot*/
+    }
+    for i in 0..=(tile2s - 1) {
+        if gs.priority[i] == 99 {
+            gs.priority[i] = 3; /*most 1*1 tiles are walls, etc*/
         }
-        if gs.exitdemo as i32 != 0 || gs.indemo == demoplay {
-            break;
+    }
+    gs.priority[167] = 1; // chest
+    for i in tile2s..=maxpics {
+        if gs.priority[i] as i32 == 99 {
+            gs.priority[i] = 4; /*most bigger tiles are monsters*/
         }
-        i += 1;
     }
+    for i in gs.objdef[player as usize].firstchar..=(gs.objdef[player as usize].firstchar + 63) {
+        gs.priority[i as usize] = 5; /*player*/
+    }
+}
+
+// Rust port: Not in the original; the demos were recorded with the stock object definitions, and
+// would desync with the custom ones, so the table is picked whenever a level is loaded.
+pub fn selectobjdefs(gs: &mut GlobalState) {
+    let customobjdef = match gs.customobjdef {
+        Some(customobjdef) => customobjdef,
+        None => return,
+    };
+
+    if gs.indemo == demoplay {
+        initobjects(&mut gs.objdef);
+    } else {
+        gs.objdef = customobjdef;
+    }
+
+    initpriority(gs);
 }
 
 /***************************************************************************/
@@ -871,29 +960,23 @@ fn gameover(
 pub fn original_main() {
     // Rust port: The SDL/Event watch initializations have been moved here, since they must stay in
     // the global scope.
-    let mut sdl = SdlManager::init_sdl();
+    let sdl = SdlManager::init_sdl();
 
     // Rust port: This needs to be on the global scope, because `Timer` lifetime(s) are bound to it;
     // if it's placed inside the SdlManager, the lifetime(s) will be bound to the manager.
     let timer_sys = sdl.timer();
 
     // Rust port: Option<TextureCreator<_>> is a workaround necessary to allow Texture live within
-    // PcrlibCState, as a texture's lifetime is bound to its texture creator, which therefore needs
-    // to be in a higher scope; this is a problem because both the variables TextureCreator depends
-    // on, and Texture, are inside PcrlibCState. The clean alternative is to move the texture out of
-    // PcrlibCState and pass it around, which is not great, considering the amount of state
-    // variables already passed around.
+    // the `Frontend`, as a texture's lifetime is bound to its texture creator, which therefore needs
+    // to be in a higher scope.
     let mut texture_creator = None;
 
-    // Rust port: Globals
-
-    let mut gs = GlobalState::default();
-    let mut cps = CpanelState::default();
-    let mut pas = PcrlibAState::new();
-
     /***************************************************************************/
 
-    let ver_arg_position = std::env::args().position(|arg| arg == "/VER");
+    // Rust port: It's possible to iterate `Args`, although it doesn't get much cleaner.
+    let mut args = std::env::args().collect::<Vec<_>>();
+
+    let ver_arg_position = args.iter().position(|arg| arg == "/VER");
 
     if let Some(1) = ver_arg_position {
         print!(
@@ -901,7 +984,7 @@ pub fn original_main() {
                     CatacombSDL\n\
                     Version 1.03\n
 \
-                    Usage: catacomb [windowed <width> <height>] [screen <num>]\n
+                    Usage: catacomb [windowed <width> <height>] [screen <num>] [{LEVEL_PACK_PARAM} <dir>]\n
 \
                     Ported by Braden \"Blzut3\" Obrzut and Rene \"Havoc\" Nicolaus\n\
                     Includes PC Speaker emulator by K1n9_Duk3\n\
@@ -913,110 +996,33 @@ pub fn original_main() {
         std::process::exit(0);
     }
 
-    initobjects(&mut gs.objdef);
-
-    gs.priority.fill(99);
-
-    gs.priority[blankfloor] = 0;
-    for i in
-        gs.objdef[teleporter as usize].firstchar..=gs.objdef[teleporter as usize].firstchar + 20
-    {
-        gs.priority[i as usize] = 0;
-    }
-    for clvar in (dead2 as usize)..=(dead5 as usize) {
-        for i in gs.objdef[clvar].firstchar
-            ..=(gs.objdef[clvar].firstchar
-                + gs.objdef[clvar].size as u16 * gs.objdef[clvar].size as u16)
-        {
-            gs.priority[i as usize] = 0; /*deadthing*/
-        }
-    }
-    for i in 152..=161 {
-        gs.priority[i] = 2; /*shots*/
-    }
-    for i in gs.objdef[bigshot as usize].firstchar..=(gs.objdef[bigshot as usize].firstchar + 31) {
-        gs.priority[i as usize] = 2; /*bigshot*/
-    }
-    for i in 0..=(tile2s - 1) {
-        if gs.priority[i] == 99 {
-            gs.priority[i] = 3; /*most 1*1 tiles are walls, etc*/
-        }
-    }
-    gs.priority[167] = 1; // chest
-    for i in tile2s..=maxpics {
-        if gs.priority[i] as i32 == 99 {
-            gs.priority[i] = 4; /*most bigger tiles are monsters*/
-        }
-    }
-    for i in gs.objdef[player as usize].firstchar..=(gs.objdef[player as usize].firstchar + 63) {
-        gs.priority[i as usize] = 5; /*player*/
-    }
-
-    gs.side = 0;
-
-    for x in 0..=85 {
-        for y in 0..=(topoff - 1) {
-            gs.view[x][y] = solidwall;
-            gs.view[x][(85 - y)] = solidwall;
-            gs.background[x][y] = solidwall;
-            gs.background[x][(85 - y)] = solidwall;
-        }
-        gs.view[86][x] = solidwall;
-    }
-    for y in 11..=74 {
-        for x in 0..=(leftoff - 1) {
-            gs.view[x][y] = solidwall;
-            gs.view[(85 - x)][y] = solidwall;
-            gs.background[x][y] = solidwall;
-            gs.background[(85 - x)][y] = solidwall;
-        }
-    }
-
-    //   puts ("CATACOMB II is executing");
-
-    //  _dontplay = 1;	// no sounds for debugging and profiling
-
-    let (mut pcs, _vbl_timer, _audio_dev) = _setupgame(
-        &mut gs,
-        &mut cps,
-        &mut pas,
-        &sdl,
-        &mut texture_creator,
-        &timer_sys,
-    );
-
-    expwin(33, 13, &mut gs, &mut pas, &mut pcs);
-    print_str("  Softdisk Publishing presents\n\n", &mut gs, &mut pcs);
-    print_str("          The Catacomb\n\n", &mut gs, &mut pcs);
-    print_str("        By John Carmack\n\n", &mut gs, &mut pcs);
-    print_str("       Copyright 1990-93\n", &mut gs, &mut pcs);
-    print_str("      Softdisk Publishing", &mut gs, &mut pcs);
-    print_str("\n\n", &mut gs, &mut pcs);
-    print_str("\n\n", &mut gs, &mut pcs);
-    print_str("         Press a key:", &mut gs, &mut pcs);
-    get(&mut gs, &mut pcs, &mut pas, &mut sdl);
+    // Rust port: Not in the original; the level pack is handled first, so that the screen mode
+    // parameters keep their positions.
+    // Rust port: Not in the original; the level pack is checked before opening the window, so that
+    // the errors are reported plainly on the console.
+    let levelpack = take_level_pack_arg(&mut args)
+        .and_then(|levelpack| {
+            if let Some(levelpack) = &levelpack {
+                validate_level_pack(levelpack)
+                    .map_err(|error| format!("Invalid level pack: {}", error))?;
+            }
+            Ok(levelpack)
+        })
+        .unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        });
 
-    clearkeys(&mut pcs, &mut pas, &mut sdl);
+    let mut frontend = Frontend::new(&args, sdl, &mut texture_creator, &timer_sys);
 
-    gs.screencenter.x = 11;
-    gs.screencenter.y = 11;
+    // Rust port: The globals, and the rest of the former main(), are in the `Engine`.
+    let mut engine = Engine::new(levelpack, frontend.joysticks());
 
-    gs.exitdemo = false;
-    pcs.level = 0;
+    frontend.startsound(engine.sound());
 
     // go until quit () is called
     loop {
-        dodemo(&mut gs, &mut cps, &mut pas, &mut pcs, &mut sdl);
-        playsetup(&mut gs, &mut cps, &mut pcs);
-        gs.indemo = notdemo;
-        gs.gamestate = statetype::ingame;
-        playloop(&mut gs, &mut cps, &mut pas, &mut pcs, &mut sdl);
-        if gs.indemo == notdemo {
-            gs.exitdemo = false;
-            if pcs.level > numlevels {
-                doendpage(&mut gs, &mut cps, &mut pas, &mut pcs, &mut sdl); // finished all levels
-            }
-            gameover(&mut gs, &mut cps, &mut pas, &mut pcs, &mut sdl);
-        }
+        let output = engine.step(frontend.input());
+        frontend.output(output);
     }
 }
@@ -4,14 +4,12 @@ use crate::{
     cat_play::{doactive, doinactive},
     catacomb::refresh,
     class_type::classtype::*,
-    cpanel_state::CpanelState,
     global_state::GlobalState,
     gr_type::grtype::*,
     pcrlib_a::{screenpitch, EGA},
     pcrlib_a_state::PcrlibAState,
     pcrlib_c::UpdateScreen,
     pcrlib_c_state::PcrlibCState,
-    sdl_manager::SdlManager,
 };
 
 //========================================================================
@@ -97,45 +95,36 @@ pub fn eraseobj(gs: &mut GlobalState) {
     }
 }
 
-pub fn doall(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
+// Rust port: This was doall(), which looped until the level was over; it now runs a single frame
+// (see `Engine`), and returns false when the level (or the game) is over.
+pub fn doframe(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) -> bool {
     assert!(gs.numobj > 0);
 
+    gs.objecton = gs.numobj;
     loop {
-        gs.objecton = gs.numobj;
-        loop {
-            gs.obj.update_from_activeobj(&gs.o[gs.objecton as usize]);
-            if gs.obj.class as i32 != nothing as i32 {
-                gs.obj
-                    .update_from_objdeftype(&gs.objdef[gs.obj.class as usize]);
-                if gs.obj.active {
-                    doactive(gs, cps, pas, pcs, sdl);
-                } else {
-                    doinactive(gs);
-                }
-            }
-            if gs.leveldone || gs.playdone {
-                return;
-            }
-            gs.objecton -= 1;
-            if !(gs.objecton >= 0) {
-                break;
+        gs.obj.update_from_activeobj(&gs.o[gs.objecton as usize]);
+        if gs.obj.class as i32 != nothing as i32 {
+            gs.obj
+                .update_from_objdeftype(&gs.objdef[gs.obj.class as usize]);
+            if gs.obj.active {
+                doactive(gs, pas, pcs);
+            } else {
+                doinactive(gs);
             }
         }
-        refresh(gs, pcs);
-        gs.frameon = gs.frameon.wrapping_add(1);
-        if gs.leveldone {
-            return;
+        if gs.leveldone || gs.playdone {
+            return false;
         }
-        if gs.playdone {
+        gs.objecton -= 1;
+        if !(gs.objecton >= 0) {
             break;
         }
     }
+    refresh(gs, pcs);
+    gs.frameon = gs.frameon.wrapping_add(1);
+    pcs.playframes = pcs.playframes.wrapping_add(1);
+
+    !(gs.leveldone || gs.playdone)
 }
 
 fn drawcgachartile(screenseg_ofs: usize, tile: i32, gs: &mut GlobalState) {
@@ -12,161 +12,206 @@ use crate::{
     pcrlib_a::{drawchar, drawpic, ContinueSound, PauseSound, WaitVBL},
     pcrlib_a_state::PcrlibAState,
     pcrlib_c::{
-        ProbeJoysticks, ProcessEvents, ReadJoystick, ScancodeToDOS, UpdateScreen, _egaok, _vgaok,
-        bioskey, bloadin, clearkeys, drawwindow, erasewindow, expwin, get, print_str,
-        CheckMouseMode, ControlJoystick,
+        _egaok, _vgaok, bioskey, bloadin, clearkeys, drawwindow, erasewindow, expwin, print_str,
+        tryget, CheckMouseMode, ControlJoystick, ProcessEvents, ReadJoystick, ScancodeToDOS,
+        UpdateScreen,
     },
     pcrlib_c_state::PcrlibCState,
     pic_file_type::picfiletype,
     pic_type::pictype,
     scan_codes::*,
-    sdl_manager::SdlManager,
+    sound_type::soundtype::*,
     sprite_type::spritetype,
+    synth::MAX_SYNTH_VOLUME,
 };
 
-const SDLK_UP: u32 = 1073741906;
-const SDLK_DOWN: u32 = 1073741905;
-const SDLK_LEFT: u32 = 1073741904;
-const SDLK_RIGHT: u32 = 1073741903;
-const SDLK_ESCAPE: u32 = 27;
-const SDLK_RETURN: u32 = 13;
-
 const rowy: [i32; 4] = [4, 9, 14, 19];
 const collumnx: [i32; 4] = [14, 20, 26, 32];
 
+// Rust port: The calibrations used to wait for the input in loops; they're now started by the
+// calibrate*() functions, which draw the window, and advanced by calibratestep() once per frame, with
+// the state of the loops kept here (see `CpanelState::calibration`).
+pub enum Calibration {
+    Gamepad,
+    Joystick {
+        joynum: i32,
+        /// The cursor animation.
+        stage: i32,
+        phase: JoyPhase,
+        /// The upper left corner, once read.
+        low: (i32, i32),
+    },
+    Mouse,
+    Synth {
+        oldvolume: u8,
+    },
+    /// `hx`/`hy` is the position of the prompt; `select` is the action whose key is being read.
+    Keys {
+        hx: i32,
+        hy: i32,
+        select: Option<i32>,
+    },
+}
+
+pub enum JoyPhase {
+    Upper,
+    /// Waiting for the fire button to be released.
+    UpperRelease,
+    Lower,
+    LowerRelease,
+}
+
 fn calibratejoy(
     joynum: i32,
     gs: &mut GlobalState,
     pas: &mut PcrlibAState,
     pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
-    let mut current_block: u64;
-    let mut stage: i32 = 0;
-    let mut dx: i32 = 0;
-    let mut dy: i32 = 0;
-    let mut xl: i32 = 0;
-    let mut yl: i32 = 0;
-    let mut xh: i32 = 0;
-    let mut yh: i32 = 0;
-    let mut ctr: ControlStruct = ControlStruct {
-        dir: north,
-        button1: false,
-        button2: false,
-    };
+) -> Calibration {
+    // Rust port: Controllers have a standard layout and range, so the mapping is shown instead.
+    if matches!(pcs.joystick[joynum as usize], Some(joystick) if joystick.controller) {
+        expwin(24, 7, gs, pas, pcs);
+        print_str(" Gamepad Configuration\n\r", gs, pcs);
+        print_str(" ---------------------\n\r", gs, pcs);
+        print_str("Stick/pad: move\n\r", gs, pcs);
+        print_str("A: fire    B: strafe\n\r", gs, pcs);
+        print_str("X: bolt    Y: nuke\n\r", gs, pcs);
+        print_str("RB: potion\n\r", gs, pcs);
+        print_str("Press a key:", gs, pcs);
+        return Calibration::Gamepad;
+    }
     expwin(24, 9, gs, pas, pcs);
     print_str(" Joystick Configuration\n\r", gs, pcs);
     print_str(" ----------------------\n\r", gs, pcs);
     print_str("Hold the joystick in the\n\r", gs, pcs);
     print_str("upper left\n\r", gs, pcs);
     print_str("corner and hit fire:", gs, pcs);
-    stage = 15;
-    loop {
-        drawchar(pcs.sx, pcs.sy, stage, gs, pcs);
-        UpdateScreen(gs, pcs);
-        WaitVBL();
-        WaitVBL();
-        WaitVBL();
-        stage += 1;
-        if stage == 23 {
-            stage = 15;
-        }
-        ProcessEvents(pcs, pas, sdl);
-        ReadJoystick(joynum, &mut xl, &mut yl, pcs, sdl);
-        ctr = ControlJoystick(joynum, pcs, sdl);
-        if pcs.keydown[SDL_SCANCODE_ESCAPE as usize] {
-            current_block = 15976468122069307450;
-            break;
-        }
-        if !(ctr.button1 as i32 != 1) {
-            current_block = 8457315219000651999;
-            break;
-        }
+    Calibration::Joystick {
+        joynum,
+        stage: 15,
+        phase: JoyPhase::Upper,
+        low: (0, 0),
     }
-    if current_block == 8457315219000651999 {
-        drawchar(pcs.sx, pcs.sy, ' ' as i32, gs, pcs);
-        loop {
-            ctr = ControlJoystick(joynum, pcs, sdl);
-            if !ctr.button1 {
-                break;
-            }
-        }
-        UpdateScreen(gs, pcs);
-        WaitVBL();
-        WaitVBL();
-        print_str("\n\n\rHold the joystick in the\n\r", gs, pcs);
-        print_str("lower right\n\r", gs, pcs);
-        print_str("corner and hit fire:", gs, pcs);
-        loop {
+}
+
+/// Rust port: A frame of the loops of calibratejoy(); returns None once done.
+fn calibratejoystep(
+    joynum: i32,
+    mut stage: i32,
+    phase: JoyPhase,
+    low: (i32, i32),
+    gs: &mut GlobalState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> Option<Calibration> {
+    let (mut xl, mut yl) = low;
+    let mut xh: i32 = 0;
+    let mut yh: i32 = 0;
+    let mut ctr: ControlStruct = ControlStruct {
+        dir: north,
+        button1: false,
+        button2: false,
+    };
+    let calibration = |stage, phase, low| {
+        Some(Calibration::Joystick {
+            joynum,
+            stage,
+            phase,
+            low,
+        })
+    };
+
+    match phase {
+        JoyPhase::Upper | JoyPhase::Lower => {
             drawchar(pcs.sx, pcs.sy, stage, gs, pcs);
             UpdateScreen(gs, pcs);
-            WaitVBL();
-            WaitVBL();
-            WaitVBL();
+            WaitVBL(pcs);
+            WaitVBL(pcs);
+            WaitVBL(pcs);
             stage += 1;
             if stage == 23 {
                 stage = 15;
             }
-            ProcessEvents(pcs, pas, sdl);
-            ReadJoystick(joynum, &mut xh, &mut yh, pcs, sdl);
-            ctr = ControlJoystick(joynum, pcs, sdl);
+            ProcessEvents(pcs, pas);
+            if let JoyPhase::Upper = phase {
+                ReadJoystick(joynum, &mut xl, &mut yl, pcs);
+            } else {
+                ReadJoystick(joynum, &mut xh, &mut yh, pcs);
+            }
+            ctr = ControlJoystick(joynum, pcs);
             if pcs.keydown[SDL_SCANCODE_ESCAPE as usize] {
-                current_block = 15976468122069307450;
-                break;
+                clearkeys(pcs, pas);
+                erasewindow(gs, pcs);
+                return None;
             }
-            if !(ctr.button1 as i32 != 1) {
-                current_block = 15597372965620363352;
-                break;
+            if ctr.button1 as i32 != 1 {
+                return calibration(stage, phase, (xl, yl));
             }
-        }
-        match current_block {
-            15976468122069307450 => {}
-            _ => {
-                drawchar(pcs.sx, pcs.sy, ' ' as i32, gs, pcs);
-                loop {
-                    ctr = ControlJoystick(joynum, pcs, sdl);
-                    if !ctr.button1 {
-                        break;
-                    }
+            drawchar(pcs.sx, pcs.sy, ' ' as i32, gs, pcs);
+            match phase {
+                JoyPhase::Upper => calibration(stage, JoyPhase::UpperRelease, (xl, yl)),
+                _ => {
+                    // Rust port: The lower right corner is applied here, since it's not kept.
+                    let dx = (xh - xl) / 4;
+                    let dy = (yh - yl) / 4;
+                    pcs.JoyXlow[joynum as usize] = xl + dx;
+                    pcs.JoyXhigh[joynum as usize] = xh - dx;
+                    pcs.JoyYlow[joynum as usize] = yl + dy;
+                    pcs.JoyYhigh[joynum as usize] = yh - dy;
+                    calibration(stage, JoyPhase::LowerRelease, low)
                 }
-                UpdateScreen(gs, pcs);
-                dx = (xh - xl) / 4;
-                dy = (yh - yl) / 4;
-                pcs.JoyXlow[joynum as usize] = xl + dx;
-                pcs.JoyXhigh[joynum as usize] = xh - dx;
-                pcs.JoyYlow[joynum as usize] = yl + dy;
-                pcs.JoyYhigh[joynum as usize] = yh - dy;
             }
         }
+        JoyPhase::UpperRelease | JoyPhase::LowerRelease => {
+            ctr = ControlJoystick(joynum, pcs);
+            if ctr.button1 {
+                // Rust port: Paces the wait, which was a busy loop.
+                WaitVBL(pcs);
+                return calibration(stage, phase, low);
+            }
+            UpdateScreen(gs, pcs);
+            if let JoyPhase::LowerRelease = phase {
+                clearkeys(pcs, pas);
+                erasewindow(gs, pcs);
+                return None;
+            }
+            WaitVBL(pcs);
+            WaitVBL(pcs);
+            print_str("\n\n\rHold the joystick in the\n\r", gs, pcs);
+            print_str("lower right\n\r", gs, pcs);
+            print_str("corner and hit fire:", gs, pcs);
+            calibration(stage, JoyPhase::Lower, low)
+        }
     }
-    clearkeys(pcs, pas, sdl);
-    erasewindow(gs, pcs);
 }
 
 fn calibratemouse(
     gs: &mut GlobalState,
     pas: &mut PcrlibAState,
     pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
-    let mut ch: i8 = 0;
+) -> Calibration {
     expwin(24, 5, gs, pas, pcs);
     print_str("  Mouse Configuration   \n\r", gs, pcs);
     print_str("  -------------------   \n\r", gs, pcs);
     print_str("Choose the sensitivity  \n\r", gs, pcs);
     print_str("of the mouse, 1 being   \n\r", gs, pcs);
     print_str("slow, 9 being fast:", gs, pcs);
-    loop {
-        ch = (get(gs, pcs, pas, sdl) % 256) as i8;
-        if ch as i32 == 27 {
-            ch = '5' as i32 as i8;
-        }
-        if !((ch as i32) < '1' as i32 || ch as i32 > '9' as i32) {
-            break;
-        }
-    }
-    pcs.MouseSensitivity = 15 - (ch as i32 - '0' as i32);
-    erasewindow(gs, pcs);
+    Calibration::Mouse
+}
+
+// Rust port: Added
+fn calibratesynth(
+    gs: &mut GlobalState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> Calibration {
+    let oldvolume = pas.lock(|pasx| pasx.synth.volume);
+    expwin(24, 5, gs, pas, pcs);
+    print_str("  Synth Configuration   \n\r", gs, pcs);
+    print_str("  -------------------   \n\r", gs, pcs);
+    print_str("Choose the volume,      \n\r", gs, pcs);
+    print_str("0 being mute, 9 being   \n\r", gs, pcs);
+    print_str(&format!("loud (now {}):", oldvolume), gs, pcs);
+    Calibration::Synth { oldvolume }
 }
 
 const chartable: [char; 128] = [
@@ -238,14 +283,10 @@ fn calibratekeys(
     gs: &mut GlobalState,
     pas: &mut PcrlibAState,
     pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
-    let mut ch: i8 = 0;
+) -> Calibration {
     let mut hx: i32 = 0;
     let mut hy: i32 = 0;
     let mut i: i32 = 0;
-    let mut select: i32 = 0;
-    let mut new = 0;
     expwin(22, 15, gs, pas, pcs);
     print_str("Keyboard Configuration\n\r", gs, pcs);
     print_str("----------------------", gs, pcs);
@@ -275,51 +316,161 @@ fn calibratekeys(
     pcs.sx = 22;
     pcs.sy = 16;
     printscan(pcs.keyB2 as i32, gs, pcs);
-    loop {
-        pcs.sx = hx;
-        pcs.sy = hy;
-        ch = (get(gs, pcs, pas, sdl) % 256) as i8;
-        if !((ch as i32) < '0' as i32 || ch as i32 > '9' as i32) {
-            select = ch as i32 - '0' as i32;
+    Calibration::Keys {
+        hx,
+        hy,
+        select: None,
+    }
+}
+
+/// Rust port: A frame of the loop of calibratekeys(); returns None once done.
+fn calibratekeysstep(
+    hx: i32,
+    hy: i32,
+    select: Option<i32>,
+    cycle: &mut i32,
+    gs: &mut GlobalState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> Option<Calibration> {
+    let select = match select {
+        None => {
+            pcs.sx = hx;
+            pcs.sy = hy;
+            let ch = match tryget(cycle, gs, pcs, pas) {
+                Some(key) => (key % 256) as i8,
+                None => return Some(Calibration::Keys { hx, hy, select }),
+            };
+            if (ch as i32) < '0' as i32 || ch as i32 > '9' as i32 {
+                erasewindow(gs, pcs);
+                return None;
+            }
             drawchar(pcs.sx, pcs.sy, ch as i32, gs, pcs);
-            select = ch as i32 - '0' as i32;
             print_str("\n\rPress the new key:", gs, pcs);
-            clearkeys(pcs, pas, sdl);
+            clearkeys(pcs, pas);
             UpdateScreen(gs, pcs);
-            loop {
-                new = bioskey(1, pcs, pas, sdl);
-                if !(new == 0) {
-                    break;
-                }
-                WaitVBL();
-            }
-            clearkeys(pcs, pas, sdl);
-            print_str("\r                  ", gs, pcs);
-            if select < 8 {
-                pcs.key[select as usize] = new;
+            return Some(Calibration::Keys {
+                hx,
+                hy,
+                select: Some(ch as i32 - '0' as i32),
+            });
+        }
+        Some(select) => select,
+    };
+
+    let new = bioskey(1, pcs, pas);
+    if new == 0 {
+        WaitVBL(pcs);
+        return Some(Calibration::Keys {
+            hx,
+            hy,
+            select: Some(select),
+        });
+    }
+    clearkeys(pcs, pas);
+    print_str("\r                  ", gs, pcs);
+    if select < 8 {
+        pcs.key[select as usize] = new;
+    }
+    if select == 8 {
+        pcs.keyB1 = new;
+    }
+    if select == 9 {
+        pcs.keyB2 = new;
+    }
+    pcs.sy = select + 7;
+    pcs.sx = 22;
+    print_str("        ", gs, pcs);
+    pcs.sx = 22;
+    printscan(new as i32, gs, pcs);
+    clearkeys(pcs, pas);
+    Some(Calibration::Keys {
+        hx,
+        hy,
+        select: None,
+    })
+}
+
+/// Rust port: Advances the calibration in progress by a frame; once it's done, the panel is redrawn,
+/// as controlpanelkey() did after the calibrate*() calls.
+pub fn calibratestep(
+    cycle: &mut i32,
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) {
+    let calibration = match cps.calibration.take() {
+        Some(calibration) => calibration,
+        None => return,
+    };
+
+    cps.calibration = match calibration {
+        Calibration::Gamepad => match tryget(cycle, gs, pcs, pas) {
+            None => Some(Calibration::Gamepad),
+            Some(_) => {
+                clearkeys(pcs, pas);
+                erasewindow(gs, pcs);
+                None
             }
-            if select == 8 {
-                pcs.keyB1 = new;
+        },
+        Calibration::Joystick {
+            joynum,
+            stage,
+            phase,
+            low,
+        } => calibratejoystep(joynum, stage, phase, low, gs, pas, pcs),
+        Calibration::Mouse => match tryget(cycle, gs, pcs, pas) {
+            None => Some(Calibration::Mouse),
+            Some(key) => {
+                let mut ch = (key % 256) as i8;
+                if ch as i32 == 27 {
+                    ch = '5' as i32 as i8;
+                }
+                if (ch as i32) < '1' as i32 || ch as i32 > '9' as i32 {
+                    Some(Calibration::Mouse)
+                } else {
+                    pcs.MouseSensitivity = 15 - (ch as i32 - '0' as i32);
+                    erasewindow(gs, pcs);
+                    None
+                }
             }
-            if select == 9 {
-                pcs.keyB2 = new;
+        },
+        Calibration::Synth { oldvolume } => match tryget(cycle, gs, pcs, pas) {
+            None => Some(Calibration::Synth { oldvolume }),
+            Some(key) => {
+                let mut ch = (key % 256) as i8;
+                if ch as i32 == 27 {
+                    ch = (b'0' + oldvolume) as i8;
+                }
+                if (ch as i32) < '0' as i32 || ch as i32 > '9' as i32 {
+                    Some(Calibration::Synth { oldvolume })
+                } else {
+                    pas.lock(|pasx| pasx.synth.volume = (ch as u8 - b'0').min(MAX_SYNTH_VOLUME));
+                    erasewindow(gs, pcs);
+                    None
+                }
             }
-            pcs.sy = select + 7;
-            pcs.sx = 22;
-            print_str("        ", gs, pcs);
-            pcs.sx = 22;
-            printscan(new as i32, gs, pcs);
-            ch = '0' as i32 as i8;
-            clearkeys(pcs, pas, sdl);
-        }
-        if !(ch as i32 >= '0' as i32 && ch as i32 <= '9' as i32) {
-            break;
+        },
+        Calibration::Keys { hx, hy, select } => {
+            calibratekeysstep(hx, hy, select, cycle, gs, pas, pcs)
         }
+    };
+
+    if cps.calibration.is_none() {
+        drawpanel(gs, cps, pcs);
+        drawchar(
+            collumnx[cps.collumn as usize] + 1,
+            rowy[cps.row as usize] + 3,
+            15,
+            gs,
+            pcs,
+        );
+        controlpanelcursor(cps, pcs);
     }
-    erasewindow(gs, pcs);
 }
 
-pub fn getconfig(cps: &mut CpanelState, sdl: &SdlManager) {
+pub fn getconfig(cps: &mut CpanelState, pcs: &PcrlibCState) {
     cps.spotok[0][0] = true;
     cps.spotok[0][1] = _egaok;
     cps.spotok[0][2] = _vgaok;
@@ -327,12 +478,12 @@ pub fn getconfig(cps: &mut CpanelState, sdl: &SdlManager) {
     cps.spotok[0][4] = false;
     cps.spotok[1][0] = true;
     cps.spotok[1][1] = true;
-    cps.spotok[1][2] = false;
+    cps.spotok[1][2] = true; // Rust port: synthesizer
     cps.spotok[1][3] = false;
     cps.spotok[1][4] = false;
-    let numjoy = sdl.joystick().num_joysticks().unwrap();
-    cps.joy1ok = numjoy > 0;
-    cps.joy2ok = numjoy > 1;
+    // Rust port: The devices are opened by the frontend.
+    cps.joy1ok = pcs.joystick[1].is_some();
+    cps.joy2ok = pcs.joystick[2].is_some();
     cps.mouseok = true;
     cps.spotok[2][0] = true;
     cps.spotok[2][1] = cps.mouseok;
@@ -341,18 +492,13 @@ pub fn getconfig(cps: &mut CpanelState, sdl: &SdlManager) {
     cps.spotok[2][4] = false;
 }
 
-fn drawpanel(
-    gs: &mut GlobalState,
-    cps: &mut CpanelState,
-    pcs: &mut PcrlibCState,
-    sdl: &SdlManager,
-) {
+fn drawpanel(gs: &mut GlobalState, cps: &mut CpanelState, pcs: &mut PcrlibCState) {
     pcs.leftedge = 1;
     // pas.xormask = 0; // Rust port: Never read
     pcs.sx = 8;
     pcs.sy = 2;
     print_str("       Control Panel      \n\r", gs, pcs);
-    getconfig(cps, sdl);
+    getconfig(cps, pcs);
     pcs.sy = rowy[0] + 2;
     pcs.sx = 2;
     print_str("VIDEO:", gs, pcs);
@@ -367,6 +513,10 @@ fn drawpanel(
     print_str("SOUND:", gs, pcs);
     drawpic(collumnx[0] * 8, rowy[1] * 8, 5, gs, cps, pcs);
     drawpic(collumnx[1] * 8, rowy[1] * 8, 6, gs, cps, pcs);
+    // Rust port: There is no picture for the synthesizer, since it's not in the original.
+    pcs.sy = rowy[1] + 1;
+    pcs.sx = collumnx[2];
+    print_str("SYNTH", gs, pcs);
     pcs.sy = rowy[2] + 2;
     pcs.sx = 2;
     print_str("CONTROL:", gs, pcs);
@@ -407,6 +557,7 @@ fn drawpanel(
         gs,
         pcs,
     );
+    drawaimode(gs, pcs);
     pcs.sy = 21;
     pcs.sx = 1;
     print_str("  Move the cursor with the arrow keys \n\r", gs, pcs);
@@ -414,19 +565,33 @@ fn drawpanel(
     print_str("       ESC to return to your game     \n\r", gs, pcs);
 }
 
-pub fn controlpanel(
+// Rust port: Not in the original; the AI mode is not one of the spots, since there is no room for
+// another row of pictures, so it's toggled with a key.
+fn drawaimode(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
+    let mode = if pcs.enhancedai {
+        "ENHANCED"
+    } else {
+        "CLASSIC "
+    };
+    pcs.sy = rowy[3];
+    pcs.sx = 2;
+    print_str(&format!("MONSTER AI: {}  (A to change)", mode), gs, pcs);
+}
+
+// Rust port: controlpanel() looped on get() until ESC was pressed; it's been split in
+// controlpanelstart(), controlpanelkey() (for each key) and controlpanelend(), so that it can be
+// run one frame at a time (see `Engine`). The calibrations are run across frames as well (see
+// calibratestep()).
+
+pub fn controlpanelstart(
     gs: &mut GlobalState,
     cps: &mut CpanelState,
     pas: &mut PcrlibAState,
     pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
 ) {
-    let mut chf: i32 = 0;
-    let mut oldcenterx: i32 = 0;
-    let mut oldcentery: i32 = 0;
-    clearkeys(pcs, pas, sdl);
+    clearkeys(pcs, pas);
     PauseSound(pas);
-    ProbeJoysticks(pcs, sdl);
+    // Rust port: ProbeJoysticks() is run by the frontend, whenever a device is plugged or unplugged.
     cps.oldgrmode = pcs.grmode;
     cps.newgrmode = cps.oldgrmode;
     cps.oldsoundmode = pas.lock(|pasx| pasx.soundmode);
@@ -435,123 +600,158 @@ pub fn controlpanel(
     cps.newplayermode[1] = cps.oldplayermode[1];
     cps.oldplayermode[2] = pcs.playermode[2];
     cps.newplayermode[2] = cps.oldplayermode[2];
-    oldcenterx = gs.screencenter.x;
-    oldcentery = gs.screencenter.y;
+    cps.oldcenterx = gs.screencenter.x;
+    cps.oldcentery = gs.screencenter.y;
     gs.screencenter.x = 19;
     gs.screencenter.y = 11;
     drawwindow(0, 0, 39, 24, gs, pcs);
-    drawpanel(gs, cps, pcs, sdl);
+    drawpanel(gs, cps, pcs);
     cps.row = 0;
     cps.collumn = pcs.grmode as i32 - 1;
-    loop {
-        pcs.sx = collumnx[cps.collumn as usize] + 2;
-        pcs.sy = rowy[cps.row as usize] + 3;
-        chf = get(gs, pcs, pas, sdl);
-        if chf == SDLK_UP as i32 {
-            cps.row -= 1;
-            if cps.row < 0 {
-                cps.row = 2;
-            }
+    controlpanelcursor(cps, pcs);
+}
+
+/// Places the text cursor (where get() animates) on the current spot.
+pub fn controlpanelcursor(cps: &mut CpanelState, pcs: &mut PcrlibCState) {
+    pcs.sx = collumnx[cps.collumn as usize] + 2;
+    pcs.sy = rowy[cps.row as usize] + 3;
+}
+
+/// Handles a key, as returned by tryget(); returns false when the panel must be closed. If a
+/// calibration is started, the panel is redrawn when it's over (see calibratestep()).
+pub fn controlpanelkey(
+    chf: i32,
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> bool {
+    if chf == SDLK_UP {
+        cps.row -= 1;
+        if cps.row < 0 {
+            cps.row = 2;
         }
-        if chf == SDLK_DOWN as i32 {
-            cps.row += 1;
-            if cps.row > 2 {
-                cps.row = 0;
-            }
+    }
+    if chf == SDLK_DOWN {
+        cps.row += 1;
+        if cps.row > 2 {
+            cps.row = 0;
         }
-        while !cps.spotok[cps.row as usize][cps.collumn as usize] {
-            cps.collumn -= 1;
+    }
+    while !cps.spotok[cps.row as usize][cps.collumn as usize] {
+        cps.collumn -= 1;
+    }
+    if chf == SDLK_LEFT {
+        if cps.collumn == 0 {
+            cps.collumn = 4;
         }
-        if chf == SDLK_LEFT as i32 {
-            if cps.collumn == 0 {
-                cps.collumn = 4;
-            }
-            loop {
-                cps.collumn -= 1;
-                if cps.spotok[cps.row as usize][cps.collumn as usize] {
-                    break;
-                }
+        loop {
+            cps.collumn -= 1;
+            if cps.spotok[cps.row as usize][cps.collumn as usize] {
+                break;
             }
         }
-        if chf == SDLK_RIGHT as i32 {
-            loop {
-                cps.collumn += 1;
-                if cps.spotok[cps.row as usize][cps.collumn as usize] && cps.collumn <= 3 {
-                    break;
-                }
-                if cps.collumn == 4 {
-                    cps.collumn = -1;
-                }
+    }
+    if chf == SDLK_RIGHT {
+        loop {
+            cps.collumn += 1;
+            if cps.spotok[cps.row as usize][cps.collumn as usize] && cps.collumn <= 3 {
+                break;
+            }
+            if cps.collumn == 4 {
+                cps.collumn = -1;
             }
         }
-        if chf == SDLK_RETURN as i32 {
-            match cps.row {
-                0 => {
-                    if !(cps.newgrmode as i32 == cps.collumn + 1) {
-                        drawchar(
-                            collumnx[(cps.newgrmode as i32 - 1) as usize] + 1,
-                            rowy[cps.row as usize] + 3,
-                            32,
-                            gs,
-                            pcs,
-                        );
-                        cps.newgrmode = (cps.collumn + 1).into();
-                        pcs.grmode = cps.newgrmode;
-                        loadgrfiles(gs, cps, pcs);
-                        drawwindow(0, 0, 39, 24, gs, pcs);
-                        drawpanel(gs, cps, pcs, sdl);
-                    }
-                }
-                1 => {
+    }
+    if chf == SDLK_a {
+        pcs.enhancedai = !pcs.enhancedai;
+        drawaimode(gs, pcs);
+    }
+    if chf == SDLK_RETURN {
+        match cps.row {
+            0 => {
+                if !(cps.newgrmode as i32 == cps.collumn + 1) {
                     drawchar(
-                        collumnx[cps.newsoundmode as i32 as usize] + 1,
+                        collumnx[(cps.newgrmode as i32 - 1) as usize] + 1,
                         rowy[cps.row as usize] + 3,
                         32,
                         gs,
                         pcs,
                     );
-                    cps.newsoundmode = cps.collumn.into();
+                    cps.newgrmode = (cps.collumn + 1).into();
+                    pcs.grmode = cps.newgrmode;
+                    loadgrfiles(gs, cps, pcs);
+                    drawwindow(0, 0, 39, 24, gs, pcs);
+                    drawpanel(gs, cps, pcs);
                 }
-                2 => {
-                    drawchar(
-                        collumnx[cps.newplayermode[1] as i32 as usize] + 1,
-                        rowy[cps.row as usize] + 3,
-                        32,
-                        gs,
-                        pcs,
-                    );
-                    cps.newplayermode[1] = cps.collumn.into();
-                    if cps.newplayermode[1] as u32 == keyboard as i32 as u32 {
-                        calibratekeys(gs, pas, pcs, sdl);
-                    } else if cps.newplayermode[1] as u32 == mouse as i32 as u32 {
-                        calibratemouse(gs, pas, pcs, sdl);
-                    } else if cps.newplayermode[1] as u32 == joystick1 as i32 as u32 {
-                        calibratejoy(1, gs, pas, pcs, sdl);
-                    } else if cps.newplayermode[1] as u32 == joystick2 as i32 as u32 {
-                        calibratejoy(2, gs, pas, pcs, sdl);
-                    }
-                    drawpanel(gs, cps, pcs, sdl);
+            }
+            1 => {
+                drawchar(
+                    collumnx[cps.newsoundmode as i32 as usize] + 1,
+                    rowy[cps.row as usize] + 3,
+                    32,
+                    gs,
+                    pcs,
+                );
+                cps.newsoundmode = cps.collumn.into();
+                if cps.newsoundmode == synth {
+                    cps.calibration = Some(calibratesynth(gs, pas, pcs));
+                    return true;
                 }
-                _ => {}
             }
-            drawchar(
-                collumnx[cps.collumn as usize] + 1,
-                rowy[cps.row as usize] + 3,
-                15,
-                gs,
-                pcs,
-            );
-        }
-        if !(chf != SDLK_ESCAPE as i32) {
-            break;
+            2 => {
+                drawchar(
+                    collumnx[cps.newplayermode[1] as i32 as usize] + 1,
+                    rowy[cps.row as usize] + 3,
+                    32,
+                    gs,
+                    pcs,
+                );
+                cps.newplayermode[1] = cps.collumn.into();
+                cps.calibration = if cps.newplayermode[1] as u32 == keyboard as i32 as u32 {
+                    Some(calibratekeys(gs, pas, pcs))
+                } else if cps.newplayermode[1] as u32 == mouse as i32 as u32 {
+                    Some(calibratemouse(gs, pas, pcs))
+                } else if cps.newplayermode[1] as u32 == joystick1 as i32 as u32 {
+                    Some(calibratejoy(1, gs, pas, pcs))
+                } else if cps.newplayermode[1] as u32 == joystick2 as i32 as u32 {
+                    Some(calibratejoy(2, gs, pas, pcs))
+                } else {
+                    None
+                };
+                if cps.calibration.is_some() {
+                    return true;
+                }
+                drawpanel(gs, cps, pcs);
+            }
+            _ => {}
         }
+        drawchar(
+            collumnx[cps.collumn as usize] + 1,
+            rowy[cps.row as usize] + 3,
+            15,
+            gs,
+            pcs,
+        );
     }
+
+    controlpanelcursor(cps, pcs);
+
+    chf != SDLK_ESCAPE
+}
+
+pub fn controlpanelend(
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) {
     pcs.playermode[1] = cps.newplayermode[1];
     pcs.playermode[2] = cps.newplayermode[2];
-    CheckMouseMode(pcs, sdl);
+    CheckMouseMode(pcs);
     pcs.grmode = cps.newgrmode;
-    gs.screencenter.x = oldcenterx;
-    gs.screencenter.y = oldcentery;
+    gs.screencenter.x = cps.oldcenterx;
+    gs.screencenter.y = cps.oldcentery;
     pas.lock(|pasx| pasx.soundmode = cps.newsoundmode);
     repaintscreen(gs, cps, pcs);
     ContinueSound(pas);
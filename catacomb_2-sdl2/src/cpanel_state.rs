@@ -1,4 +1,5 @@
 use crate::{
+    cpanel::Calibration,
     gr_type::grtype::{self, *},
     input_type::inputtype::{self, *},
     pic_type::pictype,
@@ -24,6 +25,11 @@ pub struct CpanelState {
     pub joy1ok: bool,
     pub joy2ok: bool,
     pub mouseok: bool,
+    // Rust port: Locals of controlpanel(), which is now run across frames.
+    pub oldcenterx: i32,
+    pub oldcentery: i32,
+    // Rust port: The calibration in progress (see calibratestep()).
+    pub calibration: Option<Calibration>,
 
     pub egaplane: [u32; 4],
     pub image: spritetype,
@@ -55,6 +61,9 @@ impl CpanelState {
         joy1ok: bool,
         joy2ok: bool,
         mouseok: bool,
+        oldcenterx: i32,
+        oldcentery: i32,
+        calibration: Option<Calibration>,
         egaplane: [u32; 4],
         image: spritetype,
         spritetable: [spritetype; 10],
@@ -77,6 +86,9 @@ impl CpanelState {
             joy1ok,
             joy2ok,
             mouseok,
+            oldcenterx,
+            oldcentery,
+            calibration,
             egaplane,
             image,
             spritetable,
@@ -104,6 +116,9 @@ impl Default for CpanelState {
             false,
             false,
             false,
+            0,
+            0,
+            None,
             [0; 4],
             spritetype {
                 width: 0,
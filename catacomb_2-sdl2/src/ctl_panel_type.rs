@@ -15,4 +15,7 @@ pub struct ctlpaneltype {
     pub key: [u8; 8],
     pub keyB1: u8,
     pub keyB2: u8,
+    // Rust port: Added; files written by the original game end before this (see _loadctrls()).
+    pub SynthVolume: u8,
+    pub EnhancedAI: u8,
 }
@@ -0,0 +1,204 @@
+use crate::{
+    cat_play::{givebolt, givekey, givenuke, givepotion, newobject},
+    catacomb::restore,
+    dir_type::dirtype::*,
+    extra_constants::numlevels,
+    global_state::GlobalState,
+    objects::CLASS_NAMES,
+    pcrlib_a_state::PcrlibAState,
+    pcrlib_c::{centerwindow, drawwindow, print_str, LineInput},
+    pcrlib_c_state::PcrlibCState,
+    scan_codes::*,
+    vec2::Vec2,
+};
+
+// Rust port: Not in the original; a console for testers, opened with F8 while playing in debug
+// builds (see `Screen::DebugConsole`). It's drawn over the side panel, so that the whole view stays
+// visible; the get() cursor, placed on the view, marks where monsters are spawned.
+//
+// The level warp works like the C-T-Space cheat: the level is loaded (through loadlevel()) once the
+// console is closed.
+
+/// Side of the view, in tiles.
+const VIEW_SIZE: i32 = 24;
+/// Rows of the object counts; the classes past these aren't listed.
+const COUNT_ROWS: usize = 7;
+
+pub struct DebugConsole {
+    /// Index in CLASS_NAMES; the player can't be spawned.
+    spawnclass: usize,
+    /// Map coordinates.
+    cursor: Vec2,
+    /// The level number, while it's being entered.
+    warp: Option<LineInput>,
+}
+
+impl DebugConsole {
+    /// The cursor starts right of the player.
+    pub fn new(gs: &GlobalState) -> Self {
+        let mut console = Self {
+            spawnclass: 1,
+            cursor: Vec2::new(gs.o[0].x as i32 + 3, gs.o[0].y as i32),
+            warp: None,
+        };
+        console.movecursor(0, 0, gs);
+        console
+    }
+
+    /// Redraws the view (clearing the previous cursor) and the console, then places the get()
+    /// cursor on the map cursor.
+    pub fn draw(&self, gs: &mut GlobalState, pas: &PcrlibAState, pcs: &mut PcrlibCState) {
+        let counts = objectcounts(gs);
+        let total = counts.iter().map(|(_, count)| count).sum::<usize>();
+
+        restore(gs, pcs);
+        drawwindow(24, 0, 38, 23, gs, pcs);
+        print_str("DEBUG CONSOLE\n", gs, pcs);
+        print_str(&format!("LEVEL {}\n", pcs.level), gs, pcs);
+        print_str(
+            if gs.GODMODE {
+                "GOD MODE ON\n"
+            } else {
+                "GOD MODE OFF\n"
+            },
+            gs,
+            pcs,
+        );
+        print_str(&format!("RNDINDEX {}\n", pas.rndindex), gs, pcs);
+        print_str(&format!("OBJECTS {total}\n"), gs, pcs);
+        for (name, count) in counts.iter().take(COUNT_ROWS) {
+            print_str(&format!("{:<10}{count:>3}\n", name.to_uppercase()), gs, pcs);
+        }
+
+        pcs.sy = 14;
+        print_str("SPAWN:\n", gs, pcs);
+        print_str(
+            &format!(" {}", CLASS_NAMES[self.spawnclass].1.to_uppercase()),
+            gs,
+            pcs,
+        );
+
+        pcs.sy = 17;
+        pcs.sx = pcs.leftedge;
+        print_str("W:WARP G:GOD\n", gs, pcs);
+        print_str("K P B N:GIVE\n", gs, pcs);
+        print_str("-=:CLASS\n", gs, pcs);
+        print_str("SPC:SPAWN\n", gs, pcs);
+        print_str("ARROWS:CURSOR\n", gs, pcs);
+        print_str("ESC:CLOSE", gs, pcs);
+
+        pcs.sx = self.cursor.x - gs.origin.x;
+        pcs.sy = self.cursor.y - gs.origin.y;
+    }
+
+    /// Returns false when the console must be closed; otherwise, it's redrawn, unless the level
+    /// number is being entered.
+    pub fn key(
+        &mut self,
+        key: i32,
+        gs: &mut GlobalState,
+        pas: &mut PcrlibAState,
+        pcs: &mut PcrlibCState,
+    ) -> bool {
+        let spawnclasses = CLASS_NAMES.len() - 1;
+
+        if let Some(mut input) = self.warp.take() {
+            match input.key(key, gs, pcs) {
+                None => self.warp = Some(input),
+                Some(entered) => {
+                    if let Some(level) = inputlevel(&input).filter(|_| entered) {
+                        pcs.level = level;
+                        gs.leveldone = true;
+                        return false;
+                    }
+                    self.draw(gs, pas, pcs);
+                }
+            }
+            return true;
+        }
+
+        match key {
+            SDLK_ESCAPE | SDLK_F8 => return false,
+            SDLK_UP => self.movecursor(0, -1, gs),
+            SDLK_DOWN => self.movecursor(0, 1, gs),
+            SDLK_LEFT => self.movecursor(-1, 0, gs),
+            SDLK_RIGHT => self.movecursor(1, 0, gs),
+            SDLK_EQUALS | SDLK_KP_PLUS => self.spawnclass = self.spawnclass % spawnclasses + 1,
+            SDLK_MINUS | SDLK_KP_MINUS => {
+                self.spawnclass = (self.spawnclass + spawnclasses - 2) % spawnclasses + 1
+            }
+            SDLK_SPACE => self.spawn(gs),
+            SDLK_g => gs.GODMODE = !gs.GODMODE,
+            SDLK_k => givekey(gs, pcs),
+            SDLK_p => givepotion(gs, pcs),
+            SDLK_b => givebolt(gs, pcs),
+            SDLK_n => givenuke(gs, pcs),
+            SDLK_w => {
+                centerwindow(16, 2, gs, pcs);
+                print_str(&format!("warp to which\nlevel (1-{numlevels})?"), gs, pcs);
+                self.warp = Some(LineInput::new(2));
+                return true;
+            }
+            _ => {}
+        }
+
+        self.draw(gs, pas, pcs);
+        true
+    }
+
+    /// The cursor is kept inside the view.
+    fn movecursor(&mut self, dx: i32, dy: i32, gs: &GlobalState) {
+        self.cursor.x = (self.cursor.x + dx).clamp(gs.origin.x, gs.origin.x + VIEW_SIZE - 1);
+        self.cursor.y = (self.cursor.y + dy).clamp(gs.origin.y, gs.origin.y + VIEW_SIZE - 1);
+    }
+
+    /// Adds an object as loadlevel() does; it's activated when the game resumes, since it's in
+    /// view.
+    fn spawn(&self, gs: &mut GlobalState) {
+        let class = CLASS_NAMES[self.spawnclass].0;
+        let i = newobject(gs) as usize;
+
+        gs.o[i].active = false;
+        gs.o[i].class = class;
+        gs.o[i].x = self.cursor.x as u8;
+        gs.o[i].y = self.cursor.y as u8;
+        gs.o[i].stage = 0;
+        gs.o[i].delay = 0;
+        gs.o[i].dir = north as u16;
+        gs.o[i].hp = gs.objdef[class as usize].hitpoints as i8;
+        gs.o[i].oldx = gs.o[i].x;
+        gs.o[i].oldy = gs.o[i].y;
+    }
+}
+
+/// Number of objects of each class present, in CLASS_NAMES order.
+fn objectcounts(gs: &GlobalState) -> Vec<(&'static str, usize)> {
+    CLASS_NAMES
+        .iter()
+        .map(|&(class, name)| {
+            let count = gs.o[..=gs.numobj as usize]
+                .iter()
+                .filter(|obj| obj.class as i32 == class as i32)
+                .count();
+            (name, count)
+        })
+        .filter(|&(_, count)| count > 0)
+        .collect()
+}
+
+/// Parses the level number entered; None if not valid.
+fn inputlevel(input: &LineInput) -> Option<i16> {
+    let digits = input
+        .string()
+        .iter()
+        .take_while(|&&ch| ch != 0)
+        .copied()
+        .collect();
+    let level = String::from_utf8(digits).ok()?.parse().ok()?;
+
+    if (1..=numlevels).contains(&level) {
+        Some(level)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,969 @@
+use std::{mem, path::PathBuf};
+
+use crate::{
+    cat_play::{endlevel, enterlevel, savedemo, startlevel, PlayPrompt},
+    catacomb::{
+        clearold, dofkeys, drawhelppage, initglobals, playsetup, repaintscreen, FKeyPrompt,
+        HELP_PAGES,
+    },
+    catasm::doframe,
+    cpanel::{calibratestep, controlpanelend, controlpanelkey, controlpanelstart},
+    cpanel_state::CpanelState,
+    debug_console::DebugConsole,
+    demo_enum::demoenum::*,
+    extra_constants::{numlevels, NUM_DEMOS},
+    frame_input::{FrameInput, JoystickInput},
+    frame_output::FrameOutput,
+    global_state::GlobalState,
+    high_scores::HighScoreSort,
+    pcrlib_a::{drawpic, rnd, PlaySound, SoundPlaying, WaitVBL},
+    pcrlib_a_state::PcrlibAState,
+    pcrlib_c::{
+        _checkhighscore, _quit, _setupgame, _showhighscorelist, _showhighscores, bioskey,
+        clearkeys, drawwindow, expwin, print_str, tryget, ControlPlayer, HighScoreName, LoadDemo,
+        UpdateScreen, HIGHSCORES_PAGE,
+    },
+    pcrlib_c_state::PcrlibCState,
+    scan_codes::*,
+    state_type::statetype,
+};
+
+// Rust port: The original program was a set of nested loops (main() -> dodemo()/playloop() ->
+// doall(), with help() and controlpanel() opened from inside), each one blocking until its part was
+// over. They've been converted to the screens of a state machine, which advance by one frame at a
+// time, so that the game can be embedded, paused, and driven from tests. The modal dialogs (the
+// F3..F10 prompts, saving/loading games, the cheats, recording demos and entering the high score
+// name) are screens as well (see `Prompt`), and so are the control panel calibrations.
+//
+// The engine doesn't access SDL: `Engine::step()` takes the input collected by the frontend since
+// the previous frame (see `FrameInput`), and returns what the frontend must carry out (see
+// `FrameOutput`), including the VBL waits, which pace the game; the main program just passes them
+// along, in a loop.
+
+const TITLE_FRAMES: i32 = 300;
+const SCORES_FRAMES: i32 = 500;
+const GAMEOVER_FRAMES: i32 = 120;
+const ENDPAGE_FANFARES: i32 = 4;
+
+/// Initial state of the get() cursor animation.
+const CURSOR_CYCLE: i32 = 9;
+
+/// Screens opened by dofkeys(), on top of the current one.
+pub enum Overlay {
+    Help,
+    ControlPanel,
+    HighScores,
+    DebugConsole,
+    Prompt(Prompt),
+}
+
+/// Dialogs waiting for keys, opened by the game code; they're drawn when opened, after the frame.
+pub enum Prompt {
+    FKey(FKeyPrompt),
+    Play(PlayPrompt),
+    HighScore(HighScoreName),
+}
+
+pub enum Screen {
+    /// Opening credits, waiting for a key.
+    Intro {
+        cycle: i32,
+    },
+    /// Title picture; when the time is over, a demo starts.
+    Title {
+        frames: i32,
+    },
+    /// High scores, after a demo.
+    Scores {
+        frames: i32,
+    },
+    /// A game, or a demo.
+    Play {
+        demo: bool,
+        phase: PlayPhase,
+    },
+    /// Shown after the last level, before the game over.
+    EndPage(EndPagePhase),
+    GameOver(GameOverPhase),
+    Help {
+        page: usize,
+        cycle: i32,
+        previous: Box<Screen>,
+    },
+    ControlPanel {
+        cycle: i32,
+        previous: Box<Screen>,
+    },
+    /// High scores viewer; `first` is the first entry shown.
+    HighScores {
+        sort: HighScoreSort,
+        first: usize,
+        cycle: i32,
+        previous: Box<Screen>,
+    },
+    DebugConsole {
+        console: DebugConsole,
+        cycle: i32,
+        previous: Box<Screen>,
+    },
+    Prompt {
+        prompt: Prompt,
+        cycle: i32,
+        previous: Box<Screen>,
+    },
+}
+
+pub enum PlayPhase {
+    /// "Entering level" window, until the sound ends.
+    Entering,
+    Running,
+    /// The level is over; reached once the demo has been saved, when recording.
+    Ending,
+}
+
+pub enum EndPagePhase {
+    /// Ending picture, with the fanfare played a few times.
+    Fanfare {
+        plays: i32,
+    },
+    Congratulations {
+        cycle: i32,
+    },
+    Thanks {
+        cycle: i32,
+    },
+}
+
+pub enum GameOverPhase {
+    /// "Game over" window, until the sound ends.
+    Sound,
+    Delay {
+        frames: i32,
+    },
+    Scores {
+        frames: i32,
+    },
+}
+
+pub struct Engine {
+    gs: GlobalState,
+    cps: CpanelState,
+    pas: PcrlibAState,
+    pcs: PcrlibCState,
+    /// Taken while a frame runs.
+    screen: Option<Screen>,
+}
+
+impl Engine {
+    /// `joysticks` are the devices found by the frontend.
+    pub fn new(levelpack: Option<PathBuf>, joysticks: [Option<JoystickInput>; 3]) -> Self {
+        let mut gs = GlobalState::default();
+        let mut cps = CpanelState::default();
+        let mut pas = PcrlibAState::new();
+
+        //   puts ("CATACOMB II is executing");
+
+        //  _dontplay = 1;	// no sounds for debugging and profiling
+
+        let mut pcs = _setupgame(&mut gs, &mut cps, &mut pas, levelpack, joysticks);
+
+        // Rust port: The frontend quits on the first frame.
+        if let Err(error) = initglobals(&mut gs) {
+            _quit(
+                Some(format!("Invalid object definitions: {}", error)),
+                &mut pas,
+                &mut pcs,
+            );
+        }
+
+        Self::start(gs, cps, pas, pcs)
+    }
+
+    /// Shows the opening credits.
+    fn start(
+        mut gs: GlobalState,
+        cps: CpanelState,
+        mut pas: PcrlibAState,
+        mut pcs: PcrlibCState,
+    ) -> Self {
+        expwin(33, 13, &mut gs, &mut pas, &mut pcs);
+        print_str("  Softdisk Publishing presents\n\n", &mut gs, &mut pcs);
+        print_str("          The Catacomb\n\n", &mut gs, &mut pcs);
+        print_str("        By John Carmack\n\n", &mut gs, &mut pcs);
+        print_str("       Copyright 1990-93\n", &mut gs, &mut pcs);
+        print_str("      Softdisk Publishing", &mut gs, &mut pcs);
+        print_str("\n\n", &mut gs, &mut pcs);
+        print_str("\n\n", &mut gs, &mut pcs);
+        print_str("         Press a key:", &mut gs, &mut pcs);
+
+        Self {
+            gs,
+            cps,
+            pas,
+            pcs,
+            screen: Some(Screen::Intro {
+                cycle: CURSOR_CYCLE,
+            }),
+        }
+    }
+
+    /// The sound state, for the frontend to start the sound device on.
+    pub fn sound(&mut self) -> &mut PcrlibAState {
+        &mut self.pas
+    }
+
+    pub fn screen(&self) -> &Screen {
+        self.screen.as_ref().unwrap()
+    }
+
+    /// Runs a frame of the current screen, with the input collected since the previous one. It
+    /// doesn't block; the frames are paced by the frontend, with the VBL waits in the output.
+    pub fn step(&mut self, input: FrameInput) -> FrameOutput {
+        self.pcs.events.extend(input.events);
+        self.pcs.mouse = input.mouse;
+        self.pcs.joystick = input.joysticks;
+
+        let screen = self.screen.take().unwrap();
+        let mut next = self.stepscreen(screen);
+
+        if let Some(overlay) = self.gs.overlay.take() {
+            let Self {
+                gs, cps, pas, pcs, ..
+            } = self;
+
+            next = openoverlay(overlay, next, gs, cps, pas, pcs);
+        }
+
+        self.screen = Some(next);
+
+        FrameOutput {
+            display: mem::take(&mut self.pcs.display),
+            relative_mouse: self.pcs.relativemouse,
+            rumble: mem::take(&mut self.pcs.rumble),
+            quit: self.pcs.quit,
+        }
+    }
+
+    fn stepscreen(&mut self, screen: Screen) -> Screen {
+        let Self {
+            gs, cps, pas, pcs, ..
+        } = self;
+
+        match screen {
+            Screen::Intro { mut cycle } => match tryget(&mut cycle, gs, pcs, pas) {
+                None => Screen::Intro { cycle },
+                Some(_) => {
+                    clearkeys(pcs, pas);
+
+                    gs.screencenter.x = 11;
+                    gs.screencenter.y = 11;
+
+                    gs.exitdemo = false;
+                    pcs.level = 0;
+
+                    startdemos(gs, cps, pas, pcs)
+                }
+            },
+            Screen::Title { frames } => {
+                WaitVBL(pcs);
+                gs.indemo = notdemo;
+                gs.ctrl = ControlPlayer(1, gs, pcs, pas);
+                if startpressed(gs, pcs) {
+                    pcs.level = 0;
+                    gs.exitdemo = true;
+                    gs.gamestate = statetype::ingame;
+                    return startgame(gs, cps, pas, pcs);
+                }
+                gs.indemo = demoplay;
+                if bioskey(1, pcs, pas) != 0 {
+                    dofkeys(gs, pas, pcs);
+                    UpdateScreen(gs, pcs);
+                }
+                if gs.exitdemo {
+                    gs.gamestate = statetype::ingame;
+                    startgame(gs, cps, pas, pcs)
+                } else if frames + 1 < TITLE_FRAMES {
+                    Screen::Title { frames: frames + 1 }
+                } else {
+                    gs.gamestate = statetype::ingame;
+                    startdemo(gs, cps, pas, pcs)
+                }
+            }
+            Screen::Scores { frames } => {
+                WaitVBL(pcs);
+                gs.indemo = notdemo;
+                gs.ctrl = ControlPlayer(1, gs, pcs, pas);
+                if startpressed(gs, pcs) {
+                    gs.exitdemo = true;
+                    return startgame(gs, cps, pas, pcs);
+                }
+                if bioskey(1, pcs, pas) != 0 {
+                    dofkeys(gs, pas, pcs);
+                }
+                if gs.exitdemo {
+                    startgame(gs, cps, pas, pcs)
+                } else if frames + 1 < SCORES_FRAMES {
+                    Screen::Scores { frames: frames + 1 }
+                } else {
+                    title(gs, cps, pcs)
+                }
+            }
+            Screen::Play {
+                demo,
+                phase: PlayPhase::Entering,
+            } => {
+                if gs.indemo == notdemo && SoundPlaying(pas) {
+                    WaitVBL(pcs);
+                    return Screen::Play {
+                        demo,
+                        phase: PlayPhase::Entering,
+                    };
+                }
+                startlevel(gs, pas, pcs);
+                Screen::Play {
+                    demo,
+                    phase: PlayPhase::Running,
+                }
+            }
+            Screen::Play {
+                demo,
+                phase: PlayPhase::Running,
+            } => {
+                if doframe(gs, pas, pcs) {
+                    return Screen::Play {
+                        demo,
+                        phase: PlayPhase::Running,
+                    };
+                }
+                savedemo(gs, pas, pcs);
+                if gs.overlay.is_some() {
+                    return Screen::Play {
+                        demo,
+                        phase: PlayPhase::Ending,
+                    };
+                }
+                endplay(demo, gs, cps, pas, pcs)
+            }
+            Screen::Play {
+                demo,
+                phase: PlayPhase::Ending,
+            } => endplay(demo, gs, cps, pas, pcs),
+            Screen::EndPage(EndPagePhase::Fanfare { plays }) => {
+                if SoundPlaying(pas) {
+                    WaitVBL(pcs);
+                    return Screen::EndPage(EndPagePhase::Fanfare { plays });
+                }
+                if plays == 0 {
+                    drawpic(0, 0, 15, gs, cps, pcs);
+                }
+                if plays < ENDPAGE_FANFARES {
+                    PlaySound(3, pas);
+                    UpdateScreen(gs, pcs);
+                    return Screen::EndPage(EndPagePhase::Fanfare { plays: plays + 1 });
+                }
+                drawwindow(0, 0, 17, 9, gs, pcs);
+                print_str("Congratulation! \n", gs, pcs);
+                print_str("One as skilled  \n", gs, pcs);
+                print_str("as yourself     \n", gs, pcs);
+                print_str("deserves the    \n", gs, pcs);
+                print_str("10,000,000 gold \n", gs, pcs);
+                print_str("you pulled out  \n", gs, pcs);
+                print_str("of the palace! ", gs, pcs);
+                clearkeys(pcs, pas);
+                Screen::EndPage(EndPagePhase::Congratulations {
+                    cycle: CURSOR_CYCLE,
+                })
+            }
+            Screen::EndPage(EndPagePhase::Congratulations { mut cycle }) => {
+                if tryget(&mut cycle, gs, pcs, pas).is_none() {
+                    return Screen::EndPage(EndPagePhase::Congratulations { cycle });
+                }
+                drawwindow(0, 0, 17, 9, gs, pcs);
+                print_str("Let us know what\n", gs, pcs);
+                print_str("you enjoyed     \n", gs, pcs);
+                print_str("about this game,\n", gs, pcs);
+                print_str("so we can give  \n", gs, pcs);
+                print_str("you more of it. \n", gs, pcs);
+                print_str("Thank you for   \n", gs, pcs);
+                print_str("playing!", gs, pcs);
+                Screen::EndPage(EndPagePhase::Thanks {
+                    cycle: CURSOR_CYCLE,
+                })
+            }
+            Screen::EndPage(EndPagePhase::Thanks { mut cycle }) => {
+                match tryget(&mut cycle, gs, pcs, pas) {
+                    None => Screen::EndPage(EndPagePhase::Thanks { cycle }),
+                    Some(_) => gameover(gs, pas, pcs),
+                }
+            }
+            Screen::GameOver(GameOverPhase::Sound) => {
+                if SoundPlaying(pas) {
+                    WaitVBL(pcs);
+                    Screen::GameOver(GameOverPhase::Sound)
+                } else {
+                    Screen::GameOver(GameOverPhase::Delay { frames: 0 })
+                }
+            }
+            Screen::GameOver(GameOverPhase::Delay { frames }) => {
+                WaitVBL(pcs);
+                if frames + 1 < GAMEOVER_FRAMES {
+                    return Screen::GameOver(GameOverPhase::Delay { frames: frames + 1 });
+                }
+                gs.gamestate = statetype::inscores;
+                _checkhighscore(gs, pas, pcs);
+                pcs.level = 0;
+                Screen::GameOver(GameOverPhase::Scores { frames: 0 })
+            }
+            Screen::GameOver(GameOverPhase::Scores { frames }) => {
+                WaitVBL(pcs);
+                gs.ctrl = ControlPlayer(1, gs, pcs, pas);
+                if startpressed(gs, pcs) {
+                    return startdemos(gs, cps, pas, pcs);
+                }
+                if bioskey(1, pcs, pas) != 0 {
+                    dofkeys(gs, pas, pcs);
+                }
+                if gs.exitdemo || gs.indemo == demoplay || frames + 1 >= SCORES_FRAMES {
+                    startdemos(gs, cps, pas, pcs)
+                } else {
+                    Screen::GameOver(GameOverPhase::Scores { frames: frames + 1 })
+                }
+            }
+            Screen::Help {
+                page,
+                mut cycle,
+                previous,
+            } => match tryget(&mut cycle, gs, pcs, pas) {
+                None => Screen::Help {
+                    page,
+                    cycle,
+                    previous,
+                },
+                Some(key) if key as i8 == 27 || page + 1 == HELP_PAGES => {
+                    closeoverlay(*previous, gs, cps, pas, pcs)
+                }
+                Some(_) => {
+                    drawhelppage(page + 1, gs, pcs);
+                    Screen::Help {
+                        page: page + 1,
+                        cycle: CURSOR_CYCLE,
+                        previous,
+                    }
+                }
+            },
+            Screen::ControlPanel {
+                mut cycle,
+                previous,
+            } if cps.calibration.is_some() => {
+                calibratestep(&mut cycle, gs, cps, pas, pcs);
+                Screen::ControlPanel { cycle, previous }
+            }
+            Screen::ControlPanel {
+                mut cycle,
+                previous,
+            } => match tryget(&mut cycle, gs, pcs, pas) {
+                None => Screen::ControlPanel { cycle, previous },
+                Some(key) => {
+                    if controlpanelkey(key, gs, cps, pas, pcs) {
+                        Screen::ControlPanel {
+                            cycle: CURSOR_CYCLE,
+                            previous,
+                        }
+                    } else {
+                        controlpanelend(gs, cps, pas, pcs);
+                        closeoverlay(*previous, gs, cps, pas, pcs)
+                    }
+                }
+            },
+            Screen::HighScores {
+                mut sort,
+                mut first,
+                mut cycle,
+                previous,
+            } => match tryget(&mut cycle, gs, pcs, pas) {
+                None => Screen::HighScores {
+                    sort,
+                    first,
+                    cycle,
+                    previous,
+                },
+                Some(SDLK_ESCAPE) | Some(SDLK_RETURN) => closeoverlay(*previous, gs, cps, pas, pcs),
+                Some(key) => {
+                    match key {
+                        SDLK_s => (sort, first) = (HighScoreSort::Score, 0),
+                        SDLK_l => (sort, first) = (HighScoreSort::Level, 0),
+                        SDLK_UP => first = first.saturating_sub(HIGHSCORES_PAGE),
+                        SDLK_DOWN if first + HIGHSCORES_PAGE < pcs.highscores.len() => {
+                            first += HIGHSCORES_PAGE
+                        }
+                        _ => {}
+                    }
+                    _showhighscorelist(sort, first, gs, pcs);
+                    Screen::HighScores {
+                        sort,
+                        first,
+                        cycle: CURSOR_CYCLE,
+                        previous,
+                    }
+                }
+            },
+            Screen::DebugConsole {
+                mut console,
+                mut cycle,
+                previous,
+            } => match tryget(&mut cycle, gs, pcs, pas) {
+                None => Screen::DebugConsole {
+                    console,
+                    cycle,
+                    previous,
+                },
+                Some(key) => {
+                    if console.key(key, gs, pas, pcs) {
+                        Screen::DebugConsole {
+                            console,
+                            cycle: CURSOR_CYCLE,
+                            previous,
+                        }
+                    } else {
+                        closeoverlay(*previous, gs, cps, pas, pcs)
+                    }
+                }
+            },
+            // The F-key prompts are closed like the other overlays; the others repaint the screen
+            // themselves.
+            Screen::Prompt {
+                prompt,
+                mut cycle,
+                previous,
+            } => {
+                let prompt = match prompt {
+                    Prompt::FKey(prompt) => match prompt.step(&mut cycle, gs, cps, pas, pcs) {
+                        Some(prompt) => Prompt::FKey(prompt),
+                        None => return closeoverlay(*previous, gs, cps, pas, pcs),
+                    },
+                    Prompt::Play(prompt) => match prompt.step(&mut cycle, gs, pas, pcs) {
+                        Some(prompt) => Prompt::Play(prompt),
+                        None => return *previous,
+                    },
+                    Prompt::HighScore(prompt) => match prompt.step(&mut cycle, gs, pas, pcs) {
+                        Some(prompt) => Prompt::HighScore(prompt),
+                        None => return *previous,
+                    },
+                };
+                Screen::Prompt {
+                    prompt,
+                    cycle,
+                    previous,
+                }
+            }
+        }
+    }
+}
+
+fn startpressed(gs: &GlobalState, pcs: &PcrlibCState) -> bool {
+    gs.ctrl.button1 || gs.ctrl.button2 || pcs.keydown[SDL_SCANCODE_SPACE as usize]
+}
+
+/// Former start of dodemo(): title and demos, unless a game has been requested.
+fn startdemos(
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> Screen {
+    if gs.exitdemo {
+        startgame(gs, cps, pas, pcs)
+    } else {
+        title(gs, cps, pcs)
+    }
+}
+
+fn title(gs: &mut GlobalState, cps: &mut CpanelState, pcs: &mut PcrlibCState) -> Screen {
+    drawpic(0, 0, 14, gs, cps, pcs);
+    UpdateScreen(gs, pcs);
+    gs.gamestate = statetype::intitle;
+    Screen::Title { frames: 0 }
+}
+
+fn startdemo(
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> Screen {
+    let i = rnd(NUM_DEMOS - 1, pas) + 1;
+    LoadDemo(i, gs, pcs);
+    pcs.level = 0;
+    playsetup(gs, cps, pcs);
+    enterlevel(gs, pas, pcs);
+    Screen::Play {
+        demo: true,
+        phase: PlayPhase::Entering,
+    }
+}
+
+fn startgame(
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> Screen {
+    playsetup(gs, cps, pcs);
+    gs.indemo = notdemo;
+    gs.gamestate = statetype::ingame;
+    enterlevel(gs, pas, pcs);
+    Screen::Play {
+        demo: false,
+        phase: PlayPhase::Entering,
+    }
+}
+
+/// The rest of the former playloop(), once a level is over.
+fn endplay(
+    demo: bool,
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> Screen {
+    if !endlevel(gs) {
+        enterlevel(gs, pas, pcs);
+        return Screen::Play {
+            demo,
+            phase: PlayPhase::Entering,
+        };
+    }
+
+    if demo {
+        if gs.exitdemo {
+            startgame(gs, cps, pas, pcs)
+        } else {
+            scores(gs, pcs)
+        }
+    } else if gs.indemo == notdemo {
+        gs.exitdemo = false;
+        if pcs.level > numlevels {
+            // finished all levels
+            Screen::EndPage(EndPagePhase::Fanfare { plays: 0 })
+        } else {
+            gameover(gs, pas, pcs)
+        }
+    } else {
+        startdemos(gs, cps, pas, pcs)
+    }
+}
+
+fn scores(gs: &mut GlobalState, pcs: &mut PcrlibCState) -> Screen {
+    pcs.level = 0;
+    gs.gamestate = statetype::inscores;
+    gs.indemo = demoplay;
+    _showhighscores(gs, pcs);
+    UpdateScreen(gs, pcs);
+    Screen::Scores { frames: 0 }
+}
+
+fn gameover(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) -> Screen {
+    expwin(11, 4, gs, pas, pcs);
+    print_str("\n GAME OVER\n     ", gs, pcs);
+    UpdateScreen(gs, pcs);
+    Screen::GameOver(GameOverPhase::Sound)
+}
+
+fn openoverlay(
+    overlay: Overlay,
+    previous: Screen,
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> Screen {
+    let previous = Box::new(previous);
+
+    match overlay {
+        Overlay::Help => {
+            drawhelppage(0, gs, pcs);
+            Screen::Help {
+                page: 0,
+                cycle: CURSOR_CYCLE,
+                previous,
+            }
+        }
+        Overlay::ControlPanel => {
+            controlpanelstart(gs, cps, pas, pcs);
+            Screen::ControlPanel {
+                cycle: CURSOR_CYCLE,
+                previous,
+            }
+        }
+        Overlay::DebugConsole => {
+            let console = DebugConsole::new(gs);
+            console.draw(gs, pas, pcs);
+            Screen::DebugConsole {
+                console,
+                cycle: CURSOR_CYCLE,
+                previous,
+            }
+        }
+        Overlay::HighScores => {
+            _showhighscorelist(HighScoreSort::Score, 0, gs, pcs);
+            Screen::HighScores {
+                sort: HighScoreSort::Score,
+                first: 0,
+                cycle: CURSOR_CYCLE,
+                previous,
+            }
+        }
+        Overlay::Prompt(prompt) => {
+            let prompt = match prompt {
+                Prompt::FKey(prompt) => Prompt::FKey(prompt.open(gs, pas, pcs)),
+                Prompt::Play(prompt) => Prompt::Play(prompt.open(gs, pcs)),
+                Prompt::HighScore(prompt) => Prompt::HighScore(prompt.open(gs, pas, pcs)),
+            };
+            Screen::Prompt {
+                prompt,
+                cycle: CURSOR_CYCLE,
+                previous,
+            }
+        }
+    }
+}
+
+/// Repaints the screen below the overlay, as dofkeys() does after the other keys.
+fn closeoverlay(
+    previous: Screen,
+    gs: &mut GlobalState,
+    cps: &mut CpanelState,
+    pas: &mut PcrlibAState,
+    pcs: &mut PcrlibCState,
+) -> Screen {
+    clearold(&mut gs.oldtiles);
+    clearkeys(pcs, pas);
+    repaintscreen(gs, cps, pcs);
+    previous
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        catacomb::selectobjdefs,
+        class_type::classtype::goblin,
+        dir_type::dirtype::east,
+        frame_input::InputEvent,
+        frame_output::Display,
+        gr_type::grtype::EGAgr,
+        high_scores::{HighScore, TABLE_ENTRIES},
+        level::{level_filename, Level, FIRST_TOKEN},
+        obj_def_type::objdeftype,
+        objects::{initobjects, OBJDEFS_FILENAME},
+        pcrlib_a::{initrnd, initrndt},
+        save_game::SaveSlot,
+        test_dir::TestDir,
+    };
+
+    /// Engine at the opening credits, with a level pack holding an open level 1, with the player on
+    /// the left, and a goblin far away. Both the pack and the saves are in the test directory, so
+    /// nothing is loaded from, or saved to, the current directory.
+    fn engine(dir: &TestDir) -> Engine {
+        let mut level = Level::new();
+        level.set_player_start(30, 30);
+        level.set_tile(60, 60, FIRST_TOKEN + 2);
+        level
+            .save(&dir.path().join(level_filename(1)))
+            .unwrap_or_else(|err| panic!("{}", err));
+
+        let mut gs = GlobalState {
+            levelpack: Some(dir.path().to_path_buf()),
+            savedir: dir.path().to_path_buf(),
+            pics: vec![0; 1 << 20],
+            ..GlobalState::default()
+        };
+
+        let mut pas = PcrlibAState::new();
+        pas._dontplay = true;
+        initrnd(false, &mut pas);
+        initrndt(false, &mut pas);
+
+        let mut pcs = PcrlibCState::new();
+        pcs.grmode = EGAgr;
+        pcs.picfile_data = vec![0; 1 << 20];
+        pcs.charptr = 0;
+        pcs.picptr = 0;
+        pcs.key[east as usize] = SDL_SCANCODE_RIGHT;
+        pcs.highscores = vec![HighScore::new(100, 1, 0); TABLE_ENTRIES];
+
+        initglobals(&mut gs).unwrap_or_else(|err| panic!("{}", err));
+
+        Engine::start(gs, CpanelState::default(), pas, pcs)
+    }
+
+    fn key(scancode: SDL_Scancode, keycode: SDL_Keycode) -> FrameInput {
+        FrameInput {
+            events: vec![
+                InputEvent::KeyDown { scancode, keycode },
+                InputEvent::KeyUp { scancode },
+            ],
+            ..FrameInput::default()
+        }
+    }
+
+    fn hold(scancode: SDL_Scancode, keycode: SDL_Keycode) -> FrameInput {
+        FrameInput {
+            events: vec![InputEvent::KeyDown { scancode, keycode }],
+            ..FrameInput::default()
+        }
+    }
+
+    /// Starts a game from the title, up to the first frame of level 1, which is autosaved.
+    fn play(engine: &mut Engine) {
+        engine.step(key(SDL_SCANCODE_SPACE, SDLK_SPACE));
+        engine.step(hold(SDL_SCANCODE_SPACE, SDLK_SPACE));
+        assert!(matches!(
+            engine.screen(),
+            Screen::Play {
+                demo: false,
+                phase: PlayPhase::Entering
+            }
+        ));
+
+        assert!(!SaveSlot::Auto.exists(&engine.gs.savedir));
+        engine.step(FrameInput::default());
+        assert!(matches!(
+            engine.screen(),
+            Screen::Play {
+                phase: PlayPhase::Running,
+                ..
+            }
+        ));
+        assert!(engine.gs.indemo == notdemo);
+        assert_eq!(engine.pcs.level, 1);
+        assert_eq!(engine.gs.numobj, 1);
+        assert!(SaveSlot::Auto.exists(&engine.gs.savedir));
+    }
+
+    #[test]
+    fn intro_waits_for_a_key() {
+        let dir = TestDir::new("intro");
+        let mut engine = engine(&dir);
+
+        for _ in 0..3 {
+            let output = engine.step(FrameInput::default());
+
+            assert!(matches!(engine.screen(), Screen::Intro { .. }));
+            assert!(matches!(output.display.last(), Some(Display::WaitVBL)));
+            assert!(output.quit.is_none());
+        }
+
+        engine.step(key(SDL_SCANCODE_SPACE, SDLK_SPACE));
+
+        assert!(matches!(engine.screen(), Screen::Title { frames: 0 }));
+    }
+
+    #[test]
+    fn held_keys_move_the_player() {
+        let dir = TestDir::new("moves");
+        let mut engine = engine(&dir);
+        engine.step(key(SDL_SCANCODE_SPACE, SDLK_SPACE));
+        play(&mut engine);
+
+        let x = engine.gs.o[0].x;
+        let y = engine.gs.o[0].y;
+        engine.step(hold(SDL_SCANCODE_RIGHT, SDLK_RIGHT));
+        for _ in 0..10 {
+            let output = engine.step(FrameInput::default());
+
+            assert!(output
+                .display
+                .iter()
+                .any(|display| matches!(display, Display::Present(_))));
+        }
+
+        assert!(engine.gs.o[0].x > x);
+        assert_eq!(engine.gs.o[0].y, y);
+        assert!(engine.gs.frameon > 0);
+    }
+
+    #[test]
+    fn pause_prompt_stops_the_game_until_a_key() {
+        let dir = TestDir::new("pause");
+        let mut engine = engine(&dir);
+        engine.step(key(SDL_SCANCODE_SPACE, SDLK_SPACE));
+        play(&mut engine);
+        engine.step(FrameInput::default());
+        engine.step(FrameInput {
+            events: vec![InputEvent::KeyUp {
+                scancode: SDL_SCANCODE_SPACE,
+            }],
+            ..FrameInput::default()
+        });
+
+        // dofkeys() reads the F-keys by scancode
+        engine.step(key(SDL_SCANCODE_F9, 0));
+        assert!(matches!(engine.screen(), Screen::Prompt { .. }));
+
+        let frameon = engine.gs.frameon;
+        for _ in 0..5 {
+            let output = engine.step(FrameInput::default());
+
+            assert!(matches!(engine.screen(), Screen::Prompt { .. }));
+            assert!(matches!(output.display.last(), Some(Display::WaitVBL)));
+        }
+        assert_eq!(engine.gs.frameon, frameon);
+
+        engine.step(key(SDL_SCANCODE_SPACE, SDLK_SPACE));
+        engine.step(FrameInput::default());
+
+        assert!(matches!(
+            engine.screen(),
+            Screen::Play {
+                phase: PlayPhase::Running,
+                ..
+            }
+        ));
+        assert!(engine.gs.frameon > frameon);
+    }
+
+    #[test]
+    fn custom_objdefs_are_only_used_in_games() {
+        let dir = TestDir::new("objdefs");
+        fs::write(
+            dir.path().join(OBJDEFS_FILENAME),
+            "[goblin]\nhitpoints = 9\n",
+        )
+        .unwrap();
+        let mut engine = engine(&dir);
+
+        let mut stock = [objdeftype::default(); 23];
+        initobjects(&mut stock);
+        let stock_hitpoints = stock[goblin as usize].hitpoints;
+        assert_ne!(stock_hitpoints, 9);
+        assert_eq!(engine.gs.objdef[goblin as usize].hitpoints, stock_hitpoints);
+
+        engine.step(key(SDL_SCANCODE_SPACE, SDLK_SPACE));
+        play(&mut engine);
+        assert_eq!(engine.gs.objdef[goblin as usize].hitpoints, 9);
+
+        // As loadlevel() does when a demo starts
+        engine.gs.indemo = demoplay;
+        selectobjdefs(&mut engine.gs);
+        assert_eq!(engine.gs.objdef[goblin as usize].hitpoints, stock_hitpoints);
+    }
+
+    #[test]
+    fn invalid_objdefs_are_reported() {
+        let dir = TestDir::new("invalid-objdefs");
+        fs::write(dir.path().join(OBJDEFS_FILENAME), "[goblin]\nsize = 9\n").unwrap();
+
+        let mut gs = GlobalState {
+            levelpack: Some(dir.path().to_path_buf()),
+            ..GlobalState::default()
+        };
+
+        match initglobals(&mut gs) {
+            Ok(()) => panic!("the definitions are valid"),
+            Err(error) => assert!(error.contains("goblin.size"), "{}", error),
+        }
+    }
+}
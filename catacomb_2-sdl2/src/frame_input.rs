@@ -0,0 +1,59 @@
+use crate::scan_codes::SDL_Scancode;
+
+// Rust port: Not in the original; the input collected by the frontend (see `frontend`) since the
+// previous frame, which is passed to `Engine::step()`. The game reads it through the same routines
+// that used to poll SDL (ProcessEvents(), bioskey(), ControlMouse() and ControlJoystick()).
+
+#[derive(Default)]
+pub struct FrameInput {
+    pub events: Vec<InputEvent>,
+    pub mouse: MouseInput,
+    /// Devices currently plugged, indexed like `PcrlibCState::joystick` (0 is unused).
+    pub joysticks: [Option<JoystickInput>; 3],
+}
+
+pub enum InputEvent {
+    /// The keycode (which depends on the keyboard layout) is what get() returns.
+    KeyDown {
+        scancode: SDL_Scancode,
+        keycode: i32,
+    },
+    KeyUp {
+        scancode: SDL_Scancode,
+    },
+    MouseMotion,
+    /// A controller button mapped to a key (see the frontend's ControllerButtonKey()).
+    ControllerButton {
+        joynum: usize,
+        scancode: SDL_Scancode,
+        pressed: bool,
+    },
+    /// A controller or joystick was plugged or unplugged; the devices are reopened, so their
+    /// indexes may change.
+    JoysticksChanged,
+    FocusLost,
+    FocusGained,
+    Quit,
+}
+
+/// Relative motion since the previous frame, and the buttons state.
+#[derive(Clone, Copy, Default)]
+pub struct MouseInput {
+    pub x: i32,
+    pub y: i32,
+    pub left: bool,
+    pub right: bool,
+}
+
+/// State of a joystick or controller; for controllers, the axes are the left stick, and the buttons
+/// are A and B.
+#[derive(Clone, Copy, Default)]
+pub struct JoystickInput {
+    pub controller: bool,
+    pub x: i32,
+    pub y: i32,
+    pub button1: bool,
+    pub button2: bool,
+    /// Controllers only; the d-pad direction, as (-1..=1, -1..=1).
+    pub dpad: (i32, i32),
+}
@@ -0,0 +1,26 @@
+// Rust port: Not in the original; what the frontend (see `frontend`) must carry out after a frame,
+// as returned by `Engine::step()`. The game fills it through the routines that used to act on SDL
+// directly (UpdateScreen(), WaitVBL(), CheckMouseMode(), RumbleJoystick() and _quit()).
+
+pub struct FrameOutput {
+    /// In order of execution.
+    pub display: Vec<Display>,
+    /// Whether the mouse must be grabbed.
+    pub relative_mouse: bool,
+    pub rumble: Vec<Rumble>,
+    /// Set when the program must exit; it's the exit code.
+    pub quit: Option<i32>,
+}
+
+pub enum Display {
+    /// The screen contents, as 320x200 ARGB pixels.
+    Present(Vec<u32>),
+    /// Waits for the (emulated) VBL, which paces the game.
+    WaitVBL,
+}
+
+pub struct Rumble {
+    pub joynum: usize,
+    pub strength: u16,
+    pub duration_ms: u32,
+}
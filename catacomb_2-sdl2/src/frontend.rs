@@ -0,0 +1,536 @@
+use std::{
+    mem,
+    sync::{Condvar, Mutex},
+};
+
+use sdl2::{
+    audio::AudioDevice,
+    controller::{Axis, Button, GameController},
+    event::{Event, WindowEvent},
+    joystick::Joystick,
+    mouse::MouseButton,
+    pixels::PixelFormatEnum,
+    rect::Rect,
+    render::{Texture, TextureAccess, TextureCreator, WindowCanvas},
+    sys::SDL_WindowFlags,
+    timer::Timer,
+    video::WindowContext,
+    TimerSubsystem,
+};
+
+use crate::{
+    frame_input::{FrameInput, InputEvent, JoystickInput, MouseInput},
+    frame_output::{Display, FrameOutput, Rumble},
+    pcrlib_a::{Sound, StartupSound, VBL_TIME},
+    pcrlib_a_state::PcrlibAState,
+    scan_codes::*,
+    sdl_manager::SdlManager,
+};
+
+// Rust port: Not in the original; the SDL side of the program, which was spread across pcrlib_a.rs
+// (VBL emulation and sound device) and pcrlib_c.rs (events, joysticks, video and exit). It collects the
+// input for `Engine::step()`, and carries out its output; the game itself doesn't access SDL.
+
+const VIDEO_PARAM_WINDOWED: &str = "windowed";
+const VIDEO_PARAM_FULLSCREEN: &str = "screen";
+
+// Rust port: Simulation of the SDL Semaphore
+static vblSemMutex: Mutex<u32> = Mutex::new(0);
+static vblSemCondvar: Condvar = Condvar::new();
+
+pub enum joyinfo_t {
+    Controller(GameController),
+    Joy(Joystick),
+}
+
+pub struct Frontend<'t> {
+    sdl: SdlManager,
+    // Rust port: Previously in PcrlibCState.
+    renderer: WindowCanvas,
+    sdltexture: Texture<'t>,
+    updateRect: Rect,
+    joystick: [Option<joyinfo_t>; 3],
+    /// Current SDL mouse mode; see CheckMouseMode().
+    relativemouse: bool,
+    // The following need to stay in scope.
+    _vbl_timer: Timer<'t, 't>,
+    _audio_dev: Option<AudioDevice<Sound>>,
+}
+
+impl<'t> Frontend<'t> {
+    /// Rust port: The video part of _setupgame(); `args` are the program arguments, without the level
+    /// pack parameter.
+    pub fn new(
+        args: &[String],
+        sdl: SdlManager,
+        texture_creator: &'t mut Option<TextureCreator<WindowContext>>,
+        timer_sys: &'t TimerSubsystem,
+    ) -> Self {
+        let mut windowed = false;
+        let mut winWidth = 640;
+        let mut winHeight = 480;
+        let mut displayindex = 0;
+
+        if let Some(screen_mode) = args.get(1) {
+            match screen_mode.as_str() {
+                VIDEO_PARAM_WINDOWED => {
+                    if args.len() == 4 {
+                        winWidth = args[2]
+                            .parse()
+                            .expect(&format!("Invalid width parameter: {}", args[2]));
+                        winHeight = args[3]
+                            .parse()
+                            .expect(&format!("Invalid height parameter: {}", args[3]));
+                    } else {
+                        panic!("Incorrect number of windowed mode parameters");
+                    }
+
+                    windowed = true;
+                }
+                VIDEO_PARAM_FULLSCREEN => {
+                    if args.len() == 3 {
+                        displayindex = args[2]
+                            .parse()
+                            .expect(&format!("Invalid screen parameter: {}", args[2]));
+                    } else {
+                        panic!("Incorrect number of screen mode parameters");
+                    }
+                }
+                _ => panic!("Unexpected screen mode parameter"),
+            }
+        }
+
+        let mut mode = sdl
+            .video()
+            .current_display_mode(displayindex)
+            .expect("Could not get display mode");
+
+        let mut bounds = sdl
+            .video()
+            .display_bounds(displayindex)
+            .expect("Could not get display mode");
+
+        let window_flags = if windowed {
+            // Rust port: the SDL port intentionally chooses SDL_WINDOWPOS_UNDEFINED; this has different
+            // default behavior, depending on the system.
+            bounds.x = sdl2::sys::SDL_WINDOWPOS_UNDEFINED_MASK as i32;
+            bounds.y = sdl2::sys::SDL_WINDOWPOS_UNDEFINED_MASK as i32;
+            mode.w = winWidth as i32;
+            mode.h = winHeight as i32;
+            0
+            // Rust port: WindowBuilder's defaults are position:undefined and flags:0.
+        } else {
+            // Rust port: There's a an explicit API for this, but then we need to separate the conditionals
+            // and initialize the window builder in the middle.
+            SDL_WindowFlags::SDL_WINDOW_FULLSCREEN_DESKTOP as u32
+        };
+
+        let window = sdl
+            .video()
+            .window("The Catacomb", mode.w as u32, mode.h as u32)
+            .set_window_flags(window_flags)
+            .position(bounds.x, bounds.y)
+            .build()
+            .expect("Failed to create SDL window");
+
+        // Rust port: the error message is not exact (copied from the SDL port).
+        // The default flags in the Rust library are 0, like the C SDL port.
+        // The rendering driver index is not set, which is equivalent to the SDL port -1.
+        let renderer = window
+            .into_canvas()
+            .build()
+            .expect("Failed to create SDL window");
+
+        texture_creator.replace(renderer.texture_creator());
+
+        let sdltexture = texture_creator
+            .as_ref()
+            .unwrap()
+            .create_texture(
+                PixelFormatEnum::ARGB8888,
+                TextureAccess::Streaming,
+                320,
+                200,
+            )
+            .expect("Could not create video buffer");
+
+        let mut updateRect = Rect::new(0, 0, 0, 0);
+
+        // Handle 320x200 and 640x400 specially so they are unscaled.
+        if mode.w == 320 && mode.h == 200 || mode.w == 640 && mode.h == 400 {
+            updateRect.w = mode.w;
+            updateRect.h = mode.h;
+            updateRect.y = 0;
+            updateRect.x = updateRect.y;
+        } else {
+            // Pillar box the 4:3 game
+            updateRect.h = mode.h;
+            updateRect.w = mode.h * 4 / 3;
+            updateRect.x = (mode.w - updateRect.w) >> 1;
+            updateRect.y = 0;
+        }
+
+        // Rust port: This needs to stay outside a global state instance, otherwise the lifetime becomes
+        // too restrictive. It doesn't make much sense anyway, to keep it there, since it's not associated
+        // to a specific scope.
+        let _vbl_timer = SetupEmulatedVBL(timer_sys);
+
+        let mut frontend = Self {
+            sdl,
+            renderer,
+            sdltexture,
+            updateRect,
+            // Invalidate joysticks.
+            joystick: [None, None, None],
+            relativemouse: false,
+            _vbl_timer,
+            _audio_dev: None,
+        };
+
+        frontend.ProbeJoysticks();
+
+        frontend
+    }
+
+    /// Rust port: Called once the sound data has been loaded (see _setupgame()).
+    pub fn startsound(&mut self, pas: &mut PcrlibAState) {
+        self._audio_dev = StartupSound(pas, &self.sdl);
+    }
+
+    /// Rust port: The polling part of ProcessEvents()/bioskey(), ControlMouse() and ReadJoystick().
+    pub fn input(&mut self) -> FrameInput {
+        let mut events = vec![];
+
+        let polled_events = self.sdl.event_pump().poll_iter().collect::<Vec<_>>();
+
+        for event in polled_events {
+            match event {
+                Event::KeyDown {
+                    scancode: Some(scancode),
+                    keycode,
+                    ..
+                } => {
+                    events.push(InputEvent::KeyDown {
+                        scancode: scancode as SDL_Scancode,
+                        keycode: keycode.map_or(0, |keycode| keycode as i32),
+                    });
+                }
+                Event::KeyUp {
+                    scancode: Some(scancode),
+                    ..
+                } => {
+                    events.push(InputEvent::KeyUp {
+                        scancode: scancode as SDL_Scancode,
+                    });
+                }
+                Event::MouseMotion { .. } => {
+                    events.push(InputEvent::MouseMotion);
+                }
+                Event::ControllerButtonDown { which, button, .. } => {
+                    events.extend(self.controllerbutton(which, button, true));
+                }
+                Event::ControllerButtonUp { which, button, .. } => {
+                    events.extend(self.controllerbutton(which, button, false));
+                }
+                // Rust port: Hot-plug; the device indexes change on any addition/removal, so all the
+                // devices are reopened.
+                Event::ControllerDeviceAdded { .. }
+                | Event::ControllerDeviceRemoved { .. }
+                | Event::JoyDeviceAdded { .. }
+                | Event::JoyDeviceRemoved { .. } => {
+                    self.ProbeJoysticks();
+                    events.push(InputEvent::JoysticksChanged);
+                }
+                Event::Quit { .. } => {
+                    events.push(InputEvent::Quit);
+                }
+                Event::Window {
+                    win_event: WindowEvent::FocusLost,
+                    ..
+                } => {
+                    events.push(InputEvent::FocusLost);
+                }
+                Event::Window {
+                    win_event: WindowEvent::FocusGained,
+                    ..
+                } => {
+                    // Try to wait until the window obtains mouse focus before
+                    // regrabbing input in order to try to prevent grabbing while
+                    // the user is trying to move the window around.
+                    //
+                    // Rust port: It's not 100% clear how this works, and under which exact cirmustances, since
+                    // it's still possible to move the window, although it's a bit tricky, as the window grabs
+                    // the mouse immediately if it's inside it.
+                    // With or without this workaround, there are no visible differences, at least, when testing
+                    // on X11.
+                    // Pumping the events doesn't directly work in Rust, since the event subsystem is locked
+                    // during events iteration (which is the location when event watchers are invoked by SDL).
+                    // Since adjusting this logic requires some restructuring, and it's not really clear if
+                    // it works as intended, it's kept commented out.
+                    //
+                    /*
+                    while self.sdl.mouse().focused_window_id()
+                        != Some(self.renderer.window().id())
+                    {
+                        // self.sdl.event_pump().pump_events();
+
+                        // Rust port: in the SDL port, this called `SDL_Delay`, however, the Rust sdl2
+                        // crate recommeds to use thread::sleep(). This also simplifies a BCK issue,
+                        // because `Timer#delay()` requires a mutable sdl instance, which is a problem
+                        // when the timer instance is owned by RcSdl.
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    */
+
+                    events.push(InputEvent::FocusGained);
+                }
+                _ => {}
+            }
+        }
+
+        let mouse_state = self.sdl.event_pump().relative_mouse_state();
+        let mut mouse = MouseInput {
+            x: mouse_state.x(),
+            y: mouse_state.y(),
+            ..MouseInput::default()
+        };
+
+        for (button, pressed) in mouse_state.mouse_buttons() {
+            match button {
+                MouseButton::Left => mouse.left = pressed,
+                MouseButton::Right => mouse.right = pressed,
+                _ => {}
+            }
+        }
+
+        FrameInput {
+            events,
+            mouse,
+            joysticks: self.joysticks(),
+        }
+    }
+
+    /// The current state of the open devices.
+    pub fn joysticks(&self) -> [Option<JoystickInput>; 3] {
+        self.sdl.joystick().update();
+
+        let mut joysticks = [None; 3];
+
+        for (joystick, input) in self.joystick.iter().zip(&mut joysticks) {
+            *input = match joystick {
+                Some(joyinfo_t::Controller(controller)) => {
+                    let dpad = |negative: Button, positive: Button| {
+                        controller.button(positive) as i32 - controller.button(negative) as i32
+                    };
+
+                    Some(JoystickInput {
+                        controller: true,
+                        x: controller.axis(Axis::LeftX) as i32,
+                        y: controller.axis(Axis::LeftY) as i32,
+                        button1: controller.button(Button::A),
+                        button2: controller.button(Button::B),
+                        dpad: (
+                            dpad(Button::DPadLeft, Button::DPadRight),
+                            dpad(Button::DPadUp, Button::DPadDown),
+                        ),
+                    })
+                }
+                Some(joyinfo_t::Joy(joystick)) => Some(JoystickInput {
+                    controller: false,
+                    x: joystick.axis(0).unwrap() as i32,
+                    y: joystick.axis(1).unwrap() as i32,
+                    button1: joystick.button(0).unwrap(),
+                    button2: joystick.button(1).unwrap(),
+                    dpad: (0, 0),
+                }),
+                None => None,
+            };
+        }
+
+        joysticks
+    }
+
+    /// Rust port: The SDL part of UpdateScreen(), WaitVBL(), CheckMouseMode(), RumbleJoystick() and
+    /// _quit().
+    pub fn output(&mut self, output: FrameOutput) {
+        if let Some(exit_code) = output.quit {
+            // Rust port: We don't need manual clearing; this will cascade-drop all the systems, since the
+            // Sdl instance is dropped inside the method.
+            self.sdl.quit();
+
+            std::process::exit(exit_code);
+        }
+
+        // Devices without rumble support return an error, which is irrelevant.
+        for Rumble {
+            joynum,
+            strength,
+            duration_ms,
+        } in output.rumble
+        {
+            match &mut self.joystick[joynum] {
+                Some(joyinfo_t::Controller(controller)) => {
+                    let _ = controller.set_rumble(strength, strength, duration_ms);
+                }
+                Some(joyinfo_t::Joy(joystick)) => {
+                    let _ = joystick.set_rumble(strength, strength, duration_ms);
+                }
+                None => {}
+            }
+        }
+
+        if output.relative_mouse != self.relativemouse {
+            self.sdl
+                .mouse()
+                .set_relative_mouse_mode(output.relative_mouse);
+            self.relativemouse = output.relative_mouse;
+        }
+
+        for display in output.display {
+            match display {
+                Display::Present(conv) => self.present(&conv),
+                Display::WaitVBL => WaitVBL(),
+            }
+        }
+    }
+
+    fn present(&mut self, conv: &[u32]) {
+        let pixel_bytes = conv
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        self.sdltexture
+            .update(None, pixel_bytes.as_slice(), 320 * mem::size_of::<u32>())
+            .unwrap();
+        self.renderer.clear();
+        self.renderer
+            .copy(&self.sdltexture, None, Some(self.updateRect))
+            .unwrap();
+        self.renderer.present();
+    }
+
+    // Rust port: Controller buttons that trigger actions which have no ControlStruct button; they're
+    // mapped to the corresponding keys, so that they go through the same path as the keyboard.
+    fn controllerbutton(&self, which: u32, button: Button, pressed: bool) -> Option<InputEvent> {
+        let scancode = match button {
+            Button::X => SDL_SCANCODE_B,
+            Button::Y => SDL_SCANCODE_N,
+            Button::RightShoulder => SDL_SCANCODE_P,
+            _ => return None,
+        };
+
+        let joynum = self.joystick.iter().position(|joystick| {
+            matches!(
+                joystick,
+                Some(joyinfo_t::Controller(controller)) if controller.instance_id() == which
+            )
+        })?;
+
+        Some(InputEvent::ControllerButton {
+            joynum,
+            scancode,
+            pressed,
+        })
+    }
+
+    /*
+    ===============================
+    =
+    = ShutdownJoysticks
+    = Try to identify joysticks and open them.
+    =
+    ===============================
+    */
+
+    fn ShutdownJoysticks(&mut self) {
+        for joystick in &mut self.joystick[1..3] {
+            if joystick.is_some() {
+                // Rust port: Dropping the instance will close it.
+                *joystick = None;
+            }
+        }
+    }
+
+    /*
+    ===============================
+    =
+    = ProbeJoysticks
+    = Try to identify joysticks and open them.
+    =
+    ===============================
+    */
+
+    fn ProbeJoysticks(&mut self) {
+        // Rust port: The conditional is unnecessary, since ShutdownJoystcisk will skip empty slots.
+        if self.joystick[1].is_some() || self.joystick[2].is_some() {
+            self.ShutdownJoysticks();
+        }
+
+        let sdl = &self.sdl;
+
+        for (j, joystick) in self.joystick.iter_mut().enumerate().skip(1) {
+            let j = j as u32;
+
+            if j - 1 >= sdl.joystick().num_joysticks().unwrap() {
+                *joystick = None;
+                continue;
+            }
+
+            // Rust port: Devices recognized by the SDL mappings are opened as controllers; the others
+            // fall back to the legacy (calibrated) joystick path. Opening can fail if the device is
+            // unplugged in the meantime.
+            if sdl.game_controller().is_game_controller(j - 1) {
+                *joystick = sdl
+                    .game_controller()
+                    .open(j - 1)
+                    .ok()
+                    .map(joyinfo_t::Controller);
+            } else {
+                *joystick = sdl.joystick().open(j - 1).ok().map(joyinfo_t::Joy);
+            }
+        }
+    }
+}
+
+fn VBLCallback() -> u32 {
+    let mut guard = vblSemMutex.lock().unwrap();
+
+    *guard += 1;
+    vblSemCondvar.notify_one();
+
+    VBL_TIME
+}
+
+// In the SDL port, this was registered on atexit. Although it's tidy, it's not necessary, since (SQL)
+// quit events (e.g. window closing) are trapped by SDL and handled by the WatchUIEvents.
+// The only case where this can run is probably an unexpected termination. Since it's not strictly
+// necessary anyway, and the cost is to require globals (atexit() doesn't support parameters), it
+// can be safely removed.
+//
+// pub unsafe extern "C" fn ShutdownEmulatedVBL() {
+//     safe_SDL_RemoveTimer(pas.vbltimer);
+//     safe_SDL_DestroySemaphore(pas.vblsem);
+// }
+
+fn SetupEmulatedVBL(timer_sys: &TimerSubsystem) -> Timer<'_, '_> {
+    // Rust port: No need to create the semaphore here
+
+    timer_sys.add_timer(VBL_TIME, Box::new(VBLCallback))
+
+    // Disabled; see comment on ShutdownEmulatedVBL().
+    // safe_register_shutdown_vbl_on_exit();
+}
+
+fn WaitVBL() {
+    let mut guard = vblSemMutex.lock().unwrap();
+
+    loop {
+        if *guard > 0 {
+            *guard -= 1;
+            break;
+        } else {
+            guard = vblSemCondvar.wait(guard).unwrap();
+        }
+    }
+}
@@ -1,9 +1,12 @@
+use std::path::PathBuf;
+
 use crate::{
     active_obj::activeobj,
     class_type::classtype::*,
     control_struct::ControlStruct,
     demo_enum::demoenum,
     dir_type::dirtype::*,
+    engine::Overlay,
     exit_type::exittype::{self, *},
     obj_def_type::objdeftype,
     obj_type::objtype,
@@ -35,6 +38,7 @@ pub struct GlobalState {
     pub objecton: i32,       // ^^
 
     pub objdef: [objdeftype; 23],
+    pub customobjdef: Option<[objdeftype; 23]>, // Rust port: see selectobjdefs()
 
     pub playdone: bool,
     pub leveldone: bool,
@@ -56,6 +60,11 @@ pub struct GlobalState {
 
     pub indemo: demoenum,
 
+    pub levelpack: Option<PathBuf>, /*directory of custom levels*/
+    pub savedir: PathBuf,           // Rust port: directory of the save games (see `save_game`)
+
+    pub overlay: Option<Overlay>, // Rust port: screen requested by dofkeys()
+
     // //////////////////////////////////////////////////////////
     // Rust port: Private to catacomb.rs
     // //////////////////////////////////////////////////////////
@@ -102,6 +111,7 @@ impl GlobalState {
         numobj: i32,
         objecton: i32,
         objdef: [objdeftype; 23],
+        customobjdef: Option<[objdeftype; 23]>,
         playdone: bool,
         leveldone: bool,
         chkx: i32,
@@ -125,6 +135,9 @@ impl GlobalState {
         background: [[i32; 86]; 87],
         origin: Vec2,
         indemo: demoenum,
+        levelpack: Option<PathBuf>,
+        savedir: PathBuf,
+        overlay: Option<Overlay>,
     ) -> Self {
         Self {
             priority,
@@ -139,6 +152,7 @@ impl GlobalState {
             objecton,
             numobj,
             objdef,
+            customobjdef,
             playdone,
             leveldone,
             chkx,
@@ -162,6 +176,9 @@ impl GlobalState {
             background,
             origin,
             indemo,
+            levelpack,
+            savedir,
+            overlay,
         }
     }
 }
@@ -270,6 +287,7 @@ impl Default for GlobalState {
                 points: 0,
                 filler: [0; 2],
             }; 23],
+            None,
             false,
             false,
             0,
@@ -297,6 +315,9 @@ impl Default for GlobalState {
             [[0; 86]; 87],
             Vec2::new(0, 0),
             demoenum::notdemo,
+            None,
+            PathBuf::from("."),
+            None,
         )
     }
 }
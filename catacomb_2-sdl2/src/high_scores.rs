@@ -0,0 +1,410 @@
+use std::{
+    cmp::Reverse,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serdine::Deserialize as DeserializeTrait;
+
+use crate::{extra_constants::_extension, pcrlib_a::VBL_TIME, scores::scores};
+
+// Rust port: The high scores used to be a raw dump of the five table entries in `SCORES.CA2`, in
+// the current directory. They're now a JSON file in the user data directory, which keeps more
+// entries (shown by the high scores viewer), and records when each game was played and for how
+// long. The legacy file is imported the first time, when there is no JSON file yet; it's left
+// untouched afterwards.
+
+pub const HIGHSCORES_FILENAME: &str = "highscores.json";
+
+const PREF_ORG: &str = "Softdisk";
+const PREF_APP: &str = "Catacomb II";
+
+/// Entries in the classic table (see _showhighscores()).
+pub const TABLE_ENTRIES: usize = 5;
+/// Entries kept in the file; ranks are printed with two digits.
+pub const MAX_ENTRIES: usize = 99;
+pub const MAX_NAME_LEN: usize = 8;
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct HighScore {
+    pub score: i32,
+    /// Level reached.
+    pub level: i16,
+    pub name: String,
+    /// Seconds since the Unix epoch; 0 if unknown (imported scores).
+    pub date: u64,
+    /// Seconds of play, excluding pauses; 0 if unknown (imported scores).
+    pub playtime: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HighScoreSort {
+    Score,
+    Level,
+}
+
+pub enum HighScoresError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+}
+
+impl From<io::Error> for HighScoresError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for HighScoresError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl fmt::Display for HighScoresError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Parse(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl HighScore {
+    fn default_entry() -> Self {
+        Self {
+            score: 100,
+            level: 1,
+            name: "PCR".to_string(),
+            date: 0,
+            playtime: 0,
+        }
+    }
+
+    /// Entry for the game just finished; the name is entered afterwards.
+    pub fn new(score: i32, level: i16, playframes: u32) -> Self {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            score,
+            level,
+            name: String::new(),
+            date,
+            playtime: playframes as u64 * VBL_TIME as u64 / 1000,
+        }
+    }
+}
+
+/// The user data directory, if SDL can provide one, otherwise the current directory.
+pub fn highscores_path() -> PathBuf {
+    match sdl2::filesystem::pref_path(PREF_ORG, PREF_APP) {
+        Ok(dir) => PathBuf::from(dir).join(HIGHSCORES_FILENAME),
+        Err(_) => PathBuf::from(HIGHSCORES_FILENAME),
+    }
+}
+
+fn legacy_path() -> PathBuf {
+    PathBuf::from(format!("SCORES.{_extension}"))
+}
+
+/// Loads the high scores, importing the legacy file (or creating the default table) if there are
+/// none yet. The list is sorted by score, and has at least TABLE_ENTRIES entries.
+pub fn loadhighscores() -> Vec<HighScore> {
+    readhighscores(&highscores_path(), &legacy_path())
+}
+
+/// A file that can't be read is reported and otherwise treated as missing, so that a corrupt file
+/// doesn't prevent the game from starting; it's only replaced once a new score is saved.
+fn readhighscores(path: &Path, legacy_path: &Path) -> Vec<HighScore> {
+    let loaded = if path.is_file() {
+        fs::read_to_string(path)
+            .map_err(HighScoresError::from)
+            .and_then(|source| Ok(serde_json::from_str::<Vec<HighScore>>(&source)?))
+            .map_err(|error| {
+                eprintln!(
+                    "Error loading the high scores from {}: {}",
+                    path.display(),
+                    error
+                )
+            })
+            .ok()
+    } else {
+        None
+    };
+
+    let mut entries = match loaded {
+        Some(entries) => entries,
+        None => {
+            let entries = importlegacy(legacy_path).unwrap_or_default();
+
+            // Saved right away, so that the legacy file is imported only once.
+            if !entries.is_empty() && !path.is_file() {
+                if let Err(error) = writehighscores(path, &entries) {
+                    eprintln!("Error saving the high scores: {}", error);
+                }
+            }
+
+            entries
+        }
+    };
+
+    entries.sort_by_key(|entry| Reverse(entry.score));
+    entries.truncate(MAX_ENTRIES);
+
+    while entries.len() < TABLE_ENTRIES {
+        entries.push(HighScore::default_entry());
+    }
+
+    entries
+}
+
+pub fn savehighscores(entries: &[HighScore]) -> Result<(), String> {
+    writehighscores(&highscores_path(), entries)
+}
+
+fn writehighscores(path: &Path, entries: &[HighScore]) -> Result<(), String> {
+    serde_json::to_string_pretty(entries)
+        .map_err(HighScoresError::from)
+        .and_then(|source| Ok(fs::write(path, source)?))
+        .map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// Reads `SCORES.CA2`; None if it doesn't exist or it's incomplete.
+fn importlegacy(path: &Path) -> Option<Vec<HighScore>> {
+    let buffer = fs::read(path).ok()?;
+
+    let entries = buffer
+        .chunks_exact(scores::ondisk_struct_size())
+        .take(TABLE_ENTRIES)
+        .map(|chunk| {
+            let legacy: scores = DeserializeTrait::deserialize(chunk).unwrap();
+            let name = legacy
+                .initials
+                .iter()
+                .take_while(|&&ch| ch != 0)
+                .map(|&ch| ch as char)
+                .collect();
+
+            HighScore {
+                score: legacy.score,
+                level: legacy.level,
+                name,
+                date: 0,
+                playtime: 0,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if entries.len() == TABLE_ENTRIES {
+        Some(entries)
+    } else {
+        None
+    }
+}
+
+/// Inserts the entry below the ones with the same score, as the original did; returns its rank
+/// (0-based), or None if it didn't make it into the list. Games without points aren't recorded.
+pub fn inserthighscore(entries: &mut Vec<HighScore>, entry: HighScore) -> Option<usize> {
+    if entry.score <= 0 {
+        return None;
+    }
+
+    let rank = entries
+        .iter()
+        .position(|other| entry.score > other.score)
+        .unwrap_or(entries.len());
+
+    if rank >= MAX_ENTRIES {
+        return None;
+    }
+
+    entries.insert(rank, entry);
+    entries.truncate(MAX_ENTRIES);
+
+    Some(rank)
+}
+
+/// Entries in viewing order, with their rank by score. Ties keep the score order.
+pub fn sortedhighscores(entries: &[HighScore], sort: HighScoreSort) -> Vec<(usize, &HighScore)> {
+    let mut sorted = entries.iter().enumerate().collect::<Vec<_>>();
+
+    if sort == HighScoreSort::Level {
+        sorted.sort_by_key(|(_, entry)| Reverse(entry.level));
+    }
+
+    sorted
+}
+
+/// "H:MM:SS"; dashes if unknown.
+pub fn format_playtime(playtime: u64) -> String {
+    if playtime == 0 {
+        return "-:--:--".to_string();
+    }
+
+    format!(
+        "{}:{:02}:{:02}",
+        playtime / 3600,
+        playtime % 3600 / 60,
+        playtime % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn entry(score: i32, name: &str) -> HighScore {
+        HighScore {
+            score,
+            level: 1,
+            name: name.to_string(),
+            date: 0,
+            playtime: 0,
+        }
+    }
+
+    fn names(entries: &[HighScore]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    /// `SCORES.CA2` entries: score, level, and NUL-terminated initials.
+    fn legacy_file(entries: &[(i32, i16, &[u8; 3])]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+
+        for (score, level, initials) in entries {
+            buffer.extend(score.to_le_bytes());
+            buffer.extend(level.to_le_bytes());
+            buffer.extend(initials.iter());
+            buffer.push(0);
+        }
+
+        buffer
+    }
+
+    const LEGACY_ENTRIES: [(i32, i16, &[u8; 3]); 5] = [
+        (9000, 7, b"ABC"),
+        (5000, 4, b"DE\0"),
+        (3000, 3, b"F\0\0"),
+        (200, 1, b"GHI"),
+        (100, 1, b"PCR"),
+    ];
+
+    #[test]
+    fn legacy_scores_are_imported() {
+        let dir = TestDir::new("import");
+        let path = dir.join("SCORES.CA2");
+
+        assert!(importlegacy(&path).is_none());
+
+        fs::write(&path, legacy_file(&LEGACY_ENTRIES)).unwrap();
+        let entries = importlegacy(&path).unwrap();
+
+        assert_eq!(names(&entries), ["ABC", "DE", "F", "GHI", "PCR"]);
+        assert_eq!(entries[0].score, 9000);
+        assert_eq!(entries[0].level, 7);
+        assert_eq!((entries[0].date, entries[0].playtime), (0, 0));
+
+        // Incomplete files are ignored.
+        fs::write(&path, legacy_file(&LEGACY_ENTRIES[..4])).unwrap();
+        assert!(importlegacy(&path).is_none());
+    }
+
+    #[test]
+    fn legacy_scores_are_imported_once() {
+        let dir = TestDir::new("import-once");
+        let path = dir.join(HIGHSCORES_FILENAME);
+        let legacy_path = dir.join("SCORES.CA2");
+
+        fs::write(&legacy_path, legacy_file(&LEGACY_ENTRIES)).unwrap();
+        assert_eq!(readhighscores(&path, &legacy_path)[0].name, "ABC");
+        assert!(path.is_file());
+
+        fs::remove_file(&legacy_path).unwrap();
+        assert_eq!(readhighscores(&path, &legacy_path)[0].name, "ABC");
+    }
+
+    #[test]
+    fn corrupt_high_scores_fall_back() {
+        let dir = TestDir::new("corrupt");
+        let path = dir.join(HIGHSCORES_FILENAME);
+        let legacy_path = dir.join("SCORES.CA2");
+
+        fs::write(&path, "[{\"score\": 12").unwrap();
+
+        let entries = readhighscores(&path, &legacy_path);
+        assert_eq!(entries.len(), TABLE_ENTRIES);
+        assert!(entries.iter().all(|entry| entry.name == "PCR"));
+
+        fs::write(&legacy_path, legacy_file(&LEGACY_ENTRIES)).unwrap();
+        assert_eq!(readhighscores(&path, &legacy_path)[0].name, "ABC");
+
+        // The corrupt file is left alone until a score is saved.
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[{\"score\": 12");
+    }
+
+    #[test]
+    fn loaded_high_scores_are_sorted_and_sized() {
+        let dir = TestDir::new("sized");
+        let path = dir.join(HIGHSCORES_FILENAME);
+        let legacy_path = dir.join("SCORES.CA2");
+
+        writehighscores(&path, &[entry(10, "LOW"), entry(30, "HIGH")]).unwrap();
+        let entries = readhighscores(&path, &legacy_path);
+        assert_eq!(names(&entries), ["HIGH", "LOW", "PCR", "PCR", "PCR"]);
+
+        let many = (0..MAX_ENTRIES as i32 + 10)
+            .map(|i| entry(1000 + i, "X"))
+            .collect::<Vec<_>>();
+        writehighscores(&path, &many).unwrap();
+        let entries = readhighscores(&path, &legacy_path);
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].score, 1000 + MAX_ENTRIES as i32 + 9);
+        assert_eq!(entries[MAX_ENTRIES - 1].score, 1010);
+    }
+
+    #[test]
+    fn high_scores_are_inserted_by_score() {
+        let mut entries = vec![entry(300, "A"), entry(200, "B"), entry(100, "C")];
+
+        assert_eq!(inserthighscore(&mut entries, entry(250, "NEW")), Some(1));
+        assert_eq!(inserthighscore(&mut entries, entry(50, "LAST")), Some(4));
+        assert_eq!(inserthighscore(&mut entries, entry(1000, "TOP")), Some(0));
+        assert_eq!(names(&entries), ["TOP", "A", "NEW", "B", "C", "LAST"]);
+
+        // Games without points aren't recorded.
+        assert_eq!(inserthighscore(&mut entries, entry(0, "NONE")), None);
+        assert_eq!(entries.len(), 6);
+    }
+
+    #[test]
+    fn tied_high_scores_go_below_the_older_ones() {
+        let mut entries = vec![entry(300, "A"), entry(200, "B"), entry(100, "C")];
+
+        assert_eq!(inserthighscore(&mut entries, entry(200, "TIE")), Some(2));
+        assert_eq!(inserthighscore(&mut entries, entry(200, "TIE2")), Some(3));
+        assert_eq!(names(&entries), ["A", "B", "TIE", "TIE2", "C"]);
+    }
+
+    #[test]
+    fn high_scores_are_truncated_to_the_table_size() {
+        let mut entries = (0..MAX_ENTRIES as i32)
+            .map(|i| entry(1000 - i, "X"))
+            .collect::<Vec<_>>();
+
+        // Lower than (or tied with) the last entry: not recorded.
+        assert_eq!(inserthighscore(&mut entries, entry(1, "LOW")), None);
+        let last = entries[MAX_ENTRIES - 1].score;
+        assert_eq!(inserthighscore(&mut entries, entry(last, "TIE")), None);
+        assert_eq!(entries.len(), MAX_ENTRIES);
+
+        // Higher: the last entry drops off.
+        assert_eq!(inserthighscore(&mut entries, entry(2000, "TOP")), Some(0));
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[MAX_ENTRIES - 1].score, last + 1);
+    }
+}
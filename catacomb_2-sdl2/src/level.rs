@@ -0,0 +1,263 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    class_type::classtype::{self, *},
+    extra_constants::{_extension, numlevels},
+    rleasm::RLECompress,
+};
+
+// Rust port: The level files (`LEVELn.CA2`) are a 4-byte header (the expanded size), followed by the
+// RLE-compressed 64x64 tile map. Values from `FIRST_TOKEN` onwards are not tiles, but object
+// placeholders (see `tokens`); they're replaced with floor when the level is loaded.
+
+pub const LEVEL_SIZE: usize = 64;
+pub const FIRST_TOKEN: u8 = 230;
+pub const FLOOR_TILE: u8 = 128;
+pub const WALL_TILE: u8 = 129;
+
+pub const tokens: [classtype; 26] = [
+    player, teleporter, goblin, skeleton, ogre, gargoyle, dragon, turbogre, guns, gune, secretgate,
+    nothing, nothing, nothing, nothing, nothing, nothing, nothing, nothing, nothing, nothing,
+    nothing, nothing, nothing, nothing, nothing,
+];
+
+/// loadlevel() loads the file in a 4096 bytes buffer, and RLEExpand() reads slightly past the end of
+/// the data, so saved files must leave some margin.
+const MAX_FILE_SIZE: usize = 4096 - 4 - 130;
+
+pub struct Level {
+    pub tiles: Vec<u8>,
+}
+
+pub enum LevelError {
+    Io(io::Error),
+    Truncated,
+    TooLarge(usize),
+    PlayerStarts(usize),
+    UnknownToken { x: usize, y: usize, tile: u8 },
+}
+
+impl From<io::Error> for LevelError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Truncated => write!(f, "the level data is truncated"),
+            Self::TooLarge(size) => write!(
+                f,
+                "the compressed level is {size} bytes, while the maximum is {MAX_FILE_SIZE}"
+            ),
+            Self::PlayerStarts(count) => write!(
+                f,
+                "the level must have exactly one player start, but it has {count}"
+            ),
+            Self::UnknownToken { x, y, tile } => {
+                write!(f, "unknown object token {tile} at ({x}, {y})")
+            }
+        }
+    }
+}
+
+impl Level {
+    /// An empty room, surrounded by walls, with the player start in the upper left corner (the same
+    /// location that loadlevel() defaults to).
+    pub fn new() -> Self {
+        let mut tiles = vec![FLOOR_TILE; LEVEL_SIZE * LEVEL_SIZE];
+
+        for i in 0..LEVEL_SIZE {
+            tiles[i] = WALL_TILE;
+            tiles[(LEVEL_SIZE - 1) * LEVEL_SIZE + i] = WALL_TILE;
+            tiles[i * LEVEL_SIZE] = WALL_TILE;
+            tiles[i * LEVEL_SIZE + LEVEL_SIZE - 1] = WALL_TILE;
+        }
+
+        let mut level = Self { tiles };
+        level.set_tile(2, 2, FIRST_TOKEN);
+        level
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LevelError> {
+        let data = fs::read(path)?;
+
+        if data.len() > MAX_FILE_SIZE {
+            return Err(LevelError::TooLarge(data.len()));
+        }
+
+        Self::decode(&data)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), LevelError> {
+        self.validate()?;
+
+        let data = self.encode();
+
+        if data.len() > MAX_FILE_SIZE {
+            return Err(LevelError::TooLarge(data.len()));
+        }
+
+        fs::write(path, data)?;
+
+        Ok(())
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> u8 {
+        self.tiles[y * LEVEL_SIZE + x]
+    }
+
+    pub fn set_tile(&mut self, x: usize, y: usize, tile: u8) {
+        self.tiles[y * LEVEL_SIZE + x] = tile;
+    }
+
+    /// Returns the class of the object placed at the given location, if any.
+    pub fn token(&self, x: usize, y: usize) -> Option<classtype> {
+        token_class(self.tile(x, y))
+    }
+
+    /// Places the player start, removing the existing one(s), since there can be only one.
+    pub fn set_player_start(&mut self, x: usize, y: usize) {
+        for tile in self.tiles.iter_mut() {
+            if *tile == FIRST_TOKEN {
+                *tile = FLOOR_TILE;
+            }
+        }
+        self.set_tile(x, y, FIRST_TOKEN);
+    }
+
+    pub fn validate(&self) -> Result<(), LevelError> {
+        let player_starts = self.tiles.iter().filter(|&&t| t == FIRST_TOKEN).count();
+
+        if player_starts != 1 {
+            return Err(LevelError::PlayerStarts(player_starts));
+        }
+
+        for (i, &tile) in self.tiles.iter().enumerate() {
+            if tile >= FIRST_TOKEN && token_class(tile).is_none() {
+                return Err(LevelError::UnknownToken {
+                    x: i % LEVEL_SIZE,
+                    y: i / LEVEL_SIZE,
+                    tile,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = (self.tiles.len() as u32).to_le_bytes().to_vec();
+        RLECompress(&self.tiles, &mut data);
+        data
+    }
+
+    /// Same as RLEExpand(), but stops at the map size, and reports truncated data instead of
+    /// panicking.
+    fn decode(data: &[u8]) -> Result<Self, LevelError> {
+        let source = data.get(4..).ok_or(LevelError::Truncated)?;
+        let map_size = LEVEL_SIZE * LEVEL_SIZE;
+        let mut tiles = Vec::with_capacity(map_size + 130);
+        let mut source_i = 0;
+
+        while tiles.len() < map_size {
+            let val = *source.get(source_i).ok_or(LevelError::Truncated)?;
+            source_i += 1;
+
+            if val & 0x80 != 0 {
+                let len = ((val & 0x7f) + 1) as usize;
+                let block = source
+                    .get(source_i..source_i + len)
+                    .ok_or(LevelError::Truncated)?;
+                tiles.extend(block);
+                source_i += len;
+            } else {
+                let len = (val + 3) as usize;
+                let byte = *source.get(source_i).ok_or(LevelError::Truncated)?;
+                tiles.resize(tiles.len() + len, byte);
+                source_i += 1;
+            }
+        }
+
+        tiles.truncate(map_size);
+
+        Ok(Self { tiles })
+    }
+}
+
+pub fn token_class(tile: u8) -> Option<classtype> {
+    if tile < FIRST_TOKEN {
+        return None;
+    }
+
+    match tokens[(tile - FIRST_TOKEN) as usize] {
+        nothing => None,
+        class => Some(class),
+    }
+}
+
+pub fn level_filename(level: i16) -> String {
+    format!("LEVEL{level}.{_extension}")
+}
+
+/// Levels missing from the pack are taken from the current directory, like the stock ones.
+pub fn level_path(level: i16, levelpack: Option<&Path>) -> PathBuf {
+    let filename = level_filename(level);
+
+    match levelpack {
+        Some(levelpack) if levelpack.join(&filename).is_file() => levelpack.join(filename),
+        _ => PathBuf::from(filename),
+    }
+}
+
+pub const LEVEL_PACK_PARAM: &str = "--level-pack";
+
+/// Removes the level pack parameter (and its value) from the arguments, so that the positional
+/// (screen mode) parameters are unaffected.
+pub fn take_level_pack_arg(args: &mut Vec<String>) -> Result<Option<PathBuf>, String> {
+    let position = match args.iter().position(|arg| arg == LEVEL_PACK_PARAM) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    if position + 1 >= args.len() {
+        return Err(format!("Missing {} directory", LEVEL_PACK_PARAM));
+    }
+
+    let levelpack = PathBuf::from(args.remove(position + 1));
+    args.remove(position);
+
+    Ok(Some(levelpack))
+}
+
+/// Checks all the levels in the pack upfront, so that a broken level doesn't crash the game when
+/// reached.
+pub fn validate_level_pack(levelpack: &Path) -> Result<(), String> {
+    if !levelpack.is_dir() {
+        return Err(format!("{} is not a directory", levelpack.display()));
+    }
+
+    let mut found = false;
+
+    for level in 1..=numlevels {
+        let path = levelpack.join(level_filename(level));
+
+        if path.is_file() {
+            Level::load(&path)
+                .and_then(|level| level.validate())
+                .map_err(|error| format!("{}: {}", path.display(), error))?;
+            found = true;
+        }
+    }
+
+    if found {
+        Ok(())
+    } else {
+        Err(format!("{} contains no levels", levelpack.display()))
+    }
+}
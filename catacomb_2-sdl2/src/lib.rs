@@ -0,0 +1,72 @@
+#![allow(
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    unused_assignments,              // there are a lot, and can't be automatically fixed
+    clippy::assign_op_pattern,
+    clippy::collapsible_else_if,
+    clippy::collapsible_if,
+    clippy::comparison_chain,
+    clippy::derive_partial_eq_without_eq,
+    clippy::expect_fun_call,         // meh
+    clippy::identity_op,             // actually more readable
+    clippy::int_plus_one,
+    clippy::len_zero,
+    clippy::manual_range_contains,
+    clippy::missing_safety_doc,
+    clippy::new_without_default,
+    clippy::nonminimal_bool,         // actually more readable
+    clippy::too_many_arguments,
+    clippy::type_complexity,
+    clippy::wildcard_in_or_patterns, // actually more readable
+)]
+
+mod active_obj;
+mod ai;
+mod cat_play;
+pub mod catacomb;
+mod catasm;
+pub mod class_type;
+mod control_struct;
+mod cpanel;
+mod cpanel_state;
+mod ctl_panel_type;
+mod debug_console;
+mod demo_enum;
+mod dir_type;
+pub mod engine;
+mod exit_type;
+mod extra_constants;
+pub mod frame_input;
+pub mod frame_output;
+mod frontend;
+mod global_state;
+mod gr_type;
+mod high_scores;
+mod input_type;
+pub mod level;
+pub mod obj_def_type;
+mod obj_type;
+pub mod objects;
+pub mod pcrlib_a;
+mod pcrlib_a_state;
+pub mod pcrlib_c;
+mod pcrlib_c_state;
+mod pic_file_type;
+mod pic_type;
+mod rleasm;
+mod save_game;
+mod scan_codes;
+mod scores;
+mod sdl_manager;
+mod sound_type;
+mod spkr_table;
+mod spksndtype;
+mod sprite_type;
+mod state_type;
+mod synth;
+mod tag_type;
+#[cfg(test)]
+mod test_dir;
+mod think_type;
+mod vec2;
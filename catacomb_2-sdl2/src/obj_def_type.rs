@@ -1,4 +1,4 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub struct objdeftype {
     pub think: u8,
     pub contact: u8,
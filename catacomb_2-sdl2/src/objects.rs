@@ -1,227 +1,329 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
 use crate::{
-    class_type::classtype::*, obj_def_type::objdeftype, tag_type::tagtype::*,
-    think_type::thinktype::*,
+    class_type::classtype::{self, *},
+    extra_constants::maxpics,
+    obj_def_type::objdeftype,
+    tag_type::tagtype,
+    think_type::thinktype,
 };
 
+// Rust port: The object definitions were hardcoded in initobjects(); they're now described in a TOML
+// file, with one table per class. The stock definitions (`objdefs.toml`, which has the original
+// values) are built in; a file with the same format, placed in the level pack or in the game
+// directory, overrides them, and may list only the classes/fields that it changes.
+
+pub const OBJDEFS_FILENAME: &str = "OBJDEFS.TOML";
+
+const STOCK_OBJDEFS: &str = include_str!("../objdefs.toml");
+
+/// Classes that can be defined, with their names in the file.
+pub const CLASS_NAMES: [(classtype, &str); 20] = [
+    (player, "player"),
+    (goblin, "goblin"),
+    (skeleton, "skeleton"),
+    (ogre, "ogre"),
+    (gargoyle, "gargoyle"),
+    (dragon, "dragon"),
+    (turbogre, "turbogre"),
+    (wallhit, "wallhit"),
+    (dead1, "dead1"),
+    (dead2, "dead2"),
+    (dead3, "dead3"),
+    (dead4, "dead4"),
+    (dead5, "dead5"),
+    (shot, "shot"),
+    (guns, "guns"),
+    (gune, "gune"),
+    (rock, "rock"),
+    (bigshot, "bigshot"),
+    (teleporter, "teleporter"),
+    (secretgate, "secretgate"),
+];
+
+/// Dying objects are replaced with the `deadN` class of the same size (see dieobj()).
+const MAX_SIZE: u8 = 5;
+const MAX_DIRMASK: u8 = 3;
+
+/// All the fields are optional, so that a file can change only some of them.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjDefEntry {
+    think: Option<thinktype>,
+    contact: Option<tagtype>,
+    solid: Option<bool>,
+    firstchar: Option<u16>,
+    size: Option<u8>,
+    stages: Option<u8>,
+    dirmask: Option<u8>,
+    speed: Option<u16>,
+    hitpoints: Option<u8>,
+    damage: Option<u8>,
+    points: Option<u16>,
+}
+
+pub enum ObjDefError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    UnknownClass(String),
+    InvalidField {
+        class: &'static str,
+        field: &'static str,
+        message: String,
+    },
+}
+
+impl From<io::Error> for ObjDefError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for ObjDefError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl fmt::Display for ObjDefError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Parse(error) => write!(f, "{error}"),
+            Self::UnknownClass(name) => write!(f, "unknown object class `{name}`"),
+            Self::InvalidField {
+                class,
+                field,
+                message,
+            } => write!(f, "{class}.{field}: {message}"),
+        }
+    }
+}
+
 pub fn initobjects(objdef: &mut [objdeftype]) {
-    objdef[player as usize].think = playercmd as u8;
-    objdef[player as usize].contact = benign as u8;
-    objdef[player as usize].solid = true as u8;
-    objdef[player as usize].firstchar = 256;
-    objdef[player as usize].size = 2;
-    objdef[player as usize].stages = 4;
-    objdef[player as usize].dirmask = 3;
-    objdef[player as usize].speed = 256;
-    objdef[player as usize].hitpoints = 12;
-    objdef[player as usize].damage = 0;
-    objdef[player as usize].points = 0;
-    objdef[goblin as usize].think = ramstraight as u8;
-    objdef[goblin as usize].contact = monster as u8;
-    objdef[goblin as usize].solid = true as u8;
-    objdef[goblin as usize].firstchar = 256 + 64;
-    objdef[goblin as usize].size = 2;
-    objdef[goblin as usize].stages = 4;
-    objdef[goblin as usize].dirmask = 3;
-    objdef[goblin as usize].speed = 75;
-    objdef[goblin as usize].hitpoints = 1;
-    objdef[goblin as usize].damage = 1;
-    objdef[goblin as usize].points = 50;
-    objdef[skeleton as usize].think = ramdiag as u8;
-    objdef[skeleton as usize].contact = monster as u8;
-    objdef[skeleton as usize].solid = true as u8;
-    objdef[skeleton as usize].firstchar = 256 + 128;
-    objdef[skeleton as usize].size = 2;
-    objdef[skeleton as usize].stages = 4;
-    objdef[skeleton as usize].dirmask = 3;
-    objdef[skeleton as usize].speed = 130;
-    objdef[skeleton as usize].hitpoints = 1;
-    objdef[skeleton as usize].damage = 1;
-    objdef[skeleton as usize].points = 150;
-    objdef[ogre as usize].think = ramstraight as u8;
-    objdef[ogre as usize].contact = monster as u8;
-    objdef[ogre as usize].solid = true as u8;
-    objdef[ogre as usize].firstchar = 256 + 67 * 4;
-    objdef[ogre as usize].size = 3;
-    objdef[ogre as usize].stages = 4;
-    objdef[ogre as usize].dirmask = 3;
-    objdef[ogre as usize].speed = 75;
-    objdef[ogre as usize].hitpoints = 5;
-    objdef[ogre as usize].damage = 2;
-    objdef[ogre as usize].points = 250;
-    objdef[gargoyle as usize].think = gargcmd as u8;
-    objdef[gargoyle as usize].contact = monster as u8;
-    objdef[gargoyle as usize].solid = true as u8;
-    objdef[gargoyle as usize].firstchar = 256 + 67 * 4 + 35 * 9;
-    objdef[gargoyle as usize].size = 4;
-    objdef[gargoyle as usize].stages = 4;
-    objdef[gargoyle as usize].dirmask = 3;
-    objdef[gargoyle as usize].speed = 175;
-    objdef[gargoyle as usize].hitpoints = 10;
-    objdef[gargoyle as usize].damage = 3;
-    objdef[gargoyle as usize].points = 500;
-    objdef[dragon as usize].think = dragoncmd as u8;
-    objdef[dragon as usize].contact = monster as u8;
-    objdef[dragon as usize].solid = true as u8;
-    objdef[dragon as usize].firstchar = 256 + 67 * 4 + 35 * 9 + 19 * 16;
-    objdef[dragon as usize].size = 5;
-    objdef[dragon as usize].stages = 4;
-    objdef[dragon as usize].dirmask = 3;
-    objdef[dragon as usize].speed = 100;
-    objdef[dragon as usize].hitpoints = 75;
-    objdef[dragon as usize].damage = 5;
-    objdef[dragon as usize].points = 1000;
-    objdef[turbogre as usize].think = ramstraight as u8;
-    objdef[turbogre as usize].contact = monster as u8;
-    objdef[turbogre as usize].solid = true as u8;
-    objdef[turbogre as usize].firstchar = 256 + 67 * 4 + 19 * 9;
-    objdef[turbogre as usize].size = 3;
-    objdef[turbogre as usize].stages = 4;
-    objdef[turbogre as usize].dirmask = 3;
-    objdef[turbogre as usize].speed = 255;
-    objdef[turbogre as usize].hitpoints = 5;
-    objdef[turbogre as usize].damage = 2;
-    objdef[turbogre as usize].points = 500;
-    objdef[wallhit as usize].think = fade as u8;
-    objdef[wallhit as usize].contact = benign as u8;
-    objdef[wallhit as usize].solid = true as u8;
-    objdef[wallhit as usize].firstchar = 26;
-    objdef[wallhit as usize].size = 1;
-    objdef[wallhit as usize].stages = 3;
-    objdef[wallhit as usize].dirmask = 0;
-    objdef[wallhit as usize].speed = 80;
-    objdef[wallhit as usize].hitpoints = 0;
-    objdef[wallhit as usize].damage = 0;
-    objdef[wallhit as usize].points = 0;
-    objdef[dead1 as usize].think = explode as u8;
-    objdef[dead1 as usize].contact = benign as u8;
-    objdef[dead1 as usize].solid = false as u8;
-    objdef[dead1 as usize].firstchar = 29;
-    objdef[dead1 as usize].size = 1;
-    objdef[dead1 as usize].stages = 3;
-    objdef[dead1 as usize].dirmask = 0;
-    objdef[dead1 as usize].speed = 80;
-    objdef[dead1 as usize].hitpoints = 0;
-    objdef[dead1 as usize].damage = 0;
-    objdef[dead1 as usize].points = 0;
-    objdef[dead2 as usize].think = fade as u8;
-    objdef[dead2 as usize].contact = benign as u8;
-    objdef[dead2 as usize].solid = false as u8;
-    objdef[dead2 as usize].firstchar = 256 + 224;
-    objdef[dead2 as usize].size = 2;
-    objdef[dead2 as usize].stages = 3;
-    objdef[dead2 as usize].dirmask = 0;
-    objdef[dead2 as usize].speed = 80;
-    objdef[dead2 as usize].hitpoints = 0;
-    objdef[dead2 as usize].damage = 0;
-    objdef[dead2 as usize].points = 0;
-    objdef[dead3 as usize].think = fade as u8;
-    objdef[dead3 as usize].contact = benign as u8;
-    objdef[dead3 as usize].solid = false as u8;
-    objdef[dead3 as usize].firstchar = 256 + 67 * 4 + 9 * 16;
-    objdef[dead3 as usize].size = 3;
-    objdef[dead3 as usize].stages = 3;
-    objdef[dead3 as usize].dirmask = 0;
-    objdef[dead3 as usize].speed = 80;
-    objdef[dead3 as usize].hitpoints = 0;
-    objdef[dead3 as usize].damage = 0;
-    objdef[dead3 as usize].points = 0;
-    objdef[dead4 as usize].think = fade as u8;
-    objdef[dead4 as usize].contact = benign as u8;
-    objdef[dead4 as usize].solid = false as u8;
-    objdef[dead4 as usize].firstchar = 256 + 67 * 4 + 35 * 9 + 16 * 16;
-    objdef[dead4 as usize].size = 4;
-    objdef[dead4 as usize].stages = 3;
-    objdef[dead4 as usize].dirmask = 0;
-    objdef[dead4 as usize].speed = 80;
-    objdef[dead4 as usize].hitpoints = 0;
-    objdef[dead4 as usize].damage = 0;
-    objdef[dead4 as usize].points = 0;
-    objdef[dead5 as usize].think = fade as u8;
-    objdef[dead5 as usize].contact = benign as u8;
-    objdef[dead5 as usize].solid = false as u8;
-    objdef[dead5 as usize].firstchar = 256 + 67 * 4 + 35 * 9 + 19 * 16 + 25 * 16;
-    objdef[dead5 as usize].size = 5;
-    objdef[dead5 as usize].stages = 3;
-    objdef[dead5 as usize].dirmask = 0;
-    objdef[dead5 as usize].speed = 80;
-    objdef[dead5 as usize].hitpoints = 0;
-    objdef[dead5 as usize].damage = 0;
-    objdef[dead5 as usize].points = 0;
-    objdef[shot as usize].think = straight as u8;
-    objdef[shot as usize].contact = pshot as u8;
-    objdef[shot as usize].solid = false as u8;
-    objdef[shot as usize].firstchar = 154;
-    objdef[shot as usize].size = 1;
-    objdef[shot as usize].stages = 2;
-    objdef[shot as usize].dirmask = 3;
-    objdef[shot as usize].speed = 256;
-    objdef[shot as usize].hitpoints = 0;
-    objdef[shot as usize].damage = 1;
-    objdef[shot as usize].points = 0;
-    objdef[guns as usize].think = gunthinks as u8;
-    objdef[guns as usize].contact = benign as u8;
-    objdef[guns as usize].solid = true as u8;
-    objdef[guns as usize].firstchar = 256 + 67 * 4 - 8;
-    objdef[guns as usize].size = 2;
-    objdef[guns as usize].stages = 1;
-    objdef[guns as usize].dirmask = 0;
-    objdef[guns as usize].speed = 10;
-    objdef[guns as usize].hitpoints = 255;
-    objdef[guns as usize].damage = 0;
-    objdef[guns as usize].points = 0;
-    objdef[gune as usize].think = gunthinke as u8;
-    objdef[gune as usize].contact = benign as u8;
-    objdef[gune as usize].solid = true as u8;
-    objdef[gune as usize].firstchar = 256 + 67 * 4 - 4;
-    objdef[gune as usize].size = 2;
-    objdef[gune as usize].stages = 1;
-    objdef[gune as usize].dirmask = 0;
-    objdef[gune as usize].speed = 10;
-    objdef[gune as usize].hitpoints = 255;
-    objdef[gune as usize].damage = 0;
-    objdef[gune as usize].points = 0;
-    objdef[rock as usize].think = straight as u8;
-    objdef[rock as usize].contact = mshot as u8;
-    objdef[rock as usize].solid = false as u8;
-    objdef[rock as usize].firstchar = 153;
-    objdef[rock as usize].size = 1;
-    objdef[rock as usize].stages = 2;
-    objdef[rock as usize].dirmask = 0;
-    objdef[rock as usize].speed = 256;
-    objdef[rock as usize].hitpoints = 0;
-    objdef[rock as usize].damage = 1;
-    objdef[rock as usize].points = 0;
-    objdef[bigshot as usize].think = straight as u8;
-    objdef[bigshot as usize].contact = nukeshot as u8;
-    objdef[bigshot as usize].solid = false as u8;
-    objdef[bigshot as usize].firstchar = 256 + 192;
-    objdef[bigshot as usize].size = 2;
-    objdef[bigshot as usize].stages = 2;
-    objdef[bigshot as usize].dirmask = 3;
-    objdef[bigshot as usize].speed = 256;
-    objdef[bigshot as usize].hitpoints = 0;
-    objdef[bigshot as usize].damage = 1;
-    objdef[bigshot as usize].points = 0;
-    objdef[teleporter as usize].think = idle as u8;
-    objdef[teleporter as usize].contact = benign as u8;
-    objdef[teleporter as usize].solid = false as u8;
-    objdef[teleporter as usize].firstchar = 256 + 236;
-    objdef[teleporter as usize].size = 2;
-    objdef[teleporter as usize].stages = 5;
-    objdef[teleporter as usize].dirmask = 0;
-    objdef[teleporter as usize].speed = 200;
-    objdef[teleporter as usize].hitpoints = 0;
-    objdef[teleporter as usize].damage = 0;
-    objdef[teleporter as usize].points = 0;
-    objdef[secretgate as usize].think = idle as u8;
-    objdef[secretgate as usize].contact = benign as u8;
-    objdef[secretgate as usize].solid = false as u8;
-    objdef[secretgate as usize].firstchar = 256 + 67 * 4 - 12;
-    objdef[secretgate as usize].size = 2;
-    objdef[secretgate as usize].stages = 1;
-    objdef[secretgate as usize].dirmask = 0;
-    objdef[secretgate as usize].speed = 200;
-    objdef[secretgate as usize].hitpoints = 0;
-    objdef[secretgate as usize].damage = 0;
-    objdef[secretgate as usize].points = 0;
+    if let Err(error) = applyobjdefs(STOCK_OBJDEFS, objdef) {
+        panic!("Invalid stock object definitions: {}", error);
+    }
+}
+
+/// The definitions in the level pack take precedence over the ones in the current directory; if
+/// there are none, the stock ones are kept.
+pub fn objdefs_path(levelpack: Option<&Path>) -> Option<PathBuf> {
+    levelpack
+        .map(|levelpack| levelpack.join(OBJDEFS_FILENAME))
+        .into_iter()
+        .chain(Some(PathBuf::from(OBJDEFS_FILENAME)))
+        .find(|path| path.is_file())
+}
+
+/// Applies the custom definitions (if any) on top of the current ones.
+pub fn loadobjects(levelpack: Option<&Path>, objdef: &mut [objdeftype]) -> Result<(), String> {
+    let path = match objdefs_path(levelpack) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    fs::read_to_string(&path)
+        .map_err(ObjDefError::from)
+        .and_then(|source| applyobjdefs(&source, objdef))
+        .map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// All the definitions are validated before being applied, so that on error, `objdef` is
+/// unchanged.
+pub fn applyobjdefs(source: &str, objdef: &mut [objdeftype]) -> Result<(), ObjDefError> {
+    let entries: BTreeMap<String, ObjDefEntry> = toml::from_str(source)?;
+    let mut new_objdef = objdef.to_vec();
+
+    for (name, entry) in entries {
+        let (class, name) = CLASS_NAMES
+            .iter()
+            .find(|(_, class_name)| *class_name == name)
+            .copied()
+            .ok_or(ObjDefError::UnknownClass(name))?;
+        let def = &mut new_objdef[class as usize];
+
+        if let Some(think) = entry.think {
+            def.think = think as u8;
+        }
+        if let Some(contact) = entry.contact {
+            def.contact = contact as u8;
+        }
+        if let Some(solid) = entry.solid {
+            def.solid = solid as u8;
+        }
+        if let Some(firstchar) = entry.firstchar {
+            def.firstchar = firstchar;
+        }
+        if let Some(size) = entry.size {
+            def.size = size;
+        }
+        if let Some(stages) = entry.stages {
+            def.stages = stages;
+        }
+        if let Some(dirmask) = entry.dirmask {
+            def.dirmask = dirmask;
+        }
+        if let Some(speed) = entry.speed {
+            def.speed = speed;
+        }
+        if let Some(hitpoints) = entry.hitpoints {
+            def.hitpoints = hitpoints;
+        }
+        if let Some(damage) = entry.damage {
+            def.damage = damage;
+        }
+        if let Some(points) = entry.points {
+            def.points = points;
+        }
+
+        validateobjdef(name, def)?;
+    }
+
+    objdef.copy_from_slice(&new_objdef);
+
+    Ok(())
+}
+
+fn validateobjdef(class: &'static str, def: &objdeftype) -> Result<(), ObjDefError> {
+    let invalid = |field, message: String| ObjDefError::InvalidField {
+        class,
+        field,
+        message,
+    };
+
+    if def.size == 0 || def.size > MAX_SIZE {
+        return Err(invalid(
+            "size",
+            format!("must be between 1 and {MAX_SIZE}, but it's {}", def.size),
+        ));
+    }
+    if def.stages == 0 {
+        return Err(invalid("stages", "must be at least 1".to_string()));
+    }
+    if def.dirmask > MAX_DIRMASK {
+        return Err(invalid(
+            "dirmask",
+            format!(
+                "must be between 0 and {MAX_DIRMASK}, but it's {}",
+                def.dirmask
+            ),
+        ));
+    }
+
+    // Each stage of each direction is a square of tiles.
+    let tiles =
+        def.size as usize * def.size as usize * def.stages as usize * (def.dirmask as usize + 1);
+    let lastchar = def.firstchar as usize + tiles - 1;
+
+    if lastchar > maxpics {
+        return Err(invalid(
+            "firstchar",
+            format!(
+                "the {tiles} tiles starting at {} go past the last one ({maxpics})",
+                def.firstchar
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tag_type::tagtype::*, think_type::thinktype::*};
+
+    use super::*;
+
+    /// The definitions that were hardcoded in initobjects(), in the same order:
+    /// (class, think, contact, solid, firstchar, size, stages, dirmask, speed, hitpoints, damage,
+    /// points).
+    #[rustfmt::skip]
+    const ORIGINAL_OBJDEFS: [(classtype, thinktype, tagtype, bool, u16, u8, u8, u8, u16, u8, u8, u16); 20] = [
+        (player, playercmd, benign, true, 256, 2, 4, 3, 256, 12, 0, 0),
+        (goblin, ramstraight, monster, true, 256 + 64, 2, 4, 3, 75, 1, 1, 50),
+        (skeleton, ramdiag, monster, true, 256 + 128, 2, 4, 3, 130, 1, 1, 150),
+        (ogre, ramstraight, monster, true, 256 + 67 * 4, 3, 4, 3, 75, 5, 2, 250),
+        (gargoyle, gargcmd, monster, true, 256 + 67 * 4 + 35 * 9, 4, 4, 3, 175, 10, 3, 500),
+        (dragon, dragoncmd, monster, true, 256 + 67 * 4 + 35 * 9 + 19 * 16, 5, 4, 3, 100, 75, 5, 1000),
+        (turbogre, ramstraight, monster, true, 256 + 67 * 4 + 19 * 9, 3, 4, 3, 255, 5, 2, 500),
+        (wallhit, fade, benign, true, 26, 1, 3, 0, 80, 0, 0, 0),
+        (dead1, explode, benign, false, 29, 1, 3, 0, 80, 0, 0, 0),
+        (dead2, fade, benign, false, 256 + 224, 2, 3, 0, 80, 0, 0, 0),
+        (dead3, fade, benign, false, 256 + 67 * 4 + 9 * 16, 3, 3, 0, 80, 0, 0, 0),
+        (dead4, fade, benign, false, 256 + 67 * 4 + 35 * 9 + 16 * 16, 4, 3, 0, 80, 0, 0, 0),
+        (dead5, fade, benign, false, 256 + 67 * 4 + 35 * 9 + 19 * 16 + 25 * 16, 5, 3, 0, 80, 0, 0, 0),
+        (shot, straight, pshot, false, 154, 1, 2, 3, 256, 0, 1, 0),
+        (guns, gunthinks, benign, true, 256 + 67 * 4 - 8, 2, 1, 0, 10, 255, 0, 0),
+        (gune, gunthinke, benign, true, 256 + 67 * 4 - 4, 2, 1, 0, 10, 255, 0, 0),
+        (rock, straight, mshot, false, 153, 1, 2, 0, 256, 0, 1, 0),
+        (bigshot, straight, nukeshot, false, 256 + 192, 2, 2, 3, 256, 0, 1, 0),
+        (teleporter, idle, benign, false, 256 + 236, 2, 5, 0, 200, 0, 0, 0),
+        (secretgate, idle, benign, false, 256 + 67 * 4 - 12, 2, 1, 0, 200, 0, 0, 0),
+    ];
+
+    #[test]
+    fn stock_objdefs_match_the_original_table() {
+        let mut objdef = [objdeftype::default(); lastclass as usize];
+        initobjects(&mut objdef);
+
+        for (
+            class,
+            think,
+            contact,
+            solid,
+            firstchar,
+            size,
+            stages,
+            dirmask,
+            speed,
+            hitpoints,
+            damage,
+            points,
+        ) in ORIGINAL_OBJDEFS
+        {
+            let def = &objdef[class as usize];
+            let name = CLASS_NAMES.iter().find(|(c, _)| *c == class).unwrap().1;
+
+            assert_eq!(def.think, think as u8, "{}.think", name);
+            assert_eq!(def.contact, contact as u8, "{}.contact", name);
+            assert_eq!(def.solid, solid as u8, "{}.solid", name);
+            assert_eq!(def.firstchar, firstchar, "{}.firstchar", name);
+            assert_eq!(def.size, size, "{}.size", name);
+            assert_eq!(def.stages, stages, "{}.stages", name);
+            assert_eq!(def.dirmask, dirmask, "{}.dirmask", name);
+            assert_eq!(def.speed, speed, "{}.speed", name);
+            assert_eq!(def.hitpoints, hitpoints, "{}.hitpoints", name);
+            assert_eq!(def.damage, damage, "{}.damage", name);
+            assert_eq!(def.points, points, "{}.points", name);
+        }
+    }
+
+    #[test]
+    fn partial_objdefs_only_change_the_listed_fields() {
+        let mut objdef = [objdeftype::default(); lastclass as usize];
+        initobjects(&mut objdef);
+        let stock = objdef;
+
+        applyobjdefs("[goblin]\nspeed = 100\n", &mut objdef)
+            .ok()
+            .unwrap();
+
+        assert_eq!(objdef[goblin as usize].speed, 100);
+        assert_eq!(
+            objdef[goblin as usize].points,
+            stock[goblin as usize].points
+        );
+        assert_eq!(objdef[ogre as usize].speed, stock[ogre as usize].speed);
+
+        // Invalid definitions leave the table untouched.
+        assert!(applyobjdefs("[goblin]\nsize = 9\n", &mut objdef).is_err());
+        assert_eq!(objdef[goblin as usize].size, stock[goblin as usize].size);
+    }
 }
@@ -1,20 +1,13 @@
-use std::{
-    sync::{Condvar, Mutex},
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use sdl2::{
-    audio::{AudioCallback, AudioDevice, AudioSpecDesired},
-    timer::Timer,
-    TimerSubsystem,
-};
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
 
 use crate::{
     cpanel_state::CpanelState,
+    frame_output::Display,
     global_state::GlobalState,
     gr_type::grtype::*,
     pcrlib_a_state::{PcrlibAState, PcrlibAStateExclusive},
-    pcrlib_c::UpdateScreen,
     pcrlib_c_state::PcrlibCState,
     sdl_manager::SdlManager,
     sound_type::soundtype::*,
@@ -23,10 +16,6 @@ use crate::{
 
 const PC_BASE_TIMER: u32 = 1193181;
 
-// Rust port: Simulation of the SDL Semaphore
-static vblSemMutex: Mutex<u32> = Mutex::new(0);
-static vblSemCondvar: Condvar = Condvar::new();
-
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub struct SavedSoundStruct {
@@ -38,7 +27,7 @@ pub struct SavedSoundStruct {
 }
 
 pub const screenpitch: usize = 320;
-const VBL_TIME: u32 = 14;
+pub const VBL_TIME: u32 = 14;
 
 #[inline]
 pub fn EGA(chan: &[u8], ofs: u8) -> u8 {
@@ -122,7 +111,7 @@ impl AudioCallback for Sound {
 
     fn callback(&mut self, out: &mut [Self::Channel]) {
         self.pas.lock(|pasx| {
-            if pasx.soundmode != spkr {
+            if pasx.soundmode != spkr && pasx.soundmode != synth {
                 out.fill(0);
                 return;
             }
@@ -131,7 +120,16 @@ impl AudioCallback for Sound {
             let out_len = out.len();
 
             for (i, w) in out.iter_mut().enumerate() {
-                if pasx.pcActive {
+                if pasx.soundmode == synth {
+                    // Rust port: The synthesizer plays the same sequence, but generates the wave
+                    // from the frequency, rather than from the phase length.
+                    let frequency = if pasx.pcActive {
+                        Some(PC_BASE_TIMER as f32 / pasx.pcLastSample as f32)
+                    } else {
+                        None
+                    };
+                    *w = pasx.synth.next_sample(frequency);
+                } else if pasx.pcActive {
                     *w = pasx.pcVolume;
 
                     if pasx.pcPhaseTick >= pasx.pcPhaseLength {
@@ -197,9 +195,13 @@ pub fn StartupSound(pas: &mut PcrlibAState, sdl: &SdlManager) -> Option<AudioDev
             }
             Ok(audio_dev) => {
                 pasx.AudioSpecFreq = audio_dev.spec().freq;
+                pasx.synth.set_sample_rate(pasx.AudioSpecFreq);
                 // Typical value for init since samplerate is usually 8
                 pasx.pcSamplesPerTick = (pasx.AudioSpecFreq / 145) as u32;
-                pasx.soundmode = spkr;
+                // Rust port: The synthesizer selection (loaded by _loadctrls()) is kept.
+                if pasx.soundmode != synth {
+                    pasx.soundmode = spkr;
+                }
                 audio_dev.resume();
                 Some(audio_dev)
             }
@@ -263,14 +265,21 @@ pub fn ContinueSound(pas: &mut PcrlibAState) {
     });
 }
 
-pub fn WaitEndSound(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
-    if pas._dontplay {
-        return;
-    }
-    UpdateScreen(gs, pcs);
-    while pas.lock(|pasx| pasx.pcSound.is_some()) {
-        WaitVBL();
-    }
+// Rust port: unused; the waits are done a frame at a time, through SoundPlaying().
+//
+// pub fn WaitEndSound(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
+//     if pas._dontplay {
+//         return;
+//     }
+//     UpdateScreen(gs, pcs);
+//     while SoundPlaying(pas) {
+//         WaitVBL(pcs);
+//     }
+// }
+
+// Rust port: Added, for waiting the end of a sound without blocking.
+pub fn SoundPlaying(pas: &mut PcrlibAState) -> bool {
+    !pas._dontplay && pas.lock(|pasx| pasx.pcSound.is_some())
 }
 const rndtable: [u8; 256] = [
     0, 8, 109, 220, 222, 241, 149, 107, 75, 248, 254, 140, 16, 66, 74, 21, 211, 47, 80, 242, 154,
@@ -358,46 +367,10 @@ pub fn rndt(pas: &mut PcrlibAState) -> i32 {
     rndtable[pas.rndindex as usize] as i32
 }
 
-fn VBLCallback() -> u32 {
-    let mut guard = vblSemMutex.lock().unwrap();
-
-    *guard += 1;
-    vblSemCondvar.notify_one();
-
-    VBL_TIME
-}
-
-// In the SDL port, this was registered on atexit. Although it's tidy, it's not necessary, since (SQL)
-// quit events (e.g. window closing) are trapped by SDL and handled by the WatchUIEvents.
-// The only case where this can run is probably an unexpected termination. Since it's not strictly
-// necessary anyway, and the cost is to require globals (atexit() doesn't support parameters), it
-// can be safely removed.
-//
-// pub unsafe extern "C" fn ShutdownEmulatedVBL() {
-//     safe_SDL_RemoveTimer(pas.vbltimer);
-//     safe_SDL_DestroySemaphore(pas.vblsem);
-// }
-
-pub fn SetupEmulatedVBL(timer_sys: &TimerSubsystem) -> Timer<'_, '_> {
-    // Rust port: No need to create the semaphore here
-
-    timer_sys.add_timer(VBL_TIME, Box::new(VBLCallback))
-
-    // Disabled; see comment on ShutdownEmulatedVBL().
-    // safe_register_shutdown_vbl_on_exit();
-}
-
-pub fn WaitVBL() {
-    let mut guard = vblSemMutex.lock().unwrap();
-
-    loop {
-        if *guard > 0 {
-            *guard -= 1;
-            break;
-        } else {
-            guard = vblSemCondvar.wait(guard).unwrap();
-        }
-    }
+// Rust port: The VBL emulation (VBLCallback(), SetupEmulatedVBL() and the semaphore) is in the
+// frontend, which does the waits requested by the game.
+pub fn WaitVBL(pcs: &mut PcrlibCState) {
+    pcs.display.push(Display::WaitVBL);
 }
 
 pub fn drawchar(x: i32, y: i32, charnum: i32, gs: &mut GlobalState, pcs: &mut PcrlibCState) {
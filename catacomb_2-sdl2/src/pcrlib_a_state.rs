@@ -1,6 +1,6 @@
 use crate::{
     pcrlib_a::SavedSoundStruct, sound_type::soundtype, sound_type::soundtype::*,
-    spkr_table::SPKRtable,
+    spkr_table::SPKRtable, synth::Synth,
 };
 use std::sync::{Arc, Mutex};
 
@@ -43,6 +43,8 @@ pub struct PcrlibAStateExclusive {
     // Rust port: Pointer to SoundData.freqdata
     pub pcSound: Option<usize>,
     pub SavedSound: SavedSoundStruct,
+    // Rust port: Added
+    pub synth: Synth,
 
     // //////////////////////////////////////////////////////////
     // Rust port: private to cpanel.rs
@@ -93,6 +95,7 @@ impl PcrlibAState {
                 pcLengthLeft: 0,
                 pcSound: None,
             },
+            synth: Synth::new(),
             SoundData: SPKRtable::default(),
             soundmode: spkr,
         };
@@ -1,33 +1,21 @@
-use std::convert::TryInto;
 use std::ffi::CString;
+use std::fs;
 use std::fs::{File, OpenOptions};
 use std::io::{self, Read, Write};
-use std::path::Path;
-use std::{fs, mem};
-
-use sdl2::audio::AudioDevice;
-use sdl2::controller::{Axis, Button, GameController};
-use sdl2::event::{Event, WindowEvent};
-use sdl2::joystick::Joystick;
-use sdl2::keyboard::{Keycode, Scancode};
-use sdl2::mouse::MouseButton;
-use sdl2::pixels::PixelFormatEnum;
-use sdl2::rect::Rect;
-use sdl2::render::{TextureAccess, TextureCreator};
-use sdl2::sys::SDL_WindowFlags;
-use sdl2::timer::Timer;
-use sdl2::video::WindowContext;
-use sdl2::TimerSubsystem;
+use std::path::{Path, PathBuf};
+
 use serdine::{Deserialize, Serialize};
 
 use crate::catacomb::loadgrfiles;
 use crate::cpanel_state::CpanelState;
 use crate::ctl_panel_type::ctlpaneltype;
+use crate::engine::{Overlay, Prompt};
+use crate::frame_input::{InputEvent, JoystickInput};
+use crate::frame_output::{Display, Rumble};
 use crate::input_type::inputtype::*;
-use crate::pcrlib_a::{initrnd, initrndt, SetupEmulatedVBL, Sound, StartupSound};
+use crate::pcrlib_a::{initrnd, initrndt};
 use crate::pcrlib_a_state::PcrlibAState;
 use crate::pcrlib_c_state::PcrlibCState;
-use crate::sdl_manager::SdlManager;
 use crate::sound_type::soundtype::{self, *};
 use crate::spkr_table::SPKRtable;
 use crate::{
@@ -38,14 +26,36 @@ use crate::{
     extra_constants::_extension,
     global_state::GlobalState,
     gr_type::grtype::{self, *},
+    high_scores::{
+        format_playtime, inserthighscore, loadhighscores, savehighscores, sortedhighscores,
+        HighScore, HighScoreSort, MAX_NAME_LEN, TABLE_ENTRIES,
+    },
     pcrlib_a::{drawchar, PlaySound, WaitVBL},
+    save_game::format_timestamp,
     scan_codes::*,
-    scores::scores,
+    synth::{DEFAULT_SYNTH_VOLUME, MAX_SYNTH_VOLUME},
 };
 
-pub enum joyinfo_t {
-    Controller(GameController),
-    Joy(Joystick),
+// Rust port: Stick deflection (out of 32767) below which a controller is considered centered. Unlike
+// legacy joysticks, controllers have a standard range, so they don't need calibration.
+const CONTROLLER_DEADZONE: i32 = 8000;
+
+// Rust port: Whether the controller in the given slot is the device picked by a player in the control
+// panel; buttons of other controllers must not act as keys.
+fn ControllerSelected(joynum: usize, pcs: &PcrlibCState) -> bool {
+    pcs.playermode[1..].iter().any(|mode| match mode {
+        joystick1 => joynum == 1,
+        joystick2 => joynum == 2,
+        _ => false,
+    })
+}
+
+// Rust port: Whether a key is held on any controller; the keyboard and the controllers are tracked
+// separately, so that releasing one doesn't release a key still held on the other.
+fn PadKeyHeld(scancode: SDL_Scancode, pcs: &PcrlibCState) -> bool {
+    pcs.padkeydown
+        .iter()
+        .any(|padkeydown| padkeydown[scancode as usize])
 }
 
 // Rust port: unnecessary in Rust (false is the default)
@@ -56,25 +66,55 @@ pub enum joyinfo_t {
 //     }
 // }
 
-pub fn ProcessEvents(pcs: &mut PcrlibCState, pas: &mut PcrlibAState, sdl: &mut SdlManager) {
+// Rust port: The events are collected by the frontend, and passed with the frame input (see
+// `Engine::step()`); the joystick hot-plug is handled by the frontend as well.
+pub fn ProcessEvents(pcs: &mut PcrlibCState, pas: &mut PcrlibAState) {
     pcs.mouseEvent = false;
 
-    let polled_events = sdl.event_pump().poll_iter().collect::<Vec<_>>();
+    let polled_events = pcs.events.drain(..).collect::<Vec<_>>();
 
     for event in polled_events {
         match event {
-            Event::KeyDown { scancode, .. } => {
-                pcs.keydown[scancode.unwrap() as usize] = true;
-                pcs.lastkey = scancode.unwrap() as u32;
+            InputEvent::KeyDown { scancode, keycode } => {
+                pcs.kbdkeydown[scancode as usize] = true;
+                pcs.keydown[scancode as usize] = true;
+                pcs.keycode[scancode as usize] = keycode;
+                pcs.lastkey = scancode;
             }
-            Event::KeyUp { scancode, .. } => {
-                pcs.keydown[scancode.unwrap() as usize] = false;
+            InputEvent::KeyUp { scancode } => {
+                pcs.kbdkeydown[scancode as usize] = false;
+                pcs.keydown[scancode as usize] = PadKeyHeld(scancode, pcs);
             }
-            Event::MouseMotion { .. } => {
+            InputEvent::MouseMotion => {
                 pcs.mouseEvent = true;
             }
+            // Rust port: Releases are always applied, even if the controller is no longer selected, so
+            // that keys don't get stuck.
+            InputEvent::ControllerButton {
+                joynum,
+                scancode,
+                pressed,
+            } => {
+                if pressed && ControllerSelected(joynum, pcs) {
+                    pcs.padkeydown[joynum][scancode as usize] = true;
+                    pcs.keydown[scancode as usize] = true;
+                } else if !pressed {
+                    pcs.padkeydown[joynum][scancode as usize] = false;
+                    pcs.keydown[scancode as usize] =
+                        pcs.kbdkeydown[scancode as usize] || PadKeyHeld(scancode, pcs);
+                }
+            }
+            // Rust port: The buttons held on the old devices won't be released.
+            InputEvent::JoysticksChanged => {
+                for scancode in 0..pcs.keydown.len() {
+                    if PadKeyHeld(scancode as SDL_Scancode, pcs) {
+                        pcs.keydown[scancode] = pcs.kbdkeydown[scancode];
+                    }
+                }
+                pcs.padkeydown = [[false; 512]; 3];
+            }
             event => {
-                WatchUIEvents(event, pcs, pas, sdl);
+                WatchUIEvents(event, pcs, pas);
             }
         }
     }
@@ -101,60 +141,19 @@ pub fn ProcessEvents(pcs: &mut PcrlibCState, pas: &mut PcrlibAState, sdl: &mut S
 // and they are only two. The downside is that in Rust, we need refcounting, which is a hassle to add
 // (in terms of noise; it should be added to PcrlibCState).
 // For this reason, approach 2 is overall more convenient.
-fn WatchUIEvents(
-    event: Event,
-    pcs: &mut PcrlibCState,
-    pas: &mut PcrlibAState,
-    sdl: &mut SdlManager,
-) {
+fn WatchUIEvents(event: InputEvent, pcs: &mut PcrlibCState, pas: &mut PcrlibAState) {
     match event {
-        Event::Quit { .. } => {
-            _quit(None, pas, pcs, sdl);
-        }
-        Event::Window {
-            win_event: WindowEvent::FocusLost,
-            ..
-        } => {
-            let pcs = pcs;
+        InputEvent::Quit => {
+            _quit(None, pas, pcs);
+        }
+        InputEvent::FocusLost => {
             pcs.hasFocus = false;
-            CheckMouseMode(pcs, sdl);
-        }
-        Event::Window {
-            win_event: WindowEvent::FocusGained,
-            ..
-        } => {
-            let pcs = pcs;
-
-            // Try to wait until the window obtains mouse focus before
-            // regrabbing input in order to try to prevent grabbing while
-            // the user is trying to move the window around.
-            //
-            // Rust port: It's not 100% clear how this works, and under which exact cirmustances, since
-            // it's still possible to move the window, although it's a bit tricky, as the window grabs
-            // the mouse immediately if it's inside it.
-            // With or without this workaround, there are no visible differences, at least, when testing
-            // on X11.
-            // Pumping the events doesn't directly work in Rust, since the event subsystem is locked
-            // during events iteration (which is the location when event watchers are invoked by SDL).
-            // Since adjusting this logic requires some restructuring, and it's not really clear if
-            // it works as intended, it's kept commented out.
-            //
-            /*
-            while sdl.mouse().focused_window_id()
-                != Some(pcs.renderer.as_ref().unwrap().window().id())
-            {
-                // sdl.event_pump().pump_events();
-
-                // Rust port: in the SDL port, this called `SDL_Delay`, however, the Rust sdl2
-                // crate recommeds to use thread::sleep(). This also simplifies a BCK issue,
-                // because `Timer#delay()` requires a mutable sdl instance, which is a problem
-                // when the timer instance is owned by RcSdl.
-                thread::sleep(Duration::from_millis(10));
-            }
-            */
-
+            CheckMouseMode(pcs);
+        }
+        // Rust port: See the frontend for the SDL port wait for the mouse focus.
+        InputEvent::FocusGained => {
             pcs.hasFocus = true;
-            CheckMouseMode(pcs, sdl);
+            CheckMouseMode(pcs);
         }
         _ => {}
     }
@@ -239,7 +238,7 @@ fn ControlKBD(pcs: &mut PcrlibCState) -> ControlStruct {
 ============================
 */
 
-fn ControlMouse(pcs: &mut PcrlibCState, sdl: &SdlManager) -> ControlStruct {
+fn ControlMouse(pcs: &mut PcrlibCState) -> ControlStruct {
     /* mickeys the mouse has moved */
 
     let mut action: ControlStruct = ControlStruct {
@@ -249,18 +248,12 @@ fn ControlMouse(pcs: &mut PcrlibCState, sdl: &SdlManager) -> ControlStruct {
     };
 
     /* mouse status */
-    let mouse_state = sdl.event_pump().relative_mouse_state();
-
-    let newx = mouse_state.x();
-    let newy = mouse_state.y();
+    // Rust port: Read by the frontend, once per frame.
+    let newx = pcs.mouse.x;
+    let newy = pcs.mouse.y;
 
-    for (button, pressed) in mouse_state.mouse_buttons() {
-        match button {
-            MouseButton::Left => action.button1 = pressed,
-            MouseButton::Right => action.button2 = pressed,
-            _ => {}
-        }
-    }
+    action.button1 = pcs.mouse.left;
+    action.button2 = pcs.mouse.right;
 
     if !pcs.mouseEvent {
         action.dir = nodir;
@@ -317,54 +310,31 @@ fn ControlMouse(pcs: &mut PcrlibCState, sdl: &SdlManager) -> ControlStruct {
     action
 }
 
-/*
-===============================
-=
-= ShutdownJoysticks
-= Try to identify joysticks and open them.
-=
-===============================
-*/
-
-fn ShutdownJoysticks(pcs: &mut PcrlibCState) {
-    for joystick in &mut pcs.joystick[1..3] {
-        if joystick.is_some() {
-            // Rust port: Dropping the instance will close it.
-            *joystick = None;
-        }
-    }
-}
+// Rust port: ShutdownJoysticks() and ProbeJoysticks() are in the frontend, which owns the devices.
 
 /*
 ===============================
 =
-= ProbeJoysticks
-= Try to identify joysticks and open them.
+= RumbleJoystick
+= Rust port: Added; rumbles the device of the given player, if it's using one
 =
 ===============================
 */
 
-pub fn ProbeJoysticks(pcs: &mut PcrlibCState, sdl: &SdlManager) {
-    // Rust port: The conditional is unnecessary, since ShutdownJoystcisk will skip empty slots.
-    if pcs.joystick[1].is_some() || pcs.joystick[2].is_some() {
-        ShutdownJoysticks(pcs);
-    }
-
-    for (j, joystick) in pcs.joystick.iter_mut().enumerate().skip(1) {
-        let j = j as u32;
-
-        if j - 1 >= sdl.joystick().num_joysticks().unwrap() {
-            *joystick = None;
-            continue;
-        }
+pub fn RumbleJoystick(player: i32, strength: u16, duration_ms: u32, pcs: &mut PcrlibCState) {
+    let joynum = match pcs.playermode[player as usize] {
+        joystick1 => 1,
+        joystick2 => 2,
+        _ => return,
+    };
 
-        if sdl.game_controller().is_game_controller(j - 1) {
-            let controller = sdl.game_controller().open(j - 1).unwrap();
-            *joystick = Some(joyinfo_t::Controller(controller));
-        } else {
-            let joy = sdl.joystick().open(j - 1).unwrap();
-            *joystick = Some(joyinfo_t::Joy(joy));
-        }
+    // Carried out by the frontend.
+    if pcs.joystick[joynum].is_some() {
+        pcs.rumble.push(Rumble {
+            joynum,
+            strength,
+            duration_ms,
+        });
     }
 }
 
@@ -377,31 +347,18 @@ pub fn ProbeJoysticks(pcs: &mut PcrlibCState, sdl: &SdlManager) {
 ===============================
 */
 
-pub fn ReadJoystick(
-    joynum: i32,
-    xcount: &mut i32,
-    ycount: &mut i32,
-    pcs: &mut PcrlibCState,
-    sdl: &SdlManager,
-) {
+pub fn ReadJoystick(joynum: i32, xcount: &mut i32, ycount: &mut i32, pcs: &mut PcrlibCState) {
     let mut a1: i32 = 0;
     let mut a2: i32 = 0;
 
     *xcount = 0;
     *ycount = 0;
 
-    sdl.joystick().update();
-
-    match &pcs.joystick[joynum as usize] {
-        Some(joyinfo_t::Controller(controller)) => {
-            a1 = controller.axis(Axis::LeftX) as i32;
-            a2 = controller.axis(Axis::LeftY) as i32;
-        }
-        Some(joyinfo_t::Joy(joystick)) => {
-            a1 = joystick.axis(0).unwrap() as i32;
-            a2 = joystick.axis(1).unwrap() as i32;
-        }
-        None => unreachable!(),
+    // Rust port: The state is read by the frontend, once per frame; the device may have been
+    // unplugged.
+    if let Some(joystick) = &pcs.joystick[joynum as usize] {
+        a1 = joystick.x;
+        a2 = joystick.y;
     }
 
     *xcount = a1;
@@ -416,7 +373,7 @@ pub fn ReadJoystick(
 =============================
 */
 
-pub fn ControlJoystick(joynum: i32, pcs: &mut PcrlibCState, sdl: &SdlManager) -> ControlStruct {
+pub fn ControlJoystick(joynum: i32, pcs: &mut PcrlibCState) -> ControlStruct {
     let mut joyx: i32 = 0;
     let mut joyy: i32 = 0;
     let mut xmove: i32 = 0;
@@ -427,35 +384,42 @@ pub fn ControlJoystick(joynum: i32, pcs: &mut PcrlibCState, sdl: &SdlManager) ->
         button2: false,
     };
 
-    ReadJoystick(joynum, &mut joyx, &mut joyy, pcs, sdl);
+    ReadJoystick(joynum, &mut joyx, &mut joyy, pcs);
 
     /* get all four button status */
     match &pcs.joystick[joynum as usize] {
-        Some(joyinfo_t::Controller(controller)) => {
-            action.button1 = controller.button(Button::A);
-            action.button2 = controller.button(Button::B);
-        }
-        Some(joyinfo_t::Joy(joystick)) => {
-            action.button1 = joystick.button(0).unwrap();
-            action.button2 = joystick.button(1).unwrap();
+        Some(joystick) if joystick.controller => {
+            action.button1 = joystick.button1;
+            action.button2 = joystick.button2;
+
+            let (x, y) = ControllerMove(joystick, joyx, joyy);
+            xmove = x;
+            ymove = y;
         }
-        None => unreachable!(),
-    }
+        Some(joystick) => {
+            action.button1 = joystick.button1;
+            action.button2 = joystick.button2;
 
-    if joyx == 0 && joyy == 0 {
-        action.dir = nodir;
-        return action;
-    }
+            if joyx == 0 && joyy == 0 {
+                action.dir = nodir;
+                return action;
+            }
 
-    if joyx > pcs.JoyXhigh[joynum as usize] {
-        xmove = 1;
-    } else if joyx < pcs.JoyXlow[joynum as usize] {
-        xmove = -1;
-    }
-    if joyy > pcs.JoyYhigh[joynum as usize] {
-        ymove = 1;
-    } else if joyy < pcs.JoyYlow[joynum as usize] {
-        ymove = -1;
+            if joyx > pcs.JoyXhigh[joynum as usize] {
+                xmove = 1;
+            } else if joyx < pcs.JoyXlow[joynum as usize] {
+                xmove = -1;
+            }
+            if joyy > pcs.JoyYhigh[joynum as usize] {
+                ymove = 1;
+            } else if joyy < pcs.JoyYlow[joynum as usize] {
+                ymove = -1;
+            }
+        }
+        None => {
+            action.dir = nodir;
+            return action;
+        }
     }
 
     match ymove * 3 + xmove {
@@ -492,6 +456,27 @@ pub fn ControlJoystick(joynum: i32, pcs: &mut PcrlibCState, sdl: &SdlManager) ->
     action
 }
 
+// Rust port: The d-pad takes precedence over the left stick (whose position is passed).
+fn ControllerMove(controller: &JoystickInput, joyx: i32, joyy: i32) -> (i32, i32) {
+    let stick = |value: i32| {
+        if value > CONTROLLER_DEADZONE {
+            1
+        } else if value < -CONTROLLER_DEADZONE {
+            -1
+        } else {
+            0
+        }
+    };
+
+    let (xmove, ymove) = controller.dpad;
+
+    if xmove != 0 || ymove != 0 {
+        (xmove, ymove)
+    } else {
+        (stick(joyx), stick(joyy))
+    }
+}
+
 /*
 =============================
 =
@@ -507,24 +492,23 @@ pub fn ControlPlayer(
     gs: &mut GlobalState,
     pcs: &mut PcrlibCState,
     pas: &mut PcrlibAState,
-    sdl: &mut SdlManager,
 ) -> ControlStruct {
     let mut ret: ControlStruct = ControlStruct {
         dir: north,
         button1: false,
         button2: false,
     };
-    ProcessEvents(pcs, pas, sdl);
+    ProcessEvents(pcs, pas);
     if gs.indemo == demoenum::notdemo || gs.indemo == demoenum::recording {
         match pcs.playermode[player as usize] as u32 {
             1 => {
-                ret = ControlMouse(pcs, sdl);
+                ret = ControlMouse(pcs);
             }
             2 => {
-                ret = ControlJoystick(1, pcs, sdl);
+                ret = ControlJoystick(1, pcs);
             }
             3 => {
-                ret = ControlJoystick(2, pcs, sdl);
+                ret = ControlJoystick(2, pcs);
             }
             0 | _ => {
                 ret = ControlKBD(pcs);
@@ -589,9 +573,9 @@ pub fn SaveDemo(demonum: u8, gs: &mut GlobalState, pcs: &mut PcrlibCState) {
 
 /*=========================================================================*/
 
-pub fn clearkeys(pcs: &mut PcrlibCState, pas: &mut PcrlibAState, sdl: &mut SdlManager) {
-    while bioskey(1, pcs, pas, sdl) != 0 {
-        bioskey(0, pcs, pas, sdl);
+pub fn clearkeys(pcs: &mut PcrlibCState, pas: &mut PcrlibAState) {
+    while bioskey(1, pcs, pas) != 0 {
+        bioskey(0, pcs, pas);
     }
     for i in 0..128 {
         pcs.keydown[i] = false;
@@ -774,7 +758,7 @@ pub fn expwin(
         expwinv(width, height - 2, gs, pas, pcs);
     }
     UpdateScreen(gs, pcs);
-    WaitVBL();
+    WaitVBL(pcs);
     centerwindow(width, height, gs, pcs);
 }
 
@@ -789,7 +773,7 @@ fn expwinh(
         expwinh(width - 2, height, gs, pas, pcs);
     }
     UpdateScreen(gs, pcs);
-    WaitVBL();
+    WaitVBL(pcs);
     centerwindow(width, height, gs, pcs);
 }
 
@@ -804,7 +788,7 @@ fn expwinv(
         expwinv(width, height - 2, gs, pas, pcs);
     }
     UpdateScreen(gs, pcs);
-    WaitVBL();
+    WaitVBL(pcs);
     centerwindow(width, height, gs, pcs);
 }
 
@@ -815,12 +799,7 @@ fn expwinv(
 //
 /////////////////////////
 
-pub fn bioskey(
-    cmd: i32,
-    pcs: &mut PcrlibCState,
-    pas: &mut PcrlibAState,
-    sdl: &mut SdlManager,
-) -> u32 {
+pub fn bioskey(cmd: i32, pcs: &mut PcrlibCState, pas: &mut PcrlibAState) -> u32 {
     if pcs.lastkey != 0 {
         let oldkey = pcs.lastkey;
         if cmd != 1 {
@@ -829,7 +808,7 @@ pub fn bioskey(
         return oldkey;
     }
 
-    let polled_events = sdl.event_pump().poll_iter().collect::<Vec<_>>();
+    let polled_events = pcs.events.drain(..).collect::<Vec<_>>();
     let mut returnKey = None;
 
     // Rust port: Slightly different from the the SDL port - here, we iterate all the events
@@ -837,14 +816,15 @@ pub fn bioskey(
     // see WatchUIEvents() for context.
     for event in polled_events {
         match event {
-            Event::KeyDown { scancode, .. } if returnKey.is_none() => {
-                returnKey = Some(scancode.unwrap() as u32);
+            InputEvent::KeyDown { scancode, keycode } if returnKey.is_none() => {
+                pcs.keycode[scancode as usize] = keycode;
+                returnKey = Some(scancode);
                 if cmd == 1 {
-                    pcs.lastkey = returnKey.unwrap();
+                    pcs.lastkey = scancode;
                 }
             }
             event => {
-                WatchUIEvents(event, pcs, pas, sdl);
+                WatchUIEvents(event, pcs, pas);
             }
         }
     }
@@ -856,7 +836,7 @@ pub fn bioskey(
     pcs.lastkey
 }
 
-const EGAPalette: [u32; 16] = [
+pub const EGAPalette: [u32; 16] = [
     0, 0xaa, 0xaa00, 0xaaaa, 0xaa0000, 0xaa00aa, 0xaa5500, 0xaaaaaa, 0x555555, 0x5555ff, 0x55ff55,
     0x55ffff, 0xff5555, 0xff55ff, 0xffff55, 0xffffff,
 ];
@@ -878,57 +858,55 @@ pub fn UpdateScreen(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
         panic!("VGA Palette conversion not implemented.");
     }
 
-    let pixel_bytes = pcs
-        .conv
-        .iter()
-        .flat_map(|v| v.to_le_bytes())
-        .collect::<Vec<_>>();
-
-    pcs.sdltexture
-        .update(None, pixel_bytes.as_slice(), 320 * mem::size_of::<u32>())
-        .unwrap();
-    pcs.renderer.as_mut().unwrap().clear();
-    pcs.renderer
-        .as_mut()
-        .unwrap()
-        .copy(&pcs.sdltexture, None, Some(pcs.updateRect))
-        .unwrap();
-    pcs.renderer.as_mut().unwrap().present();
+    // Rust port: The texture is updated by the frontend; a frame that hasn't been waited for is
+    // never shown, so it's replaced.
+    if let Some(Display::Present(_)) = pcs.display.last() {
+        pcs.display.pop();
+    }
+    pcs.display.push(Display::Present(pcs.conv.to_vec()));
 }
 
-pub fn get(
+// Rust port: get() waited for a key, in a loop; it's been replaced by tryget(), which is run once
+// per frame, until it returns a key.
+
+/////////////////////////
+//
+// tryget
+// Rust port: Non-blocking version of get(); if no key is available, it advances the cursor
+// animation (whose state is `cycle`) by one step, and returns None.
+//
+/////////////////////////
+
+pub fn tryget(
+    cycle: &mut i32,
     gs: &mut GlobalState,
     pcs: &mut PcrlibCState,
     pas: &mut PcrlibAState,
-    sdl: &mut SdlManager,
-) -> i32 {
-    let mut key = 0;
-
-    loop {
-        let mut cycle = 9;
-        loop {
-            key = bioskey(0, pcs, pas, sdl);
-            if key != 0 || cycle == 13 {
-                break;
-            }
-            drawchar(pcs.sx, pcs.sy, cycle, gs, pcs);
-            cycle += 1;
-            UpdateScreen(gs, pcs);
-            WaitVBL();
-            WaitVBL();
-            WaitVBL();
-            WaitVBL();
-            WaitVBL();
-        }
-        if key != 0 {
-            break;
-        }
+) -> Option<i32> {
+    let key = bioskey(0, pcs, pas);
+
+    if key == 0 {
+        if *cycle < 9 || *cycle >= 13 {
+            *cycle = 9;
+        }
+        drawchar(pcs.sx, pcs.sy, *cycle, gs, pcs);
+        *cycle += 1;
+        UpdateScreen(gs, pcs);
+        WaitVBL(pcs);
+        WaitVBL(pcs);
+        WaitVBL(pcs);
+        WaitVBL(pcs);
+        WaitVBL(pcs);
+
+        return None;
     }
+
+    // The next get() starts the animation from the beginning.
+    *cycle = 9;
     drawchar(pcs.sx, pcs.sy, ' ' as i32, gs, pcs);
     UpdateScreen(gs, pcs);
 
-    let scancode = Scancode::from_i32(key as i32).unwrap();
-    Keycode::from_scancode(scancode).unwrap() as i32 // take it out of the buffer
+    Some(pcs.keycode[key as usize]) // take it out of the buffer
 }
 
 /////////////////////////
@@ -1059,18 +1037,12 @@ fn strlen(string: &[u8]) -> usize {
 // input unsigned
 //
 ////////////////////////////////////////////////////////////////////
-pub fn _inputint(
-    gs: &mut GlobalState,
-    pcs: &mut PcrlibCState,
-    pas: &mut PcrlibAState,
-    sdl: &mut SdlManager,
-) -> u32 {
-    let mut string = vec![0; 18];
+// Rust port: Parses the string read by a LineInput of 17 characters (see `PlayPrompt::Warp`).
+pub fn _inputint(input: &LineInput) -> u32 {
+    let string = input.string().to_vec();
     let hexstr = b"0123456789ABCDEF";
     let mut value = 0;
 
-    _input(&mut string, 17, gs, pcs, pas, sdl);
-
     if string[0] == b'$' {
         let digits = strlen(&string) as isize - 2;
         if digits < 0 {
@@ -1108,43 +1080,52 @@ pub fn _inputint(
 // line input routine
 //
 ////////////////////////////////////////////////////////////////////
-fn _input(
-    string: &mut [u8],
+// Rust port: _input() read the keys in a loop, until Enter or Esc; LineInput takes them one at a time
+// (see tryget()).
+pub struct LineInput {
+    string: Vec<u8>,
+    count: usize,
     max: usize,
-    gs: &mut GlobalState,
-    pcs: &mut PcrlibCState,
-    pas: &mut PcrlibAState,
-    sdl: &mut SdlManager,
-) -> i32 {
-    let mut key_ = 0;
-    let mut count = 0;
-
-    loop {
-        key_ = (get(gs, pcs, pas, sdl) as u8).to_ascii_uppercase();
-        if (key_ == 127 || key_ == 8) && count > 0 {
-            count -= 1;
+}
+
+impl LineInput {
+    pub fn new(max: usize) -> Self {
+        Self {
+            string: vec![0; max + 1],
+            count: 0,
+            max,
+        }
+    }
+
+    /// Returns None until Enter (Some(true)) or Esc (Some(false)) is pressed.
+    pub fn key(&mut self, key: i32, gs: &mut GlobalState, pcs: &mut PcrlibCState) -> Option<bool> {
+        let key_ = (key as u8).to_ascii_uppercase();
+
+        if (key_ == 127 || key_ == 8) && self.count > 0 {
+            self.count -= 1;
+            self.string[self.count] = 0;
             drawchar(pcs.sx, pcs.sy, ' ' as i32, gs, pcs);
             pcs.sx -= 1;
         }
 
-        if key_ >= b' ' && key_ <= b'z' && count < max {
-            string[count as usize] = key_;
-            count += 1;
+        if key_ >= b' ' && key_ <= b'z' && self.count < self.max {
+            self.string[self.count] = key_;
+            self.count += 1;
             drawchar(pcs.sx, pcs.sy, key_ as i32, gs, pcs);
             pcs.sx += 1;
         }
 
-        if key_ == 27 || key_ == 13 {
-            break;
+        match key_ {
+            13 => Some(true),
+            27 => Some(false),
+            _ => None,
         }
     }
-    for c in &mut string[count..max] {
-        *c = 0;
-    }
-    if key_ == 13 {
-        return 1;
+
+    /// Zero-terminated, like the _input() buffer.
+    pub fn string(&self) -> &[u8] {
+        &self.string
     }
-    0
 }
 
 // Rust port: dead code!
@@ -1180,10 +1161,40 @@ pub fn ScancodeToDOS(sc: SDL_Scancode) -> i32 {
 }
 
 // Enable and disable mouse grabbing
-pub fn CheckMouseMode(pcs: &mut PcrlibCState, sdl: &SdlManager) {
-    sdl.mouse().set_relative_mouse_mode(
-        pcs.hasFocus && (pcs.playermode[1] == mouse || pcs.playermode[2] == mouse),
-    )
+// Rust port: Carried out by the frontend.
+pub fn CheckMouseMode(pcs: &mut PcrlibCState) {
+    pcs.relativemouse = pcs.hasFocus && (pcs.playermode[1] == mouse || pcs.playermode[2] == mouse);
+}
+
+// Rust port: Size of the control panel files written by the original game, which end before the
+// fields added by the port.
+const ORIGINAL_CTLPANEL_LEN: usize = 46;
+
+// Rust port: Decodes the control panel file, filling the fields added by the port, which are missing
+// from the original files, with the defaults.
+fn readctlpanel(mut data: Vec<u8>) -> ctlpaneltype {
+    let original = data.len() <= ORIGINAL_CTLPANEL_LEN;
+
+    let mut defaults = vec![];
+    ctlpaneltype {
+        SynthVolume: DEFAULT_SYNTH_VOLUME,
+        ..ctlpaneltype::default()
+    }
+    .serialize(&mut defaults)
+    .unwrap();
+    if data.len() < defaults.len() {
+        data.extend_from_slice(&defaults[data.len()..]);
+    }
+
+    let mut ctlpanel = ctlpaneltype::deserialize(data.as_slice()).unwrap();
+
+    // In the original files, the mode now used by the synthesizer is AdLib, which was never
+    // supported, and fell back to the speaker.
+    if original && ctlpanel.soundmode == synth {
+        ctlpanel.soundmode = spkr;
+    }
+
+    ctlpanel
 }
 
 ////////////////////////
@@ -1194,16 +1205,18 @@ pub fn CheckMouseMode(pcs: &mut PcrlibCState, sdl: &SdlManager) {
 //
 ////////////////////////
 
-fn _loadctrls(pas: &mut PcrlibAState, pcs: &mut PcrlibCState, sdl: &SdlManager) {
+fn _loadctrls(pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     let str = format!("CTLPANEL.{_extension}");
     // Rust port: the original flags where O_RDONLY, O_BINARY, S_IRUSR, S_IWUSR.
     // For simplicity, we do a standard file open.
-    if let Ok(file) = File::open(&str) {
-        let ctlpanel = ctlpaneltype::deserialize(file).unwrap();
+    if let Ok(data) = fs::read(&str) {
+        let ctlpanel = readctlpanel(data);
 
         pcs.grmode = ctlpanel.grmode as grtype;
+        pcs.enhancedai = ctlpanel.EnhancedAI != 0;
         pas.lock(|pasx| {
             pasx.soundmode = ctlpanel.soundmode as soundtype;
+            pasx.synth.volume = ctlpanel.SynthVolume.min(MAX_SYNTH_VOLUME);
         });
         for i in 0..3 {
             pcs.playermode[i] = ctlpanel.playermode[i].into();
@@ -1213,11 +1226,11 @@ fn _loadctrls(pas: &mut PcrlibAState, pcs: &mut PcrlibCState, sdl: &SdlManager)
             pcs.JoyYhigh[i] = ctlpanel.JoyYhigh[i] as i32;
 
             if pcs.playermode[i] == mouse {
-                CheckMouseMode(pcs, sdl);
+                CheckMouseMode(pcs);
             }
 
+            // Rust port: The joysticks have been probed by the frontend.
             if pcs.playermode[i] == joystick1 || pcs.playermode[i] == joystick2 {
-                ProbeJoysticks(pcs, sdl);
                 if (pcs.playermode[i] == joystick1 && pcs.joystick[1].is_none())
                     || (pcs.playermode[i] == joystick2 && pcs.joystick[2].is_none())
                 {
@@ -1274,6 +1287,8 @@ fn _savectrls(pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     if let Ok(file) = File::create(str) {
         ctlpanel.grmode = pcs.grmode;
         ctlpanel.soundmode = pas.lock(|pasx| pasx.soundmode);
+        ctlpanel.SynthVolume = pas.lock(|pasx| pasx.synth.volume);
+        ctlpanel.EnhancedAI = pcs.enhancedai as u8;
         for i in 0..3 {
             ctlpanel.playermode[i] = pcs.playermode[i] as u16;
             ctlpanel.JoyXlow[i] = pcs.JoyXlow[i] as i16;
@@ -1292,50 +1307,29 @@ fn _savectrls(pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     }
 }
 
+// Rust port: The high scores are stored in a different format and place; see `high_scores`.
 fn _loadhighscores(pcs: &mut PcrlibCState) {
-    let filename = format!("SCORES.{_extension}");
-    let mut buffer = [0_u8; scores::ondisk_struct_size() * 5];
-
-    let bytes_loaded = loadFile(&filename, &mut buffer);
-
-    if bytes_loaded > 0 {
-        // Rust port: there isn't a type for the whole scores data (file), so we deserialize in
-        // chunks.
-        for (highscore, score_buffer) in pcs
-            .highscores
-            .iter_mut()
-            .zip(buffer.chunks_exact(scores::ondisk_struct_size()))
-        {
-            *highscore = Deserialize::deserialize(score_buffer).unwrap();
-        }
-    } else {
-        for i in 0..5 {
-            pcs.highscores[i].score = 100;
-            pcs.highscores[i].level = 1;
-            pcs.highscores[i].initials = "PCR".as_bytes().try_into().unwrap();
-        }
-    }
+    pcs.highscores = loadhighscores();
 }
 
 fn _savehighscores(pcs: &mut PcrlibCState) {
-    let mut buffer = Vec::new();
-
-    Serialize::serialize(&pcs.highscores, &mut buffer).unwrap();
-
-    let str = format!("SCORES.{_extension}");
-
-    SaveFile(&str, &buffer);
+    if let Err(error) = savehighscores(&pcs.highscores) {
+        eprintln!("Error saving the high scores: {}", error);
+    }
 }
 
+// Rust port: The window is wider than the original (17), to fit longer names.
+const HIGHSCORES_WIDTH: i32 = 22;
+
 pub fn _showhighscores(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
     let mut i: i32 = 0;
     let mut h: i64 = 0;
-    centerwindow(17, 17, gs, pcs);
-    print_str("\n   HIGH SCORES\n\n", gs, pcs);
+    centerwindow(HIGHSCORES_WIDTH, 17, gs, pcs);
+    print_str("\n      HIGH SCORES\n\n", gs, pcs);
     print_str(" #  SCORE LV  BY\n", gs, pcs);
-    print_str(" - ------ -- ---\n", gs, pcs);
+    print_str(" - ------ -- --------\n", gs, pcs);
     i = 0;
-    while i < 5 {
+    while i < TABLE_ENTRIES as i32 {
         pcs.sx += 1;
         drawchar(pcs.sx, pcs.sy, '1' as i32 + i, gs, pcs);
         pcs.sx += 2;
@@ -1363,8 +1357,12 @@ pub fn _showhighscores(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
         let str = { pcs.highscores[i as usize].level }.to_string();
         print_str(&str, gs, pcs);
         pcs.sx += 1;
-        let highscore_bytes = &pcs.highscores[i as usize].initials.clone();
-        print(highscore_bytes, gs, pcs);
+        let name = pcs.highscores[i as usize]
+            .name
+            .chars()
+            .take(MAX_NAME_LEN)
+            .collect::<String>();
+        print_str(&name, gs, pcs);
         print_str("\n\n", gs, pcs);
         i += 1;
     }
@@ -1372,64 +1370,156 @@ pub fn _showhighscores(gs: &mut GlobalState, pcs: &mut PcrlibCState) {
     _printc(&str, gs, pcs);
 }
 
-pub fn _checkhighscore(
-    gs: &mut GlobalState,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
-    let mut i: i32 = 0;
-    let mut j: i32 = 0;
-    let mut k: i32 = 0;
-    i = 0;
-    while i < 5 {
-        if pcs.score > pcs.highscores[i as usize].score {
-            j = 4;
-            while i < j {
-                k = j - 1;
-                pcs.highscores[j as usize] = pcs.highscores[k as usize];
-                j -= 1;
-            }
-            pcs.highscores[i as usize].score = pcs.score;
-            pcs.highscores[i as usize].level = pcs.level;
-            pcs.highscores[i as usize].initials = b"   ".to_owned();
-            break;
-        } else {
-            i += 1;
-        }
-    }
+// Rust port: Scores below the table are kept as well (see `high_scores`); their name is entered in
+// a separate window. The name can be longer than the original three initials.
+//
+// The name is entered in a prompt (see `HighScoreName`), opened at the end of the frame.
+pub fn _checkhighscore(gs: &mut GlobalState, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
+    let entry = HighScore::new(pcs.score, pcs.level, pcs.playframes);
+    let rank = inserthighscore(&mut pcs.highscores, entry);
+
     _showhighscores(gs, pcs);
     UpdateScreen(gs, pcs);
-    if i < 5 {
+    if let Some(i) = rank {
         PlaySound(16, pas);
-        clearkeys(pcs, pas, sdl);
-        pcs.sx = gs.screencenter.x - 17 / 2 + 14;
-        pcs.sy = gs.screencenter.y - 17 / 2 + 6 + i * 2;
-        j = 0;
-        loop {
-            k = get(gs, pcs, pas, sdl);
-            let ch = k as i8;
-            if ch >= ' ' as i8 && j < 3 {
-                drawchar(pcs.sx, pcs.sy, ch as i32, gs, pcs);
-                pcs.sx += 1;
-                pcs.highscores[i as usize].initials[j as usize] = ch as u8;
-                j += 1;
-            }
-            if ch as i32 == 8 || k == 19200 {
-                if j > 0 {
-                    pcs.sx -= 1;
-                    j -= 1;
-                }
-            }
-            if !(ch as i32 != 13) {
-                break;
+        clearkeys(pcs, pas);
+        gs.overlay = Some(Overlay::Prompt(Prompt::HighScore(HighScoreName {
+            rank: i,
+            name: vec![],
+        })));
+    }
+}
+
+/// Rust port: Extracted from _checkhighscore(); reads the name of the entry at `rank`, until Enter.
+pub struct HighScoreName {
+    rank: usize,
+    name: Vec<u8>,
+}
+
+impl HighScoreName {
+    pub fn open(
+        self,
+        gs: &mut GlobalState,
+        pas: &mut PcrlibAState,
+        pcs: &mut PcrlibCState,
+    ) -> Self {
+        if self.rank < TABLE_ENTRIES {
+            pcs.sx = gs.screencenter.x - HIGHSCORES_WIDTH / 2 + 14;
+            pcs.sy = gs.screencenter.y - 17 / 2 + 6 + self.rank as i32 * 2;
+        } else {
+            expwin(20, 1, gs, pas, pcs);
+            print_str(&format!("#{} NAME: ", self.rank + 1), gs, pcs);
+        }
+        self
+    }
+
+    /// Returns None once the name has been entered (and the high scores saved).
+    pub fn step(
+        mut self,
+        cycle: &mut i32,
+        gs: &mut GlobalState,
+        pas: &mut PcrlibAState,
+        pcs: &mut PcrlibCState,
+    ) -> Option<Self> {
+        let k = match tryget(cycle, gs, pcs, pas) {
+            Some(k) => k,
+            None => return Some(self),
+        };
+        let ch = k as i8;
+        if ch >= ' ' as i8 && self.name.len() < MAX_NAME_LEN {
+            drawchar(pcs.sx, pcs.sy, ch as i32, gs, pcs);
+            pcs.sx += 1;
+            self.name.push(ch as u8);
+        }
+        if ch as i32 == 8 || k == 19200 {
+            if self.name.pop().is_some() {
+                pcs.sx -= 1;
             }
         }
+        if ch as i32 != 13 {
+            return Some(self);
+        }
+
+        let i = self.rank;
+        pcs.highscores[i].name = String::from_utf8_lossy(&self.name).into_owned();
+        if i >= TABLE_ENTRIES {
+            _showhighscores(gs, pcs);
+            UpdateScreen(gs, pcs);
+        }
+        _savehighscores(pcs);
+        None
     }
 }
 
-const VIDEO_PARAM_WINDOWED: &str = "windowed";
-const VIDEO_PARAM_FULLSCREEN: &str = "screen";
+/// Rust port: Not in the original; the high scores viewer (see `Screen::HighScores`). Shows a page
+/// of the list, starting from `first`, with the dates and play times.
+pub fn _showhighscorelist(
+    sort: HighScoreSort,
+    first: usize,
+    gs: &mut GlobalState,
+    pcs: &mut PcrlibCState,
+) {
+    let sorted = sortedhighscores(&pcs.highscores, sort);
+    let count = sorted.len();
+    let last = (first + HIGHSCORES_PAGE).min(count);
+    let rows = sorted[first..last]
+        .iter()
+        .map(|(rank, entry)| {
+            let name = entry.name.chars().take(MAX_NAME_LEN).collect::<String>();
+            highscorerow(
+                &(rank + 1).to_string(),
+                &entry.score.to_string(),
+                &entry.level.to_string(),
+                &name,
+                // Without the century, to fit the window.
+                &format_timestamp(entry.date)[2..10],
+                &format_playtime(entry.playtime),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    drawwindow(0, 0, 39, 24, gs, pcs);
+    print_str(" HIGH SCORES", gs, pcs);
+    pcs.sx = 29;
+    print_str(
+        match sort {
+            HighScoreSort::Score => "BY SCORE",
+            HighScoreSort::Level => "BY LEVEL",
+        },
+        gs,
+        pcs,
+    );
+    print_str("\n\n", gs, pcs);
+    print_str(
+        &highscorerow("#", "SCORE", "LV", "NAME", "DATE", "TIME"),
+        gs,
+        pcs,
+    );
+    print_str(
+        &highscorerow("-", "-----", "--", "----", "--------", "-------"),
+        gs,
+        pcs,
+    );
+    for row in rows {
+        print_str(&row, gs, pcs);
+    }
+    pcs.sy = 21;
+    print_str(&format!("{}-{} OF {}\n", first + 1, last, count), gs, pcs);
+    print_str("S/L:SORT  UP/DOWN:SCROLL  ESC:DONE ", gs, pcs);
+}
+
+pub const HIGHSCORES_PAGE: usize = 15;
+
+fn highscorerow(
+    rank: &str,
+    score: &str,
+    level: &str,
+    name: &str,
+    date: &str,
+    time: &str,
+) -> String {
+    format!("{rank:>2} {score:>6} {level:>2} {name:<8} {date:<8} {time:>7}\n")
+}
 
 ////////////////////
 //
@@ -1437,124 +1527,17 @@ const VIDEO_PARAM_FULLSCREEN: &str = "screen";
 //
 ////////////////////
 
-pub fn _setupgame<'tc, 'ts>(
+// Rust port: The window, the renderer, the sound device and the VBL timer are set up by the frontend
+// (see `frontend`), which also parses the screen mode parameters; the joysticks it found are passed.
+pub fn _setupgame(
     gs: &mut GlobalState,
     cps: &mut CpanelState,
     pas: &mut PcrlibAState,
-    sdl: &SdlManager,
-    texture_creator: &'tc mut Option<TextureCreator<WindowContext>>,
-    timer_sys: &'ts TimerSubsystem,
-) -> (
-    PcrlibCState<'tc>,
-    Timer<'ts, 'ts>,
-    Option<AudioDevice<Sound>>,
-) {
-    let mut windowed = false;
-    let mut winWidth = 640;
-    let mut winHeight = 480;
-    let mut displayindex = 0;
-
-    // Rust port: It's possible to iterate `Args`, although it doesn't get much cleaner.
-    let args = std::env::args().into_iter().collect::<Vec<_>>();
-
-    if let Some(screen_mode) = args.get(1) {
-        match screen_mode.as_str() {
-            VIDEO_PARAM_WINDOWED => {
-                if args.len() == 4 {
-                    winWidth = args[2]
-                        .parse()
-                        .expect(&format!("Invalid width parameter: {}", args[2]));
-                    winHeight = args[3]
-                        .parse()
-                        .expect(&format!("Invalid height parameter: {}", args[3]));
-                } else {
-                    panic!("Incorrect number of windowed mode parameters");
-                }
-
-                windowed = true;
-            }
-            VIDEO_PARAM_FULLSCREEN => {
-                if args.len() == 3 {
-                    displayindex = args[2]
-                        .parse()
-                        .expect(&format!("Invalid screen parameter: {}", args[2]));
-                } else {
-                    panic!("Incorrect number of screen mode parameters");
-                }
-            }
-            _ => panic!("Unexpected screen mode parameter"),
-        }
-    }
-
-    let mut mode = sdl
-        .video()
-        .current_display_mode(displayindex)
-        .expect("Could not get display mode");
-
-    let mut bounds = sdl
-        .video()
-        .display_bounds(displayindex)
-        .expect("Could not get display mode");
-
-    let window_flags = if windowed {
-        // Rust port: the SDL port intentionally chooses SDL_WINDOWPOS_UNDEFINED; this has different
-        // default behavior, depending on the system.
-        bounds.x = sdl2::sys::SDL_WINDOWPOS_UNDEFINED_MASK as i32;
-        bounds.y = sdl2::sys::SDL_WINDOWPOS_UNDEFINED_MASK as i32;
-        mode.w = winWidth as i32;
-        mode.h = winHeight as i32;
-        0
-        // Rust port: WindowBuilder's defaults are position:undefined and flags:0.
-    } else {
-        // Rust port: There's a an explicit API for this, but then we need to separate the conditionals
-        // and initialize the window builder in the middle.
-        SDL_WindowFlags::SDL_WINDOW_FULLSCREEN_DESKTOP as u32
-    };
-
-    let pcs_window = sdl
-        .video()
-        .window("The Catacomb", mode.w as u32, mode.h as u32)
-        .set_window_flags(window_flags)
-        .position(bounds.x, bounds.y)
-        .build()
-        .expect("Failed to create SDL window");
-
-    // Rust port: the error message is not exact (copied from the SDL port).
-    // The default flags in the Rust library are 0, like the C SDL port.
-    // The rendering driver index is not set, which is equivalent to the SDL port -1.
-    let pcs_renderer = pcs_window
-        .into_canvas()
-        .build()
-        .expect("Failed to create SDL window");
-
-    texture_creator.replace(pcs_renderer.texture_creator());
-
-    let pcs_sdltexture = texture_creator
-        .as_ref()
-        .unwrap()
-        .create_texture(
-            PixelFormatEnum::ARGB8888,
-            TextureAccess::Streaming,
-            320,
-            200,
-        )
-        .expect("Could not create video buffer");
-
-    let mut pcs_updateRect = Rect::new(0, 0, 0, 0);
-
-    // Handle 320x200 and 640x400 specially so they are unscaled.
-    if mode.w == 320 && mode.h == 200 || mode.w == 640 && mode.h == 400 {
-        pcs_updateRect.w = mode.w;
-        pcs_updateRect.h = mode.h;
-        pcs_updateRect.y = 0;
-        pcs_updateRect.x = pcs_updateRect.y;
-    } else {
-        // Pillar box the 4:3 game
-        pcs_updateRect.h = mode.h;
-        pcs_updateRect.w = mode.h * 4 / 3;
-        pcs_updateRect.x = (mode.w - pcs_updateRect.w) >> 1;
-        pcs_updateRect.y = 0;
-    }
+    levelpack: Option<PathBuf>,
+    joysticks: [Option<JoystickInput>; 3],
+) -> PcrlibCState {
+    // Rust port: Not in the original; the level pack parameter is taken by the frontend.
+    gs.levelpack = levelpack;
 
     // Rust port: unnecessary in Rust
     // gs.screenseg.fill(0);
@@ -1565,12 +1548,11 @@ pub fn _setupgame<'tc, 'ts>(
     // Rust port: This is just a null value initialization; it's overwritten immediately by _loadctrls()
     // let mut pcs_grmode = EGAgr;
 
-    // Invalidate joysticks.
-    let pcs_joystick = [None, None, None];
+    let mut pcs = PcrlibCState::new();
 
-    let mut pcs = PcrlibCState::new(pcs_renderer, pcs_sdltexture, pcs_updateRect, pcs_joystick);
+    pcs.joystick = joysticks;
 
-    _loadctrls(pas, &mut pcs, sdl);
+    _loadctrls(pas, &mut pcs);
 
     if pcs.grmode == VGAgr && _vgaok {
         pcs.grmode = VGAgr;
@@ -1587,7 +1569,7 @@ pub fn _setupgame<'tc, 'ts>(
         pas.SoundData = SPKRtable::deserialize(sound_data_buffer.as_slice()).unwrap();
     });
 
-    let audio_dev = StartupSound(pas, sdl);
+    // Rust port: StartupSound() is called by the frontend, which owns the device.
 
     // Rust port: unnecessary (see method)
     // SetupKBD(&mut pcs);
@@ -1599,12 +1581,7 @@ pub fn _setupgame<'tc, 'ts>(
 
     loadgrfiles(gs, cps, &mut pcs);
 
-    // Rust port: This needs to stay outside a global state instance, otherwise the lifetime becomes
-    // too restrictive. It doesn't make much sense anyway, to keep it there, since it's not associated
-    // to a specific scope.
-    let vbl_timer = SetupEmulatedVBL(timer_sys);
-
-    (pcs, vbl_timer, audio_dev)
+    pcs
 }
 
 ////////////////////
@@ -1615,16 +1592,12 @@ pub fn _setupgame<'tc, 'ts>(
 
 // Rust port: Redesigning the exit is a pain. The original game was not designed to exit by interrupting
 // the main loop, so even if exit points are added to the code on each level leading to the _quit() call,
-// the game may still not exit in certain points. For this reason, the SDL port approach is left as is.
+// the game may still not exit in certain points. For this reason, the exit is requested to the frontend,
+// which carries it out at the end of the frame (see `FrameOutput::quit`).
 //
 // Rust port: There are no occurrences (in the SDL port, at least) where an error is passed.
 // In the original version, there are two cases - out of memory, and a certain EXE file not found.
-pub fn _quit(
-    error: Option<String>,
-    pas: &mut PcrlibAState,
-    pcs: &mut PcrlibCState,
-    sdl: &mut SdlManager,
-) {
+pub fn _quit(error: Option<String>, pas: &mut PcrlibAState, pcs: &mut PcrlibCState) {
     let exit_code = if let Some(error) = &error {
         print!("{}", error);
         println!();
@@ -1634,14 +1607,130 @@ pub fn _quit(
         println!();
         1
     } else {
-        _savehighscores(pcs);
         _savectrls(pas, pcs);
         0
     };
 
-    // Rust port: We don't need manual clearing; this will cascade-drop all the systems, since the
-    // Sdl instance is dropped inside the method.
-    sdl.quit();
+    pcs.quit = Some(exit_code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(joynum: usize, pressed: bool) -> InputEvent {
+        InputEvent::ControllerButton {
+            joynum,
+            scancode: SDL_SCANCODE_B,
+            pressed,
+        }
+    }
+
+    fn process(events: Vec<InputEvent>, pcs: &mut PcrlibCState, pas: &mut PcrlibAState) {
+        pcs.events.extend(events);
+        ProcessEvents(pcs, pas);
+    }
+
+    #[test]
+    fn controller_releases_keep_the_keyboard_keys() {
+        let mut pcs = PcrlibCState::new();
+        let mut pas = PcrlibAState::new();
+        pcs.playermode[1] = joystick1;
+        let b = SDL_SCANCODE_B as usize;
+
+        process(
+            vec![
+                InputEvent::KeyDown {
+                    scancode: SDL_SCANCODE_B,
+                    keycode: SDLK_b,
+                },
+                button(1, true),
+                button(1, false),
+            ],
+            &mut pcs,
+            &mut pas,
+        );
+        assert!(pcs.keydown[b]);
+
+        process(
+            vec![
+                button(1, true),
+                InputEvent::KeyUp {
+                    scancode: SDL_SCANCODE_B,
+                },
+            ],
+            &mut pcs,
+            &mut pas,
+        );
+        assert!(pcs.keydown[b]);
+
+        process(vec![button(1, false)], &mut pcs, &mut pas);
+        assert!(!pcs.keydown[b]);
+    }
+
+    #[test]
+    fn controller_releases_are_always_applied() {
+        let mut pcs = PcrlibCState::new();
+        let mut pas = PcrlibAState::new();
+        pcs.playermode[1] = joystick1;
+        let b = SDL_SCANCODE_B as usize;
 
-    std::process::exit(exit_code);
+        // Other controllers are ignored.
+        process(vec![button(2, true)], &mut pcs, &mut pas);
+        assert!(!pcs.keydown[b]);
+
+        // The player switches to the keyboard while holding the button.
+        process(vec![button(1, true)], &mut pcs, &mut pas);
+        pcs.playermode[1] = keyboard;
+        process(vec![button(1, false)], &mut pcs, &mut pas);
+        assert!(!pcs.keydown[b]);
+
+        // The controller is unplugged while holding the button.
+        pcs.playermode[1] = joystick1;
+        process(
+            vec![button(1, true), InputEvent::JoysticksChanged],
+            &mut pcs,
+            &mut pas,
+        );
+        assert!(!pcs.keydown[b]);
+        assert!(!PadKeyHeld(SDL_SCANCODE_B, &pcs));
+    }
+
+    fn ctlpanel_data(soundmode: soundtype) -> Vec<u8> {
+        let mut data = vec![];
+        ctlpaneltype {
+            soundmode,
+            SynthVolume: 3,
+            ..ctlpaneltype::default()
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data
+    }
+
+    #[test]
+    fn original_adlib_mode_is_loaded_as_speaker() {
+        let mut data = ctlpanel_data(synth);
+        data.truncate(ORIGINAL_CTLPANEL_LEN);
+        let ctlpanel = readctlpanel(data);
+        assert!(ctlpanel.soundmode == spkr);
+        assert_eq!(ctlpanel.SynthVolume, DEFAULT_SYNTH_VOLUME);
+
+        let mut data = ctlpanel_data(off);
+        data.truncate(ORIGINAL_CTLPANEL_LEN);
+        assert!(readctlpanel(data).soundmode == off);
+    }
+
+    #[test]
+    fn port_synth_mode_is_kept() {
+        let ctlpanel = readctlpanel(ctlpanel_data(synth));
+        assert!(ctlpanel.soundmode == synth);
+        assert_eq!(ctlpanel.SynthVolume, 3);
+
+        assert_eq!(
+            ctlpanel_data(off).len(),
+            ORIGINAL_CTLPANEL_LEN + 2,
+            "only SynthVolume and EnhancedAI are added by the port"
+        );
+    }
 }
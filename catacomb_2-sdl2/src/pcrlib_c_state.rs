@@ -1,20 +1,18 @@
-use sdl2::{
-    rect::Rect,
-    render::{Texture, WindowCanvas},
-};
+use std::collections::VecDeque;
 
 use crate::{
+    frame_input::{InputEvent, JoystickInput, MouseInput},
+    frame_output::{Display, Rumble},
     gr_type::grtype::{self, *},
+    high_scores::HighScore,
     input_type::inputtype::{self, *},
-    pcrlib_c::joyinfo_t,
     scan_codes::{SDL_Scancode, SDL_SCANCODE_UNKNOWN},
-    scores::scores,
 };
 
 // Globals previously belonging to pcrlib_c.rs.
 //
 #[rustfmt::skip]
-pub struct PcrlibCState<'t> {
+pub struct PcrlibCState {
     // //////////////////////////////////////////////////////////
     // Rust port: shared
     // //////////////////////////////////////////////////////////
@@ -39,9 +37,11 @@ pub struct PcrlibCState<'t> {
     pub sx: i32,
     pub sy: i32,
     pub leftedge: i32,
-    pub highscores: [scores; 5],
+    pub highscores: Vec<HighScore>, // Rust port: see `high_scores`
     pub score: i32,
     pub level: i16,
+    pub playframes: u32,  // Rust port: Added; frames played in the current game
+    pub enhancedai: bool, // Rust port: Added; see `ai`
 
     // //////////////////////////////////////////////////////////
     // Rust port: private to pcrlib_c.rs
@@ -53,13 +53,23 @@ pub struct PcrlibCState<'t> {
     // pub democount: i32,            // Rust port: Never used
     // pub lastdemoval: i32,          // Rust port: Never used
     pub lastkey: SDL_Scancode,
-    // pub window: Window, // Rust port: not needed, as we can get the ref from the renderer
-    // Rust port: the Option here is quite ugly, but needed in order to perform drop on _quit().
-    pub renderer: Option<WindowCanvas>,
-    pub sdltexture: Texture<'t>,
-    pub updateRect: Rect,
+    // Rust port: The window, renderer, texture and devices belong to the frontend (see `frontend`);
+    // the game exchanges the following with it, through `Engine::step()`.
+    // pub window: Window,
+    // pub renderer: WindowCanvas,
+    // pub sdltexture: Texture,
+    // pub updateRect: Rect,
     // pub mode: DisplayMode,         // Rust port: Never used
-    pub joystick: [Option<joyinfo_t>; 3],
+    pub events: VecDeque<InputEvent>, // Rust port: Added; events not read yet
+    pub keycode: [i32; 512],          // Rust port: Added; keycode of the last key down, by scancode
+    pub kbdkeydown: [bool; 512],      // Rust port: Added; keys held on the keyboard
+    pub padkeydown: [[bool; 512]; 3], // Rust port: Added; keys held on each controller
+    pub mouse: MouseInput,            // Rust port: Added
+    pub joystick: [Option<JoystickInput>; 3],
+    pub display: Vec<Display>,        // Rust port: Added
+    pub relativemouse: bool,          // Rust port: Added; see CheckMouseMode()
+    pub rumble: Vec<Rumble>,          // Rust port: Added
+    pub quit: Option<i32>,            // Rust port: Added; see _quit()
     pub hasFocus: bool,
     pub win_xl: i32,
     pub win_yl: i32,
@@ -68,21 +78,22 @@ pub struct PcrlibCState<'t> {
     pub conv: [u32; 64000],
 }
 
-impl<'t> PcrlibCState<'t> {
-    pub fn new(
-        renderer: WindowCanvas,
-        sdltexture: Texture<'t>,
-        updateRect: Rect,
-        joystick: [Option<joyinfo_t>; 3],
-    ) -> Self {
+impl PcrlibCState {
+    pub fn new() -> Self {
         Self {
             mouseEvent: false,
             demobuffer: [0; 5000],
             demoptr: 0,
             lastkey: SDL_SCANCODE_UNKNOWN,
-            renderer: Some(renderer),
-            sdltexture,
-            updateRect,
+            events: VecDeque::new(),
+            keycode: [0; 512],
+            kbdkeydown: [false; 512],
+            padkeydown: [[false; 512]; 3],
+            mouse: MouseInput::default(),
+            display: vec![],
+            relativemouse: false,
+            rumble: vec![],
+            quit: None,
             playermode: [keyboard, keyboard, joystick1],
             keydown: [false; 512],
             JoyXlow: [0; 3],
@@ -93,7 +104,7 @@ impl<'t> PcrlibCState<'t> {
             key: [0; 8],
             keyB1: 0,
             keyB2: 0,
-            joystick,
+            joystick: [None; 3],
             hasFocus: true,
             win_xl: 0,
             win_yl: 0,
@@ -108,13 +119,11 @@ impl<'t> PcrlibCState<'t> {
             sx: 0,
             sy: 0,
             leftedge: 0,
-            highscores: [scores {
-                score: 0,
-                level: 0,
-                initials: [0; 3],
-            }; 5],
+            highscores: vec![],
             score: 0,
             level: 0,
+            playframes: 0,
+            enhancedai: false,
         }
     }
 }
@@ -24,3 +24,85 @@ pub fn RLEExpand(source: &[u8], source_len: usize, dest: &mut Vec<u8>) {
         }
     }
 }
+
+// Rust port: Not in the original project, which shipped the levels already compressed; this is the
+// inverse of RLEExpand(), used by the level editor.
+// Runs of 3+ identical bytes are encoded as (len - 3, byte), with len up to 130; anything else is
+// encoded as literal blocks of up to 128 bytes, prefixed by (len - 1) | 0x80.
+pub fn RLECompress(source: &[u8], dest: &mut Vec<u8>) {
+    let mut source_i = 0;
+    let mut literal_start = 0;
+
+    let flush_literals = |from: usize, to: usize, dest: &mut Vec<u8>| {
+        for block in source[from..to].chunks(128) {
+            dest.push((block.len() - 1) as u8 | 0x80);
+            dest.extend(block);
+        }
+    };
+
+    while source_i < source.len() {
+        let val = source[source_i];
+        let run_len = source[source_i..]
+            .iter()
+            .take(130)
+            .take_while(|&&b| b == val)
+            .count();
+
+        if run_len >= 3 {
+            flush_literals(literal_start, source_i, dest);
+            dest.push((run_len - 3) as u8);
+            dest.push(val);
+            source_i += run_len;
+            literal_start = source_i;
+        } else {
+            source_i += run_len;
+        }
+    }
+
+    flush_literals(literal_start, source.len(), dest);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(source: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        RLECompress(source, &mut compressed);
+
+        let mut expanded = Vec::new();
+        RLEExpand(&compressed, compressed.len(), &mut expanded);
+        assert_eq!(expanded, source);
+
+        compressed
+    }
+
+    #[test]
+    fn empty_input_round_trips() {
+        assert!(round_trip(&[]).is_empty());
+    }
+
+    #[test]
+    fn runs_longer_than_the_cap_round_trip() {
+        for len in [3, 129, 130, 131, 132, 133, 260, 1000] {
+            let compressed = round_trip(&vec![7; len]);
+            // Every run but the last has the maximum length.
+            assert!(compressed.len() <= (len / 130 + 1) * 2 + 2, "{}", len);
+        }
+
+        let mut mixed = vec![1; 300];
+        mixed.extend([2, 3, 3, 4]);
+        mixed.extend(vec![0; 131]);
+        round_trip(&mixed);
+    }
+
+    #[test]
+    fn data_without_runs_round_trips() {
+        for len in [1_usize, 2, 127, 128, 129, 256, 300] {
+            let source: Vec<u8> = (0..len).map(|i| (i % 2 + i / 7) as u8).collect();
+            let compressed = round_trip(&source);
+            // Only the block prefixes are added.
+            assert_eq!(compressed.len(), len + len.div_ceil(128), "{}", len);
+        }
+    }
+}
@@ -0,0 +1,460 @@
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serdine::derive::{Deserialize, Serialize};
+use serdine::{Deserialize as DeserializeTrait, Serialize as SerializeTrait};
+
+use crate::{
+    active_obj::activeobj,
+    class_type::classtype,
+    extra_constants::{_extension, numlevels},
+};
+
+// Rust port: Save games used to be a raw dump of (saveitems, savescore, level, saveo[0]) in
+// `GAMEn.CA2`, with no way to detect a truncated or foreign file. The format below adds a header
+// with a version and a checksum of the payload; the payload is the same data, plus a timestamp.
+// Legacy files are still accepted on load, but never written.
+//
+// The files are in the save directory (see `GlobalState::savedir`), which is the current directory
+// when playing, as in the original.
+
+const SAVE_MAGIC: [u8; 8] = *b"CA2SAVE\x1a";
+const SAVE_VERSION: u16 = 1;
+
+const HEADER_SIZE: usize = 8 + 2 + 4;
+const PAYLOAD_SIZE: usize = 8 + 2 + 4 + 12 + ACTIVEOBJ_SIZE;
+const LEGACY_SIZE: usize = 12 + 4 + 2 + ACTIVEOBJ_SIZE;
+const ACTIVEOBJ_SIZE: usize = 15;
+
+/// Slots up to this number can be backed by a legacy `GAMEn.CA2` file.
+const LEGACY_MAX_SLOT: u32 = 9;
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct SaveGame {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub level: i16,
+    pub score: i32,
+    pub items: [i16; 6],
+    pub player: activeobj,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SaveSlot {
+    Auto,
+    Numbered(u32),
+}
+
+pub enum SaveGameError {
+    Io(io::Error),
+    Truncated,
+    Unrecognized,
+    BadVersion(u16),
+    BadChecksum,
+    InvalidField(&'static str),
+}
+
+impl From<io::Error> for SaveGameError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => Self::Truncated,
+            _ => Self::Io(error),
+        }
+    }
+}
+
+/// Messages are kept short, since they're printed in the game windows.
+impl fmt::Display for SaveGameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) if error.kind() == io::ErrorKind::NotFound => {
+                write!(f, "Game not found.")
+            }
+            Self::Io(_) => write!(f, "File error."),
+            Self::Truncated => write!(f, "Save is truncated."),
+            Self::Unrecognized => write!(f, "Not a save game."),
+            Self::BadVersion(version) => write!(f, "Unknown version {version}."),
+            Self::BadChecksum => write!(f, "Save is corrupt."),
+            Self::InvalidField(field) => write!(f, "Bad {field} in save."),
+        }
+    }
+}
+
+impl SaveSlot {
+    /// Slot 0 is the autosave; anything else is a numbered slot.
+    pub fn from_number(number: u32) -> Self {
+        if number == 0 {
+            Self::Auto
+        } else {
+            Self::Numbered(number)
+        }
+    }
+
+    pub fn filename(&self) -> String {
+        match self {
+            Self::Auto => format!("AUTOSAVE.{_extension}"),
+            Self::Numbered(number) => format!("SAVE{number}.{_extension}"),
+        }
+    }
+
+    fn legacy_filename(&self) -> Option<String> {
+        match self {
+            Self::Numbered(number) if *number <= LEGACY_MAX_SLOT => {
+                Some(format!("GAME{number}.{_extension}"))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Auto => "A".to_string(),
+            Self::Numbered(number) => number.to_string(),
+        }
+    }
+
+    fn from_filename(filename: &str) -> Option<Self> {
+        let stem = filename.strip_suffix(&format!(".{_extension}"))?;
+
+        if stem == "AUTOSAVE" {
+            return Some(Self::Auto);
+        }
+
+        let number = stem
+            .strip_prefix("SAVE")
+            .or_else(|| stem.strip_prefix("GAME"))?;
+
+        // Reject signs, spaces etc., which `parse()` would partially accept.
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        match number.parse() {
+            Ok(0) | Err(_) => None,
+            Ok(number) => Some(Self::Numbered(number)),
+        }
+    }
+
+    pub fn exists(&self, dir: &Path) -> bool {
+        self.existing_path(dir).is_some()
+    }
+
+    /// The new format takes precedence over a legacy file in the same slot.
+    fn existing_path(&self, dir: &Path) -> Option<PathBuf> {
+        let candidates = [Some(self.filename()), self.legacy_filename()];
+
+        candidates
+            .iter()
+            .flatten()
+            .map(|filename| dir.join(filename))
+            .find(|path| fs::metadata(path).is_ok())
+    }
+}
+
+impl SaveGame {
+    pub fn new(level: i16, score: i32, items: [i16; 6], player: activeobj) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        Self {
+            timestamp,
+            level,
+            score,
+            items,
+            player,
+        }
+    }
+
+    pub fn write(&self, slot: SaveSlot, dir: &Path) -> Result<(), SaveGameError> {
+        let buffer = self.encode()?;
+
+        // Rust port: Write to a temporary file first, so that a crash while saving can't destroy
+        // the previous save in the slot.
+        let filename = slot.filename();
+        let temp_path = dir.join(format!("{filename}.TMP"));
+        fs::write(&temp_path, buffer)?;
+        fs::rename(temp_path, dir.join(filename))?;
+
+        Ok(())
+    }
+
+    pub fn read(slot: SaveSlot, dir: &Path) -> Result<Self, SaveGameError> {
+        let path = slot
+            .existing_path(dir)
+            .unwrap_or_else(|| dir.join(slot.filename()));
+
+        Self::decode(&fs::read(path)?)
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, SaveGameError> {
+        let mut payload = Vec::with_capacity(PAYLOAD_SIZE);
+        self.serialize(&mut payload)?;
+
+        let mut buffer = Vec::with_capacity(HEADER_SIZE + PAYLOAD_SIZE);
+        buffer.write_all(&SAVE_MAGIC)?;
+        SAVE_VERSION.serialize(&mut buffer)?;
+        checksum(&payload).serialize(&mut buffer)?;
+        buffer.extend(payload);
+
+        Ok(buffer)
+    }
+
+    fn decode(buffer: &[u8]) -> Result<Self, SaveGameError> {
+        let save_game = if let Some(rest) = buffer.strip_prefix(&SAVE_MAGIC) {
+            let mut rest = rest;
+            let version = u16::deserialize(&mut rest)?;
+            if version != SAVE_VERSION {
+                return Err(SaveGameError::BadVersion(version));
+            }
+            let stored_checksum = u32::deserialize(&mut rest)?;
+            if rest.len() < PAYLOAD_SIZE {
+                return Err(SaveGameError::Truncated);
+            }
+            if rest.len() > PAYLOAD_SIZE || checksum(rest) != stored_checksum {
+                return Err(SaveGameError::BadChecksum);
+            }
+            check_player_class(&rest[PAYLOAD_SIZE - ACTIVEOBJ_SIZE..])?;
+
+            Self::deserialize(rest)?
+        } else {
+            Self::decode_legacy(buffer)?
+        };
+
+        save_game.validate()?;
+
+        Ok(save_game)
+    }
+
+    /// Legacy layout: items, score, level, player; there is no timestamp, so it's reported as 0.
+    fn decode_legacy(buffer: &[u8]) -> Result<Self, SaveGameError> {
+        if buffer.len() < LEGACY_SIZE {
+            return Err(SaveGameError::Truncated);
+        } else if buffer.len() > LEGACY_SIZE {
+            return Err(SaveGameError::Unrecognized);
+        }
+        check_player_class(&buffer[LEGACY_SIZE - ACTIVEOBJ_SIZE..])?;
+
+        let mut buffer = buffer;
+
+        Ok(Self {
+            items: DeserializeTrait::deserialize(&mut buffer)?,
+            score: DeserializeTrait::deserialize(&mut buffer)?,
+            level: DeserializeTrait::deserialize(&mut buffer)?,
+            player: DeserializeTrait::deserialize(&mut buffer)?,
+            timestamp: 0,
+        })
+    }
+
+    fn validate(&self) -> Result<(), SaveGameError> {
+        if self.level < 1 || self.level > numlevels {
+            return Err(SaveGameError::InvalidField("level"));
+        }
+        if self.score < 0 {
+            return Err(SaveGameError::InvalidField("score"));
+        }
+        if self.items.iter().any(|&count| count < 0) {
+            return Err(SaveGameError::InvalidField("items"));
+        }
+        // The level occupies 64x64 tiles, starting at 11 on both axes.
+        let on_map = |coordinate: u8| (11..11 + 64).contains(&coordinate);
+        if !on_map(self.player.x) || !on_map(self.player.y) {
+            return Err(SaveGameError::InvalidField("position"));
+        }
+        if self.player.hp <= 0 || self.player.dir > 7 {
+            return Err(SaveGameError::InvalidField("player"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns all the slots with a save file, in slot order, each with the result of loading it, so
+/// that corrupt saves can be listed as such.
+pub fn list_saves(dir: &Path) -> Vec<(SaveSlot, Result<SaveGame, SaveGameError>)> {
+    let mut slots = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .filter_map(|filename| SaveSlot::from_filename(&filename.to_ascii_uppercase()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    slots.sort();
+    slots.dedup();
+
+    slots
+        .into_iter()
+        .map(|slot| (slot, SaveGame::read(slot, dir)))
+        .collect()
+}
+
+/// Rust port: serdine doesn't know which discriminants are valid for `classtype`, so the class is
+/// checked on the raw record before deserializing it.
+fn check_player_class(activeobj_bytes: &[u8]) -> Result<(), SaveGameError> {
+    let class = u16::from_le_bytes([activeobj_bytes[1], activeobj_bytes[2]]);
+
+    if class == classtype::player as u16 {
+        Ok(())
+    } else {
+        Err(SaveGameError::InvalidField("class"))
+    }
+}
+
+/// CRC-32 (IEEE); speed is not a concern for a few dozen bytes, so there's no table.
+fn checksum(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM` (UTC); 0 (legacy saves) is printed as unknown.
+pub fn format_timestamp(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "----------------".to_string();
+    }
+
+    let days = (timestamp / 86400) as i64;
+    let seconds_of_day = timestamp % 86400;
+
+    // Days to civil date; see http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> activeobj {
+        activeobj {
+            active: true,
+            class: classtype::player,
+            x: 12,
+            y: 40,
+            stage: 0,
+            delay: 0,
+            dir: 2,
+            hp: 13,
+            oldx: 12,
+            oldy: 40,
+            oldtile: 0,
+            filler: [0],
+        }
+    }
+
+    fn save_game() -> SaveGame {
+        SaveGame {
+            timestamp: 1_700_000_000,
+            level: 3,
+            score: 12345,
+            items: [1, 0, 2, 0, 5, 1],
+            player: player(),
+        }
+    }
+
+    fn assert_same(decoded: &SaveGame, expected: &SaveGame) {
+        assert_eq!(decoded.timestamp, expected.timestamp);
+        assert_eq!(decoded.level, expected.level);
+        assert_eq!(decoded.score, expected.score);
+        assert_eq!(decoded.items, expected.items);
+        assert_eq!(
+            (decoded.player.x, decoded.player.y, decoded.player.hp),
+            (expected.player.x, expected.player.y, expected.player.hp)
+        );
+        assert!(decoded.player.class == classtype::player);
+    }
+
+    #[test]
+    fn save_games_round_trip() {
+        let buffer = save_game().encode().ok().unwrap();
+        assert_eq!(buffer.len(), HEADER_SIZE + PAYLOAD_SIZE);
+
+        let decoded = SaveGame::decode(&buffer).ok().unwrap();
+        assert_same(&decoded, &save_game());
+    }
+
+    #[test]
+    fn corrupt_save_games_are_rejected() {
+        let buffer = save_game().encode().ok().unwrap();
+
+        let mut flipped_payload = buffer.clone();
+        flipped_payload[HEADER_SIZE + 10] ^= 0x01;
+        assert!(matches!(
+            SaveGame::decode(&flipped_payload),
+            Err(SaveGameError::BadChecksum)
+        ));
+
+        let mut flipped_checksum = buffer.clone();
+        flipped_checksum[HEADER_SIZE - 1] ^= 0x80;
+        assert!(matches!(
+            SaveGame::decode(&flipped_checksum),
+            Err(SaveGameError::BadChecksum)
+        ));
+
+        assert!(matches!(
+            SaveGame::decode(&buffer[..buffer.len() - 1]),
+            Err(SaveGameError::Truncated)
+        ));
+
+        let mut future_version = buffer;
+        future_version[SAVE_MAGIC.len()] = 2;
+        assert!(matches!(
+            SaveGame::decode(&future_version),
+            Err(SaveGameError::BadVersion(2))
+        ));
+    }
+
+    #[test]
+    fn legacy_save_games_are_decoded() {
+        let expected = SaveGame {
+            timestamp: 0,
+            ..save_game()
+        };
+
+        let mut buffer = Vec::new();
+        expected.items.serialize(&mut buffer).ok().unwrap();
+        expected.score.serialize(&mut buffer).ok().unwrap();
+        expected.level.serialize(&mut buffer).ok().unwrap();
+        expected.player.serialize(&mut buffer).ok().unwrap();
+        assert_eq!(buffer.len(), LEGACY_SIZE);
+
+        let decoded = SaveGame::decode(&buffer).ok().unwrap();
+        assert_same(&decoded, &expected);
+
+        buffer.push(0);
+        assert!(matches!(
+            SaveGame::decode(&buffer),
+            Err(SaveGameError::Unrecognized)
+        ));
+    }
+}
@@ -90,6 +90,32 @@ pub const SDL_SCANCODE_B: SDL_Scancode = 5;
 pub const SDL_SCANCODE_A: SDL_Scancode = 4;
 pub const SDL_SCANCODE_UNKNOWN: SDL_Scancode = 0;
 
+/// Rust port: Keycodes (which depend on the keyboard layout), as returned by get(); only the ones the
+/// game compares against.
+pub type SDL_Keycode = i32;
+
+pub const SDLK_UP: SDL_Keycode = 1073741906;
+pub const SDLK_DOWN: SDL_Keycode = 1073741905;
+pub const SDLK_LEFT: SDL_Keycode = 1073741904;
+pub const SDLK_RIGHT: SDL_Keycode = 1073741903;
+pub const SDLK_F8: SDL_Keycode = 1073741889;
+pub const SDLK_KP_PLUS: SDL_Keycode = 1073741911;
+pub const SDLK_KP_MINUS: SDL_Keycode = 1073741910;
+pub const SDLK_ESCAPE: SDL_Keycode = 27;
+pub const SDLK_RETURN: SDL_Keycode = 13;
+pub const SDLK_SPACE: SDL_Keycode = 32;
+pub const SDLK_MINUS: SDL_Keycode = 45;
+pub const SDLK_EQUALS: SDL_Keycode = 61;
+pub const SDLK_a: SDL_Keycode = 97;
+pub const SDLK_b: SDL_Keycode = 98;
+pub const SDLK_g: SDL_Keycode = 103;
+pub const SDLK_k: SDL_Keycode = 107;
+pub const SDLK_l: SDL_Keycode = 108;
+pub const SDLK_n: SDL_Keycode = 110;
+pub const SDLK_p: SDL_Keycode = 112;
+pub const SDLK_s: SDL_Keycode = 115;
+pub const SDLK_w: SDL_Keycode = 119;
+
 pub const DOSScanCodeMap: [SDL_Scancode; 128] = [
     SDL_SCANCODE_UNKNOWN,
     SDL_SCANCODE_ESCAPE,
@@ -1,5 +1,6 @@
 use std::{
     cell::{RefCell, RefMut},
+    path::Path,
     rc::Rc,
 };
 
@@ -8,6 +9,8 @@ use sdl2::{
     JoystickSubsystem, Sdl, TimerSubsystem, VideoSubsystem,
 };
 
+const CONTROLLER_MAPPINGS_FILE: &str = "gamecontrollerdb.txt";
+
 #[derive(Clone)]
 pub struct SdlManager {
     /// The Rc is necessary in order to be used in Sdl events.
@@ -27,6 +30,14 @@ impl SdlManager {
         let audio = sdl.audio().unwrap();
         let joystick = sdl.joystick().unwrap();
         let game_controller = sdl.game_controller().unwrap();
+
+        // Mappings for controllers unknown to SDL, in the SDL_GameControllerDB format.
+        if Path::new(CONTROLLER_MAPPINGS_FILE).is_file() {
+            if let Err(error) = game_controller.load_mappings(CONTROLLER_MAPPINGS_FILE) {
+                eprintln!("Error loading {}: {}", CONTROLLER_MAPPINGS_FILE, error);
+            }
+        }
+
         let event_pump = Rc::new(RefCell::new(sdl.event_pump().unwrap()));
 
         Self {
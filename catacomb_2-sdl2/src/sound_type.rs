@@ -5,7 +5,9 @@ use serdine::derive::{Deserialize, Serialize};
 #[derive(Clone, Copy, Default, Deserialize, FromPrimitive, PartialEq, Serialize)]
 #[repr(u16)]
 pub enum soundtype {
-    sdlib = 2,
+    // Rust port: This was `sdlib` (AdLib), which was never supported; the slot is used for the
+    // synthesizer, and original control panel files with it load as `spkr` (see readctlpanel()).
+    synth = 2,
     spkr = 1,
     #[default]
     off = 0,
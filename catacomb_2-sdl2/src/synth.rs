@@ -0,0 +1,148 @@
+use std::f32::consts::PI;
+
+// Rust port: Alternative to the PC speaker emulation (see `Sound`); it plays the same frequency
+// sequences, but the square wave is band-limited (PolyBLEP), so that high pitches don't alias, and
+// it's passed through a resonant low-pass filter, which approximates the response of a small
+// speaker cone.
+
+pub const MAX_SYNTH_VOLUME: u8 = 9;
+pub const DEFAULT_SYNTH_VOLUME: u8 = 6;
+
+/// Amplitude at maximum volume; there is some headroom for the filter resonance.
+const MAX_AMPLITUDE: f32 = 16000.0;
+
+const CONE_CUTOFF_HZ: f32 = 3500.0;
+const CONE_Q: f32 = 1.2;
+
+pub struct Synth {
+    pub volume: u8,
+    sample_rate: f32,
+    phase: f32,
+    filter: LowPass,
+}
+
+impl Synth {
+    pub fn new() -> Self {
+        Self {
+            volume: DEFAULT_SYNTH_VOLUME,
+            sample_rate: 0.0,
+            phase: 0.0,
+            filter: LowPass::default(),
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: i32) {
+        self.sample_rate = sample_rate as f32;
+        self.filter = LowPass::new(CONE_CUTOFF_HZ, CONE_Q, self.sample_rate);
+    }
+
+    /// Generates the next sample; with no frequency (speaker off), the filter still runs, so that
+    /// the cone settles without clicks.
+    pub fn next_sample(&mut self, frequency: Option<f32>) -> i16 {
+        let input = match frequency {
+            Some(frequency) if frequency < self.sample_rate / 2.0 => {
+                let dt = frequency / self.sample_rate;
+                let sample = self.square(dt);
+
+                self.phase += dt;
+                if self.phase >= 1.0 {
+                    self.phase -= 1.0;
+                }
+
+                sample
+            }
+            // Above the Nyquist frequency nothing can be reproduced (and the speaker couldn't
+            // either).
+            _ => {
+                self.phase = 0.0;
+                0.0
+            }
+        };
+
+        let amplitude = MAX_AMPLITUDE * self.volume as f32 / MAX_SYNTH_VOLUME as f32;
+        let output = self.filter.process(input) * amplitude;
+
+        output.clamp(i16::MIN as f32, i16::MAX as f32) as i16
+    }
+
+    /// Naive square, with the two discontinuities (rising at 0, falling at 0.5) smoothed.
+    fn square(&self, dt: f32) -> f32 {
+        let naive = if self.phase < 0.5 { 1.0 } else { -1.0 };
+        let falling_phase = (self.phase + 0.5) % 1.0;
+
+        naive + poly_blep(self.phase, dt) - poly_blep(falling_phase, dt)
+    }
+}
+
+/// Polynomial approximation of the band-limited step residual, for a discontinuity at phase 0.
+fn poly_blep(phase: f32, dt: f32) -> f32 {
+    if phase < dt {
+        let t = phase / dt;
+        2.0 * t - t * t - 1.0
+    } else if phase > 1.0 - dt {
+        let t = (phase - 1.0) / dt;
+        t * t + 2.0 * t + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// Biquad low-pass (RBJ cookbook); the default one passes the input through.
+struct LowPass {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl LowPass {
+    fn new(cutoff: f32, q: f32, sample_rate: f32) -> Self {
+        let w0 = 2.0 * PI * cutoff.min(sample_rate * 0.45) / sample_rate;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos_w0 = w0.cos();
+        let a0 = 1.0 + alpha;
+
+        Self {
+            b0: (1.0 - cos_w0) / 2.0 / a0,
+            b1: (1.0 - cos_w0) / a0,
+            b2: (1.0 - cos_w0) / 2.0 / a0,
+            a1: -2.0 * cos_w0 / a0,
+            a2: (1.0 - alpha) / a0,
+            ..Self::default()
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+
+        y
+    }
+}
+
+impl Default for LowPass {
+    fn default() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use serde::Deserialize;
+
 /// In the original, this is an enum, (automatically) narrowed down to u8 on assignment.
 #[repr(u8)]
+#[derive(Deserialize)]
 pub enum thinktype {
     gunthinks = 10,
     gunthinke = 9,
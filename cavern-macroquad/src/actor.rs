@@ -1,4 +1,6 @@
-use macroquad::prelude::{draw_texture, Texture2D, WHITE};
+use macroquad::prelude::{draw_texture_ex, vec2, Color, DrawTextureParams, WHITE};
+
+use crate::sprites::Sprite;
 
 #[derive(Clone, Copy)]
 pub enum Anchor {
@@ -8,9 +10,12 @@ pub enum Anchor {
 
 // Rust: A private trait could be used, but it doesn't help much.
 //
-fn top_left_pos(anchor: Anchor, x: i32, y: i32, image: &Texture2D) -> (i32, i32) {
-    let (image_width, image_height) = (image.width() as i32, image.height() as i32);
-
+fn top_left_pos(
+    anchor: Anchor,
+    x: i32,
+    y: i32,
+    (image_width, image_height): (i32, i32),
+) -> (i32, i32) {
     let (diff_x, diff_y) = match anchor {
         Anchor::Centre => (image_width / 2, image_height / 2),
         Anchor::CentreBottom => (image_width / 2, image_height),
@@ -26,36 +31,51 @@ pub trait Actor {
     fn x_mut(&mut self) -> &mut i32;
     fn y(&self) -> i32;
     fn y_mut(&mut self) -> &mut i32;
-    fn image(&self) -> &Texture2D;
+    fn image(&self) -> Sprite;
     fn anchor(&self) -> Anchor;
 
+    /// Size multiplier of the image, both when drawing and for the collisions.
+    fn scale(&self) -> i32 {
+        1
+    }
+
+    /// Tint applied when drawing.
+    fn colour(&self) -> Color {
+        WHITE
+    }
+
     // Rust: All the geometry methods below are meant to be conveniently implemented, not fast.
 
-    fn top(&self) -> i32 {
+    /// Size of the image, scaled.
+    fn size(&self) -> (i32, i32) {
         let image = self.image();
-        let top_left_pos = top_left_pos(self.anchor(), self.x(), self.y(), &image);
+
+        (image.width() * self.scale(), image.height() * self.scale())
+    }
+
+    fn top(&self) -> i32 {
+        let top_left_pos = top_left_pos(self.anchor(), self.x(), self.y(), self.size());
 
         top_left_pos.1
     }
 
     fn bottom(&self) -> i32 {
-        self.top() + self.image().height() as i32
+        self.top() + self.size().1
     }
 
     fn left(&self) -> i32 {
-        let image = self.image();
-        let top_left_pos = top_left_pos(self.anchor(), self.x(), self.y(), &image);
+        let top_left_pos = top_left_pos(self.anchor(), self.x(), self.y(), self.size());
 
         top_left_pos.0
     }
 
     fn right(&self) -> i32 {
-        self.left() + self.image().width() as i32
+        self.left() + self.size().0
     }
 
     fn center(&self) -> (i32, i32) {
-        let center_x = self.left() + self.image().width() as i32 / 2;
-        let center_y = self.top() + self.image().height() as i32 / 2;
+        let center_x = self.left() + self.size().0 / 2;
+        let center_y = self.top() + self.size().1 / 2;
 
         (center_x, center_y)
     }
@@ -67,10 +87,25 @@ pub trait Actor {
             && pos.1 <= self.bottom()
     }
 
-    fn draw(&self) {
-        let image = self.image();
-        let top_left_pos = top_left_pos(self.anchor(), self.x(), self.y(), &image);
+    /// Whether the image overlaps the rectangle (left, top, right, bottom); as for collidepoint(),
+    /// the edges are included.
+    fn colliderect(&self, (left, top, right, bottom): (i32, i32, i32, i32)) -> bool {
+        self.left() <= right && left <= self.right() && self.top() <= bottom && top <= self.bottom()
+    }
 
-        draw_texture(image, top_left_pos.0 as f32, top_left_pos.1 as f32, WHITE);
+    fn draw(&self) {
+        let size = self.size();
+        let top_left_pos = top_left_pos(self.anchor(), self.x(), self.y(), size);
+
+        draw_texture_ex(
+            &self.image().texture(),
+            top_left_pos.0 as f32,
+            top_left_pos.1 as f32,
+            self.colour(),
+            DrawTextureParams {
+                dest_size: Some(vec2(size.0 as f32, size.1 as f32)),
+                ..Default::default()
+            },
+        );
     }
 }
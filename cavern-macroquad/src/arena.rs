@@ -0,0 +1,219 @@
+// Rust: The objects used to be in vectors, with the orbs shared through `Rc<RefCell<Orb>>`, so that
+// the player could keep a (weak) reference to the orb being blown. Borrowing mistakes with RefCell
+// only show up at runtime, as panics, and the game couldn't be cloned.
+//
+// Objects are now stored in generational arenas, and referred to by typed handles. A slot is reused
+// once its object is removed, but with a new generation, so that old handles to it don't resolve to
+// the new object. Iteration follows the order of insertion, like the vectors did, so that objects
+// are still updated and drawn in the same order.
+//
+// The objects themselves are kept in insertion order, and the slots point to them; a removed object
+// leaves a hole, so that removing doesn't move the others, and the holes are compacted once they're
+// the majority. Both removing and iterating (mutably as well) are therefore cheap.
+
+use std::{fmt, marker::PhantomData};
+
+pub struct Handle<T> {
+    index: u32,
+    generation: u32,
+    // `fn() -> T` keeps the handle Send/Sync and covariant, without owning a T.
+    _marker: PhantomData<fn() -> T>,
+}
+
+// Rust: Implemented manually, since deriving would require T to implement the traits as well.
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+#[derive(Clone)]
+struct Slot {
+    generation: u32,
+    /// Position of the object in `entries`; None if the slot is empty.
+    position: Option<usize>,
+}
+
+#[derive(Clone)]
+struct Entry<T> {
+    /// Slot of the object
+    index: u32,
+    value: T,
+}
+
+#[derive(Clone)]
+pub struct Arena<T> {
+    slots: Vec<Slot>,
+    /// Indexes of the empty slots.
+    free: Vec<u32>,
+    /// Objects in order of insertion, with holes where they've been removed.
+    entries: Vec<Option<Entry<T>>>,
+    /// Objects in `entries`, without the holes.
+    len: usize,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self {
+            slots: vec![],
+            free: vec![],
+            entries: vec![],
+            len: 0,
+        }
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn insert(&mut self, value: T) -> Handle<T> {
+        let index = self.free.pop().unwrap_or_else(|| {
+            self.slots.push(Slot {
+                generation: 0,
+                position: None,
+            });
+            (self.slots.len() - 1) as u32
+        });
+
+        self.entries.push(Some(Entry { index, value }));
+        self.len += 1;
+
+        let slot = &mut self.slots[index as usize];
+        slot.position = Some(self.entries.len() - 1);
+
+        Handle {
+            index,
+            generation: slot.generation,
+            _marker: PhantomData,
+        }
+    }
+
+    /// None if the object has already been removed.
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        let slot = self.slots.get_mut(handle.index as usize)?;
+
+        if slot.generation != handle.generation {
+            return None;
+        }
+
+        let position = slot.position.take()?;
+
+        slot.generation += 1;
+        self.free.push(handle.index);
+        self.len -= 1;
+
+        let entry = self.entries[position].take().unwrap();
+
+        if self.len * 2 < self.entries.len() {
+            self.compact();
+        }
+
+        Some(entry.value)
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        let position = self.position(handle)?;
+        self.entries[position].as_ref().map(|entry| &entry.value)
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        let position = self.position(handle)?;
+        self.entries[position]
+            .as_mut()
+            .map(|entry| &mut entry.value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Objects in order of insertion.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        let slots = &self.slots;
+
+        self.entries
+            .iter()
+            .flatten()
+            .map(move |entry| (handle(slots, entry.index), &entry.value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> {
+        let Self { slots, entries, .. } = self;
+        let slots = &*slots;
+
+        entries
+            .iter_mut()
+            .flatten()
+            .map(move |entry| (handle(slots, entry.index), &mut entry.value))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    /// Removes the objects for which `keep` returns false.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        let removed = self
+            .iter()
+            .filter(|(_, value)| !keep(value))
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+
+        for handle in removed {
+            self.remove(handle);
+        }
+    }
+
+    /// Removes all the objects; handles to them stay invalid.
+    pub fn clear(&mut self) {
+        self.retain(|_| false);
+    }
+
+    fn position(&self, handle: Handle<T>) -> Option<usize> {
+        match self.slots.get(handle.index as usize) {
+            Some(slot) if slot.generation == handle.generation => slot.position,
+            _ => None,
+        }
+    }
+
+    /// Removes the holes, keeping the order.
+    fn compact(&mut self) {
+        self.entries.retain(Option::is_some);
+
+        for (position, entry) in self.entries.iter().flatten().enumerate() {
+            self.slots[entry.index as usize].position = Some(position);
+        }
+    }
+}
+
+/// Handle of the object in the (occupied) slot.
+fn handle<T>(slots: &[Slot], index: u32) -> Handle<T> {
+    Handle {
+        index,
+        generation: slots[index as usize].generation,
+        _marker: PhantomData,
+    }
+}
@@ -1,23 +1,26 @@
-use macroquad::prelude::{collections::storage, Texture2D};
-
 use crate::{
     actor::{Actor, Anchor},
+    arena::Arena,
     collide_actor::{CollideActor, COLLIDE_ACTOR_DEFAULT_ANCHOR},
-    orb::RcOrb,
+    game_playback::GameSound,
+    orb::Orb,
     player::Player,
-    resources::Resources,
+    sprites::{Sprite, SpriteSheet},
 };
 
 const BOLT_SPEED: i32 = 7;
 
+#[derive(Clone)]
 pub struct Bolt {
     pub direction_x: i32,
+    /// Pixels per frame; only the bolts of a boss spread move vertically
+    pub direction_y: i32,
     pub active: bool,
 
     // Actor trait
     pub x: i32,
     pub y: i32,
-    pub image: Texture2D,
+    pub image: Sprite,
     pub anchor: Anchor,
 }
 
@@ -25,39 +28,44 @@ impl Bolt {
     pub fn new(x: i32, y: i32, direction_x: i32) -> Self {
         Self {
             direction_x,
+            direction_y: 0,
             active: true,
 
             x,
             y,
-            image: storage::get::<Resources>().blank_texture.clone(),
+            image: Sprite::BLANK,
             anchor: COLLIDE_ACTOR_DEFAULT_ANCHOR,
         }
     }
 
     pub fn update(
         &mut self,
-        orbs: &mut Vec<RcOrb>,
-        player: Option<&mut Player>,
+        orbs: &mut Arena<Orb>,
+        players: &mut [Player],
+        sounds: &mut Vec<GameSound>,
         game_timer: i32,
         grid: &[&str],
     ) {
+        self.y += self.direction_y;
+
         // Move horizontally and check to see if we've collided with a block
         if self.move_(self.direction_x, 0, BOLT_SPEED, grid) {
             // Collided
             self.active = false;
         } else {
             // We didn't collide with a block - check to see if we collided with an orb or the player
-            for orb in orbs {
-                if orb.borrow_mut().hit_test(self) {
+            for orb in orbs.values_mut() {
+                if orb.hit_test(self) {
                     self.active = false;
                     break;
                 }
             }
 
             if self.active {
-                if let Some(player) = player {
-                    if player.hit_test(self) {
+                for player in players.iter_mut().filter(|p| p.in_game()) {
+                    if player.hit_test(self, sounds) {
                         self.active = false;
+                        break;
                     }
                 }
             }
@@ -66,7 +74,7 @@ impl Bolt {
         let direction_factor = if self.direction_x > 0 { 2 } else { 0 };
         let timer_factor = (game_timer / 4) % 2;
         let image_i = (direction_factor + timer_factor) as usize;
-        self.image = storage::get::<Resources>().bolt_textures[image_i].clone();
+        self.image = Sprite::new(SpriteSheet::Bolt, image_i);
     }
 }
 
@@ -87,8 +95,8 @@ impl Actor for Bolt {
         &mut self.y
     }
 
-    fn image(&self) -> &macroquad::prelude::Texture2D {
-        &self.image
+    fn image(&self) -> Sprite {
+        self.image
     }
 
     fn anchor(&self) -> crate::actor::Anchor {
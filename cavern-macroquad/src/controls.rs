@@ -0,0 +1,510 @@
+// Rust: Not in the original game, which only has the keyboard controls of one player. The game reads
+// actions (move, jump, blow orb, start), each of which is bound, for each player, to a key and to a
+// gamepad button; the first player uses the first gamepad connected, and the second player the
+// second one. The left stick also moves the players.
+//
+// The bindings are edited from the options screen (see options_screen.rs), and kept in a text file
+// in the user data directory (see records.rs):
+//
+//     p1.left = Left DPadLeft
+//     p2.blow = LShift -
+//
+// with the key, then the button; `-` means unbound. Invalid lines are skipped, leaving the default
+// bindings.
+//
+// The menus (the initials entry and the options screen) have fixed keys and buttons instead (see
+// MenuAction), so that they can't be made unusable by the bindings.
+
+use gilrs::{Axis, Button, EventType, Gamepad, GamepadId, Gilrs};
+use macroquad::input::{get_last_key_pressed, is_key_down, is_key_pressed, KeyCode};
+
+use crate::{
+    input::PlayerInput,
+    records::{load_data_file, save_data_file},
+};
+
+pub const MAX_PLAYERS: usize = 2;
+
+const CONTROLS_FILENAME: &str = "controls.txt";
+const UNBOUND: &str = "-";
+/// Left stick deflection needed to move
+const STICK_THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Left,
+    Right,
+    Jump,
+    Blow,
+    /// Starts a game from the title screen, and goes back to it from the other screens
+    Start,
+}
+
+impl Action {
+    pub const ALL: [Action; 5] = [
+        Action::Left,
+        Action::Right,
+        Action::Jump,
+        Action::Blow,
+        Action::Start,
+    ];
+
+    /// As in the controls file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Jump => "jump",
+            Action::Blow => "blow",
+            Action::Start => "start",
+        }
+    }
+}
+
+/// Actions of the menus, on the keyboard or on any gamepad.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Start rather than South, which is held while playing, when the initials entry opens
+    Confirm,
+    Back,
+    Reset,
+}
+
+impl MenuAction {
+    fn key(self) -> KeyCode {
+        match self {
+            MenuAction::Up => KeyCode::Up,
+            MenuAction::Down => KeyCode::Down,
+            MenuAction::Left => KeyCode::Left,
+            MenuAction::Right => KeyCode::Right,
+            MenuAction::Confirm => KeyCode::Enter,
+            MenuAction::Back => KeyCode::Escape,
+            MenuAction::Reset => KeyCode::Backspace,
+        }
+    }
+
+    fn button(self) -> Button {
+        match self {
+            MenuAction::Up => Button::DPadUp,
+            MenuAction::Down => Button::DPadDown,
+            MenuAction::Left => Button::DPadLeft,
+            MenuAction::Right => Button::DPadRight,
+            MenuAction::Confirm => Button::Start,
+            MenuAction::Back => Button::East,
+            MenuAction::Reset => Button::North,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Binding {
+    pub key: Option<KeyCode>,
+    pub button: Option<Button>,
+}
+
+impl Binding {
+    const fn new(key: KeyCode, button: Button) -> Self {
+        Self {
+            key: Some(key),
+            button: Some(button),
+        }
+    }
+}
+
+/// Bindings of each player, by action (in Action::ALL order).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Controls {
+    pub players: [[Binding; Action::ALL.len()]; MAX_PLAYERS],
+}
+
+/// The first player has the original keys, so that single player games are unchanged; the second
+/// player starts a co-op game.
+impl Default for Controls {
+    fn default() -> Self {
+        Self {
+            players: [
+                [
+                    Binding::new(KeyCode::Left, Button::DPadLeft),
+                    Binding::new(KeyCode::Right, Button::DPadRight),
+                    Binding::new(KeyCode::Up, Button::South),
+                    Binding::new(KeyCode::Space, Button::West),
+                    Binding::new(KeyCode::Space, Button::Start),
+                ],
+                [
+                    Binding::new(KeyCode::A, Button::DPadLeft),
+                    Binding::new(KeyCode::D, Button::DPadRight),
+                    Binding::new(KeyCode::W, Button::South),
+                    Binding::new(KeyCode::LeftShift, Button::West),
+                    Binding::new(KeyCode::Key2, Button::Start),
+                ],
+            ],
+        }
+    }
+}
+
+impl Controls {
+    /// A missing or unreadable file is the same as the default bindings.
+    pub fn load() -> Self {
+        load_data_file(CONTROLS_FILENAME)
+            .map(|source| parse_controls(&source))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_data_file(CONTROLS_FILENAME, &self.to_source())
+    }
+
+    pub fn binding(&self, player_number: usize, action: Action) -> Binding {
+        self.players[player_number][action as usize]
+    }
+
+    pub fn binding_mut(&mut self, player_number: usize, action: Action) -> &mut Binding {
+        &mut self.players[player_number][action as usize]
+    }
+
+    /// Binds the key, or the button, of `pressed` to the action. If another action that can be
+    /// triggered at the same time already has it, the two actions swap their keys (or buttons), so
+    /// that no key or button does two things at once.
+    pub fn rebind(&mut self, player_number: usize, action: Action, pressed: Binding) {
+        let old = self.binding(player_number, action);
+
+        for other_player in 0..MAX_PLAYERS {
+            for &other_action in &Action::ALL {
+                if !conflicting(player_number, action, other_player, other_action) {
+                    continue;
+                }
+
+                let other = self.binding_mut(other_player, other_action);
+
+                if pressed.key.is_some() && other.key == pressed.key {
+                    other.key = old.key;
+                }
+                // Each player has their own gamepad
+                if pressed.button.is_some()
+                    && other.button == pressed.button
+                    && other_player == player_number
+                {
+                    other.button = old.button;
+                }
+            }
+        }
+
+        let binding = self.binding_mut(player_number, action);
+
+        if pressed.key.is_some() {
+            binding.key = pressed.key;
+        }
+        if pressed.button.is_some() {
+            binding.button = pressed.button;
+        }
+    }
+
+    pub fn to_source(&self) -> String {
+        let mut source = String::new();
+
+        for (player_number, bindings) in self.players.iter().enumerate() {
+            for (action, binding) in Action::ALL.iter().zip(bindings) {
+                source.push_str(&format!(
+                    "p{}.{} = {} {}\n",
+                    player_number + 1,
+                    action.name(),
+                    binding.key.map_or(UNBOUND, key_name),
+                    binding.button.map_or(UNBOUND, button_name)
+                ));
+            }
+        }
+
+        source
+    }
+}
+
+/// Start is only read outside the games, and the other actions only in them, so they can share keys
+/// and buttons (as Blow and Start do by default).
+fn conflicting(
+    player_number: usize,
+    action: Action,
+    other_player: usize,
+    other_action: Action,
+) -> bool {
+    (player_number, action) != (other_player, other_action)
+        && (action == Action::Start) == (other_action == Action::Start)
+}
+
+pub fn parse_controls(source: &str) -> Controls {
+    let mut controls = Controls::default();
+
+    for line in source.lines() {
+        let (name, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let (player, action) = match name.trim().split_once('.') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let (key, button) = match value.trim().split_once(' ') {
+            Some(pair) => pair,
+            None => continue,
+        };
+
+        let player_number = match player
+            .strip_prefix('p')
+            .and_then(|n| n.parse::<usize>().ok())
+        {
+            Some(n) if (1..=MAX_PLAYERS).contains(&n) => n - 1,
+            _ => continue,
+        };
+        let action = match Action::ALL.iter().find(|a| a.name() == action) {
+            Some(&action) => action,
+            None => continue,
+        };
+        let key = match key.trim() {
+            UNBOUND => None,
+            name => match parse_key(name) {
+                Some(key) => Some(key),
+                None => continue,
+            },
+        };
+        let button = match button.trim() {
+            UNBOUND => None,
+            name => match parse_button(name) {
+                Some(button) => Some(button),
+                None => continue,
+            },
+        };
+
+        *controls.binding_mut(player_number, action) = Binding { key, button };
+    }
+
+    controls
+}
+
+/// Reads the actions of all the players, from the keyboard and the gamepads, once per frame.
+pub struct ActionInput {
+    pub controls: Controls,
+    /// None if the gamepads can't be used on this system; the keyboard still works.
+    gilrs: Option<Gilrs>,
+    /// Buttons pressed in this frame; gilrs only tells which buttons are held.
+    buttons_pressed: Vec<(GamepadId, Button)>,
+}
+
+impl ActionInput {
+    pub fn new(controls: Controls) -> Self {
+        let gilrs = Gilrs::new()
+            .map_err(|error| eprintln!("Gamepads not available: {}", error))
+            .ok();
+
+        Self {
+            controls,
+            gilrs,
+            buttons_pressed: vec![],
+        }
+    }
+
+    /// Must be called at the start of each frame.
+    pub fn update(&mut self) {
+        self.buttons_pressed.clear();
+
+        if let Some(gilrs) = &mut self.gilrs {
+            while let Some(event) = gilrs.next_event() {
+                if let EventType::ButtonPressed(button, _) = event.event {
+                    self.buttons_pressed.push((event.id, button));
+                }
+            }
+        }
+    }
+
+    pub fn is_down(&self, player_number: usize, action: Action) -> bool {
+        let binding = self.controls.binding(player_number, action);
+
+        let gamepad = self.gamepad(player_number);
+
+        let key_down = binding.key.map_or(false, is_key_down);
+        let button_down = match (gamepad, binding.button) {
+            (Some(gamepad), Some(button)) => gamepad.is_pressed(button),
+            _ => false,
+        };
+        let stick_x = gamepad.map_or(0., |gamepad| gamepad.value(Axis::LeftStickX));
+        let stick_down = match action {
+            Action::Left => stick_x < -STICK_THRESHOLD,
+            Action::Right => stick_x > STICK_THRESHOLD,
+            _ => false,
+        };
+
+        key_down || button_down || stick_down
+    }
+
+    /// Whether the action has been pressed in this frame.
+    pub fn is_pressed(&self, player_number: usize, action: Action) -> bool {
+        let binding = self.controls.binding(player_number, action);
+
+        let key_pressed = binding.key.map_or(false, is_key_pressed);
+        let button_pressed = match (self.gamepad(player_number), binding.button) {
+            (Some(gamepad), Some(button)) => self.buttons_pressed.contains(&(gamepad.id(), button)),
+            _ => false,
+        };
+
+        key_pressed || button_pressed
+    }
+
+    pub fn any_player_pressed(&self, action: Action) -> bool {
+        (0..MAX_PLAYERS).any(|player_number| self.is_pressed(player_number, action))
+    }
+
+    /// Whether the menu action has been pressed in this frame.
+    pub fn menu_pressed(&self, action: MenuAction) -> bool {
+        is_key_pressed(action.key())
+            || self
+                .buttons_pressed
+                .iter()
+                .any(|&(_, button)| button == action.button())
+    }
+
+    /// Input frame of the given players.
+    pub fn player_inputs(&self, num_players: usize) -> Vec<PlayerInput> {
+        (0..num_players)
+            .map(|player_number| PlayerInput {
+                left: self.is_down(player_number, Action::Left),
+                right: self.is_down(player_number, Action::Right),
+                jump: self.is_down(player_number, Action::Jump),
+                blow: self.is_down(player_number, Action::Blow),
+                blow_pressed: self.is_pressed(player_number, Action::Blow),
+            })
+            .collect()
+    }
+
+    /// Key or button (of any gamepad) pressed in this frame, for the rebinding; only the keys that
+    /// can be saved are returned.
+    pub fn binding_pressed(&self) -> Option<Binding> {
+        if let Some(key) = get_last_key_pressed().filter(|&key| !key_name(key).is_empty()) {
+            return Some(Binding {
+                key: Some(key),
+                button: None,
+            });
+        }
+
+        self.buttons_pressed
+            .iter()
+            .find(|(_, button)| !button_name(*button).is_empty())
+            .map(|&(_, button)| Binding {
+                key: None,
+                button: Some(button),
+            })
+    }
+
+    /// The gamepads are assigned to the players in the order they have been connected.
+    fn gamepad(&self, player_number: usize) -> Option<Gamepad<'_>> {
+        self.gilrs
+            .as_ref()?
+            .gamepads()
+            .nth(player_number)
+            .map(|(_, gamepad)| gamepad)
+    }
+}
+
+/// Names of the keys that can be bound, as in the controls file; the options screen shows them in
+/// uppercase.
+const KEY_NAMES: [(KeyCode, &str); 49] = [
+    (KeyCode::A, "A"),
+    (KeyCode::B, "B"),
+    (KeyCode::C, "C"),
+    (KeyCode::D, "D"),
+    (KeyCode::E, "E"),
+    (KeyCode::F, "F"),
+    (KeyCode::G, "G"),
+    (KeyCode::H, "H"),
+    (KeyCode::I, "I"),
+    (KeyCode::J, "J"),
+    (KeyCode::K, "K"),
+    (KeyCode::L, "L"),
+    (KeyCode::M, "M"),
+    (KeyCode::N, "N"),
+    (KeyCode::O, "O"),
+    (KeyCode::P, "P"),
+    (KeyCode::Q, "Q"),
+    (KeyCode::R, "R"),
+    (KeyCode::S, "S"),
+    (KeyCode::T, "T"),
+    (KeyCode::U, "U"),
+    (KeyCode::V, "V"),
+    (KeyCode::W, "W"),
+    (KeyCode::X, "X"),
+    (KeyCode::Y, "Y"),
+    (KeyCode::Z, "Z"),
+    (KeyCode::Key0, "0"),
+    (KeyCode::Key1, "1"),
+    (KeyCode::Key2, "2"),
+    (KeyCode::Key3, "3"),
+    (KeyCode::Key4, "4"),
+    (KeyCode::Key5, "5"),
+    (KeyCode::Key6, "6"),
+    (KeyCode::Key7, "7"),
+    (KeyCode::Key8, "8"),
+    (KeyCode::Key9, "9"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::LeftShift, "LShift"),
+    (KeyCode::RightShift, "RShift"),
+    (KeyCode::LeftControl, "LCtrl"),
+    (KeyCode::RightControl, "RCtrl"),
+    (KeyCode::LeftAlt, "LAlt"),
+    (KeyCode::RightAlt, "RAlt"),
+];
+
+const BUTTON_NAMES: [(Button, &str); 17] = [
+    (Button::South, "South"),
+    (Button::East, "East"),
+    (Button::North, "North"),
+    (Button::West, "West"),
+    (Button::LeftTrigger, "LTrigger"),
+    (Button::LeftTrigger2, "LTrigger2"),
+    (Button::RightTrigger, "RTrigger"),
+    (Button::RightTrigger2, "RTrigger2"),
+    (Button::Select, "Select"),
+    (Button::Start, "Start"),
+    (Button::Mode, "Mode"),
+    (Button::LeftThumb, "LThumb"),
+    (Button::RightThumb, "RThumb"),
+    (Button::DPadUp, "DPadUp"),
+    (Button::DPadDown, "DPadDown"),
+    (Button::DPadLeft, "DPadLeft"),
+    (Button::DPadRight, "DPadRight"),
+];
+
+/// Keys that can't be bound have no name.
+pub fn key_name(key: KeyCode) -> &'static str {
+    KEY_NAMES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map_or("", |(_, name)| name)
+}
+
+pub fn button_name(button: Button) -> &'static str {
+    BUTTON_NAMES
+        .iter()
+        .find(|(b, _)| *b == button)
+        .map_or("", |(_, name)| name)
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(key, _)| *key)
+}
+
+fn parse_button(name: &str) -> Option<Button> {
+    BUTTON_NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(button, _)| *button)
+}
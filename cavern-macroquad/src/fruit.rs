@@ -1,19 +1,21 @@
-use macroquad::{
-    prelude::{collections::storage, Texture2D},
-    rand::ChooseRandom,
-};
+use macroquad::prelude::{Color, WHITE};
 
 use crate::{
     actor::{Actor, Anchor},
+    arena::Arena,
     collide_actor::CollideActor,
-    game_playback::play_game_sound,
+    game_playback::GameSound,
     gravity_actor::{GravityActor, GRAVITY_ACTOR_DEFAULT_ANCHOR},
-    player::Player,
+    orb::Orb,
+    player::{Player, PowerUp},
     pop::Pop,
-    resources::Resources,
-    robot::RobotType,
+    rng::Rng,
+    robot::{Robot, RobotType},
+    sprites::{Sprite, SpriteSheet},
 };
 
+const SCREEN_BOMB_COLOUR: Color = Color::new(1., 0.5, 1., 1.);
+
 #[derive(Clone, Copy)]
 pub enum FruitType {
     Apple,
@@ -21,9 +23,14 @@ pub enum FruitType {
     Lemon,
     ExtraHealth,
     ExtraLife,
+    /// Drawn as a bubble, tinted with the colour of the power-up
+    PowerUp(PowerUp),
+    /// Traps all the robots on screen
+    ScreenBomb,
 }
 
 impl FruitType {
+    /// Index of the images; the power-ups and the screen bomb don't have fruit images.
     fn val(&self) -> i32 {
         match self {
             FruitType::Apple => 0,
@@ -31,10 +38,12 @@ impl FruitType {
             FruitType::Lemon => 2,
             FruitType::ExtraHealth => 3,
             FruitType::ExtraLife => 4,
+            FruitType::PowerUp(_) | FruitType::ScreenBomb => 0,
         }
     }
 }
 
+#[derive(Clone)]
 pub struct Fruit {
     pub time_to_live: i32,
     pub trapped_enemy_type: Option<RobotType>,
@@ -43,7 +52,7 @@ pub struct Fruit {
     // Actor trait
     pub x: i32,
     pub y: i32,
-    pub image: Texture2D,
+    pub image: Sprite,
     pub anchor: Anchor,
 
     // GravityActor trait
@@ -52,13 +61,10 @@ pub struct Fruit {
 }
 
 impl Fruit {
-    pub fn new(x: i32, y: i32, trapped_enemy_type: Option<RobotType>) -> Self {
+    pub fn new(x: i32, y: i32, trapped_enemy_type: Option<RobotType>, rng: &mut Rng) -> Self {
         // Choose which type of fruit we're going to be.
         let type_ = if let Some(RobotType::Normal) = trapped_enemy_type {
-            *[FruitType::Apple, FruitType::Raspberry, FruitType::Lemon]
-                .to_vec()
-                .choose()
-                .unwrap()
+            *rng.choose(&[FruitType::Apple, FruitType::Raspberry, FruitType::Lemon])
         } else {
             // If trapped_enemy_type is 1, it means this fruit came from bursting an orb containing the more dangerous type
             // of enemy. In this case there is a chance of getting an extra help or extra life power up
@@ -67,7 +73,11 @@ impl Fruit {
             let mut types = [FruitType::Apple, FruitType::Raspberry, FruitType::Lemon].repeat(10); // Each of these appear in the list 10 times
             types.extend([FruitType::ExtraHealth].repeat(9)); // This appears 9 times
             types.extend([FruitType::ExtraLife]); // This only appears once
-            *types.choose().unwrap() // Randomly choose one from the list
+            for power_up in PowerUp::ALL {
+                types.extend([FruitType::PowerUp(power_up)].repeat(2)); // Each power-up appears twice
+            }
+            types.extend([FruitType::ScreenBomb]); // This only appears once
+            *rng.choose(&types) // Randomly choose one from the list
         };
 
         Self {
@@ -77,7 +87,7 @@ impl Fruit {
 
             x,
             y,
-            image: storage::get::<Resources>().blank_texture.clone(),
+            image: Sprite::BLANK,
             anchor: GRAVITY_ACTOR_DEFAULT_ANCHOR,
 
             vel_y: 0,
@@ -87,31 +97,51 @@ impl Fruit {
 
     pub fn update(
         &mut self,
-        pops: &mut Vec<Pop>,
-        player: Option<&mut Player>,
+        pops: &mut Arena<Pop>,
+        players: &mut [Player],
+        enemies: &mut Arena<Robot>,
+        orbs: &mut Arena<Orb>,
+        sounds: &mut Vec<GameSound>,
         game_timer: i32,
         grid: &[&str],
     ) {
         GravityActor::update(self, true, grid);
 
-        // Does the player exist, and are they colliding with us?
+        // Does a player exist, and are they colliding with us? If both are, the first one gets the fruit
 
-        match player {
-            Some(player) if player.collidepoint(self.center()) => {
-                let resources = storage::get::<Resources>();
+        let collecting_player = players
+            .iter_mut()
+            .find(|p| p.in_game() && p.collidepoint(self.center()));
 
+        match collecting_player {
+            Some(player) => {
                 match self.type_ {
                     FruitType::ExtraHealth => {
                         player.health = 3.min(player.health + 1);
-                        play_game_sound(Some(player), &resources.bonus_sound);
+                        sounds.push(GameSound::Bonus);
                     }
                     FruitType::ExtraLife => {
                         player.lives += 1;
-                        play_game_sound(Some(player), &resources.bonus_sound);
+                        sounds.push(GameSound::Bonus);
+                    }
+                    FruitType::PowerUp(power_up) => {
+                        player.add_power_up(power_up);
+                        sounds.push(GameSound::Bonus);
+                    }
+                    FruitType::ScreenBomb => {
+                        // Every free robot is hit by an orb of the player, appearing on it; the boss only loses
+                        // health, as when hit by an orb
+                        for (handle, robot) in enemies.iter_mut().filter(|(_, e)| !e.trapped) {
+                            let (x, y) = robot.center();
+                            let mut orb = Orb::new(x, y, 0, player.number);
+                            robot.hit_by_orb(handle, &mut orb, sounds);
+                            orbs.insert(orb);
+                        }
+                        sounds.push(GameSound::Bonus);
                     }
                     _ => {
                         player.score += (self.type_.val() + 1) * 100;
-                        play_game_sound(Some(player), &resources.score_sound);
+                        sounds.push(GameSound::Score);
                     }
                 }
 
@@ -124,13 +154,22 @@ impl Fruit {
 
         if self.time_to_live <= 0 {
             // Create 'pop' animation
-            pops.push(Pop::new(self.x, self.y - 27, 0));
+            pops.insert(Pop::new(self.x, self.y - 27, 0));
         }
 
-        let type_factor = self.type_.val() * 3;
-        let timer_factor = [0, 1, 2, 1][((game_timer / 6) % 4) as usize];
-        let image_i = (type_factor + timer_factor) as usize;
-        self.image = storage::get::<Resources>().fruit_textures[image_i].clone();
+        match self.type_ {
+            FruitType::PowerUp(_) | FruitType::ScreenBomb => {
+                // Floating bubble animation
+                let timer_factor = 3 + ((game_timer / 8) % 4);
+                self.image = Sprite::new(SpriteSheet::Orb, timer_factor as usize);
+            }
+            _ => {
+                let type_factor = self.type_.val() * 3;
+                let timer_factor = [0, 1, 2, 1][((game_timer / 6) % 4) as usize];
+                let image_i = (type_factor + timer_factor) as usize;
+                self.image = Sprite::new(SpriteSheet::Fruit, image_i);
+            }
+        }
     }
 }
 
@@ -151,13 +190,21 @@ impl Actor for Fruit {
         &mut self.y
     }
 
-    fn image(&self) -> &macroquad::prelude::Texture2D {
-        &self.image
+    fn image(&self) -> Sprite {
+        self.image
     }
 
     fn anchor(&self) -> crate::actor::Anchor {
         self.anchor
     }
+
+    fn colour(&self) -> Color {
+        match self.type_ {
+            FruitType::PowerUp(power_up) => power_up.colour(),
+            FruitType::ScreenBomb => SCREEN_BOMB_COLOUR,
+            _ => WHITE,
+        }
+    }
 }
 
 impl CollideActor for Fruit {}
@@ -1,41 +1,79 @@
 use crate::actor::Actor;
+use crate::arena::Arena;
 use crate::bolt::Bolt;
 use crate::fruit::Fruit;
-use crate::game_playback::play_game_sound;
-use crate::orb::RcOrb;
+use crate::game_playback::GameSound;
+use crate::input::PlayerInput;
+use crate::orb::Orb;
 use crate::pop::Pop;
 use crate::resources::Resources;
-use crate::robot::{Robot, RobotType};
-use crate::{levels::LEVELS, player::Player};
+use crate::rng::Rng;
+use crate::robot::{Robot, RobotType, BOSS_HEALTH};
+use crate::{
+    levels::{Level, LevelSettings, RepeatSettings},
+    player::Player,
+};
 use crate::{GRID_BLOCK_SIZE, LEVEL_X_OFFSET, NUM_COLUMNS, NUM_ROWS, WIDTH};
 
-use macroquad::prelude::{draw_texture, WHITE};
-use macroquad::rand::gen_range;
-use macroquad::{prelude::collections::storage, rand::ChooseRandom};
+use macroquad::prelude::collections::storage;
+use macroquad::prelude::{draw_rectangle, draw_rectangle_lines, draw_texture, BLACK, RED, WHITE};
 
-#[derive(Default)]
+/// A boss appears every this many levels
+pub const BOSS_LEVEL_INTERVAL: i32 = 4;
+
+const BOSS_HEALTH_BAR_WIDTH: f32 = 300.;
+const BOSS_HEALTH_BAR_HEIGHT: f32 = 12.;
+
+/// The objects are stored in arenas, so that they can refer to each other through handles (see
+/// arena.rs), and the whole game can be cloned.
+///
+/// The simulation depends only on the levels, the seed and the inputs passed to update(), so that it
+/// can be replayed exactly, and run without a window (see replay.rs and the tests).
+#[derive(Default, Clone)]
 pub struct Game {
-    pub player: Option<Player>,
+    /// Empty on the title screen; one or two players otherwise.
+    pub players: Vec<Player>,
     pub level_colour: i8,
     pub level: i8,
     pub timer: i32,
     pub grid: Vec<&'static str>,
-
-    pub fruits: Vec<Fruit>,
-    pub bolts: Vec<Bolt>,
-    pub enemies: Vec<Robot>,
+    pub settings: LevelSettings,
+    /// Levels played in order; once they're over, they start again from the first one.
+    pub levels: Vec<Level>,
+    /// Added to the settings of the levels on each pass after the first.
+    pub repeat: RepeatSettings,
+    pub rng: Rng,
+    /// Sounds triggered since the last time they've been taken; the game doesn't play them itself.
+    pub sounds: Vec<GameSound>,
+    /// Levels cleared since the last time they've been taken, as (level number, frames taken), for
+    /// the best times (see records.rs).
+    pub clear_times: Vec<(usize, i32)>,
+
+    pub fruits: Arena<Fruit>,
+    pub bolts: Arena<Bolt>,
+    /// Includes the enemies trapped in orbs
+    pub enemies: Arena<Robot>,
     pub pending_enemies: Vec<RobotType>,
-    pub pops: Vec<Pop>,
-    pub orbs: Vec<RcOrb>,
+    pub pops: Arena<Pop>,
+    pub orbs: Arena<Orb>,
 }
 
 impl Game {
-    pub fn new(player: Option<Player>) -> Self {
+    /// The levels must not be empty.
+    pub fn new(
+        players: Vec<Player>,
+        levels: Vec<Level>,
+        repeat: RepeatSettings,
+        seed: u64,
+    ) -> Self {
         let mut game = Self {
-            player,
+            players,
             level_colour: -1,
             level: -1,
             timer: -1,
+            levels,
+            repeat,
+            rng: Rng::new(seed),
             ..Default::default()
         };
 
@@ -45,19 +83,29 @@ impl Game {
     }
 
     pub fn fire_probability(&self) -> f32 {
-        // Likelihood per frame of each robot firing a bolt - they fire more often on higher levels
-        0.001 + (0.0001 * 100.min(self.level) as f32)
+        // Likelihood per frame of each robot firing a bolt - set by the level
+        self.settings.fire_probability
     }
 
     pub fn max_enemies(&self) -> i32 {
-        // Maximum number of enemies on-screen at once – increases as you progress through the levels
-        ((self.level + 6) / 2).min(8) as i32
+        // Maximum number of enemies on-screen at once – set by the level
+        self.settings.max_enemies as i32
+    }
+
+    /// Levels are counted from zero, so the boss levels are the last of each group.
+    pub fn is_boss_level(&self) -> bool {
+        (self.level as i32 + 1) % BOSS_LEVEL_INTERVAL == 0
     }
 
-    pub fn get_robot_spawn_x(&self) -> i32 {
+    /// Enemies not trapped in an orb.
+    pub fn free_enemies(&self) -> usize {
+        self.enemies.values().filter(|e| !e.trapped).count()
+    }
+
+    pub fn get_robot_spawn_x(&mut self) -> i32 {
         // Find a spawn location for a robot, by checking the top row of the grid for empty spots
         // Start by choosing a random grid column
-        let r = gen_range(0, NUM_COLUMNS);
+        let r = self.rng.gen_range(0, NUM_COLUMNS);
 
         for i in 0..NUM_COLUMNS {
             // Keep looking at successive columns (wrapping round if we go off the right-hand side) until
@@ -73,7 +121,8 @@ impl Game {
         WIDTH / 2
     }
 
-    pub fn update(&mut self) {
+    /// Advances the game by one frame; there must be one input per player.
+    pub fn update(&mut self, inputs: &[PlayerInput]) {
         self.timer += 1;
 
         // Rust: We precompute this here, since it can't be done inside the enemies cycle, due to borrowing
@@ -81,154 +130,185 @@ impl Game {
         let fire_probability = self.fire_probability();
 
         // Update all objects
-        for fruit in &mut self.fruits {
-            fruit.update(&mut self.pops, self.player.as_mut(), self.timer, &self.grid)
+        for fruit in self.fruits.values_mut() {
+            fruit.update(
+                &mut self.pops,
+                &mut self.players,
+                &mut self.enemies,
+                &mut self.orbs,
+                &mut self.sounds,
+                self.timer,
+                &self.grid,
+            )
         }
-        for bolt in &mut self.bolts {
-            bolt.update(&mut self.orbs, self.player.as_mut(), self.timer, &self.grid)
+        for bolt in self.bolts.values_mut() {
+            bolt.update(
+                &mut self.orbs,
+                &mut self.players,
+                &mut self.sounds,
+                self.timer,
+                &self.grid,
+            )
         }
-        for enemy in &mut self.enemies {
+        for (handle, enemy) in self.enemies.iter_mut().filter(|(_, e)| !e.trapped) {
             enemy.update(
+                handle,
                 &mut self.bolts,
                 &mut self.orbs,
-                self.player.as_ref(),
+                &mut self.pops,
+                &mut self.players,
+                &mut self.rng,
+                &mut self.sounds,
                 fire_probability,
                 self.timer,
                 &self.grid,
             )
         }
-        self.pops.iter_mut().for_each(|p| p.update());
-        if let Some(p) = &mut self.player {
-            p.update(&mut self.orbs, &self.grid, self.timer);
+        self.pops.values_mut().for_each(|p| p.update());
+        for (p, input) in self.players.iter_mut().zip(inputs) {
+            if p.in_game() {
+                p.update(
+                    *input,
+                    &mut self.orbs,
+                    &mut self.sounds,
+                    &self.grid,
+                    self.timer,
+                );
+            }
         }
-        for orb in &mut self.orbs {
-            orb.borrow_mut().update(
+        for orb in self.orbs.values_mut() {
+            orb.update(
                 &mut self.fruits,
                 &mut self.pops,
-                self.player.as_ref(),
+                &mut self.enemies,
+                &mut self.rng,
+                &mut self.sounds,
                 &self.grid,
             )
         }
 
-        // Remove objects which are no longer wanted from the lists. For example, we recreate
-        // self.fruits such that it contains all existing fruits except those whose time_to_live counter has reached zero
+        // Remove objects which are no longer wanted from the arenas. For example, we remove from
+        // self.fruits all the fruits whose time_to_live counter has reached zero. Trapped enemies are removed by their
+        // orb, when it pops
         self.fruits.retain(|f| f.time_to_live > 0);
         self.bolts.retain(|b| b.active);
-        self.enemies.retain(|e| e.alive);
         self.pops.retain(|p| p.timer < 12);
-        self.orbs
-            .retain(|o| o.borrow().timer < 250 && o.borrow().y > -40);
+        self.orbs.retain(|o| o.timer < 250 && o.y > -40);
 
         // Every 100 frames, create a random fruit (unless there are no remaining enemies on this level)
-        if self.timer % 100 == 0 && (self.pending_enemies.len() + self.enemies.len()) > 0 {
+        if self.timer % 100 == 0 && (self.pending_enemies.len() + self.free_enemies()) > 0 {
             // Create fruit at random position
-            self.fruits.push(Fruit::new(
-                gen_range(70, 730 + 1),
-                gen_range(75, 400 + 1),
-                None,
-            ));
+            let (x, y) = (
+                self.rng.gen_range(70, 730 + 1),
+                self.rng.gen_range(75, 400 + 1),
+            );
+            self.fruits.insert(Fruit::new(x, y, None, &mut self.rng));
         }
 
         // Every 81 frames, if there is at least 1 pending enemy, and the number of active enemies is below the current
         // level's maximum enemies, create a robot
         if self.timer % 81 == 0
             && self.pending_enemies.len() > 0
-            && self.enemies.len() < self.max_enemies() as usize
+            && self.free_enemies() < self.max_enemies() as usize
         {
             // Retrieve and remove the last element from the pending enemies list
             let robot_type = self.pending_enemies.pop().unwrap();
             let (x, y) = (self.get_robot_spawn_x(), -30);
-            self.enemies.push(Robot::new(x, y, robot_type));
+            self.enemies
+                .insert(Robot::new(x, y, robot_type, &mut self.rng));
         }
 
-        // End level if there are no enemies remaining to be created, no existing enemies, no fruit, no popping orbs,
-        // and no orbs containing trapped enemies. (We don't want to include orbs which don't contain trapped enemies,
-        // as the level would never end if the player kept firing new orbs)
+        // End level if there are no enemies remaining to be created, no existing enemies (including the ones trapped in
+        // orbs), no fruit and no popping orbs. (We don't want to include orbs which don't contain trapped enemies, as
+        // the level would never end if the player kept firing new orbs)
         if self.pending_enemies.len() + self.fruits.len() + self.enemies.len() + self.pops.len()
             == 0
         {
-            if self
-                .orbs
-                .iter()
-                .all(|orb| orb.borrow().trapped_enemy_type.is_none())
-            {
-                self.next_level();
-            }
+            self.next_level();
         }
     }
 
     pub fn draw(&self) {
-        let resources = storage::get::<Resources>();
-
-        draw_texture(
-            &resources.background_textures[self.level as usize % 4],
-            0.,
-            0.,
-            WHITE,
-        );
-
-        let block_sprite = &resources.block_textures[(self.level % 4) as usize];
-
-        // Display blocks
-        for row_y in 0..NUM_ROWS {
-            let row = self.grid[row_y as usize];
-            if row.len() > 0 {
-                // Initial offset - large blocks at edge of level are 50 pixels wide
-                let mut x = LEVEL_X_OFFSET;
-                for block in row.chars() {
-                    if block != ' ' {
-                        draw_texture(
-                            block_sprite,
-                            x as f32,
-                            (row_y * GRID_BLOCK_SIZE) as f32,
-                            WHITE,
-                        );
-                    }
-                    x += GRID_BLOCK_SIZE;
-                }
-            }
-        }
+        draw_level(&self.grid, self.level_colour as usize);
 
         // Draw all objects
-        self.fruits.iter().for_each(|f| f.draw());
-        self.bolts.iter().for_each(|b| b.draw());
-        self.enemies.iter().for_each(|e| e.draw());
-        self.pops.iter().for_each(|p| p.draw());
-        self.orbs.iter().for_each(|o| o.borrow().draw());
-        if let Some(p) = &self.player {
-            p.draw();
+        self.fruits.values().for_each(|f| f.draw());
+        self.bolts.values().for_each(|b| b.draw());
+        self.enemies
+            .values()
+            .filter(|e| !e.trapped)
+            .for_each(|e| e.draw());
+        self.pops.values().for_each(|p| p.draw());
+        self.orbs.values().for_each(|o| o.draw());
+        self.players
+            .iter()
+            .filter(|p| p.in_game())
+            .for_each(|p| p.draw());
+
+        self.draw_boss_health();
+    }
+
+    /// Health bar at the top of the screen, while there's a free boss.
+    fn draw_boss_health(&self) {
+        let boss = self
+            .enemies
+            .values()
+            .find(|e| matches!(e.type_, RobotType::Boss) && !e.trapped);
+
+        if let Some(boss) = boss {
+            let x = (WIDTH as f32 - BOSS_HEALTH_BAR_WIDTH) / 2.;
+            let y = 6.;
+            let health_width = BOSS_HEALTH_BAR_WIDTH * boss.health as f32 / BOSS_HEALTH as f32;
+
+            draw_rectangle(x, y, BOSS_HEALTH_BAR_WIDTH, BOSS_HEALTH_BAR_HEIGHT, BLACK);
+            draw_rectangle(x, y, health_width, BOSS_HEALTH_BAR_HEIGHT, RED);
+            draw_rectangle_lines(
+                x,
+                y,
+                BOSS_HEALTH_BAR_WIDTH,
+                BOSS_HEALTH_BAR_HEIGHT,
+                2.,
+                WHITE,
+            );
         }
     }
 
     fn next_level(&mut self) {
-        self.level_colour = (self.level_colour + 1) % 4;
-        self.level += 1;
+        // The timer is reset below, so the time of the level just cleared is taken now
+        if self.level >= 0 {
+            self.clear_times.push((self.level as usize, self.timer));
+        }
 
-        // Set up grid
-        self.grid = LEVELS[(self.level as usize) % LEVELS.len()].to_vec();
+        self.level += 1;
 
-        // The last row is a copy of the first row
-        self.grid.push(self.grid[0]);
+        // Set up grid and settings. Once the levels are over, they start again from the first one,
+        // but harder
+        let index = self.level as usize % self.levels.len();
+        let pass = self.level as usize / self.levels.len();
+        let level = &self.levels[index];
+        self.grid = level.screen_grid();
+        self.settings = level.settings.for_pass(pass, &self.repeat);
+        self.level_colour = self.settings.theme as i8;
 
         self.timer = -1;
 
-        if let Some(player) = &mut self.player {
+        for player in &mut self.players {
             player.reset();
         }
 
-        self.fruits = vec![];
-        self.bolts = vec![];
-        self.enemies = vec![];
-        self.pops = vec![];
-        self.orbs = vec![];
+        self.fruits.clear();
+        self.bolts.clear();
+        self.enemies.clear();
+        self.pops.clear();
+        self.orbs.clear();
 
         // At the start of each level we create a list of pending enemies - enemies to be created as the level plays out.
         // When this list is empty, we have no more enemies left to create, and the level will end once we have destroyed
         // all enemies currently on-screen. Each element of the list will be either 0 or 1, where 0 corresponds to
         // a standard enemy, and 1 is a more powerful enemy.
-        // First we work out how many total enemies and how many of each type to create
-        let num_enemies = 10 + self.level as usize;
-        let num_strong_enemies = 1 + (self.level as f32 / 1.5) as usize;
+        // First we work out how many total enemies and how many of each type to create, as set by the level
+        let num_enemies = self.settings.num_enemies;
+        let num_strong_enemies = self.settings.num_strong_enemies;
         let num_weak_enemies = num_enemies - num_strong_enemies;
 
         // Then we create the list of pending enemies. The resulting list will consist of a series of copies of
@@ -239,11 +319,42 @@ impl Game {
             .append(&mut [RobotType::Normal].repeat(num_weak_enemies));
 
         // Finally we shuffle the list so that the order is randomised
-        self.pending_enemies.shuffle();
+        self.rng.shuffle(&mut self.pending_enemies);
 
-        play_game_sound(
-            self.player.as_ref(),
-            &storage::get::<Resources>().level_sound,
-        );
+        // Every few levels, a boss comes first (the enemies are taken from the end of the list)
+        if self.is_boss_level() {
+            self.pending_enemies.push(RobotType::Boss);
+        }
+
+        self.sounds.push(GameSound::Level);
+    }
+}
+
+/// Draws the background and the blocks of a grid with the given theme; shared with the level editor.
+pub fn draw_level(grid: &[&str], theme: usize) {
+    let resources = storage::get::<Resources>();
+
+    draw_texture(&resources.background_textures[theme], 0., 0., WHITE);
+
+    let block_sprite = &resources.block_textures[theme];
+
+    // Display blocks
+    for row_y in 0..NUM_ROWS {
+        let row = grid[row_y as usize];
+        if row.len() > 0 {
+            // Initial offset - large blocks at edge of level are 50 pixels wide
+            let mut x = LEVEL_X_OFFSET;
+            for block in row.chars() {
+                if block != ' ' {
+                    draw_texture(
+                        block_sprite,
+                        x as f32,
+                        (row_y * GRID_BLOCK_SIZE) as f32,
+                        WHITE,
+                    );
+                }
+                x += GRID_BLOCK_SIZE;
+            }
+        }
     }
 }
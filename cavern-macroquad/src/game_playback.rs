@@ -3,20 +3,49 @@ use macroquad::{
     rand::ChooseRandom,
 };
 
-use crate::player::Player;
+use crate::resources::Resources;
 
-// Utility function for game audio playback.
-//
-// The second one is separate due to a current architectural shortcoming - in the original code, Game
-// is global, so this is a simple implementation before the redesign.
-// The first one fits in Game, but it would be ugly to have them in different locations.
+// Rust: The original code plays the sounds from inside the game objects, but only if there is a player
+// (no sounds play on the menu, where the game runs in the background). The objects now push the sounds
+// into Game::sounds, and GlobalState plays them after the update, if there are players; this way, the
+// simulation doesn't need the audio (or Resources) at all, e.g. when running headless.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameSound {
+    Over,
+    Level,
+    Pop,
+    Ouch,
+    Die,
+    Laser,
+    Trap,
+    Blow,
+    Jump,
+    Bonus,
+    Score,
+}
+
+impl GameSound {
+    /// For the sounds with multiple variants, one is chosen randomly.
+    pub fn play(self, resources: &Resources) {
+        let sound = match self {
+            GameSound::Over => &resources.over_sound,
+            GameSound::Level => &resources.level_sound,
+            GameSound::Pop => random_sound(&resources.pop_sounds),
+            GameSound::Ouch => random_sound(&resources.ouch_sounds),
+            GameSound::Die => &resources.die_sound,
+            GameSound::Laser => random_sound(&resources.laser_sounds),
+            GameSound::Trap => random_sound(&resources.trap_sounds),
+            GameSound::Blow => random_sound(&resources.blow_sounds),
+            GameSound::Jump => &resources.jump_sound,
+            GameSound::Bonus => &resources.bonus_sound,
+            GameSound::Score => &resources.score_sound,
+        };
 
-pub fn play_game_sound(player: Option<&Player>, sound: &Sound) {
-    if player.is_some() {
         audio::play_sound_once(sound);
     }
 }
 
-pub fn play_game_random_sound(player: Option<&Player>, sounds: &Vec<Sound>) {
-    play_game_sound(player, sounds.choose().unwrap())
+fn random_sound(sounds: &Vec<Sound>) -> &Sound {
+    sounds.choose().unwrap()
 }
@@ -1,63 +1,278 @@
-use macroquad::prelude::{collections::storage, draw_texture, is_key_pressed, KeyCode, WHITE};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use macroquad::prelude::{
+    collections::storage, draw_rectangle, draw_texture, draw_texture_ex, is_key_pressed, vec2,
+    Color, DrawTextureParams, KeyCode, WHITE,
+};
 
 use crate::{
+    controls::{key_name, Action, ActionInput, Controls, MenuAction, MAX_PLAYERS},
     drawing::{draw_game_text, CHAR_WIDTH, IMAGE_WIDTH},
     game::Game,
-    game_playback::play_game_sound,
-    player::Player,
+    game_playback::GameSound,
+    initials_entry::InitialsEntry,
+    level_editor::{EditorAction, LevelEditor},
+    levels::{LevelPack, RepeatSettings},
+    options_screen::OptionsScreen,
+    player::{Player, PowerUp},
+    records::{Records, MAX_HIGH_SCORES},
+    replay::Replay,
     resources::Resources,
+    sprites::{Sprite, SpriteSheet},
     state::State,
-    WIDTH,
+    HEIGHT, WIDTH,
 };
 
+const POWER_UP_Y: i32 = 418;
+/// Space taken by each power-up, with its countdown (up to two digits of the game font)
+const POWER_UP_WIDTH: i32 = 96;
+/// As high as the game font
+const POWER_UP_ICON_SIZE: i32 = 28;
+const POWER_UP_ICON_IMAGE: usize = 6;
+
+const STATS_TOP: i32 = 70;
+const STATS_ROW_HEIGHT: i32 = 32;
+/// Rows fitting above "PRESS SPACE", as many as the high scores; the best times are paged.
+const STATS_MAX_ROWS: usize = MAX_HIGH_SCORES;
+const STATS_PAGES_Y: i32 = 20;
+const HIGH_SCORES_X: i32 = 40;
+const BEST_TIMES_X: i32 = 440;
+
 pub struct GlobalState {
     state: State,
     game: Game,
+    editor: LevelEditor,
+    /// Recorded while playing, and saved on game over; loaded from the menu, to be watched.
+    replay: Replay,
+    /// Next frame of the replay being watched.
+    replay_frame: usize,
+    records: Records,
+    /// Page of the best times shown on the stats screen
+    stats_page: usize,
+    /// On game over, one for each player whose score enters the high scores; the first one is
+    /// being entered.
+    initials_entries: Vec<InitialsEntry>,
+    input: ActionInput,
+    options: OptionsScreen,
 }
 
 impl GlobalState {
-    pub fn new() -> Self {
+    /// Opens the editor on the given level of the pack, if any, otherwise the menu.
+    pub fn new(edit_level: Option<usize>) -> Self {
         Self {
             // Set the initial game state
-            state: State::Menu,
-            game: Game::new(None),
+            state: if edit_level.is_some() {
+                State::Editor
+            } else {
+                State::Menu
+            },
+            game: menu_game(),
+            editor: LevelEditor::new(edit_level.unwrap_or(0)),
+            replay: Replay::new(0, 0),
+            replay_frame: 0,
+            records: Records::load(),
+            stats_page: 0,
+            initials_entries: vec![],
+            input: ActionInput::new(Controls::load()),
+            options: OptionsScreen::new(),
         }
     }
 
     pub fn update(&mut self) {
+        self.input.update();
+
         match self.state {
             State::Menu => {
-                if is_key_pressed(KeyCode::Space) {
+                if self.input.is_pressed(0, Action::Start) {
                     // Switch to play state, and create a new Game object, passing it a new Player object to use
-                    self.state = State::Play;
-                    self.game = Game::new(Some(Player::new()));
+                    self.start_game(1);
+                } else if self.input.is_pressed(1, Action::Start) {
+                    // Co-op: same as above, with two players
+                    self.start_game(MAX_PLAYERS);
+                } else if is_key_pressed(KeyCode::E) {
+                    self.state = State::Editor;
+                } else if is_key_pressed(KeyCode::S) {
+                    self.state = State::Stats;
+                    self.stats_page = 0;
+                } else if is_key_pressed(KeyCode::O) {
+                    self.state = State::Options;
+                } else if is_key_pressed(KeyCode::R) {
+                    match Replay::load() {
+                        Ok(replay) => {
+                            self.state = State::Replay;
+                            self.game = pack_game(players(replay.num_players), replay.seed);
+                            self.replay = replay;
+                            self.replay_frame = 0;
+                        }
+                        Err(error) => eprintln!("{}", error),
+                    }
                 } else {
-                    self.game.update();
+                    self.game.update(&[]);
                 }
             }
             State::Play => {
-                if self.game.player.as_ref().unwrap().lives < 0 {
-                    play_game_sound(
-                        self.game.player.as_ref(),
-                        &storage::get::<Resources>().over_sound,
-                    );
+                // In co-op, the game goes on until both players are out of lives
+                if self.game.players.iter().all(|p| !p.in_game()) {
+                    GameSound::Over.play(&storage::get::<Resources>());
+                    if let Err(error) = self.replay.save() {
+                        eprintln!("{}", error);
+                    }
                     self.state = State::GameOver;
+                    self.start_initials_entries();
+                } else {
+                    let inputs = self.input.player_inputs(self.game.players.len());
+                    self.replay.record(&inputs);
+                    self.game.update(&inputs);
+                    self.record_clear_times();
+                }
+            }
+            State::Editor => match self.editor.update() {
+                Some(EditorAction::Playtest(level)) => {
+                    self.state = State::Playtest;
+                    self.game = Game::new(
+                        players(1),
+                        vec![level],
+                        RepeatSettings::default(),
+                        new_seed(),
+                    );
+                }
+                Some(EditorAction::Quit) => {
+                    self.state = State::Menu;
+                    self.game = menu_game();
+                }
+                None => {}
+            },
+            State::Playtest => {
+                // The level is cleared once the game moves on to the next one (which is the same level again)
+                let cleared = self.game.level > 0;
+                let game_over = self.game.players.iter().all(|p| !p.in_game());
+
+                if cleared || game_over || is_key_pressed(KeyCode::Escape) {
+                    if cleared || game_over {
+                        self.editor.playtest_over(cleared);
+                    }
+                    self.state = State::Editor;
                 } else {
-                    self.game.update();
+                    let inputs = self.input.player_inputs(self.game.players.len());
+                    self.game.update(&inputs);
+                }
+            }
+            State::Replay => {
+                let game_over = self.game.players.iter().all(|p| !p.in_game());
+
+                match self.replay.frames.get(self.replay_frame) {
+                    Some(inputs) if !game_over && !is_key_pressed(KeyCode::Escape) => {
+                        self.game.update(inputs);
+                        self.replay_frame += 1;
+                    }
+                    _ => {
+                        self.state = State::Menu;
+                        self.game = menu_game();
+                    }
                 }
             }
             State::GameOver => {
-                if is_key_pressed(KeyCode::Space) {
+                if let Some(entry) = self.initials_entries.first_mut() {
+                    if let Some(initials) = entry.update(&self.input) {
+                        self.records.add_high_score(&initials, entry.score);
+                        self.save_records();
+                        self.initials_entries.remove(0);
+                    }
+                } else if self.input.any_player_pressed(Action::Start) {
                     self.state = State::Menu;
-                    self.game = Game::new(None);
+                    self.game = menu_game();
+                }
+            }
+            State::Stats => {
+                if self.input.any_player_pressed(Action::Start) || is_key_pressed(KeyCode::Escape) {
+                    self.state = State::Menu;
+                } else {
+                    if self.input.menu_pressed(MenuAction::Left) {
+                        self.stats_page = self.stats_page.saturating_sub(1);
+                    } else if self.input.menu_pressed(MenuAction::Right) {
+                        self.stats_page = (self.stats_page + 1).min(self.stats_pages() - 1);
+                    }
+                    self.game.update(&[]);
+                }
+            }
+            State::Options => {
+                if self.options.update(&mut self.input) {
+                    if let Err(error) = self.input.controls.save() {
+                        eprintln!("{}", error);
+                    }
+                    self.state = State::Menu;
+                } else {
+                    self.game.update(&[]);
                 }
             }
         }
+
+        self.play_sounds();
+    }
+
+    /// Each new game is recorded, so that it can be watched from the menu once it's over.
+    fn start_game(&mut self, num_players: usize) {
+        let seed = new_seed();
+
+        self.state = State::Play;
+        self.game = pack_game(players(num_players), seed);
+        self.replay = Replay::new(seed, num_players);
+    }
+
+    /// The best times are saved as soon as they're set, so that they're kept even if the window is
+    /// closed before the game is over.
+    fn record_clear_times(&mut self) {
+        let pack = &storage::get::<LevelPack>().name;
+        let mut new_record = false;
+        for (level, frames) in self.game.clear_times.drain(..) {
+            new_record |= self.records.add_clear_time(pack, level, frames);
+        }
+
+        if new_record {
+            self.save_records();
+        }
+    }
+
+    /// Players enter their initials in order; the scores are checked against the table as it is
+    /// now, so in co-op, the second score may be pushed out by the first one (see Records).
+    fn start_initials_entries(&mut self) {
+        self.initials_entries = self
+            .game
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, player)| self.records.is_high_score(player.score))
+            .map(|(player_number, player)| InitialsEntry::new(player_number, player.score))
+            .collect();
+    }
+
+    fn save_records(&self) {
+        if let Err(error) = self.records.save() {
+            eprintln!("{}", error);
+        }
+    }
+
+    /// The game shown behind the title screen has no players, so its sounds are not played.
+    fn play_sounds(&mut self) {
+        let sounds = self.game.sounds.drain(..).collect::<Vec<_>>();
+
+        if !self.game.players.is_empty() {
+            let resources = storage::get::<Resources>();
+            for sound in sounds {
+                sound.play(&resources);
+            }
+        }
     }
 
     pub fn draw(&self) {
         let resources = storage::get::<Resources>();
 
+        // The editor draws the level being edited in place of the game
+        if self.state == State::Editor {
+            self.editor.draw();
+            return;
+        }
+
         self.game.draw();
 
         match self.state {
@@ -73,22 +288,103 @@ impl GlobalState {
                 // which stage the animation is at when the game first starts
                 let anim_frame = (((self.game.timer + 40) % 160) / 4).min(9) as usize;
                 draw_texture(&resources.space_textures[anim_frame], 130., 280., WHITE);
+
+                draw_game_text("PRESS O FOR CONTROLS", 30, None);
+                draw_game_text("PRESS S FOR HIGH SCORES", 231, None);
+                draw_game_text("PRESS R TO WATCH A REPLAY", 371, None);
+                draw_game_text(&self.two_players_text(), 411, None);
+                draw_game_text("PRESS E FOR THE LEVEL EDITOR", 451, None);
             }
-            State::Play => {
+            State::Editor => {}
+            State::Play | State::Playtest | State::Replay => {
                 self.draw_status();
             }
             State::GameOver => {
                 self.draw_status();
                 // Display "Game Over" image
                 draw_texture(&resources.over_texture, 0., 0., WHITE);
+
+                if let Some(entry) = self.initials_entries.first() {
+                    entry.draw(self.game.players.len() > 1);
+                }
             }
+            State::Stats => self.draw_stats(),
+            State::Options => self.options.draw(&self.input.controls),
+        }
+    }
+
+    /// The second player starts a co-op game; without a key, with the gamepad only.
+    fn two_players_text(&self) -> String {
+        match self.input.controls.binding(1, Action::Start).key {
+            Some(key) => format!("PRESS {} FOR TWO PLAYERS", key_name(key).to_uppercase()),
+            None => "START ON GAMEPAD 2 FOR CO OP".to_string(),
         }
     }
 
+    /// High scores on the left, and the best time of each level of the current pack on the right.
+    fn draw_stats(&self) {
+        draw_rectangle(
+            0.,
+            0.,
+            WIDTH as f32,
+            HEIGHT as f32,
+            Color::new(0., 0., 0., 0.8),
+        );
+
+        draw_game_text("HIGH SCORES", STATS_TOP, Some(HIGH_SCORES_X));
+        for (i, high_score) in self.records.high_scores.iter().enumerate() {
+            draw_game_text(
+                &format!("{} {} {}", i + 1, high_score.initials, high_score.score),
+                STATS_TOP + (i as i32 + 1) * STATS_ROW_HEIGHT,
+                Some(HIGH_SCORES_X),
+            );
+        }
+
+        draw_game_text("BEST TIMES", STATS_TOP, Some(BEST_TIMES_X));
+        let pack = &storage::get::<LevelPack>().name;
+        for (i, (level, frames)) in self
+            .records
+            .pack_best_times(pack)
+            .skip(self.stats_page * STATS_MAX_ROWS)
+            .take(STATS_MAX_ROWS)
+            .enumerate()
+        {
+            draw_game_text(
+                &format!("L{} {}", level + 1, format_time(frames)),
+                STATS_TOP + (i as i32 + 1) * STATS_ROW_HEIGHT,
+                Some(BEST_TIMES_X),
+            );
+        }
+
+        if self.stats_pages() > 1 {
+            // Changed with Left/Right
+            let pages = format!("TIMES {} OF {}", self.stats_page + 1, self.stats_pages());
+            draw_game_text(&pages, STATS_PAGES_Y, None);
+        }
+
+        draw_game_text("PRESS SPACE", 441, None);
+    }
+
+    /// Pages of the best times of the current pack; at least one, even if empty.
+    fn stats_pages(&self) -> usize {
+        let pack = &storage::get::<LevelPack>().name;
+        let num_times = self.records.pack_best_times(pack).count();
+
+        ((num_times + STATS_MAX_ROWS - 1) / STATS_MAX_ROWS).max(1)
+    }
+
     fn draw_status(&self) {
-        // For Rust convenience
-        let player = self.game.player.as_ref().unwrap();
+        match self.game.players.as_slice() {
+            [player] => self.draw_single_status(player),
+            [first, second] => {
+                draw_half_status(first, false);
+                draw_half_status(second, true);
+            }
+            _ => {}
+        }
+    }
 
+    fn draw_single_status(&self, player: &Player) {
         // Display score, right-justified at edge of screen
         let number_width = CHAR_WIDTH[0];
         let s = player.score.to_string();
@@ -99,21 +395,135 @@ impl GlobalState {
 
         // Display lives and health
         // We only display a maximum of two lives - if there are more than two, a plus symbol is displayed
-        let mut lives_health = ["life"].repeat(2.min(player.lives as usize));
-        if player.lives > 2 {
-            lives_health.push("plus");
-        }
-        if player.lives >= 0 {
-            lives_health.extend(["health"].repeat(player.health as usize));
-        };
-
         let status_textures = &storage::get::<Resources>().status_textures;
 
         let mut x = 0;
-        for image in lives_health {
+        for image in lives_health_images(player, 2) {
             let texture = &status_textures[image];
             draw_texture(texture, x as f32, 450., WHITE);
             x += IMAGE_WIDTH[image];
         }
+
+        draw_power_ups(player, false);
+    }
+}
+
+/// In co-op, each player has half of the status bar: the first player on the left, as in single
+/// player mode, and the second on the right, mirrored. There is no room for the level number.
+fn draw_half_status(player: &Player, right_half: bool) {
+    // Only one life is displayed - if there are more, a plus symbol is displayed
+    let images = lives_health_images(player, 1);
+    let status_textures = &storage::get::<Resources>().status_textures;
+
+    // Display score next to the centre of the screen
+    let number_width = CHAR_WIDTH[0];
+    let s = player.score.to_string();
+    let score_x = if right_half {
+        WIDTH / 2 + 10
+    } else {
+        WIDTH / 2 - 10 - (number_width * s.len() as i32)
+    };
+    draw_game_text(&s, 451, Some(score_x));
+
+    // Display lives and health, starting from the edge of the screen
+    let mut x = if right_half { WIDTH } else { 0 };
+    for image in images {
+        let texture = &status_textures[image];
+        if right_half {
+            x -= IMAGE_WIDTH[image];
+            draw_texture(texture, x as f32, 450., WHITE);
+        } else {
+            draw_texture(texture, x as f32, 450., WHITE);
+            x += IMAGE_WIDTH[image];
+        }
     }
+
+    draw_power_ups(player, right_half);
+}
+
+/// Active power-ups, just above the status bar: a bubble with the power-up colour, followed by the
+/// seconds left. Like the lives, they start from the edge of the screen.
+fn draw_power_ups(player: &Player, right_half: bool) {
+    let texture = Sprite::new(SpriteSheet::Orb, POWER_UP_ICON_IMAGE).texture();
+
+    let mut x = if right_half {
+        WIDTH - POWER_UP_WIDTH
+    } else {
+        0
+    };
+
+    for power_up in PowerUp::ALL {
+        if !player.has_power_up(power_up) {
+            continue;
+        }
+
+        let seconds_left = (player.power_up_timer(power_up) + 59) / 60;
+
+        draw_texture_ex(
+            &texture,
+            (x + 4) as f32,
+            POWER_UP_Y as f32,
+            power_up.colour(),
+            DrawTextureParams {
+                dest_size: Some(vec2(POWER_UP_ICON_SIZE as f32, POWER_UP_ICON_SIZE as f32)),
+                ..Default::default()
+            },
+        );
+        draw_game_text(
+            &seconds_left.to_string(),
+            POWER_UP_Y,
+            Some(x + POWER_UP_ICON_SIZE + 8),
+        );
+
+        if right_half {
+            x -= POWER_UP_WIDTH;
+        } else {
+            x += POWER_UP_WIDTH;
+        }
+    }
+}
+
+/// Status images of a player, from the edge of the screen: lives (up to `max_lives`, followed by a
+/// plus if there are more), then health. Players out of lives have none.
+fn lives_health_images(player: &Player, max_lives: i32) -> Vec<&'static str> {
+    let mut lives_health = ["life"].repeat(max_lives.min(player.lives.max(0)) as usize);
+    if player.lives > max_lives {
+        lives_health.push("plus");
+    }
+    if player.in_game() {
+        lives_health.extend(["health"].repeat(player.health as usize));
+    };
+    lives_health
+}
+
+/// The font has no punctuation, hence "1M 05S" rather than "1:05".
+fn format_time(frames: i32) -> String {
+    let seconds = frames / 60;
+    if seconds < 60 {
+        format!("{}S", seconds)
+    } else {
+        format!("{}M {:02}S", seconds / 60, seconds % 60)
+    }
+}
+
+fn players(num_players: usize) -> Vec<Player> {
+    (0..num_players).map(Player::new).collect()
+}
+
+fn pack_game(players: Vec<Player>, seed: u64) -> Game {
+    let pack = storage::get::<LevelPack>();
+
+    Game::new(players, pack.levels.clone(), pack.repeat, seed)
+}
+
+/// The title screen shows a game without players in the background.
+fn menu_game() -> Game {
+    pack_game(vec![], new_seed())
+}
+
+fn new_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default()
 }
@@ -0,0 +1,110 @@
+use macroquad::{
+    color::{Color, YELLOW},
+    input::get_char_pressed,
+    shapes::draw_rectangle,
+};
+
+use crate::{
+    controls::{ActionInput, MenuAction},
+    drawing::draw_game_text,
+    records::INITIALS_LENGTH,
+    WIDTH,
+};
+
+const PANEL_Y: f32 = 380.;
+const PANEL_HEIGHT: f32 = 64.;
+const TEXT_Y: i32 = 386;
+const HELP_Y: i32 = 416;
+/// Letters are drawn one by one, at fixed distance, so that the selected one can be underlined
+const LETTER_SPACING: i32 = 34;
+const LETTER_WIDTH: i32 = 27;
+const LETTERS_X: i32 = 560;
+
+/// Entered on the game over screen, by each player whose score enters the high scores. The letters
+/// can be typed, or chosen with Up/Down; Left/Right select the letter to change, and Enter confirms
+/// (see MenuAction for the gamepad).
+pub struct InitialsEntry {
+    pub player_number: usize,
+    pub score: i32,
+    letters: [u8; INITIALS_LENGTH],
+    cursor: usize,
+}
+
+impl InitialsEntry {
+    pub fn new(player_number: usize, score: i32) -> Self {
+        // The characters typed while playing (e.g. the second player keys) are queued; they're not
+        // initials.
+        while get_char_pressed().is_some() {}
+
+        Self {
+            player_number,
+            score,
+            letters: [b'A'; INITIALS_LENGTH],
+            cursor: 0,
+        }
+    }
+
+    /// Returns the initials once confirmed.
+    pub fn update(&mut self, input: &ActionInput) -> Option<String> {
+        while let Some(chr) = get_char_pressed() {
+            if chr.is_ascii_alphabetic() {
+                self.letters[self.cursor] = chr.to_ascii_uppercase() as u8;
+                self.cursor = (self.cursor + 1).min(INITIALS_LENGTH - 1);
+            }
+        }
+
+        if input.menu_pressed(MenuAction::Confirm) {
+            return Some(self.letters.iter().map(|&letter| letter as char).collect());
+        } else if input.menu_pressed(MenuAction::Up) {
+            self.change_letter(1);
+        } else if input.menu_pressed(MenuAction::Down) {
+            self.change_letter(-1);
+        } else if input.menu_pressed(MenuAction::Left) {
+            self.cursor = self.cursor.saturating_sub(1);
+        } else if input.menu_pressed(MenuAction::Right) {
+            self.cursor = (self.cursor + 1).min(INITIALS_LENGTH - 1);
+        }
+
+        None
+    }
+
+    /// The player is named only in co-op games.
+    pub fn draw(&self, co_op: bool) {
+        draw_rectangle(
+            0.,
+            PANEL_Y,
+            WIDTH as f32,
+            PANEL_HEIGHT,
+            Color::new(0., 0., 0., 0.8),
+        );
+
+        let title = if co_op {
+            format!("PLAYER {} HIGH SCORE", self.player_number + 1)
+        } else {
+            "NEW HIGH SCORE".to_string()
+        };
+        draw_game_text(&title, TEXT_Y, Some(40));
+
+        for (i, letter) in self.letters.iter().enumerate() {
+            let x = LETTERS_X + i as i32 * LETTER_SPACING;
+            draw_game_text(&(*letter as char).to_string(), TEXT_Y, Some(x));
+
+            if i == self.cursor {
+                draw_rectangle(
+                    x as f32,
+                    (TEXT_Y + 29) as f32,
+                    LETTER_WIDTH as f32,
+                    3.,
+                    YELLOW,
+                );
+            }
+        }
+
+        draw_game_text("ENTER OR START TO SAVE", HELP_Y, None);
+    }
+
+    fn change_letter(&mut self, delta: i8) {
+        let letter = &mut self.letters[self.cursor];
+        *letter = b'A' + (*letter as i8 - b'A' as i8 + delta).rem_euclid(26) as u8;
+    }
+}
@@ -0,0 +1,36 @@
+// Rust: Player::update() used to read the keyboard directly. The actions are now read once per
+// frame (see controls.rs), into an input frame (one PlayerInput per player), which is passed to
+// Game::update(); this way, the inputs can be recorded and played back (see replay.rs), or scripted,
+// without a window.
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct PlayerInput {
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+    /// Blow key held down
+    pub blow: bool,
+    /// Blow key pressed in this frame
+    pub blow_pressed: bool,
+}
+
+impl PlayerInput {
+    /// Inputs as bit flags, for the replay files.
+    pub fn to_bits(self) -> u8 {
+        self.left as u8
+            | (self.right as u8) << 1
+            | (self.jump as u8) << 2
+            | (self.blow as u8) << 3
+            | (self.blow_pressed as u8) << 4
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            left: bits & 1 != 0,
+            right: bits & 1 << 1 != 0,
+            jump: bits & 1 << 2 != 0,
+            blow: bits & 1 << 3 != 0,
+            blow_pressed: bits & 1 << 4 != 0,
+        }
+    }
+}
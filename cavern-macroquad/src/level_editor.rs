@@ -0,0 +1,331 @@
+use std::fs;
+
+use macroquad::{
+    color::{Color, YELLOW},
+    input::{is_key_pressed, is_mouse_button_down, mouse_position, KeyCode, MouseButton},
+    prelude::collections::storage,
+    shapes::{draw_rectangle, draw_rectangle_lines},
+};
+
+use crate::{
+    drawing::draw_game_text,
+    game::draw_level,
+    levels::{intern_row, parse_level, Level, LevelPack, MAX_ENEMIES, NUM_THEMES},
+    GRID_BLOCK_SIZE, HEIGHT, LEVEL_X_OFFSET, NUM_COLUMNS, NUM_ROWS, WIDTH,
+};
+
+/// Two lines of the game font, over the last row, which is the copy of the first one
+const STATUS_Y: i32 = (NUM_ROWS - 1) * GRID_BLOCK_SIZE;
+const STATUS_LINE_Y: [i32; 2] = [STATUS_Y + 1, STATUS_Y + 27];
+const HELP_Y: i32 = 90;
+const HELP_LINE_HEIGHT: i32 = 35;
+const FIRE_PROBABILITY_STEP: f32 = 0.0001;
+
+/// The game font has no punctuation, so the keys are spelled out.
+const HELP: [&str; 8] = [
+    "MOUSE  PAINT AND ERASE",
+    "UP DOWN  SELECT A SETTING",
+    "LEFT RIGHT  CHANGE IT",
+    "PAGE UP DOWN  CHANGE LEVEL",
+    "P  PLAYTEST",
+    "S  SAVE    L  RELOAD",
+    "ESC  MENU",
+    "H  CLOSE THE HELP",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum EditorSetting {
+    Theme,
+    Enemies,
+    StrongEnemies,
+    MaxEnemies,
+    FireProbability,
+}
+
+const SETTINGS: [EditorSetting; 5] = [
+    EditorSetting::Theme,
+    EditorSetting::Enemies,
+    EditorSetting::StrongEnemies,
+    EditorSetting::MaxEnemies,
+    EditorSetting::FireProbability,
+];
+
+pub enum EditorAction {
+    Playtest(Level),
+    Quit,
+}
+
+/// Blocks are painted with the left mouse button, and erased with the right one; the level settings
+/// are shown in the status bar, where they're selected with Up/Down and changed with Left/Right.
+///
+/// The levels of the pack are edited in place: the changes of each level are kept while switching
+/// to the others, and saved to its file in the pack directory (which also updates the pack played
+/// from the menu).
+pub struct LevelEditor {
+    levels: Vec<Level>,
+    /// Index of the edited level in the pack
+    level_number: usize,
+    selected: usize,
+    /// Outcome of the last save, load or playtest; shown in place of the help hint.
+    message: Option<String>,
+    show_help: bool,
+}
+
+impl LevelEditor {
+    /// Starts from the given level of the pack.
+    pub fn new(level_number: usize) -> Self {
+        let levels = storage::get::<LevelPack>().levels.clone();
+
+        Self {
+            level_number: level_number.min(levels.len() - 1),
+            levels,
+            selected: 0,
+            message: None,
+            show_help: false,
+        }
+    }
+
+    pub fn update(&mut self) -> Option<EditorAction> {
+        if let Some((grid_x, grid_y)) = hovered_cell() {
+            if is_mouse_button_down(MouseButton::Left) {
+                self.set_cell(grid_x, grid_y, true);
+            } else if is_mouse_button_down(MouseButton::Right) {
+                self.set_cell(grid_x, grid_y, false);
+            }
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(EditorAction::Quit);
+        } else if is_key_pressed(KeyCode::P) {
+            self.message = None;
+            return Some(EditorAction::Playtest(self.level().clone()));
+        } else if is_key_pressed(KeyCode::H) {
+            self.show_help = !self.show_help;
+        } else if is_key_pressed(KeyCode::PageUp) {
+            self.change_level(-1);
+        } else if is_key_pressed(KeyCode::PageDown) {
+            self.change_level(1);
+        } else if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + SETTINGS.len() - 1) % SETTINGS.len();
+        } else if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % SETTINGS.len();
+        } else if is_key_pressed(KeyCode::Left) {
+            self.change_setting(-1);
+        } else if is_key_pressed(KeyCode::Right) {
+            self.change_setting(1);
+        } else if is_key_pressed(KeyCode::S) {
+            self.message = Some(self.save());
+        } else if is_key_pressed(KeyCode::L) {
+            self.message = Some(self.reload());
+        }
+
+        None
+    }
+
+    /// Called when returning from a playtest.
+    pub fn playtest_over(&mut self, cleared: bool) {
+        self.message = Some(if cleared {
+            "LEVEL CLEARED".to_string()
+        } else {
+            "GAME OVER".to_string()
+        });
+    }
+
+    pub fn draw(&self) {
+        draw_level(&self.level().screen_grid(), self.level().settings.theme);
+
+        // The last row is a copy of the first, so painting in either changes both
+        if let Some((grid_x, grid_y)) = hovered_cell() {
+            draw_cell_outline(grid_x, grid_y);
+            if grid_y == 0 {
+                draw_cell_outline(grid_x, NUM_ROWS - 1);
+            } else if grid_y == NUM_ROWS - 1 {
+                draw_cell_outline(grid_x, 0);
+            }
+        }
+
+        draw_shade(STATUS_Y, HEIGHT);
+
+        let status = format!(
+            "LEVEL {}  {}",
+            self.level_number + 1,
+            self.setting_text(SETTINGS[self.selected])
+        );
+        draw_game_text(&status, STATUS_LINE_Y[0], None);
+
+        let second_line = if !self.level().top_row_has_gap() {
+            "NO GAP IN THE TOP ROW"
+        } else if let Some(message) = &self.message {
+            message
+        } else {
+            "H FOR HELP"
+        };
+        draw_game_text(second_line, STATUS_LINE_Y[1], None);
+
+        if self.show_help {
+            let bottom = HELP_Y + HELP_LINE_HEIGHT * HELP.len() as i32;
+            draw_shade(HELP_Y - 15, bottom + 10);
+
+            for (i, line) in HELP.iter().enumerate() {
+                draw_game_text(line, HELP_Y + HELP_LINE_HEIGHT * i as i32, None);
+            }
+        }
+    }
+
+    fn level(&self) -> &Level {
+        &self.levels[self.level_number]
+    }
+
+    fn level_mut(&mut self) -> &mut Level {
+        &mut self.levels[self.level_number]
+    }
+
+    fn change_level(&mut self, delta: i32) {
+        let num_levels = self.levels.len() as i32;
+
+        self.level_number = (self.level_number as i32 + delta).rem_euclid(num_levels) as usize;
+        self.message = None;
+    }
+
+    /// The details of the errors, which the game font can't show, are printed.
+    fn save(&mut self) -> String {
+        let mut pack = storage::get_mut::<LevelPack>();
+        let path = pack.level_path(self.level_number);
+
+        match fs::write(&path, self.level().to_source()) {
+            Ok(()) => {
+                pack.levels[self.level_number] = self.level().clone();
+                format!("SAVED LEVEL {}", self.level_number + 1)
+            }
+            Err(error) => {
+                eprintln!("Error saving {}: {}", path, error);
+                format!("ERROR SAVING LEVEL {}", self.level_number + 1)
+            }
+        }
+    }
+
+    /// Discards the changes of the level.
+    fn reload(&mut self) -> String {
+        let path = storage::get::<LevelPack>().level_path(self.level_number);
+
+        match load_level_file(&path) {
+            Ok(level) => {
+                *self.level_mut() = level;
+                format!("RELOADED LEVEL {}", self.level_number + 1)
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                format!("ERROR LOADING LEVEL {}", self.level_number + 1)
+            }
+        }
+    }
+
+    /// Row NUM_ROWS - 1 is the copy of row 0.
+    fn set_cell(&mut self, grid_x: i32, grid_y: i32, block: bool) {
+        let grid_y = (grid_y % (NUM_ROWS - 1)) as usize;
+        let level = self.level_mut();
+        let mut row = level.grid[grid_y].chars().collect::<Vec<_>>();
+
+        row[grid_x as usize] = if block { 'X' } else { ' ' };
+
+        level.grid[grid_y] = intern_row(&row.into_iter().collect::<String>());
+    }
+
+    /// The strong enemies can't be more than the total; changing either keeps it so.
+    fn change_setting(&mut self, delta: i32) {
+        let settings = &mut self.levels[self.level_number].settings;
+
+        match SETTINGS[self.selected] {
+            EditorSetting::Theme => {
+                settings.theme =
+                    (settings.theme as i32 + delta).rem_euclid(NUM_THEMES as i32) as usize
+            }
+            EditorSetting::Enemies => {
+                settings.num_enemies = add_clamped(settings.num_enemies, delta, 1);
+                settings.num_strong_enemies = settings.num_strong_enemies.min(settings.num_enemies);
+            }
+            EditorSetting::StrongEnemies => {
+                settings.num_strong_enemies =
+                    add_clamped(settings.num_strong_enemies, delta, 0).min(settings.num_enemies);
+            }
+            EditorSetting::MaxEnemies => {
+                settings.max_enemies = add_clamped(settings.max_enemies, delta, 1).min(MAX_ENEMIES);
+            }
+            EditorSetting::FireProbability => {
+                // Rounded to the step, so that repeated changes don't accumulate errors.
+                let steps =
+                    (settings.fire_probability / FIRE_PROBABILITY_STEP).round() + delta as f32;
+                settings.fire_probability = (steps * FIRE_PROBABILITY_STEP).clamp(0., 1.);
+            }
+        }
+    }
+
+    fn setting_text(&self, setting: EditorSetting) -> String {
+        let settings = &self.level().settings;
+
+        match setting {
+            EditorSetting::Theme => format!("THEME {}", settings.theme),
+            EditorSetting::Enemies => format!("ENEMIES {}", settings.num_enemies),
+            EditorSetting::StrongEnemies => {
+                format!("STRONG ENEMIES {}", settings.num_strong_enemies)
+            }
+            EditorSetting::MaxEnemies => format!("MAX ON SCREEN {}", settings.max_enemies),
+            // In steps, since the font has no decimal point
+            EditorSetting::FireProbability => format!(
+                "FIRE {} IN {}",
+                (settings.fire_probability / FIRE_PROBABILITY_STEP).round(),
+                (1. / FIRE_PROBABILITY_STEP).round()
+            ),
+        }
+    }
+}
+
+fn load_level_file(path: &str) -> Result<Level, String> {
+    let source =
+        fs::read_to_string(path).map_err(|error| format!("Error loading {}: {}", path, error))?;
+
+    parse_level(&source).map_err(|error| format!("{}:{}", path, error))
+}
+
+fn draw_shade(top: i32, bottom: i32) {
+    draw_rectangle(
+        0.,
+        top as f32,
+        WIDTH as f32,
+        (bottom - top) as f32,
+        Color::new(0., 0., 0., 0.8),
+    );
+}
+
+/// Grid cell under the mouse, if any.
+fn hovered_cell() -> Option<(i32, i32)> {
+    let (mouse_x, mouse_y) = mouse_position();
+    let (x, y) = (mouse_x as i32 - LEVEL_X_OFFSET, mouse_y as i32);
+
+    if x < 0 || y < 0 {
+        return None;
+    }
+
+    let (grid_x, grid_y) = (x / GRID_BLOCK_SIZE, y / GRID_BLOCK_SIZE);
+
+    if grid_x < NUM_COLUMNS && grid_y < NUM_ROWS {
+        Some((grid_x, grid_y))
+    } else {
+        None
+    }
+}
+
+fn draw_cell_outline(grid_x: i32, grid_y: i32) {
+    draw_rectangle_lines(
+        (LEVEL_X_OFFSET + grid_x * GRID_BLOCK_SIZE) as f32,
+        (grid_y * GRID_BLOCK_SIZE) as f32,
+        GRID_BLOCK_SIZE as f32,
+        GRID_BLOCK_SIZE as f32,
+        2.,
+        YELLOW,
+    );
+}
+
+fn add_clamped(value: usize, delta: i32, min: usize) -> usize {
+    (value as i32 + delta).max(min as i32) as usize
+}
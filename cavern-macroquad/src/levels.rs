@@ -1,59 +1,747 @@
-pub const LEVELS: [[&str; 17]; 3] = [
-    [
-        "XXXXX     XXXXXXXX     XXXXX",
-        "",
-        "",
-        "",
-        "",
-        "   XXXXXXX        XXXXXXX   ",
-        "",
-        "",
-        "",
-        "   XXXXXXXXXXXXXXXXXXXXXX   ",
-        "",
-        "",
-        "",
-        "XXXXXXXXX          XXXXXXXXX",
-        "",
-        "",
-        "",
-    ],
-    [
-        "XXXX    XXXXXXXXXXXX    XXXX",
-        "",
-        "",
-        "",
-        "",
-        "    XXXXXXXXXXXXXXXXXXXX    ",
-        "",
-        "",
-        "",
-        "XXXXXX                XXXXXX",
-        "      X              X      ",
-        "       X            X       ",
-        "        X          X        ",
-        "         X        X         ",
-        "",
-        "",
-        "",
-    ],
-    [
-        "XXXX    XXXX    XXXX    XXXX",
-        "",
-        "",
-        "",
-        "",
-        "  XXXXXXXX        XXXXXXXX  ",
-        "",
-        "",
-        "",
-        "XXXX      XXXXXXXX      XXXX",
-        "",
-        "",
-        "",
-        "    XXXXXX        XXXXXX    ",
-        "",
-        "",
-        "",
-    ],
-];
+// Rust: The levels used to be a const array, cycled forever. They're now text files in a level pack
+// directory, listed in order by the pack index (`pack.txt`); a directory listing is not possible
+// on all the platforms supported by Macroquad, so the files are loaded by name. The index can also
+// hold the name of the pack, which identifies it in the records (the canonical path of the
+// directory if not given), and the settings added to the ones of every level each time the pack
+// starts again (all zero if not given):
+//
+//     name = Original levels
+//     repeat_enemies = 8
+//     repeat_strong_enemies = 5
+//     repeat_max_enemies = 4
+//     repeat_fire_probability = 0.0008
+//
+//     level01.txt
+//     ...
+//
+// Each level file has a header with its settings, followed by the grid:
+//
+//     # Comments start with '#'.
+//     theme = 0
+//     enemies = 10
+//     strong_enemies = 1
+//     max_enemies = 3
+//     fire_probability = 0.001
+//
+//     [grid]
+//     XXXXX.....XXXXXXXX.....XXXXX
+//     ............................
+//     ...
+//
+// The grid has one line per row, with 'X' for a block and '.' for an empty cell (spaces at the end
+// of a line are too easily lost). The last row of the level is a copy of the first, so it's not
+// in the file.
+
+use std::{cell::RefCell, collections::HashSet, fmt, fs};
+
+use macroquad::file::load_string;
+
+use crate::{NUM_COLUMNS, NUM_ROWS};
+
+pub const DEFAULT_PACK_DIR: &str = "resources/levels";
+/// As given in the index of the default pack; the records saved before the packs were named used
+/// the directory name.
+pub const DEFAULT_PACK_NAME: &str = "levels";
+pub const PACK_INDEX_FILENAME: &str = "pack.txt";
+
+/// Number of background/block image pairs.
+pub const NUM_THEMES: usize = 4;
+/// Rows stored in a level file.
+pub const LEVEL_ROWS: usize = NUM_ROWS as usize - 1;
+/// Limit of the enemies on-screen at once, as in the original game, including the repetitions.
+pub const MAX_ENEMIES: usize = 8;
+
+const GRID_HEADER: &str = "[grid]";
+const BLOCK_CELL: char = 'X';
+const EMPTY_CELL: char = '.';
+const COMMENT_PREFIX: char = '#';
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct LevelSettings {
+    /// Index of the background and block images.
+    pub theme: usize,
+    /// Total number of enemies created during the level.
+    pub num_enemies: usize,
+    /// How many of the enemies are of the more powerful type.
+    pub num_strong_enemies: usize,
+    /// Maximum number of enemies on-screen at once.
+    pub max_enemies: usize,
+    /// Likelihood per frame of each robot firing a bolt.
+    pub fire_probability: f32,
+}
+
+/// Added to the settings of the levels each time the pack starts again; given by the pack index.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct RepeatSettings {
+    pub num_enemies: usize,
+    pub num_strong_enemies: usize,
+    pub max_enemies: usize,
+    pub fire_probability: f32,
+}
+
+impl LevelSettings {
+    /// Settings for the given pass through the pack (counted from zero): on the first one they're
+    /// the ones in the file; afterwards, the difficulty keeps increasing by the repeat settings,
+    /// rather than going back to the one of the first levels. The limits of the level files still
+    /// apply.
+    pub fn for_pass(&self, pass: usize, repeat: &RepeatSettings) -> Self {
+        let num_enemies = self.num_enemies + pass * repeat.num_enemies;
+
+        Self {
+            theme: self.theme,
+            num_enemies,
+            num_strong_enemies: (self.num_strong_enemies + pass * repeat.num_strong_enemies)
+                .min(num_enemies),
+            max_enemies: (self.max_enemies + pass * repeat.max_enemies).min(MAX_ENEMIES),
+            fire_probability: (self.fire_probability + pass as f32 * repeat.fire_probability)
+                .min(1.),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Level {
+    /// LEVEL_ROWS rows, in the format used by Game (' ' for an empty cell).
+    pub grid: Vec<&'static str>,
+    pub settings: LevelSettings,
+}
+
+impl Level {
+    /// The grid as shown on screen (NUM_ROWS rows), where the last row is a copy of the first.
+    pub fn screen_grid(&self) -> Vec<&'static str> {
+        let mut grid = self.grid.clone();
+        grid.push(grid[0]);
+        grid
+    }
+
+    /// Robots enter from the gaps in the top row; without any, they're spawned in the centre of the
+    /// screen (see Game::get_robot_spawn_x()).
+    pub fn top_row_has_gap(&self) -> bool {
+        self.grid[0].contains(' ')
+    }
+
+    /// Source of the level file, in the format read by parse_level().
+    pub fn to_source(&self) -> String {
+        let settings = &self.settings;
+        let mut source = format!(
+            "theme = {}\nenemies = {}\nstrong_enemies = {}\nmax_enemies = {}\nfire_probability = {}\n\n{}\n",
+            settings.theme,
+            settings.num_enemies,
+            settings.num_strong_enemies,
+            settings.max_enemies,
+            settings.fire_probability,
+            GRID_HEADER
+        );
+
+        for row in &self.grid {
+            source.extend(row.chars().map(|cell| match cell {
+                ' ' => EMPTY_CELL,
+                _ => BLOCK_CELL,
+            }));
+            source.push('\n');
+        }
+
+        source
+    }
+}
+
+pub struct LevelPack {
+    /// Identifies the pack in the records.
+    pub name: String,
+    pub dir: String,
+    /// Level files, relative to `dir`; the editor saves the levels back to them.
+    pub filenames: Vec<String>,
+    pub levels: Vec<Level>,
+    pub repeat: RepeatSettings,
+}
+
+/// Content of the pack index.
+pub struct PackIndex<'a> {
+    pub name: Option<&'a str>,
+    pub filenames: Vec<&'a str>,
+    pub repeat: RepeatSettings,
+}
+
+/// Error position and description; lines and columns start from 1.
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+pub enum LevelError {
+    Io(macroquad::Error),
+    Parse { path: String, error: ParseError },
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, column: usize, message: String) -> Self {
+        Self {
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl From<macroquad::Error> for LevelError {
+    fn from(error: macroquad::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::Parse { path, error } => write!(f, "{}:{}", path, error),
+        }
+    }
+}
+
+impl LevelPack {
+    /// Loads the pack index, then all the levels it lists.
+    pub async fn load(pack_dir: &str) -> Result<Self, LevelError> {
+        let index_path = format!("{}/{}", pack_dir, PACK_INDEX_FILENAME);
+        let index_source = load_string(&index_path).await?;
+        let index = parse_pack_index(&index_source).map_err(|error| LevelError::Parse {
+            path: index_path,
+            error,
+        })?;
+
+        let mut levels = vec![];
+
+        for filename in &index.filenames {
+            let path = format!("{}/{}", pack_dir, filename);
+            let source = load_string(&path).await?;
+            let level = parse_level(&source).map_err(|error| LevelError::Parse { path, error })?;
+            levels.push(level);
+        }
+
+        Ok(Self {
+            name: index
+                .name
+                .map_or_else(|| canonical_pack_dir(pack_dir), str::to_string),
+            dir: pack_dir.to_string(),
+            filenames: index.filenames.iter().map(|s| s.to_string()).collect(),
+            levels,
+            repeat: index.repeat,
+        })
+    }
+
+    /// Levels are cycled once the pack is over.
+    pub fn level(&self, level_number: usize) -> &Level {
+        &self.levels[level_number % self.levels.len()]
+    }
+
+    /// File of the given level (not cycled).
+    pub fn level_path(&self, level_number: usize) -> String {
+        format!("{}/{}", self.dir, self.filenames[level_number])
+    }
+}
+
+/// The path as given if it can't be resolved (e.g. on the web).
+fn canonical_pack_dir(pack_dir: &str) -> String {
+    fs::canonicalize(pack_dir)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| pack_dir.to_string())
+}
+
+/// One level filename per line, and the name and the repeat settings, as `name = value`; blank lines
+/// and comments are skipped.
+pub fn parse_pack_index(source: &str) -> Result<PackIndex<'_>, ParseError> {
+    let mut filenames = vec![];
+    let mut name = None;
+    let mut num_enemies = None;
+    let mut num_strong_enemies = None;
+    let mut max_enemies = None;
+    let mut fire_probability = None;
+
+    for (line_number, line) in source.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with(COMMENT_PREFIX) {
+            continue;
+        } else if !trimmed.contains('=') {
+            filenames.push(trimmed);
+            continue;
+        }
+
+        let (key, key_column, value) = parse_setting_line(line_number, line, "a level filename")?;
+
+        let setting = match key {
+            "name" => &mut name,
+            "repeat_enemies" => &mut num_enemies,
+            "repeat_strong_enemies" => &mut num_strong_enemies,
+            "repeat_max_enemies" => &mut max_enemies,
+            "repeat_fire_probability" => &mut fire_probability,
+            other => return Err(unknown_setting(line_number, key_column, other)),
+        };
+
+        set_once(setting, value, key, line_number, key_column)?;
+    }
+
+    if filenames.is_empty() {
+        return Err(ParseError::new(
+            source.lines().count() + 1,
+            1,
+            "the pack doesn't list any level".to_string(),
+        ));
+    }
+
+    if let Some(("", (line_number, column))) = name {
+        return Err(ParseError::new(
+            line_number,
+            column,
+            "the pack name is empty".to_string(),
+        ));
+    }
+
+    let optional =
+        |value: Option<SettingValue>| value.map_or(Ok(0), |value| parse_value(value, 0, None));
+
+    Ok(PackIndex {
+        name: name.map(|(name, _)| name),
+        filenames,
+        repeat: RepeatSettings {
+            num_enemies: optional(num_enemies)?,
+            num_strong_enemies: optional(num_strong_enemies)?,
+            max_enemies: optional(max_enemies)?,
+            fire_probability: fire_probability
+                .map_or(Ok(0.), |value| parse_value(value, 0., Some(1.)))?,
+        },
+    })
+}
+
+pub fn parse_level(source: &str) -> Result<Level, ParseError> {
+    let mut lines = source.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    // Header; each setting is stored with its position, for the errors found after parsing them.
+
+    let mut theme = None;
+    let mut num_enemies = None;
+    let mut num_strong_enemies = None;
+    let mut max_enemies = None;
+    let mut fire_probability = None;
+
+    let grid_line = loop {
+        let (line_number, line) = match lines.next() {
+            Some(line) => line,
+            None => {
+                return Err(ParseError::new(
+                    source.lines().count() + 1,
+                    1,
+                    format!("missing {} section", GRID_HEADER),
+                ))
+            }
+        };
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with(COMMENT_PREFIX) {
+            continue;
+        } else if trimmed == GRID_HEADER {
+            break line_number;
+        }
+
+        let (key, key_column, value) = parse_setting_line(line_number, line, GRID_HEADER)?;
+
+        let setting = match key {
+            "theme" => &mut theme,
+            "enemies" => &mut num_enemies,
+            "strong_enemies" => &mut num_strong_enemies,
+            "max_enemies" => &mut max_enemies,
+            "fire_probability" => &mut fire_probability,
+            other => return Err(unknown_setting(line_number, key_column, other)),
+        };
+
+        set_once(setting, value, key, line_number, key_column)?;
+    };
+
+    let missing = |name: &str| {
+        ParseError::new(
+            grid_line,
+            1,
+            format!("setting `{}` missing before {}", name, GRID_HEADER),
+        )
+    };
+
+    let theme = theme.ok_or_else(|| missing("theme"))?;
+    let num_enemies = num_enemies.ok_or_else(|| missing("enemies"))?;
+    let num_strong_enemies = num_strong_enemies.ok_or_else(|| missing("strong_enemies"))?;
+    let max_enemies = max_enemies.ok_or_else(|| missing("max_enemies"))?;
+    let fire_probability = fire_probability.ok_or_else(|| missing("fire_probability"))?;
+
+    let settings = LevelSettings {
+        theme: parse_value(theme, 0, Some(NUM_THEMES - 1))?,
+        num_enemies: parse_value(num_enemies, 1, None)?,
+        num_strong_enemies: parse_value(num_strong_enemies, 0, None)?,
+        max_enemies: parse_value(max_enemies, 1, Some(MAX_ENEMIES))?,
+        fire_probability: parse_value(fire_probability, 0., Some(1.))?,
+    };
+
+    if settings.num_strong_enemies > settings.num_enemies {
+        let (_, (line_number, value_column)) = num_strong_enemies;
+        return Err(ParseError::new(
+            line_number,
+            value_column,
+            format!(
+                "strong_enemies ({}) is greater than enemies ({})",
+                settings.num_strong_enemies, settings.num_enemies
+            ),
+        ));
+    }
+
+    // Grid
+
+    let mut grid = vec![];
+
+    for (line_number, line) in lines {
+        let line = line.trim_end();
+
+        if grid.len() == LEVEL_ROWS {
+            if line.is_empty() {
+                continue;
+            }
+            return Err(ParseError::new(
+                line_number,
+                1,
+                format!("too many grid rows (expected {})", LEVEL_ROWS),
+            ));
+        }
+
+        let mut row = String::new();
+
+        for (i, cell) in line.chars().enumerate() {
+            if i == NUM_COLUMNS as usize {
+                return Err(ParseError::new(
+                    line_number,
+                    i + 1,
+                    format!("row longer than {} cells", NUM_COLUMNS),
+                ));
+            }
+            match cell {
+                BLOCK_CELL => row.push(BLOCK_CELL),
+                EMPTY_CELL => row.push(' '),
+                other => {
+                    return Err(ParseError::new(
+                        line_number,
+                        i + 1,
+                        format!(
+                            "invalid cell {:?} (expected '{}' or '{}')",
+                            other, BLOCK_CELL, EMPTY_CELL
+                        ),
+                    ))
+                }
+            }
+        }
+
+        let cells = line.chars().count();
+        if cells < NUM_COLUMNS as usize {
+            return Err(ParseError::new(
+                line_number,
+                cells + 1,
+                format!("row has {} cells (expected {})", cells, NUM_COLUMNS),
+            ));
+        }
+
+        grid.push(intern_row(&row));
+    }
+
+    if grid.len() < LEVEL_ROWS {
+        return Err(ParseError::new(
+            source.lines().count() + 1,
+            1,
+            format!("the grid has {} rows (expected {})", grid.len(), LEVEL_ROWS),
+        ));
+    }
+
+    Ok(Level { grid, settings })
+}
+
+thread_local! {
+    static ROWS: RefCell<HashSet<&'static str>> = RefCell::new(HashSet::new());
+}
+
+/// Rust: Rows are leaked, so that Game can keep borrowing them as `&'static str`, as it did with the
+/// const array. They're interned, so that the memory is bounded by the number of distinct rows
+/// (painting in the editor creates new rows all the time).
+pub fn intern_row(row: &str) -> &'static str {
+    ROWS.with(|rows| {
+        let mut rows = rows.borrow_mut();
+
+        match rows.get(row) {
+            Some(interned) => interned,
+            None => {
+                let interned = &*Box::leak(row.to_string().into_boxed_str());
+                rows.insert(interned);
+                interned
+            }
+        }
+    })
+}
+
+/// A value, with its line and column.
+pub(crate) type SettingValue<'a> = (&'a str, (usize, usize));
+
+/// Splits a `name = value` line; `alternative` is what else the line could have been, for the error.
+pub(crate) fn parse_setting_line<'a>(
+    line_number: usize,
+    line: &'a str,
+    alternative: &str,
+) -> Result<(&'a str, usize, SettingValue<'a>), ParseError> {
+    let key_column = column(line, line.len() - line.trim_start().len());
+
+    let (key, value) = match line.split_once('=') {
+        Some(pair) => pair,
+        None => {
+            return Err(ParseError::new(
+                line_number,
+                key_column,
+                format!("expected `name = value` or {}", alternative),
+            ))
+        }
+    };
+    let value_column = column(line, key.len() + 1 + value.len() - value.trim_start().len());
+
+    Ok((
+        key.trim(),
+        key_column,
+        (value.trim(), (line_number, value_column)),
+    ))
+}
+
+pub(crate) fn unknown_setting(line_number: usize, key_column: usize, key: &str) -> ParseError {
+    ParseError::new(
+        line_number,
+        key_column,
+        format!("unknown setting `{}`", key),
+    )
+}
+
+fn set_once<'a>(
+    setting: &mut Option<SettingValue<'a>>,
+    value: SettingValue<'a>,
+    key: &str,
+    line_number: usize,
+    key_column: usize,
+) -> Result<(), ParseError> {
+    if setting.is_some() {
+        return Err(ParseError::new(
+            line_number,
+            key_column,
+            format!("setting `{}` given more than once", key),
+        ));
+    }
+
+    *setting = Some(value);
+
+    Ok(())
+}
+
+/// Column (1-based) of the given byte index in the line.
+pub(crate) fn column(line: &str, byte_index: usize) -> usize {
+    line[..byte_index].chars().count() + 1
+}
+
+/// `max` is inclusive; None if there is no upper limit.
+fn parse_value<T>(
+    (value, (line_number, value_column)): SettingValue,
+    min: T,
+    max: Option<T>,
+) -> Result<T, ParseError>
+where
+    T: std::str::FromStr + PartialOrd + fmt::Display + Copy,
+{
+    let error = |message| ParseError::new(line_number, value_column, message);
+
+    let parsed = value
+        .parse::<T>()
+        .map_err(|_| error(format!("invalid value `{}`", value)))?;
+
+    // Written this way, so that NaN is out of range.
+    let in_range = parsed >= min && max.map_or(true, |max| parsed <= max);
+
+    if !in_range {
+        return Err(error(match max {
+            Some(max) => format!("value {} must be between {} and {}", parsed, min, max),
+            None => format!("value {} must be at least {}", parsed, min),
+        }));
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Five lines.
+    const SETTINGS: &str = "theme = 1
+enemies = 10
+strong_enemies = 2
+max_enemies = 3
+fire_probability = 0.001
+";
+
+    /// The grid header follows a blank line, so with SETTINGS, the rows start from line 8.
+    fn source(settings: &str, rows: &[String]) -> String {
+        format!("{}\n{}\n{}\n", settings, GRID_HEADER, rows.join("\n"))
+    }
+
+    fn empty_rows() -> Vec<String> {
+        vec![EMPTY_CELL.to_string().repeat(NUM_COLUMNS as usize); LEVEL_ROWS]
+    }
+
+    /// Line, column and message.
+    fn level_error(source: &str) -> (usize, usize, String) {
+        match parse_level(source) {
+            Ok(_) => panic!("the level has no errors"),
+            Err(error) => (error.line, error.column, error.message),
+        }
+    }
+
+    fn pack_error(source: &str) -> (usize, usize, String) {
+        match parse_pack_index(source) {
+            Ok(_) => panic!("the pack index has no errors"),
+            Err(error) => (error.line, error.column, error.message),
+        }
+    }
+
+    #[test]
+    fn level_round_trips() {
+        let mut rows = empty_rows();
+        rows[0].replace_range(0..5, "XXXXX");
+
+        let level = parse_level(&source(SETTINGS, &rows)).unwrap_or_else(|e| panic!("{}", e));
+
+        assert_eq!(
+            level.settings,
+            LevelSettings {
+                theme: 1,
+                num_enemies: 10,
+                num_strong_enemies: 2,
+                max_enemies: 3,
+                fire_probability: 0.001,
+            }
+        );
+        assert_eq!(level.grid.len(), LEVEL_ROWS);
+        assert!(level.grid[0].starts_with("XXXXX "));
+
+        let again = parse_level(&level.to_source()).unwrap_or_else(|e| panic!("{}", e));
+        assert_eq!(again.settings, level.settings);
+        assert_eq!(again.grid, level.grid);
+    }
+
+    #[test]
+    fn bad_rows_are_reported_at_the_cell() {
+        let mut rows = empty_rows();
+        rows[2].replace_range(4..5, "O");
+        let (line, column, message) = level_error(&source(SETTINGS, &rows));
+        assert_eq!((line, column), (10, 5));
+        assert!(message.contains("invalid cell 'O'"), "{}", message);
+
+        let mut rows = empty_rows();
+        rows[0].truncate(20);
+        let (line, column, message) = level_error(&source(SETTINGS, &rows));
+        assert_eq!((line, column), (8, 21));
+        assert!(message.contains("row has 20 cells"), "{}", message);
+
+        let mut rows = empty_rows();
+        rows[1].push(BLOCK_CELL);
+        let (line, column, _) = level_error(&source(SETTINGS, &rows));
+        assert_eq!((line, column), (9, NUM_COLUMNS as usize + 1));
+
+        // At the end of the file, after the last row
+        let mut rows = empty_rows();
+        rows.pop();
+        let (line, column, message) = level_error(&source(SETTINGS, &rows));
+        assert_eq!((line, column), (8 + rows.len(), 1));
+        assert!(message.contains("the grid has"), "{}", message);
+    }
+
+    #[test]
+    fn unknown_settings_are_reported_at_the_name() {
+        let settings = format!("{}  speed = 3\n", SETTINGS);
+        let (line, column, message) = level_error(&source(&settings, &empty_rows()));
+
+        assert_eq!((line, column), (6, 3));
+        assert_eq!(message, "unknown setting `speed`");
+    }
+
+    #[test]
+    fn missing_settings_are_reported_at_the_grid() {
+        let settings = SETTINGS.replace("max_enemies = 3\n", "");
+        let (line, column, message) = level_error(&source(&settings, &empty_rows()));
+
+        assert_eq!((line, column), (6, 1));
+        assert!(message.contains("`max_enemies` missing"), "{}", message);
+    }
+
+    #[test]
+    fn bad_values_are_reported_at_the_value() {
+        let settings = SETTINGS.replace("enemies = 10", "enemies =  ten");
+        let (line, column, message) = level_error(&source(&settings, &empty_rows()));
+        assert_eq!((line, column), (2, 12));
+        assert_eq!(message, "invalid value `ten`");
+
+        let settings = SETTINGS.replace("max_enemies = 3", "max_enemies = 9");
+        let (line, column, _) = level_error(&source(&settings, &empty_rows()));
+        assert_eq!((line, column), (4, 15));
+
+        let settings = SETTINGS.replace("strong_enemies = 2", "strong_enemies = 11");
+        let (line, column, _) = level_error(&source(&settings, &empty_rows()));
+        assert_eq!((line, column), (3, 18));
+    }
+
+    #[test]
+    fn pack_index_lists_the_levels_and_repeat_settings() {
+        let index = parse_pack_index("# Pack\nrepeat_enemies = 2\n\nfirst.txt\n  second.txt\n")
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        assert_eq!(index.name, None);
+        assert_eq!(index.filenames, ["first.txt", "second.txt"]);
+        assert_eq!(
+            index.repeat,
+            RepeatSettings {
+                num_enemies: 2,
+                ..RepeatSettings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn pack_index_has_the_pack_name() {
+        let index = parse_pack_index("name = My pack = 2\nlevel01.txt\n")
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        assert_eq!(index.name, Some("My pack = 2"));
+    }
+
+    #[test]
+    fn pack_index_errors_have_the_position() {
+        let (line, column, message) = pack_error("level01.txt\n repeat_speed = 1\n");
+        assert_eq!((line, column), (2, 2));
+        assert_eq!(message, "unknown setting `repeat_speed`");
+
+        let (line, column, _) = pack_error("repeat_fire_probability = 2\nlevel01.txt\n");
+        assert_eq!((line, column), (1, 27));
+
+        let (line, column, message) = pack_error("repeat_enemies = 1\nrepeat_enemies = 2\n");
+        assert_eq!((line, column), (2, 1));
+        assert!(message.contains("more than once"), "{}", message);
+
+        let (line, column, message) = pack_error("name =  \nlevel01.txt\n");
+        assert_eq!((line, column), (1, 9));
+        assert_eq!(message, "the pack name is empty");
+
+        let (line, column, message) = pack_error("# Nothing\n\n");
+        assert_eq!((line, column), (3, 1));
+        assert_eq!(message, "the pack doesn't list any level");
+    }
+}
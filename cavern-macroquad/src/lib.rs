@@ -0,0 +1,42 @@
+#![allow(clippy::all)]
+#![deny(clippy::correctness)]
+
+// Rust: The game is a library, so that the simulation can be driven without a window, e.g. by the
+// tests (see tests/); main.rs only sets up the window, the resources and the main loop.
+
+pub mod actor;
+pub mod arena;
+pub mod bolt;
+pub mod collide_actor;
+pub mod controls;
+pub mod drawing;
+pub mod fruit;
+pub mod game;
+pub mod game_playback;
+pub mod global_state;
+pub mod gravity_actor;
+pub mod initials_entry;
+pub mod input;
+pub mod level_editor;
+pub mod levels;
+pub mod options_screen;
+pub mod orb;
+pub mod player;
+pub mod pop;
+pub mod records;
+pub mod replay;
+pub mod resources;
+pub mod rng;
+pub mod robot;
+pub mod sprites;
+pub mod state;
+
+pub const WIDTH: i32 = 800;
+pub const HEIGHT: i32 = 480;
+pub const TITLE: &str = "Cavern Macroquad Naive";
+
+pub const NUM_ROWS: i32 = 18;
+pub const NUM_COLUMNS: i32 = 28;
+
+pub const LEVEL_X_OFFSET: i32 = 50;
+pub const GRID_BLOCK_SIZE: i32 = 25;
@@ -1,42 +1,19 @@
 #![allow(clippy::all)]
 #![deny(clippy::correctness)]
 
-mod actor;
-mod bolt;
-mod collide_actor;
-mod drawing;
-mod fruit;
-mod game;
-mod game_playback;
-mod global_state;
-mod gravity_actor;
-mod levels;
-mod orb;
-mod player;
-mod pop;
-mod resources;
-mod robot;
-mod state;
-
 use macroquad::{
     audio::{self, PlaySoundParams},
     prelude::{collections::storage, coroutines::start_coroutine, *},
 };
 
-use global_state::GlobalState;
-use resources::Resources;
-
-use std::error;
-
-pub const WIDTH: i32 = 800;
-pub const HEIGHT: i32 = 480;
-pub const TITLE: &str = "Cavern Macroquad Naive";
-
-pub const NUM_ROWS: i32 = 18;
-pub const NUM_COLUMNS: i32 = 28;
+use cavern_macroquad::{
+    global_state::GlobalState,
+    levels::{LevelPack, DEFAULT_PACK_DIR},
+    resources::Resources,
+    HEIGHT, TITLE, WIDTH,
+};
 
-pub const LEVEL_X_OFFSET: i32 = 50;
-pub const GRID_BLOCK_SIZE: i32 = 25;
+use std::{env, error, process};
 
 fn window_conf() -> Conf {
     Conf {
@@ -74,11 +51,75 @@ async fn load_resources() -> Result<(), Box<dyn error::Error>> {
     Ok(())
 }
 
+const EDIT_PARAM: &str = "--edit";
+
+struct Args {
+    pack_dir: String,
+    /// Number of the level to open in the editor, starting from 1
+    edit_level: Option<usize>,
+}
+
+// The level pack directory can be passed as argument, and `--edit <level>` opens the editor on a
+// level of the pack.
+//
+fn parse_args() -> Args {
+    let mut args = env::args().skip(1);
+    let mut pack_dir = None;
+    let mut edit_level = None;
+
+    while let Some(arg) = args.next() {
+        if arg == EDIT_PARAM {
+            match args.next().and_then(|level| level.parse::<usize>().ok()) {
+                Some(level) if level > 0 => edit_level = Some(level),
+                _ => exit_with_error(&format!("{} needs a level number", EDIT_PARAM)),
+            }
+        } else if pack_dir.is_none() {
+            pack_dir = Some(arg);
+        } else {
+            exit_with_error(&format!("Unexpected argument: {}", arg));
+        }
+    }
+
+    Args {
+        pack_dir: pack_dir.unwrap_or_else(|| DEFAULT_PACK_DIR.to_string()),
+        edit_level,
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+// The levels are needed by the menu, so they're loaded upfront. Errors in the level files are common
+// when editing them, so they're reported plainly, rather than in debug format.
+//
+async fn load_level_pack(pack_dir: &str) -> LevelPack {
+    match LevelPack::load(pack_dir).await {
+        Ok(level_pack) => level_pack,
+        Err(error) => exit_with_error(&format!("Error loading the level pack: {}", error)),
+    }
+}
+
 #[macroquad::main(window_conf())]
 async fn main() -> Result<(), Box<dyn error::Error>> {
+    let args = parse_args();
+    let level_pack = load_level_pack(&args.pack_dir).await;
+
+    if let Some(level) = args.edit_level {
+        if level > level_pack.levels.len() {
+            exit_with_error(&format!(
+                "The pack has {} levels, there's no level {}",
+                level_pack.levels.len(),
+                level
+            ));
+        }
+    }
+
+    storage::store(level_pack);
     load_resources().await?;
 
-    let mut state = GlobalState::new();
+    let mut state = GlobalState::new(args.edit_level.map(|level| level - 1));
 
     // Start music
     let music = audio::load_sound("resources/music/theme.ogg").await?;
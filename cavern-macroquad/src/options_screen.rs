@@ -0,0 +1,132 @@
+// Rust: Not in the original game. Shows the key and the gamepad button bound to each action of each
+// player (see controls.rs); a binding is changed by selecting it with Up/Down, pressing Enter, then
+// pressing the new key or button; Backspace restores the default binding. The screen is also used
+// with a gamepad (see MenuAction).
+
+use macroquad::{
+    color::Color,
+    input::{is_key_pressed, KeyCode},
+    shapes::draw_rectangle,
+};
+
+use crate::{
+    controls::{button_name, key_name, Action, ActionInput, Controls, MenuAction, MAX_PLAYERS},
+    drawing::draw_game_text,
+    HEIGHT, WIDTH,
+};
+
+const TITLE_Y: i32 = 20;
+const ROWS_TOP: i32 = 64;
+const ROW_HEIGHT: i32 = 32;
+const ACTION_X: i32 = 40;
+const KEY_X: i32 = 300;
+const BUTTON_X: i32 = 540;
+const HELP_Y: i32 = 406;
+const SELECTED_COLOUR: Color = Color::new(1., 1., 0., 0.3);
+
+/// The font has no punctuation
+const UNBOUND_NAME: &str = "NONE";
+
+const NUM_ROWS: usize = MAX_PLAYERS * Action::ALL.len();
+
+pub struct OptionsScreen {
+    /// Row of the binding selected, counting the actions of the first player, then of the second
+    selected: usize,
+    /// Enter has been pressed, and the next key or button pressed is bound
+    waiting: bool,
+}
+
+impl OptionsScreen {
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            waiting: false,
+        }
+    }
+
+    /// Returns true when the screen is left; the caller saves the controls.
+    pub fn update(&mut self, input: &mut ActionInput) -> bool {
+        let (player_number, action) = row_binding(self.selected);
+
+        // Any button can be bound, so only Escape cancels
+        if self.waiting {
+            if is_key_pressed(KeyCode::Escape) {
+                self.waiting = false;
+            } else if let Some(pressed) = input.binding_pressed() {
+                input.controls.rebind(player_number, action, pressed);
+                self.waiting = false;
+            }
+            return false;
+        }
+
+        if input.menu_pressed(MenuAction::Back) {
+            return true;
+        } else if input.menu_pressed(MenuAction::Up) {
+            self.selected = (self.selected + NUM_ROWS - 1) % NUM_ROWS;
+        } else if input.menu_pressed(MenuAction::Down) {
+            self.selected = (self.selected + 1) % NUM_ROWS;
+        } else if input.menu_pressed(MenuAction::Confirm) {
+            self.waiting = true;
+        } else if input.menu_pressed(MenuAction::Reset) {
+            // Through rebind(), so that the default doesn't duplicate a binding
+            input.controls.rebind(
+                player_number,
+                action,
+                Controls::default().binding(player_number, action),
+            );
+        }
+
+        false
+    }
+
+    pub fn draw(&self, controls: &Controls) {
+        draw_rectangle(
+            0.,
+            0.,
+            WIDTH as f32,
+            HEIGHT as f32,
+            Color::new(0., 0., 0., 0.8),
+        );
+
+        draw_game_text("CONTROLS", TITLE_Y, None);
+
+        for row in 0..NUM_ROWS {
+            let (player_number, action) = row_binding(row);
+            let binding = controls.binding(player_number, action);
+            let y = ROWS_TOP + row as i32 * ROW_HEIGHT;
+
+            if row == self.selected {
+                draw_rectangle(
+                    0.,
+                    (y - 2) as f32,
+                    WIDTH as f32,
+                    ROW_HEIGHT as f32,
+                    SELECTED_COLOUR,
+                );
+            }
+
+            let action_name = format!("P{} {}", player_number + 1, action.name());
+            let key = binding.key.map_or(UNBOUND_NAME, key_name);
+            let button = binding.button.map_or(UNBOUND_NAME, button_name);
+
+            draw_game_text(&action_name.to_uppercase(), y, Some(ACTION_X));
+            draw_game_text(&key.to_uppercase(), y, Some(KEY_X));
+            draw_game_text(&button.to_uppercase(), y, Some(BUTTON_X));
+        }
+
+        if self.waiting {
+            draw_game_text("PRESS A KEY OR BUTTON", HELP_Y, None);
+            draw_game_text("ESC TO CANCEL", HELP_Y + 35, None);
+        } else {
+            draw_game_text("ENTER OR START TO CHANGE", HELP_Y, None);
+            draw_game_text("BACKSPACE RESETS  ESC EXITS", HELP_Y + 35, None);
+        }
+    }
+}
+
+fn row_binding(row: usize) -> (usize, Action) {
+    (
+        row / Action::ALL.len(),
+        Action::ALL[row % Action::ALL.len()],
+    )
+}
@@ -1,59 +1,55 @@
-use std::{
-    cell::RefCell,
-    rc::{Rc, Weak},
-};
-
-use macroquad::{
-    prelude::{collections::storage, Texture2D},
-    rand::gen_range,
-};
-
 use crate::{
     actor::{Actor, Anchor},
+    arena::{Arena, Handle},
     bolt::Bolt,
     collide_actor::CollideActor,
     fruit::Fruit,
-    game_playback::play_game_random_sound,
-    player::Player,
+    game_playback::GameSound,
     pop::Pop,
-    resources::Resources,
-    robot::RobotType,
+    rng::Rng,
+    robot::{Robot, RobotType},
+    sprites::{Sprite, SpriteSheet},
 };
 
 const MAX_TIMER: i32 = 250;
 
-pub type RcOrb = Rc<RefCell<Orb>>;
-pub type WkOrb = Weak<RefCell<Orb>>;
+/// Number of fruits created when a boss is cleared
+const BOSS_FRUIT_SHOWER: usize = 20;
 
 #[derive(Clone)]
-
 pub struct Orb {
     pub direction_x: i32,
     pub timer: i32,
     pub floating: bool,
     /// Number of frames during which we will be pushed horizontally
     pub blown_frames: i32,
-    /// Type of enemy trapped in this bubble
-    pub trapped_enemy_type: Option<RobotType>,
+    /// Enemy trapped in this bubble; it stays in the enemies arena (inactive) until the bubble pops
+    pub trapped_enemy: Option<Handle<Robot>>,
+    /// Number of the player who blew this orb
+    pub player_number: usize,
+    /// Orbs trapping a boss grow to its size
+    pub scale: i32,
 
     // Actor trait
     pub x: i32,
     pub y: i32,
-    pub image: Texture2D,
+    pub image: Sprite,
     pub anchor: Anchor,
 }
 
 impl Orb {
-    pub fn new(x: i32, y: i32, direction_x: i32) -> Self {
+    pub fn new(x: i32, y: i32, direction_x: i32, player_number: usize) -> Self {
         Self {
             direction_x, // Orbs are initially blown horizontally, then start floating upwards
             timer: -1,
             floating: false,
             blown_frames: 6,
-            trapped_enemy_type: None,
+            trapped_enemy: None,
+            player_number,
+            scale: 1,
             x,
             y,
-            image: storage::get::<Resources>().blank_texture.clone(),
+            image: Sprite::BLANK,
             anchor: Anchor::Centre,
         }
     }
@@ -62,23 +58,48 @@ impl Orb {
         // Check for collision with a bolt
         let collided = self.collidepoint((bolt.x, bolt.y));
         if collided {
-            self.timer = MAX_TIMER - 1;
+            self.burst();
         }
         collided
     }
 
+    /// Area that traps the robots touching it (left, top, right, bottom). For a normal orb it's just
+    /// the centre, as in the original game; it grows with the scale, up to the inner half of a
+    /// double size orb.
+    pub fn trap_rect(&self) -> (i32, i32, i32, i32) {
+        let (center_x, center_y) = self.center();
+        let (reach_x, reach_y) = (
+            (self.scale - 1) * self.image.width() / 2,
+            (self.scale - 1) * self.image.height() / 2,
+        );
+
+        (
+            center_x - reach_x,
+            center_y - reach_y,
+            center_x + reach_x,
+            center_y + reach_y,
+        )
+    }
+
+    /// Pops on the next update.
+    pub fn burst(&mut self) {
+        self.timer = MAX_TIMER - 1;
+    }
+
     pub fn update(
         &mut self,
-        fruits: &mut Vec<Fruit>,
-        pops: &mut Vec<Pop>,
-        player: Option<&Player>,
+        fruits: &mut Arena<Fruit>,
+        pops: &mut Arena<Pop>,
+        enemies: &mut Arena<Robot>,
+        rng: &mut Rng,
+        sounds: &mut Vec<GameSound>,
         grid: &[&str],
     ) {
         self.timer += 1;
 
         if self.floating {
             // Float upwards
-            self.move_(0, -1, gen_range(1, 3), grid);
+            self.move_(0, -1, rng.gen_range(1, 3), grid);
         } else {
             // Move horizontally
             if self.move_(self.direction_x, 0, 4, grid) {
@@ -91,30 +112,38 @@ impl Orb {
             self.floating = true;
         } else if self.timer >= MAX_TIMER || self.y <= -40 {
             // Pop if our lifetime has run out or if we have gone off the top of the screen
-            pops.push(Pop::new(self.x, self.y, 1));
-            if let Some(trapped_enemy_type) = self.trapped_enemy_type {
-                // trapped_enemy_type is either zero or one. A value of one means there's a chance of creating a
-                // powerup such as an extra life or extra health
-                fruits.push(Fruit::new(self.x, self.y, Some(trapped_enemy_type)));
+            pops.insert(Pop::new(self.x, self.y, 1));
+            // The trapped enemy (if any) is released from the arena, and turns into fruit
+            if let Some(trapped_enemy) = self.trapped_enemy.take().and_then(|h| enemies.remove(h)) {
+                if let RobotType::Boss = trapped_enemy.type_ {
+                    // Bonus fruit shower, falling all over the level
+                    for _ in 0..BOSS_FRUIT_SHOWER {
+                        let (x, y) = (rng.gen_range(70, 730 + 1), rng.gen_range(-100, 0));
+                        fruits.insert(Fruit::new(x, y, Some(trapped_enemy.type_), rng));
+                    }
+                    sounds.push(GameSound::Bonus);
+                } else {
+                    // The enemy type is either zero or one. A value of one means there's a chance of creating a
+                    // powerup such as an extra life or extra health
+                    fruits.insert(Fruit::new(self.x, self.y, Some(trapped_enemy.type_), rng));
+                }
             }
-            play_game_random_sound(player, &storage::get::<Resources>().pop_sounds);
+            sounds.push(GameSound::Pop);
         }
 
-        let resources = storage::get::<Resources>();
-
         if self.timer < 9 {
             // Orb grows to full size over the course of 9 frames - the animation frame updating every 3 frames
             let timer_factor = self.timer / 3;
-            self.image = resources.orb_textures[timer_factor as usize].clone();
+            self.image = Sprite::new(SpriteSheet::Orb, timer_factor as usize);
         } else {
-            if let Some(trapped_enemy_type) = self.trapped_enemy_type {
-                let enemy_type_factor = trapped_enemy_type.val() * 8;
+            if let Some(trapped_enemy) = self.trapped_enemy.and_then(|h| enemies.get(h)) {
+                let enemy_type_factor = trapped_enemy.type_.val() * 8;
                 let timer_factor = (self.timer / 4) % 8;
                 let image_i = (enemy_type_factor + timer_factor) as usize;
-                self.image = resources.trap_textures[image_i].clone();
+                self.image = Sprite::new(SpriteSheet::Trap, image_i);
             } else {
                 let timer_factor = 3 + (((self.timer - 9) / 8) % 4);
-                self.image = resources.orb_textures[timer_factor as usize].clone();
+                self.image = Sprite::new(SpriteSheet::Orb, timer_factor as usize);
             }
         }
     }
@@ -137,13 +166,17 @@ impl Actor for Orb {
         &mut self.y
     }
 
-    fn image(&self) -> &macroquad::prelude::Texture2D {
-        &self.image
+    fn image(&self) -> Sprite {
+        self.image
     }
 
     fn anchor(&self) -> crate::actor::Anchor {
         self.anchor
     }
+
+    fn scale(&self) -> i32 {
+        self.scale
+    }
 }
 
 impl CollideActor for Orb {}
@@ -1,39 +1,88 @@
-use std::{
-    cell::RefCell,
-    rc::{Rc, Weak},
-};
-
-use macroquad::prelude::{collections::storage, is_key_down, is_key_pressed, KeyCode, Texture2D};
+use macroquad::prelude::{Color, WHITE};
 
 use crate::{
     actor::{Actor, Anchor},
+    arena::{Arena, Handle},
     bolt::Bolt,
     collide_actor::CollideActor,
-    game_playback::{play_game_random_sound, play_game_sound},
+    game_playback::GameSound,
     gravity_actor::{GravityActor, GRAVITY_ACTOR_DEFAULT_ANCHOR},
-    orb::{Orb, RcOrb, WkOrb},
-    resources::Resources,
+    input::PlayerInput,
+    orb::Orb,
+    sprites::{Sprite, SpriteSheet},
     HEIGHT, WIDTH,
 };
 
+/// Maximum number of orbs each player can have on screen
+const MAX_ORBS: usize = 5;
+
+/// Tints of the players, so that they can be told apart
+const PLAYER_COLOURS: [Color; 2] = [WHITE, Color::new(0.6, 1., 0.6, 1.)];
+const SHIELD_COLOUR: Color = Color::new(0.5, 0.8, 1., 1.);
+
+const SPEED: i32 = 4;
+const BOOSTED_SPEED: i32 = 6;
+/// Minimum frames between orbs
+const FIRE_INTERVAL: i32 = 20;
+const RAPID_FIRE_INTERVAL: i32 = 8;
+const BIG_ORB_SCALE: i32 = 2;
+
+/// Frames for which a power-up lasts
+pub const POWER_UP_DURATION: i32 = 600;
+
+/// Timed power-ups, granted by the power-up fruits (see Fruit).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerUp {
+    RapidFire,
+    BigOrbs,
+    SpeedBoost,
+    /// Bolts and ground slams don't hurt
+    Shield,
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 4] = [
+        PowerUp::RapidFire,
+        PowerUp::BigOrbs,
+        PowerUp::SpeedBoost,
+        PowerUp::Shield,
+    ];
+
+    /// Tint of the power-up fruit, and of the status bar icon.
+    pub fn colour(self) -> Color {
+        match self {
+            PowerUp::RapidFire => Color::new(1., 0.5, 0.5, 1.),
+            PowerUp::BigOrbs => Color::new(0.6, 1., 0.6, 1.),
+            PowerUp::SpeedBoost => Color::new(1., 1., 0.4, 1.),
+            PowerUp::Shield => SHIELD_COLOUR,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Clone)]
 pub struct Player {
+    /// 0 for the first player, 1 for the second
+    pub number: usize,
     pub lives: i32,
     pub score: i32,
     pub direction_x: i32, // -1 = left, 1 = right
     pub fire_timer: i32,
     pub hurt_timer: i32,
     pub health: i32,
-    /// There are different approaches to keeping a reference to the blowing orb (in the existing Orbs
-    /// array). This is the simplest in terms of management (although it makes access verbose). An alternative
-    /// is to store here the index instead; it's considerably less verbose, but it introduces manual
-    /// management and coupling (when an Orb is removed, this reference to be updated accordingly).
-    /// There's not best solution, but a compromise.
-    pub blowing_orb: WkOrb,
+    /// Handle of the orb being blown, in the Game orbs arena. Once the orb is removed, the handle
+    /// doesn't resolve anymore, so there's no need to update it.
+    pub blowing_orb: Option<Handle<Orb>>,
+    /// Frames left for each power-up, in PowerUp::ALL order; not active if zero or less
+    pub power_up_timers: [i32; 4],
 
     // Actor trait
     pub x: i32,
     pub y: i32,
-    pub image: Texture2D,
+    pub image: Sprite,
     pub anchor: Anchor,
 
     // GravityActor trait
@@ -42,19 +91,21 @@ pub struct Player {
 }
 
 impl Player {
-    pub fn new() -> Self {
+    pub fn new(number: usize) -> Self {
         Self {
+            number,
             lives: 2,
             score: 0,
             direction_x: 0,
             fire_timer: 0,
             hurt_timer: 0,
             health: 0,
-            blowing_orb: Weak::new(),
+            blowing_orb: None,
+            power_up_timers: [0; 4],
 
             x: 0,
             y: 0,
-            image: storage::get::<Resources>().blank_texture.clone(),
+            image: Sprite::BLANK,
             anchor: GRAVITY_ACTOR_DEFAULT_ANCHOR,
 
             vel_y: 0,
@@ -63,48 +114,86 @@ impl Player {
     }
 
     pub fn reset(&mut self) {
-        self.x = WIDTH / 2;
+        // The second player starts a bit to the right of the first
+        self.x = WIDTH / 2 + 60 * self.number as i32;
         self.y = 100;
         self.vel_y = 0;
         self.direction_x = 1; // -1 = left, 1 = right
         self.fire_timer = 0;
         self.hurt_timer = 100; // Invulnerable for this many frames
         self.health = 3;
-        self.blowing_orb = Weak::new();
+        self.blowing_orb = None;
+        self.power_up_timers = [0; 4];
+    }
+
+    pub fn power_up_timer(&self, power_up: PowerUp) -> i32 {
+        self.power_up_timers[power_up.index()]
+    }
+
+    pub fn has_power_up(&self, power_up: PowerUp) -> bool {
+        self.power_up_timer(power_up) > 0
+    }
+
+    /// Collecting a power-up that's already active restarts its timer.
+    pub fn add_power_up(&mut self, power_up: PowerUp) {
+        self.power_up_timers[power_up.index()] = POWER_UP_DURATION;
+    }
+
+    /// Players out of lives are out of the game, although still in the list, for the status bar.
+    pub fn in_game(&self) -> bool {
+        self.lives >= 0
     }
 
-    pub fn hit_test(&mut self, other: &Bolt) -> bool {
+    pub fn hit_test(&mut self, other: &Bolt, sounds: &mut Vec<GameSound>) -> bool {
         // Check for collision between player and bolt - called from Bolt.update. Also check hurt_timer - after being hurt,
         // there is a period during which the player cannot be hurt again
         if self.collidepoint((other.x, other.y)) && self.hurt_timer < 0 {
-            // Player loses 1 health, is knocked in the direction the bolt had been moving, and can't be hurt again
-            // for a while
-            self.hurt_timer = 200;
-            self.health -= 1;
-            self.vel_y = -12;
-            self.landed = false;
-            self.direction_x = other.direction_x;
-            let resources = storage::get::<Resources>();
-            if self.health > 0 {
-                play_game_random_sound(Some(self), &resources.ouch_sounds);
-            } else {
-                play_game_sound(Some(self), &resources.die_sound);
-            }
+            // Knocked in the direction the bolt had been moving
+            self.hurt(other.direction_x, sounds);
             true
         } else {
             false
         }
     }
 
-    pub fn update(&mut self, orbs: &mut Vec<RcOrb>, grid: &[&str], game_timer: i32) {
+    /// Player loses 1 health, is knocked in the given direction, and can't be hurt again for a while; also
+    /// called by the boss ground slam (see Robot). Nothing happens if the player has a shield.
+    pub fn hurt(&mut self, direction_x: i32, sounds: &mut Vec<GameSound>) {
+        if self.has_power_up(PowerUp::Shield) {
+            return;
+        }
+
+        self.hurt_timer = 200;
+        self.health -= 1;
+        self.vel_y = -12;
+        self.landed = false;
+        self.direction_x = direction_x;
+        if self.health > 0 {
+            sounds.push(GameSound::Ouch);
+        } else {
+            sounds.push(GameSound::Die);
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        input: PlayerInput,
+        orbs: &mut Arena<Orb>,
+        sounds: &mut Vec<GameSound>,
+        grid: &[&str],
+        game_timer: i32,
+    ) {
         // Call GravityActor.update - parameter is whether we want to perform collision detection as we fall. If health
         // is zero, we want the player to just fall out of the level
         GravityActor::update(self, self.health > 0, grid);
 
         self.fire_timer -= 1;
         self.hurt_timer -= 1;
+        for timer in &mut self.power_up_timers {
+            *timer -= 1;
+        }
 
-        // Get keyboard input. dx represents the direction the player is facing
+        // Get the input. dx represents the direction the player is facing
         // Rust: In the original code, this is (inappropriately but functionally) inside the else block, which, in static
         // languages, is out of scope.
         let mut dx = 0;
@@ -130,9 +219,9 @@ impl Player {
             }
         } else {
             // We're not hurt
-            if is_key_down(KeyCode::Left) {
+            if input.left {
                 dx = -1;
-            } else if is_key_down(KeyCode::Right) {
+            } else if input.right {
                 dx = 1;
             }
 
@@ -141,71 +230,81 @@ impl Player {
 
                 // If we haven't just fired an orb, carry out horizontal movement
                 if self.fire_timer < 10 {
-                    self.move_(dx, 0, 4, grid);
+                    let speed = if self.has_power_up(PowerUp::SpeedBoost) {
+                        BOOSTED_SPEED
+                    } else {
+                        SPEED
+                    };
+                    self.move_(dx, 0, speed, grid);
                 }
             }
 
-            let resources = storage::get::<Resources>();
-
             // Do we need to create a new orb? Space must have been pressed and released, the minimum time between
-            // orbs must have passed, and there is a limit of 5 orbs.
-            if is_key_pressed(KeyCode::Space) && self.fire_timer <= 0 && orbs.len() < 5 {
+            // orbs must have passed, and there is a limit of 5 orbs per player.
+            let own_orbs = orbs
+                .values()
+                .filter(|orb| orb.player_number == self.number)
+                .count();
+            if input.blow_pressed && self.fire_timer <= 0 && own_orbs < MAX_ORBS {
                 // x position will be 38 pixels in front of the player position, while ensuring it is within the
                 // bounds of the level
                 let x = (self.x() + self.direction_x * 38).clamp(70, 730);
                 let y = self.y() - 35;
-                let new_orb = Rc::new(RefCell::new(Orb::new(x, y, self.direction_x)));
-                self.blowing_orb = Rc::downgrade(&new_orb);
-                orbs.push(new_orb);
-                play_game_random_sound(Some(self), &resources.blow_sounds);
-                self.fire_timer = 20;
+                let mut new_orb = Orb::new(x, y, self.direction_x, self.number);
+                if self.has_power_up(PowerUp::BigOrbs) {
+                    new_orb.scale = BIG_ORB_SCALE;
+                }
+                self.blowing_orb = Some(orbs.insert(new_orb));
+                sounds.push(GameSound::Blow);
+                self.fire_timer = if self.has_power_up(PowerUp::RapidFire) {
+                    RAPID_FIRE_INTERVAL
+                } else {
+                    FIRE_INTERVAL
+                };
             }
 
-            if is_key_down(KeyCode::Up) && self.vel_y == 0 && self.landed {
+            if input.jump && self.vel_y == 0 && self.landed {
                 // Jump
                 self.vel_y = -16;
                 self.landed = false;
-                play_game_sound(Some(self), &resources.jump_sound);
+                sounds.push(GameSound::Jump);
             }
         }
 
-        // Holding down space causes the current orb (if there is one) to be blown further
-        if is_key_down(KeyCode::Space) {
-            if let Some(blowing_orb) = Weak::upgrade(&self.blowing_orb) {
-                let mut blowing_orb = blowing_orb.borrow_mut();
+        // Holding down space (the blow key) causes the current orb (if there is one) to be blown further
+        if input.blow {
+            if let Some(blowing_orb) = self.blowing_orb.and_then(|h| orbs.get_mut(h)) {
                 // Increase blown distance up to a maximum of 120
                 blowing_orb.blown_frames += 4;
                 if blowing_orb.blown_frames >= 120 {
                     // Can't be blown any further
-                    self.blowing_orb = Weak::new();
+                    self.blowing_orb = None;
                 }
             }
         } else {
             // If we let go of space, we relinquish control over the current orb - it can't be blown any further
-            self.blowing_orb = Weak::new();
+            self.blowing_orb = None;
         }
 
-        let resources = storage::get::<Resources>();
-
         // Set sprite image. If we're currently hurt, the sprite will flash on and off on alternate frames.
-        self.image = resources.blank_texture.clone();
+        self.image = Sprite::BLANK;
         if self.hurt_timer <= 0 || self.hurt_timer % 2 == 1 {
             let dir_index = if self.direction_x > 0 { 1 } else { 0 };
             if self.hurt_timer > 100 {
                 if self.health > 0 {
-                    self.image = resources.recoil_textures[dir_index].clone();
+                    self.image = Sprite::new(SpriteSheet::Recoil, dir_index);
                 } else {
                     let image_i = (game_timer / 4) % 2;
-                    self.image = resources.fall_textures[image_i as usize].clone();
+                    self.image = Sprite::new(SpriteSheet::Fall, image_i as usize);
                 }
             } else if self.fire_timer > 0 {
-                self.image = resources.blow_textures[dir_index].clone();
+                self.image = Sprite::new(SpriteSheet::Blow, dir_index);
             } else if dx == 0 {
-                self.image = resources.still_texture.clone();
+                self.image = Sprite::new(SpriteSheet::Still, 0);
             } else {
                 let direction_factor = dir_index * 4;
                 let image_i = direction_factor + ((game_timer / 8) % 4) as usize;
-                self.image = resources.run_textures[image_i].clone();
+                self.image = Sprite::new(SpriteSheet::Run, image_i);
             }
         }
     }
@@ -228,13 +327,21 @@ impl Actor for Player {
         &mut self.y
     }
 
-    fn image(&self) -> &Texture2D {
-        &self.image
+    fn image(&self) -> Sprite {
+        self.image
     }
 
     fn anchor(&self) -> Anchor {
         self.anchor
     }
+
+    fn colour(&self) -> Color {
+        if self.has_power_up(PowerUp::Shield) {
+            SHIELD_COLOUR
+        } else {
+            PLAYER_COLOURS[self.number]
+        }
+    }
 }
 
 impl CollideActor for Player {}
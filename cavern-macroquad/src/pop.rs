@@ -1,10 +1,9 @@
-use macroquad::prelude::{collections::storage, Texture2D};
-
 use crate::{
     actor::{Actor, Anchor},
-    resources::Resources,
+    sprites::{Sprite, SpriteSheet},
 };
 
+#[derive(Clone)]
 pub struct Pop {
     pub timer: i32,
     pub type_: i32,
@@ -12,7 +11,7 @@ pub struct Pop {
     // Actor trait
     pub x: i32,
     pub y: i32,
-    pub image: Texture2D,
+    pub image: Sprite,
     pub anchor: Anchor,
 }
 
@@ -23,7 +22,7 @@ impl Pop {
             timer: -1,
             x,
             y,
-            image: storage::get::<Resources>().blank_texture.clone(),
+            image: Sprite::BLANK,
             anchor: Anchor::Centre,
         }
     }
@@ -34,7 +33,7 @@ impl Pop {
         let type_factor = self.type_ * 7;
         let timer_factor = self.timer / 2;
         let image_i = (type_factor + timer_factor) as usize;
-        self.image = storage::get::<Resources>().pop_textures[image_i].clone();
+        self.image = Sprite::new(SpriteSheet::Pop, image_i);
     }
 }
 
@@ -55,8 +54,8 @@ impl Actor for Pop {
         &mut self.y
     }
 
-    fn image(&self) -> &macroquad::prelude::Texture2D {
-        &self.image
+    fn image(&self) -> Sprite {
+        self.image
     }
 
     fn anchor(&self) -> crate::actor::Anchor {
@@ -0,0 +1,175 @@
+// Rust: Not in the original game, where the score is lost once the game is over. The high scores,
+// and the best time in which each level of each pack has been cleared, are kept in a text file in
+// the user data directory:
+//
+//     score = ABC 12300
+//     time = levels 0 1427
+//
+// Scores have the player initials, and times are in frames, by pack name (see LevelPack) and level
+// (counted from zero, and going on once the pack starts again, since the levels are then harder).
+// Invalid lines are skipped, so that a damaged file only loses the records on them. Times saved
+// before packs were told apart have no pack name, and belong to the default pack.
+
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use crate::levels::DEFAULT_PACK_NAME;
+
+pub const MAX_HIGH_SCORES: usize = 10;
+pub const INITIALS_LENGTH: usize = 3;
+
+/// Subdirectory of the user data directory
+const DATA_DIR: &str = "cavern-macroquad";
+const RECORDS_FILENAME: &str = "records.txt";
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: i32,
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Records {
+    /// Highest first
+    pub high_scores: Vec<HighScore>,
+    /// Fewest frames taken to clear each level, by pack name and level
+    pub best_times: BTreeMap<(String, usize), i32>,
+}
+
+impl Records {
+    /// A missing or unreadable file is the same as no records.
+    pub fn load() -> Self {
+        load_data_file(RECORDS_FILENAME)
+            .map(|source| parse_records(&source))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_data_file(RECORDS_FILENAME, &self.to_source())
+    }
+
+    /// Whether the score would enter the table.
+    pub fn is_high_score(&self, score: i32) -> bool {
+        score > 0
+            && (self.high_scores.len() < MAX_HIGH_SCORES
+                || self
+                    .high_scores
+                    .iter()
+                    .any(|high_score| score > high_score.score))
+    }
+
+    /// New scores go after the equal ones, which were there first.
+    pub fn add_high_score(&mut self, initials: &str, score: i32) {
+        let position = self
+            .high_scores
+            .iter()
+            .position(|high_score| score > high_score.score)
+            .unwrap_or(self.high_scores.len());
+
+        self.high_scores.insert(
+            position,
+            HighScore {
+                initials: initials.to_string(),
+                score,
+            },
+        );
+        self.high_scores.truncate(MAX_HIGH_SCORES);
+    }
+
+    /// Returns whether the time is a new record for the level of the pack.
+    pub fn add_clear_time(&mut self, pack: &str, level: usize, frames: i32) -> bool {
+        let key = (pack.to_string(), level);
+
+        match self.best_times.get(&key) {
+            Some(&best_frames) if best_frames <= frames => false,
+            _ => {
+                self.best_times.insert(key, frames);
+                true
+            }
+        }
+    }
+
+    /// Best times of the levels of the pack, by level.
+    pub fn pack_best_times<'a>(&'a self, pack: &'a str) -> impl Iterator<Item = (usize, i32)> + 'a {
+        self.best_times
+            .iter()
+            .filter(move |((time_pack, _), _)| time_pack == pack)
+            .map(|(&(_, level), &frames)| (level, frames))
+    }
+
+    pub fn to_source(&self) -> String {
+        let mut source = String::new();
+
+        for high_score in &self.high_scores {
+            source.push_str(&format!(
+                "score = {} {}\n",
+                high_score.initials, high_score.score
+            ));
+        }
+        for ((pack, level), frames) in &self.best_times {
+            source.push_str(&format!("time = {} {} {}\n", pack, level, frames));
+        }
+
+        source
+    }
+}
+
+pub fn parse_records(source: &str) -> Records {
+    let mut records = Records::default();
+
+    for line in source.lines() {
+        let (key, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let (first, second) = match value.trim().split_once(' ') {
+            Some(pair) => pair,
+            None => continue,
+        };
+
+        match key.trim() {
+            "score" => {
+                let valid_initials = first.len() == INITIALS_LENGTH
+                    && first.bytes().all(|letter| letter.is_ascii_uppercase());
+
+                if let (true, Ok(score)) = (valid_initials, second.trim().parse()) {
+                    records.add_high_score(first, score);
+                }
+            }
+            "time" => {
+                // The pack name may contain spaces, so the numbers are taken from the end
+                let (rest, frames) = value.trim().rsplit_once(' ').unwrap_or_default();
+                let (pack, level) = match rest.rsplit_once(' ') {
+                    Some((pack, level)) => (pack.trim().to_string(), level),
+                    None => (DEFAULT_PACK_NAME.to_string(), rest),
+                };
+
+                if let (Ok(level), Ok(frames)) = (level.parse(), frames.parse()) {
+                    records.add_clear_time(&pack, level, frames);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    records
+}
+
+/// The other files of the user data directory (see controls.rs and replay.rs) use the same functions.
+pub(crate) fn load_data_file(filename: &str) -> Option<String> {
+    data_path(filename).and_then(|path| fs::read_to_string(path).ok())
+}
+
+pub(crate) fn save_data_file(filename: &str, source: &str) -> Result<(), String> {
+    let path = data_path(filename).ok_or_else(|| "No user data directory".to_string())?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|error| format!("Error creating {}: {}", dir.display(), error))?;
+    }
+
+    fs::write(&path, source).map_err(|error| format!("Error saving {}: {}", path.display(), error))
+}
+
+pub(crate) fn data_path(filename: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(DATA_DIR).join(filename))
+}
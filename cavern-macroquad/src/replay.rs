@@ -0,0 +1,308 @@
+// Rust: Not in the original game. Since the simulation depends only on the levels, the seed and the
+// input frames (see Game), a game is recorded by storing the seed and the inputs of each frame, and
+// replayed by feeding them back to a new game. Replays are only valid with the level pack they have
+// been recorded with.
+//
+// The replay file is a text file, with the same conventions as the level files:
+//
+//     seed = 1234
+//     players = 1
+//
+//     [frames]
+//     120 00
+//     5 01
+//     ...
+//
+// Each frames line is a number of consecutive, identical frames, followed by the inputs of each
+// player in the frames, as hex bit flags (see PlayerInput::to_bits()).
+
+use std::{fs, iter};
+
+use crate::{
+    input::PlayerInput,
+    levels::{column, parse_setting_line, unknown_setting, ParseError},
+    records::{data_path, save_data_file},
+};
+
+/// The last game played is saved in the user data directory (see records.rs), and replayed from the
+/// menu.
+pub const REPLAY_FILENAME: &str = "replay.txt";
+
+/// Four hours at 60 frames per second; longer games are recorded up to here, and longer replays are
+/// rejected, so that a damaged file can't take up all the memory.
+pub const MAX_FRAMES: usize = 4 * 60 * 60 * 60;
+
+const FRAMES_HEADER: &str = "[frames]";
+
+pub struct Replay {
+    pub seed: u64,
+    pub num_players: usize,
+    /// One input per player in each frame.
+    pub frames: Vec<Vec<PlayerInput>>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, num_players: usize) -> Self {
+        Self {
+            seed,
+            num_players,
+            frames: vec![],
+        }
+    }
+
+    pub fn record(&mut self, inputs: &[PlayerInput]) {
+        if self.frames.len() < MAX_FRAMES {
+            self.frames.push(inputs.to_vec());
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        save_data_file(REPLAY_FILENAME, &self.to_source())
+    }
+
+    pub fn load() -> Result<Self, String> {
+        let path =
+            data_path(REPLAY_FILENAME).ok_or_else(|| "No user data directory".to_string())?;
+        let source = fs::read_to_string(&path)
+            .map_err(|error| format!("Error loading {}: {}", path.display(), error))?;
+
+        parse_replay(&source).map_err(|error| format!("{}:{}", path.display(), error))
+    }
+    pub fn to_source(&self) -> String {
+        let mut source = format!(
+            "seed = {}\nplayers = {}\n\n{}\n",
+            self.seed, self.num_players, FRAMES_HEADER
+        );
+
+        let mut frames = self.frames.iter().peekable();
+
+        while let Some(frame) = frames.next() {
+            let mut count = 1;
+            while frames.peek() == Some(&frame) {
+                frames.next();
+                count += 1;
+            }
+
+            source.push_str(&count.to_string());
+            for input in frame {
+                source.push_str(&format!(" {:02x}", input.to_bits()));
+            }
+            source.push('\n');
+        }
+
+        source
+    }
+}
+
+pub fn parse_replay(source: &str) -> Result<Replay, ParseError> {
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        });
+
+    let mut seed = None;
+    let mut num_players = None;
+
+    let frames_line = loop {
+        let (line_number, line) = lines.next().ok_or_else(|| {
+            ParseError::new(
+                source.lines().count() + 1,
+                1,
+                format!("missing {} section", FRAMES_HEADER),
+            )
+        })?;
+
+        if line.trim() == FRAMES_HEADER {
+            break line_number;
+        }
+
+        let (key, key_column, (value, (_, value_column))) =
+            parse_setting_line(line_number, line, FRAMES_HEADER)?;
+        let error = |message: String| ParseError::new(line_number, value_column, message);
+
+        match key {
+            "seed" => {
+                seed = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| error(format!("invalid seed `{}`", value)))?,
+                )
+            }
+            "players" => {
+                num_players = Some(
+                    value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&players| players == 1 || players == 2)
+                        .ok_or_else(|| error(format!("invalid number of players `{}`", value)))?,
+                )
+            }
+            other => return Err(unknown_setting(line_number, key_column, other)),
+        }
+    };
+
+    let missing = |name: &str| {
+        ParseError::new(
+            frames_line,
+            1,
+            format!("setting `{}` missing before {}", name, FRAMES_HEADER),
+        )
+    };
+
+    let mut replay = Replay::new(
+        seed.ok_or_else(|| missing("seed"))?,
+        num_players.ok_or_else(|| missing("players"))?,
+    );
+
+    for (line_number, line) in lines {
+        let error = |column: usize, message: String| ParseError::new(line_number, column, message);
+
+        // The line isn't blank, so there is at least a field
+        let fields = fields(line);
+        let (count_column, count) = fields[0];
+        let inputs = &fields[1..];
+
+        let count = count
+            .parse::<usize>()
+            .map_err(|_| error(count_column, format!("invalid frame count `{}`", count)))?;
+
+        if count > MAX_FRAMES - replay.frames.len() {
+            return Err(error(
+                count_column,
+                format!("more than {} frames", MAX_FRAMES),
+            ));
+        }
+
+        if inputs.len() != replay.num_players {
+            // At the first extra input, or after the last one
+            let column = match inputs.get(replay.num_players) {
+                Some((column, _)) => *column,
+                None => column(line, line.trim_end().len()),
+            };
+            return Err(error(
+                column,
+                format!("{} inputs (expected {})", inputs.len(), replay.num_players),
+            ));
+        }
+
+        let frame = inputs
+            .iter()
+            .map(|&(column, bits)| {
+                u8::from_str_radix(bits, 16)
+                    .map(PlayerInput::from_bits)
+                    .map_err(|_| error(column, format!("invalid input `{}`", bits)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        replay.frames.extend(iter::repeat(frame).take(count));
+    }
+
+    Ok(replay)
+}
+
+/// Whitespace-separated fields of the line, with their columns.
+fn fields(line: &str) -> Vec<(usize, &str)> {
+    let mut fields = vec![];
+    let mut start = None;
+
+    for (i, character) in line.char_indices().chain(iter::once((line.len(), ' '))) {
+        match (start, character.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(field_start), true) => {
+                fields.push((column(line, field_start), &line[field_start..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Line, column and message.
+    fn replay_error(source: &str) -> (usize, usize, String) {
+        match parse_replay(source) {
+            Ok(_) => panic!("the replay has no errors"),
+            Err(error) => (error.line, error.column, error.message),
+        }
+    }
+
+    #[test]
+    fn replay_round_trips() {
+        let mut replay = Replay::new(1234, 2);
+        let idle = [PlayerInput::default(); 2];
+        let moving = [PlayerInput::from_bits(1), PlayerInput::default()];
+        for _ in 0..3 {
+            replay.record(&idle);
+        }
+        replay.record(&moving);
+
+        let source = replay.to_source();
+        assert!(
+            source.ends_with("[frames]\n3 00 00\n1 01 00\n"),
+            "{}",
+            source
+        );
+
+        let parsed = parse_replay(&source).unwrap_or_else(|error| panic!("{}", error));
+        assert_eq!((parsed.seed, parsed.num_players), (1234, 2));
+        assert!(parsed.frames == replay.frames);
+    }
+
+    #[test]
+    fn frame_counts_are_limited() {
+        let source = format!("seed = 1\nplayers = 1\n[frames]\n{} 00\n", MAX_FRAMES);
+        let parsed = parse_replay(&source).unwrap_or_else(|error| panic!("{}", error));
+        assert_eq!(parsed.frames.len(), MAX_FRAMES);
+
+        let source = format!(
+            "seed = 1\nplayers = 1\n[frames]\n{} 00\n  1 00\n",
+            MAX_FRAMES
+        );
+        let (line, column, message) = replay_error(&source);
+        assert_eq!((line, column), (5, 3));
+        assert_eq!(message, format!("more than {} frames", MAX_FRAMES));
+
+        let source = "seed = 1\nplayers = 1\n[frames]\n18446744073709551615 00\n";
+        assert_eq!(replay_error(source).0, 4);
+
+        let mut replay = Replay::new(1, 1);
+        replay.frames = vec![vec![PlayerInput::default()]; MAX_FRAMES];
+        replay.record(&[PlayerInput::default()]);
+        assert_eq!(replay.frames.len(), MAX_FRAMES);
+    }
+
+    #[test]
+    fn errors_have_the_position() {
+        let (line, column, message) = replay_error("seed = 1\n  speed = 2\n[frames]\n");
+        assert_eq!((line, column), (2, 3));
+        assert_eq!(message, "unknown setting `speed`");
+
+        let (line, column, _) = replay_error("seed = 1\nplayers =  3\n[frames]\n");
+        assert_eq!((line, column), (2, 12));
+
+        let (line, column, message) = replay_error("# Replay\nseed = 1\n\n[frames]\n");
+        assert_eq!((line, column), (4, 1));
+        assert!(message.contains("`players` missing"), "{}", message);
+
+        let header = "seed = 1\nplayers = 2\n[frames]\n";
+
+        let (line, column, _) = replay_error(&format!("{}1 00 zz\n", header));
+        assert_eq!((line, column), (4, 6));
+
+        let (line, column, message) = replay_error(&format!("{}1 00\n", header));
+        assert_eq!((line, column), (4, 5));
+        assert_eq!(message, "1 inputs (expected 2)");
+
+        let (line, column, _) = replay_error(&format!("{}1 00 00  00\n", header));
+        assert_eq!((line, column), (4, 10));
+    }
+}
@@ -8,6 +8,8 @@ use macroquad::{
     prelude::{load_texture, Texture2D},
 };
 
+use crate::sprites::{SpriteSheet, IMAGES_DIR};
+
 const AVAILABLE_FONTS: [u8; 37] = [
     32, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77,
     78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90,
@@ -42,23 +44,6 @@ async fn load_textures_map(
     Ok(textures)
 }
 
-/// Rust: Load texture that have multiple states, e.g. Robots of two types, with two directions each.
-/// The textures are stored in a contiguous array, respecting the order of the states passed.
-async fn load_multi_state_textures(
-    name_prefix: &str,
-    states: &[&str],
-    state_number: u8,
-) -> Result<Vec<Texture2D>, Box<dyn error::Error>> {
-    let mut textures = vec![];
-
-    for state in states {
-        let prefix = &format!("{}{}", name_prefix, state);
-        textures.extend(load_textures_list(prefix, state_number).await?);
-    }
-
-    Ok(textures)
-}
-
 async fn load_sounds_list(
     name_prefix: &str,
     number: u8,
@@ -79,20 +64,8 @@ pub struct Resources {
     pub status_textures: HashMap<&'static str, Texture2D>,
     pub background_textures: Vec<Texture2D>,
     pub block_textures: Vec<Texture2D>,
-    pub blank_texture: Texture2D,
-    /// Rust: Stored contiguously ("00..." -> "01..." -> "10..." -> "11...")
-    pub robot_textures: Vec<Texture2D>,
-    pub recoil_textures: Vec<Texture2D>,
-    pub fall_textures: Vec<Texture2D>,
-    pub blow_textures: Vec<Texture2D>,
-    pub still_texture: Texture2D,
-    /// Rust: Stored contiguously ("0..." -> "1...")
-    pub run_textures: Vec<Texture2D>,
-    pub orb_textures: Vec<Texture2D>,
-    pub trap_textures: Vec<Texture2D>,
-    pub bolt_textures: Vec<Texture2D>,
-    pub pop_textures: Vec<Texture2D>,
-    pub fruit_textures: Vec<Texture2D>,
+    /// Rust: The actor textures, in the order of SpriteSheet::image_names() (see sprites.rs)
+    pub sprite_textures: HashMap<SpriteSheet, Vec<Texture2D>>,
 
     pub over_sound: Sound,
     pub level_sound: Sound,
@@ -117,20 +90,15 @@ impl Resources {
         let status_textures = load_textures_map(&["life", "plus", "health"]).await?;
         let background_textures = load_textures_list("bg", 4).await?;
         let block_textures = load_textures_list("block", 4).await?;
-        let blank_texture = load_texture("resources/images/blank.png").await?;
-        let robot_textures =
-            load_multi_state_textures("robot", &["00", "01", "10", "11"], 8).await?;
-        let recoil_textures = load_textures_list("recoil", 2).await?;
-        let fall_textures = load_textures_list("fall", 2).await?;
-        let blow_textures = load_textures_list("blow", 2).await?;
-        let still_texture: Texture2D = load_texture("resources/images/still.png").await?;
-        let run_textures = load_multi_state_textures("run", &["0", "1"], 4).await?;
-        let orb_textures = load_textures_list("orb", 7).await?;
-        let trap_textures = load_multi_state_textures("trap", &["0", "1"], 8).await?;
-        let bolt_textures = load_multi_state_textures("bolt", &["0", "1"], 2).await?;
-        let pop_textures = load_multi_state_textures("pop", &["0", "1"], 7).await?;
-        let fruit_textures =
-            load_multi_state_textures("fruit", &["0", "1", "2", "3", "4"], 3).await?;
+
+        let mut sprite_textures = HashMap::new();
+        for sheet in SpriteSheet::ALL {
+            let mut textures = vec![];
+            for name in sheet.image_names() {
+                textures.push(load_texture(&format!("{}/{}.png", IMAGES_DIR, name)).await?);
+            }
+            sprite_textures.insert(sheet, textures);
+        }
 
         let over_sound = audio::load_sound("resources/sounds/over0.ogg").await?;
         let level_sound = audio::load_sound("resources/sounds/level0.ogg").await?;
@@ -158,18 +126,7 @@ impl Resources {
             status_textures,
             background_textures,
             block_textures,
-            blank_texture,
-            robot_textures,
-            recoil_textures,
-            fall_textures,
-            blow_textures,
-            still_texture,
-            run_textures,
-            orb_textures,
-            trap_textures,
-            bolt_textures,
-            pop_textures,
-            fruit_textures,
+            sprite_textures,
 
             over_sound,
             level_sound,
@@ -0,0 +1,50 @@
+// Rust: The original code uses the global random generator (macroquad's one, in the port). The game
+// simulation now has its own generator, seeded when the game is created, so that a game can be
+// replayed exactly from its seed and inputs (see replay.rs). The random choices that don't affect the
+// simulation (e.g. which variant of a sound to play) still use the global generator.
+//
+// The algorithm is SplitMix64: it's tiny, fast, and good enough for a game.
+
+#[derive(Clone, Default)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Between `low` (inclusive) and `high` (exclusive), like macroquad's gen_range() for integers.
+    pub fn gen_range(&mut self, low: i32, high: i32) -> i32 {
+        let range = (high - low) as u64;
+        low + (self.next_u64() % range) as i32
+    }
+
+    /// Between 0 (inclusive) and 1 (exclusive).
+    pub fn gen_float(&mut self) -> f32 {
+        // The top 24 bits, which is the precision of f32
+        (self.next_u64() >> 40) as f32 / (1 << 24) as f32
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.gen_range(0, items.len() as i32) as usize]
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.gen_range(0, i as i32 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
@@ -1,45 +1,83 @@
-use macroquad::{
-    prelude::{collections::storage, Texture2D},
-    rand::{gen_range, ChooseRandom},
-};
+use macroquad::prelude::{Color, WHITE};
 
 use crate::{
     actor::{Actor, Anchor},
+    arena::{Arena, Handle},
     collide_actor::CollideActor,
     gravity_actor::GravityActor,
-    orb::RcOrb,
+    orb::Orb,
     player::Player,
-    resources::Resources,
+    pop::Pop,
+    rng::Rng,
+    sprites::{Sprite, SpriteSheet},
+    GRID_BLOCK_SIZE,
 };
-use crate::{bolt::Bolt, game_playback::play_game_random_sound};
+use crate::{bolt::Bolt, game_playback::GameSound};
+
+/// Orbs needed to trap a boss; all but the last one just burst on it.
+pub const BOSS_HEALTH: i32 = 5;
+const BOSS_SCALE: i32 = 2;
+const BOSS_COLOUR: Color = Color::new(1., 0.6, 0.6, 1.);
+/// Frames between the boss attacks
+const BOSS_ATTACK_INTERVAL: i32 = 150;
+/// Frames during which the boss flashes, after an orb has burst on it
+const BOSS_HIT_FLASH: i32 = 30;
+const SLAM_JUMP_SPEED: i32 = -16;
+/// Horizontal distance within which the players standing on the same platform are hurt by a slam
+const SLAM_RANGE: i32 = 250;
 
 #[derive(Clone, Copy)]
 pub enum RobotType {
     Aggressive,
     Normal,
+    /// Appears every few levels (see Game::next_level()); it's bigger, takes several orbs to be trapped, and
+    /// attacks with bolt spreads and ground slams.
+    Boss,
 }
 
 impl RobotType {
+    /// Index of the images; the boss uses the aggressive robot ones, scaled up and tinted.
     pub fn val(&self) -> i32 {
         match self {
-            RobotType::Aggressive => 0,
+            RobotType::Aggressive | RobotType::Boss => 0,
             RobotType::Normal => 1,
         }
     }
+
+    pub fn max_health(&self) -> i32 {
+        match self {
+            RobotType::Boss => BOSS_HEALTH,
+            _ => 1,
+        }
+    }
+
+    pub fn scale(&self) -> i32 {
+        match self {
+            RobotType::Boss => BOSS_SCALE,
+            _ => 1,
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct Robot {
     pub type_: RobotType,
     pub speed: i32,
     pub direction_x: i32,
-    pub alive: bool,
+    /// Trapped in an orb (see Orb::trapped_enemy); trapped robots are neither updated nor drawn
+    pub trapped: bool,
     pub change_dir_timer: i32,
     pub fire_timer: i32,
+    /// Orbs left to trap the robot; only the boss has more than one
+    pub health: i32,
+    pub hit_timer: i32,
+    /// The boss jumped, and slams the ground when it lands
+    pub slamming: bool,
 
     // Actor trait
     pub x: i32,
     pub y: i32,
-    pub image: Texture2D,
+    pub image: Sprite,
     pub anchor: Anchor,
 
     // GravityActor trait
@@ -48,18 +86,24 @@ pub struct Robot {
 }
 
 impl Robot {
-    pub fn new(x: i32, y: i32, type_: RobotType) -> Self {
+    pub fn new(x: i32, y: i32, type_: RobotType, rng: &mut Rng) -> Self {
         Self {
             x,
             y,
-            image: storage::get::<Resources>().blank_texture.clone(),
+            image: Sprite::BLANK,
             anchor: Anchor::CentreBottom,
             type_,
-            speed: gen_range(1, 4),
+            speed: match type_ {
+                RobotType::Boss => 1,
+                _ => rng.gen_range(1, 4),
+            },
             direction_x: 1,
-            alive: true,
+            trapped: false,
             change_dir_timer: 0,
             fire_timer: 100,
+            health: type_.max_health(),
+            hit_timer: 0,
+            slamming: false,
             vel_y: 0,
             landed: false,
         }
@@ -67,17 +111,29 @@ impl Robot {
 
     pub fn update(
         &mut self,
-        bolts: &mut Vec<Bolt>,
-        orbs: &mut [RcOrb],
-        player: Option<&Player>,
+        handle: Handle<Robot>,
+        bolts: &mut Arena<Bolt>,
+        orbs: &mut Arena<Orb>,
+        pops: &mut Arena<Pop>,
+        players: &mut [Player],
+        rng: &mut Rng,
+        sounds: &mut Vec<GameSound>,
         mut fire_probability: f32,
         game_timer: i32,
         grid: &[&str],
     ) {
         GravityActor::update(self, true, grid);
 
+        // We target the nearest player
+        let player_x = players
+            .iter()
+            .filter(|p| p.in_game())
+            .min_by_key(|p| (p.x() - self.x()).pow(2) + (p.y() - self.y()).pow(2))
+            .map(|p| p.x());
+
         self.change_dir_timer -= 1;
         self.fire_timer += 1;
+        self.hit_timer -= 1;
 
         // Move in current direction - turn around if we hit a wall
         if self.move_(self.direction_x, 0, self.speed, grid) {
@@ -86,20 +142,19 @@ impl Robot {
 
         if self.change_dir_timer <= 0 {
             // Randomly choose a direction to move in
-            // If there's a player, there's a two thirds chance that we'll move towards them
+            // If there's a player, there's a two thirds chance that we'll move towards the nearest one
             let mut directions = vec![-1, 1];
-            if let Some(player) = player {
-                directions.push((player.x() - self.x()).signum());
+            if let Some(player_x) = player_x {
+                directions.push((player_x - self.x()).signum());
             }
-            self.direction_x = *directions.choose().unwrap();
-            self.change_dir_timer = gen_range(100, 250 + 1);
+            self.direction_x = *rng.choose(&directions);
+            self.change_dir_timer = rng.gen_range(100, 250 + 1);
         }
 
         // The more powerful type of robot can deliberately shoot at orbs - turning to face them if necessary
         if matches!(self.type_, RobotType::Aggressive) && self.fire_timer >= 24 {
             // Go through all orbs to see if any can be shot at
-            for orb in orbs.iter_mut() {
-                let orb = orb.borrow();
+            for orb in orbs.values() {
                 // The orb must be at our height, and within 200 pixels on the x axis
                 if orb.y >= self.top() && orb.y < self.bottom() && (orb.x() - self.x()).abs() < 200
                 {
@@ -110,37 +165,47 @@ impl Robot {
             }
         }
 
-        let resources = storage::get::<Resources>();
-
-        // Check to see if we can fire at player
-        if self.fire_timer >= 12 {
-            // Random chance of firing each frame. Likelihood increases 10 times if player is at the same height as us
-            if let Some(player) = player {
-                if self.top() < player.bottom() && self.bottom() > player.top() {
-                    fire_probability *= 10.;
-                }
+        // Check to see if we can fire at player. The boss attacks at regular intervals instead
+        if let RobotType::Boss = self.type_ {
+            self.boss_attack(pops, players, rng, sounds);
+        } else if self.fire_timer >= 12 {
+            // Random chance of firing each frame. Likelihood increases 10 times if a player is at the same height as us
+            if players
+                .iter()
+                .filter(|p| p.in_game())
+                .any(|p| self.top() < p.bottom() && self.bottom() > p.top())
+            {
+                fire_probability *= 10.;
             }
-            if gen_range(0., 1.) < fire_probability {
+            if rng.gen_float() < fire_probability {
                 self.fire_timer = 0;
-                play_game_random_sound(player, &resources.laser_sounds);
+                sounds.push(GameSound::Laser);
+            }
+        }
+
+        if self.fire_timer == 8 {
+            //  Once the fire timer has been set to 0, it will count up - frame 8 of the animation is when the actual bolt is fired.
+            // The boss fires a spread of three bolts
+            let spread: &[i32] = match self.type_ {
+                RobotType::Boss => &[-1, 0, 1],
+                _ => &[0],
+            };
+            for &direction_y in spread {
+                bolts.insert(Bolt {
+                    direction_y,
+                    ..Bolt::new(
+                        self.x() + self.direction_x * 20 * self.scale(),
+                        self.y() - 38 * self.scale(),
+                        self.direction_x,
+                    )
+                });
             }
-        } else if self.fire_timer == 8 {
-            //  Once the fire timer has been set to 0, it will count up - frame 8 of the animation is when the actual bolt is fired
-            bolts.push(Bolt::new(
-                self.x() + self.direction_x * 20,
-                self.y() - 38,
-                self.direction_x,
-            ));
         }
 
         // Am I colliding with an orb? If so, become trapped by it
-        for orb in orbs.iter_mut() {
-            let mut orb = orb.borrow_mut();
-            if orb.trapped_enemy_type.is_none() && self.collidepoint(orb.center()) {
-                self.alive = false;
-                orb.floating = true;
-                orb.trapped_enemy_type = Some(self.type_);
-                play_game_random_sound(player, &resources.trap_sounds);
+        for orb in orbs.values_mut() {
+            if orb.trapped_enemy.is_none() && self.colliderect(orb.trap_rect()) {
+                self.hit_by_orb(handle, orb, sounds);
                 break;
             }
         }
@@ -154,7 +219,75 @@ impl Robot {
             1 + ((game_timer / 4) % 4)
         };
         let image_i = (type_factor + direction_factor + fire_factor) as usize;
-        self.image = storage::get::<Resources>().robot_textures[image_i].clone();
+        self.image = Sprite::new(SpriteSheet::Robot, image_i);
+    }
+
+    /// Trapped by the orb, unless there's health left, in which case the orb bursts. Also used by the screen bomb
+    /// (see Fruit).
+    pub fn hit_by_orb(
+        &mut self,
+        handle: Handle<Robot>,
+        orb: &mut Orb,
+        sounds: &mut Vec<GameSound>,
+    ) {
+        if self.health > 1 {
+            self.health -= 1;
+            self.hit_timer = BOSS_HIT_FLASH;
+            orb.burst();
+            return;
+        }
+
+        self.health = 0;
+        self.trapped = true;
+        orb.floating = true;
+        orb.trapped_enemy = Some(handle);
+        // The orb grows to the size of the robot, if it's bigger
+        orb.scale = orb.scale.max(self.scale());
+        sounds.push(GameSound::Trap);
+    }
+
+    /// The boss alternates randomly between bolt spreads (fired by update(), as for the other robots) and ground
+    /// slams: it jumps, and when it lands, it hurts the players standing on the same platform nearby.
+    fn boss_attack(
+        &mut self,
+        pops: &mut Arena<Pop>,
+        players: &mut [Player],
+        rng: &mut Rng,
+        sounds: &mut Vec<GameSound>,
+    ) {
+        if self.slamming && self.landed {
+            self.slamming = false;
+
+            // Shockwave on both sides
+            for direction_x in [-1, 1] {
+                pops.insert(Pop::new(self.x() + direction_x * 40, self.y() - 12, 0));
+            }
+            sounds.push(GameSound::Pop);
+
+            for player in players.iter_mut() {
+                if player.in_game()
+                    && player.landed
+                    && player.hurt_timer < 0
+                    && (player.y() - self.y()).abs() < GRID_BLOCK_SIZE
+                    && (player.x() - self.x()).abs() < SLAM_RANGE
+                {
+                    player.hurt((player.x() - self.x()).signum(), sounds);
+                }
+            }
+        }
+
+        if self.fire_timer >= BOSS_ATTACK_INTERVAL && !self.slamming {
+            if self.landed && rng.gen_range(0, 2) == 0 {
+                self.slamming = true;
+                self.vel_y = SLAM_JUMP_SPEED;
+                self.landed = false;
+                // Not firing
+                self.fire_timer = 12;
+            } else {
+                self.fire_timer = 0;
+                sounds.push(GameSound::Laser);
+            }
+        }
     }
 }
 
@@ -175,13 +308,26 @@ impl Actor for Robot {
         &mut self.y
     }
 
-    fn image(&self) -> &macroquad::prelude::Texture2D {
-        &self.image
+    fn image(&self) -> Sprite {
+        self.image
     }
 
     fn anchor(&self) -> Anchor {
         self.anchor
     }
+
+    fn scale(&self) -> i32 {
+        self.type_.scale()
+    }
+
+    /// The boss flashes after being hit
+    fn colour(&self) -> Color {
+        match self.type_ {
+            RobotType::Boss if self.hit_timer > 0 && self.hit_timer % 4 < 2 => WHITE,
+            RobotType::Boss => BOSS_COLOUR,
+            _ => WHITE,
+        }
+    }
 }
 
 impl CollideActor for Robot {}
@@ -0,0 +1,134 @@
+// Rust: The actors used to hold their current Texture2D, whose size is used for the collisions.
+// Textures need a window (and a GPU context), so the actors now hold a Sprite (the image sheet and
+// the index in it), and the sizes are read from the headers of the image files. This way, the
+// simulation can run without a window; the textures are looked up in Resources only when drawing.
+
+use std::{collections::HashMap, convert::TryInto, fs, sync::OnceLock};
+
+use macroquad::prelude::{collections::storage, Texture2D};
+
+use crate::resources::Resources;
+
+pub const IMAGES_DIR: &str = "resources/images";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SpriteSheet {
+    Blank,
+    Still,
+    Robot,
+    Recoil,
+    Fall,
+    Blow,
+    Run,
+    Orb,
+    Trap,
+    Bolt,
+    Pop,
+    Fruit,
+}
+
+impl SpriteSheet {
+    pub const ALL: [SpriteSheet; 12] = [
+        SpriteSheet::Blank,
+        SpriteSheet::Still,
+        SpriteSheet::Robot,
+        SpriteSheet::Recoil,
+        SpriteSheet::Fall,
+        SpriteSheet::Blow,
+        SpriteSheet::Run,
+        SpriteSheet::Orb,
+        SpriteSheet::Trap,
+        SpriteSheet::Bolt,
+        SpriteSheet::Pop,
+        SpriteSheet::Fruit,
+    ];
+
+    /// Image names (without extension), in index order. Images that have multiple states, e.g.
+    /// Robots of two types, with two directions each, are stored contiguously, respecting the order
+    /// of the states ("00..." -> "01..." -> "10..." -> "11...").
+    pub fn image_names(self) -> Vec<String> {
+        let (prefix, states, count): (&str, &[&str], usize) = match self {
+            SpriteSheet::Blank => return vec!["blank".to_string()],
+            SpriteSheet::Still => return vec!["still".to_string()],
+            SpriteSheet::Robot => ("robot", &["00", "01", "10", "11"], 8),
+            SpriteSheet::Recoil => ("recoil", &[""], 2),
+            SpriteSheet::Fall => ("fall", &[""], 2),
+            SpriteSheet::Blow => ("blow", &[""], 2),
+            SpriteSheet::Run => ("run", &["0", "1"], 4),
+            SpriteSheet::Orb => ("orb", &[""], 7),
+            SpriteSheet::Trap => ("trap", &["0", "1"], 8),
+            SpriteSheet::Bolt => ("bolt", &["0", "1"], 2),
+            SpriteSheet::Pop => ("pop", &["0", "1"], 7),
+            SpriteSheet::Fruit => ("fruit", &["0", "1", "2", "3", "4"], 3),
+        };
+
+        states
+            .iter()
+            .flat_map(|state| (0..count).map(move |i| format!("{}{}{}", prefix, state, i)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Sprite {
+    pub sheet: SpriteSheet,
+    pub index: usize,
+}
+
+impl Sprite {
+    pub const BLANK: Sprite = Sprite::new(SpriteSheet::Blank, 0);
+
+    pub const fn new(sheet: SpriteSheet, index: usize) -> Self {
+        Self { sheet, index }
+    }
+
+    pub fn width(self) -> i32 {
+        sprite_sizes()[&self.sheet][self.index].0
+    }
+
+    pub fn height(self) -> i32 {
+        sprite_sizes()[&self.sheet][self.index].1
+    }
+
+    pub fn texture(self) -> Texture2D {
+        storage::get::<Resources>().sprite_textures[&self.sheet][self.index].clone()
+    }
+}
+
+/// Read once, on first use; a missing or invalid image is a broken installation, so it's fatal.
+fn sprite_sizes() -> &'static HashMap<SpriteSheet, Vec<(i32, i32)>> {
+    static SIZES: OnceLock<HashMap<SpriteSheet, Vec<(i32, i32)>>> = OnceLock::new();
+
+    SIZES.get_or_init(|| {
+        SpriteSheet::ALL
+            .iter()
+            .map(|&sheet| {
+                let sizes = sheet
+                    .image_names()
+                    .iter()
+                    .map(|name| {
+                        let path = format!("{}/{}.png", IMAGES_DIR, name);
+                        png_size(&path).unwrap_or_else(|| panic!("Invalid image: {}", path))
+                    })
+                    .collect();
+                (sheet, sizes)
+            })
+            .collect()
+    })
+}
+
+/// The size is in the IHDR chunk, which comes first, right after the 8 bytes of the signature.
+fn png_size(path: &str) -> Option<(i32, i32)> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    let data = fs::read(path).ok()?;
+
+    if data.len() < 24 || !data.starts_with(SIGNATURE) || &data[12..16] != b"IHDR" {
+        return None;
+    }
+
+    let width = u32::from_be_bytes(data[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(data[20..24].try_into().ok()?);
+
+    Some((width as i32, height as i32))
+}
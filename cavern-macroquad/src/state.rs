@@ -2,5 +2,10 @@
 pub enum State {
     Menu,
     Play,
+    Editor,
+    Playtest,
+    Replay,
     GameOver,
+    Stats,
+    Options,
 }
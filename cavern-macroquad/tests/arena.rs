@@ -0,0 +1,116 @@
+// The generational arena that holds the game objects.
+
+use cavern_macroquad::arena::Arena;
+
+fn values(arena: &Arena<&'static str>) -> Vec<&'static str> {
+    arena.values().copied().collect()
+}
+
+#[test]
+fn removed_handles_are_stale() {
+    let mut arena = Arena::default();
+    let a = arena.insert("a");
+    let b = arena.insert("b");
+
+    assert_eq!(arena.remove(a), Some("a"));
+    assert_eq!(arena.get(a), None);
+    assert_eq!(arena.get_mut(a), None);
+    assert_eq!(arena.remove(a), None);
+
+    assert_eq!(arena.get(b), Some(&"b"));
+    assert_eq!(arena.len(), 1);
+}
+
+#[test]
+fn reused_slots_get_a_new_generation() {
+    let mut arena = Arena::default();
+    let a = arena.insert("a");
+    arena.remove(a);
+
+    // The slot of `a` is reused, but the old handle doesn't resolve to the new object
+    let c = arena.insert("c");
+    assert_ne!(a, c);
+    assert_eq!(format!("{:?}", a), "Handle(0v0)");
+    assert_eq!(format!("{:?}", c), "Handle(0v1)");
+    assert_eq!(arena.get(a), None);
+    assert_eq!(arena.get(c), Some(&"c"));
+
+    // Nor does it remove it
+    assert_eq!(arena.remove(a), None);
+    assert_eq!(arena.len(), 1);
+}
+
+#[test]
+fn iteration_follows_the_insertion_order() {
+    let mut arena = Arena::default();
+    let a = arena.insert("a");
+    arena.insert("b");
+    arena.insert("c");
+
+    // "d" goes into the slot of "a", but comes last, as it would have in a vector
+    arena.remove(a);
+    let d = arena.insert("d");
+    assert_eq!(values(&arena), ["b", "c", "d"]);
+
+    for (handle, value) in arena.iter_mut() {
+        if handle == d {
+            *value = "D";
+        }
+    }
+    let mutated = arena
+        .iter_mut()
+        .map(|(_, value)| *value)
+        .collect::<Vec<_>>();
+    assert_eq!(mutated, ["b", "c", "D"]);
+
+    let handles = arena.iter().map(|(handle, _)| handle).collect::<Vec<_>>();
+    assert_eq!(handles.last(), Some(&d));
+}
+
+#[test]
+fn retain_and_clear_remove_objects() {
+    let mut arena = Arena::default();
+    let handles = ["a", "bb", "c", "dd"].map(|value| arena.insert(value));
+
+    arena.retain(|value| value.len() == 1);
+    assert_eq!(values(&arena), ["a", "c"]);
+    assert_eq!(arena.len(), 2);
+    assert_eq!(arena.get(handles[1]), None);
+    assert_eq!(arena.get(handles[2]), Some(&"c"));
+
+    arena.clear();
+    assert!(arena.is_empty());
+    assert_eq!(arena.len(), 0);
+    assert_eq!(values(&arena), Vec::<&str>::new());
+    assert!(handles.iter().all(|&handle| arena.get(handle).is_none()));
+
+    // The arena is still usable afterwards
+    let e = arena.insert("e");
+    assert_eq!(values(&arena), ["e"]);
+    assert!(!handles.contains(&e));
+}
+
+#[test]
+fn handles_survive_the_compaction() {
+    let mut arena = Arena::default();
+    assert!(arena.is_empty());
+
+    let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+    let handles = names.map(|value| arena.insert(value));
+
+    // Removing most of the objects compacts the holes they leave
+    for &handle in &handles[..6] {
+        arena.remove(handle);
+    }
+    let i = arena.insert("i");
+    assert_eq!(values(&arena), ["g", "h", "i"]);
+    assert_eq!(arena.len(), 3);
+    assert!(!arena.is_empty());
+
+    assert_eq!(arena.get(handles[6]), Some(&"g"));
+    assert_eq!(arena.get(handles[7]), Some(&"h"));
+    assert_eq!(arena.get(handles[0]), None);
+    assert_eq!(arena.remove(handles[7]), Some("h"));
+    assert_eq!(arena.get(i), Some(&"i"));
+    assert_eq!(values(&arena), ["g", "i"]);
+}
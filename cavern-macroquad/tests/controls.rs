@@ -0,0 +1,110 @@
+use cavern_macroquad::controls::{parse_controls, Action, Binding, Controls};
+use gilrs::Button;
+use macroquad::input::KeyCode;
+
+#[test]
+fn default_controls_are_the_original_keys() {
+    let controls = Controls::default();
+
+    assert_eq!(controls.binding(0, Action::Left).key, Some(KeyCode::Left));
+    assert_eq!(controls.binding(0, Action::Jump).key, Some(KeyCode::Up));
+    assert_eq!(controls.binding(0, Action::Blow).key, Some(KeyCode::Space));
+    assert_eq!(controls.binding(1, Action::Start).key, Some(KeyCode::Key2));
+}
+
+#[test]
+fn controls_are_saved_as_text() {
+    let mut controls = Controls::default();
+    controls.binding_mut(0, Action::Jump).key = Some(KeyCode::X);
+    controls.binding_mut(1, Action::Blow).button = None;
+    controls.binding_mut(1, Action::Start).key = None;
+    controls.binding_mut(1, Action::Start).button = Some(Button::RightTrigger2);
+
+    assert_eq!(parse_controls(&controls.to_source()), controls);
+}
+
+#[test]
+fn invalid_lines_keep_the_default_bindings() {
+    let source = "p3.left = Left DPadLeft\n\
+                  p1.walk = Left DPadLeft\n\
+                  p1.left = Escape DPadLeft\n\
+                  p1.right = Right Trackpad\n\
+                  p1.jump = Up\n\
+                  p2.left = Z -\n";
+
+    let mut expected = Controls::default();
+    expected.binding_mut(1, Action::Left).key = Some(KeyCode::Z);
+    expected.binding_mut(1, Action::Left).button = None;
+
+    assert_eq!(parse_controls(source), expected);
+}
+
+#[test]
+fn rebinding_swaps_the_duplicate_bindings() {
+    let mut controls = Controls::default();
+
+    // The keyboard is shared by the players
+    controls.rebind(0, Action::Jump, key_binding(KeyCode::W));
+    assert_eq!(controls.binding(0, Action::Jump).key, Some(KeyCode::W));
+    assert_eq!(controls.binding(1, Action::Jump).key, Some(KeyCode::Up));
+
+    controls.rebind(1, Action::Blow, key_binding(KeyCode::A));
+    assert_eq!(controls.binding(1, Action::Blow).key, Some(KeyCode::A));
+    assert_eq!(
+        controls.binding(1, Action::Left).key,
+        Some(KeyCode::LeftShift)
+    );
+
+    // Each player has their own gamepad
+    controls.rebind(0, Action::Blow, button_binding(Button::South));
+    assert_eq!(
+        controls.binding(0, Action::Blow).button,
+        Some(Button::South)
+    );
+    assert_eq!(controls.binding(0, Action::Jump).button, Some(Button::West));
+    assert_eq!(
+        controls.binding(1, Action::Jump).button,
+        Some(Button::South)
+    );
+
+    // The key is kept
+    assert_eq!(controls.binding(0, Action::Blow).key, Some(KeyCode::Space));
+}
+
+#[test]
+fn start_can_share_a_binding_with_the_game_actions() {
+    let mut controls = Controls::default();
+
+    controls.rebind(1, Action::Start, key_binding(KeyCode::LeftShift));
+    assert_eq!(
+        controls.binding(1, Action::Start).key,
+        Some(KeyCode::LeftShift)
+    );
+    assert_eq!(
+        controls.binding(1, Action::Blow).key,
+        Some(KeyCode::LeftShift)
+    );
+
+    // But not with the other player's Start
+    controls.rebind(1, Action::Start, key_binding(KeyCode::Space));
+    assert_eq!(controls.binding(1, Action::Start).key, Some(KeyCode::Space));
+    assert_eq!(
+        controls.binding(0, Action::Start).key,
+        Some(KeyCode::LeftShift)
+    );
+    assert_eq!(controls.binding(0, Action::Blow).key, Some(KeyCode::Space));
+}
+
+fn key_binding(key: KeyCode) -> Binding {
+    Binding {
+        key: Some(key),
+        button: None,
+    }
+}
+
+fn button_binding(button: Button) -> Binding {
+    Binding {
+        key: None,
+        button: Some(button),
+    }
+}
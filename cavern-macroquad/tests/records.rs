@@ -0,0 +1,97 @@
+use cavern_macroquad::records::{parse_records, Records, MAX_HIGH_SCORES};
+
+const PACK: &str = "levels";
+
+#[test]
+fn high_scores_are_sorted_and_limited() {
+    let mut records = Records::default();
+
+    for score in (1..=MAX_HIGH_SCORES as i32 + 2).map(|i| i * 100) {
+        assert!(records.is_high_score(score));
+        records.add_high_score("ABC", score);
+    }
+    records.add_high_score("NEW", 1000);
+
+    let scores = records
+        .high_scores
+        .iter()
+        .map(|high_score| (high_score.initials.as_str(), high_score.score))
+        .collect::<Vec<_>>();
+    assert_eq!(scores.len(), MAX_HIGH_SCORES);
+    assert_eq!(scores[0], ("ABC", 1200));
+    // Equal scores keep their order
+    assert_eq!(scores[2], ("ABC", 1000));
+    assert_eq!(scores[3], ("NEW", 1000));
+    assert_eq!(scores[MAX_HIGH_SCORES - 1], ("ABC", 400));
+
+    assert!(!records.is_high_score(400));
+    assert!(records.is_high_score(401));
+    assert!(!Records::default().is_high_score(0));
+}
+
+#[test]
+fn only_better_times_are_kept() {
+    let mut records = Records::default();
+
+    assert!(records.add_clear_time(PACK, 2, 1500));
+    assert!(!records.add_clear_time(PACK, 2, 1500));
+    assert!(records.add_clear_time(PACK, 2, 1400));
+    assert!(!records.add_clear_time(PACK, 2, 1600));
+
+    assert_eq!(records.best_times.get(&(PACK.to_string(), 2)), Some(&1400));
+}
+
+#[test]
+fn times_are_kept_by_pack() {
+    let mut records = Records::default();
+
+    assert!(records.add_clear_time(PACK, 0, 1500));
+    assert!(records.add_clear_time("my pack", 0, 2000));
+    assert!(records.add_clear_time("my pack", 1, 900));
+    // A time that's worse than the one of another pack is still a record for its own
+    assert!(records.add_clear_time("other", 0, 1800));
+    assert!(!records.add_clear_time(PACK, 0, 1600));
+
+    assert_eq!(
+        records.pack_best_times(PACK).collect::<Vec<_>>(),
+        [(0, 1500)]
+    );
+    assert_eq!(
+        records.pack_best_times("my pack").collect::<Vec<_>>(),
+        [(0, 2000), (1, 900)]
+    );
+    assert_eq!(records.pack_best_times("none").count(), 0);
+
+    // Pack names may contain spaces
+    assert_eq!(parse_records(&records.to_source()), records);
+}
+
+#[test]
+fn records_are_saved_as_text() {
+    let mut records = Records::default();
+    records.add_high_score("ABC", 12300);
+    records.add_high_score("XYZ", 500);
+    records.add_clear_time(PACK, 0, 1427);
+
+    assert_eq!(parse_records(&records.to_source()), records);
+
+    // Invalid lines are skipped
+    let source = "score = AB 100\nscore = abc 100\nscore = ABC\ntime = 1 x\ntime = p x 1\nwhat\n"
+        .to_string()
+        + &records.to_source();
+    assert_eq!(parse_records(&source), records);
+}
+
+#[test]
+fn times_without_pack_belong_to_the_default_pack() {
+    let records = parse_records("time = 0 1427\ntime = 3 2000\n");
+
+    assert_eq!(
+        records.pack_best_times(PACK).collect::<Vec<_>>(),
+        [(0, 1427), (3, 2000)]
+    );
+    assert_eq!(
+        records.to_source(),
+        "time = levels 0 1427\ntime = levels 3 2000\n"
+    );
+}
@@ -0,0 +1,352 @@
+// The game simulation, run without a window: the levels are read from the default pack, and the
+// players are driven by scripted inputs.
+
+use std::fs;
+
+use cavern_macroquad::{
+    actor::Actor,
+    fruit::{Fruit, FruitType},
+    game::Game,
+    input::PlayerInput,
+    levels::{
+        parse_level, parse_pack_index, LevelPack, DEFAULT_PACK_DIR, MAX_ENEMIES,
+        PACK_INDEX_FILENAME,
+    },
+    orb::Orb,
+    player::Player,
+    replay::{parse_replay, Replay},
+    rng::Rng,
+    robot::{Robot, RobotType, BOSS_HEALTH},
+    WIDTH,
+};
+
+/// Enough for a level, even with a clumsy player: one minute at 60 FPS is 3600 frames.
+const MAX_FRAMES: usize = 20_000;
+
+const SEED: u64 = 1234;
+
+fn load_pack() -> LevelPack {
+    let source =
+        fs::read_to_string(format!("{}/{}", DEFAULT_PACK_DIR, PACK_INDEX_FILENAME)).unwrap();
+    let index = parse_pack_index(&source).unwrap_or_else(|error| panic!("{}", error));
+
+    let levels = index
+        .filenames
+        .iter()
+        .map(|filename| {
+            let source = fs::read_to_string(format!("{}/{}", DEFAULT_PACK_DIR, filename)).unwrap();
+            parse_level(&source).unwrap_or_else(|error| panic!("{}: {}", filename, error))
+        })
+        .collect();
+
+    LevelPack {
+        name: "levels".to_string(),
+        dir: DEFAULT_PACK_DIR.to_string(),
+        filenames: index.filenames.iter().map(|s| s.to_string()).collect(),
+        levels,
+        repeat: index.repeat,
+    }
+}
+
+fn new_game(players: Vec<Player>, pack: &LevelPack, seed: u64) -> Game {
+    Game::new(players, pack.levels.clone(), pack.repeat, seed)
+}
+
+/// Checks that the game is set up for its current level, which is taken from the pack.
+fn assert_level(game: &Game, pack: &LevelPack) {
+    let levels = &pack.levels;
+    let index = game.level as usize % levels.len();
+    let level = &levels[index];
+
+    assert_eq!(game.grid, level.screen_grid());
+    assert_eq!(
+        game.settings,
+        level
+            .settings
+            .for_pass(game.level as usize / levels.len(), &pack.repeat)
+    );
+    assert_eq!(game.level_colour as usize, level.settings.theme);
+
+    // On the boss levels, the boss comes first
+    let bosses = game
+        .pending_enemies
+        .iter()
+        .filter(|e| matches!(e, RobotType::Boss))
+        .count();
+    assert_eq!(bosses, game.is_boss_level() as usize);
+    assert_eq!(
+        game.pending_enemies.len(),
+        game.settings.num_enemies + bosses
+    );
+    if game.is_boss_level() {
+        assert!(matches!(game.pending_enemies.last(), Some(RobotType::Boss)));
+    }
+}
+
+/// Walks towards the nearest free robot, jumping when it's higher, and blows an orb at it once it's
+/// at the same height; the blow key is held, so that the orb goes as far as possible.
+fn scripted_input(game: &Game, player: &Player) -> PlayerInput {
+    let mut input = PlayerInput::default();
+
+    let target = game
+        .enemies
+        .values()
+        .filter(|robot| !robot.trapped && robot.top() >= 0)
+        .min_by_key(|robot| (robot.x() - player.x()).abs() + (robot.y() - player.y()).abs());
+
+    let robot = match target {
+        Some(robot) => robot,
+        None => return input,
+    };
+
+    let dx = robot.x() - player.x();
+    let facing = dx.signum() == player.direction_x;
+
+    if (robot.y() - player.y()).abs() < 20 {
+        if facing {
+            input.blow = true;
+            input.blow_pressed = player.fire_timer <= 0 && game.timer % 2 == 0;
+        } else {
+            input.left = dx < 0;
+            input.right = dx > 0;
+        }
+    } else {
+        input.left = dx < 0;
+        input.right = dx > 0;
+        input.jump = robot.y() < player.y();
+    }
+
+    input
+}
+
+/// Runs the game with the scripted player until the given condition holds; returns the frames played.
+fn play_until(game: &mut Game, replay: &mut Replay, done: impl Fn(&Game) -> bool) -> usize {
+    for frame in 0..MAX_FRAMES {
+        if done(game) {
+            return frame;
+        }
+
+        let inputs = game
+            .players
+            .iter()
+            .map(|player| scripted_input(game, player))
+            .collect::<Vec<_>>();
+
+        replay.record(&inputs);
+        game.update(&inputs);
+    }
+
+    panic!("not done after {} frames", MAX_FRAMES);
+}
+
+#[test]
+fn next_level_cycles_through_the_pack() {
+    let pack = load_pack();
+    let levels = &pack.levels;
+    let mut game = new_game(vec![Player::new(0)], &pack, SEED);
+
+    assert_eq!(game.level, 0);
+    assert_eq!(game.settings, levels[0].settings);
+    assert_level(&game, &pack);
+
+    // Once the pack is over, it starts again from the first level
+    for level_number in 1..=levels.len() + 1 {
+        // A level ends when there is nothing left to create or to destroy
+        game.pending_enemies.clear();
+        game.fruits.clear();
+        game.bolts.clear();
+        game.enemies.clear();
+        game.pops.clear();
+        game.orbs.clear();
+
+        game.update(&[PlayerInput::default()]);
+
+        assert_eq!(game.level as usize, level_number);
+        assert_eq!(game.timer, -1);
+        assert_level(&game, &pack);
+
+        // On the first pass, the settings are the ones in the files
+        if level_number < levels.len() {
+            assert_eq!(game.settings, levels[level_number].settings);
+        }
+    }
+
+    // The levels of the second pass have their own times
+    let cleared = game
+        .clear_times
+        .iter()
+        .map(|&(level, _)| level)
+        .collect::<Vec<_>>();
+    assert_eq!(cleared, (0..=levels.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn difficulty_keeps_increasing_once_the_pack_starts_again() {
+    let pack = load_pack();
+    let levels = &pack.levels;
+    let first = levels[0].settings;
+    let last = levels[levels.len() - 1].settings;
+
+    // The first level of the second pass is harder than both the first and the last of the first pass
+    let again = first.for_pass(1, &pack.repeat);
+    assert_eq!(again.theme, first.theme);
+    assert!(again.num_enemies > first.num_enemies.max(last.num_enemies));
+    assert!(again.num_strong_enemies > first.num_strong_enemies);
+    assert!(again.num_strong_enemies <= again.num_enemies);
+    assert!(again.max_enemies > first.max_enemies);
+    assert!(again.fire_probability > first.fire_probability);
+
+    // And so on for the following passes
+    let third = first.for_pass(2, &pack.repeat);
+    assert!(third.num_enemies > again.num_enemies);
+    assert!(third.num_strong_enemies > again.num_strong_enemies);
+    assert!(third.max_enemies >= again.max_enemies);
+    assert!(third.fire_probability > again.fire_probability);
+
+    // Up to the limit of the enemies on-screen
+    for level in levels {
+        for pass in 0..10 {
+            assert!(level.settings.for_pass(pass, &pack.repeat).max_enemies <= MAX_ENEMIES);
+        }
+    }
+    assert_eq!(first.for_pass(10, &pack.repeat).max_enemies, MAX_ENEMIES);
+}
+
+#[test]
+fn scripted_player_clears_level_1() {
+    let mut game = new_game(vec![Player::new(0)], &load_pack(), SEED);
+    let mut replay = Replay::new(SEED, 1);
+
+    play_until(&mut game, &mut replay, |game| game.level == 1);
+
+    // The time is taken before the timer is reset for the next level
+    assert_eq!(game.clear_times.len(), 1);
+    let (level, frames) = game.clear_times[0];
+    assert_eq!(level, 0);
+    assert!(frames > 0);
+
+    let player = &game.players[0];
+    assert!(player.in_game());
+    assert!(player.score > 0);
+}
+
+#[test]
+fn replay_plays_the_game_again_exactly() {
+    let pack = load_pack();
+    let mut game = new_game(vec![Player::new(0), Player::new(1)], &pack, SEED);
+    let mut replay = Replay::new(SEED, 2);
+
+    play_until(&mut game, &mut replay, |game| game.timer >= 2000);
+
+    let replay = parse_replay(&replay.to_source()).unwrap_or_else(|error| panic!("{}", error));
+    let mut replayed = new_game(vec![Player::new(0), Player::new(1)], &pack, replay.seed);
+
+    for inputs in &replay.frames {
+        replayed.update(inputs);
+    }
+
+    assert_eq!(replayed.level, game.level);
+    assert_eq!(replayed.timer, game.timer);
+    assert_eq!(replayed.sounds, game.sounds);
+    for (replayed_player, player) in replayed.players.iter().zip(&game.players) {
+        assert_eq!(replayed_player.score, player.score);
+        assert_eq!(replayed_player.lives, player.lives);
+        assert_eq!(replayed_player.health, player.health);
+        assert_eq!((replayed_player.x, replayed_player.y), (player.x, player.y));
+    }
+    assert_eq!(
+        replayed
+            .enemies
+            .values()
+            .map(|e| (e.x, e.y))
+            .collect::<Vec<_>>(),
+        game.enemies
+            .values()
+            .map(|e| (e.x, e.y))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn boss_is_trapped_by_the_last_of_several_orbs() {
+    let mut game = new_game(vec![Player::new(0)], &load_pack(), SEED);
+    game.pending_enemies.clear();
+
+    let boss = game.enemies.insert(Robot::new(
+        WIDTH / 2,
+        200,
+        RobotType::Boss,
+        &mut Rng::new(SEED),
+    ));
+    game.update(&[PlayerInput::default()]);
+
+    for health in (0..BOSS_HEALTH).rev() {
+        let (x, y) = game.enemies.get(boss).unwrap().center();
+        game.orbs.insert(Orb::new(x, y, 1, 0));
+        game.update(&[PlayerInput::default()]);
+
+        let boss = game.enemies.get(boss).unwrap();
+        assert_eq!(boss.health, health);
+        assert_eq!(boss.trapped, health == 0);
+    }
+}
+
+#[test]
+fn screen_bomb_traps_every_robot() {
+    let mut game = new_game(vec![Player::new(0)], &load_pack(), SEED);
+    game.pending_enemies.clear();
+
+    let mut rng = Rng::new(SEED);
+    for x in [200, 400, 600] {
+        game.enemies
+            .insert(Robot::new(x, 300, RobotType::Normal, &mut rng));
+    }
+    game.update(&[PlayerInput::default()]);
+
+    let (x, y) = (game.players[0].x, game.players[0].y);
+    game.fruits.clear();
+    game.fruits.insert(Fruit {
+        type_: FruitType::ScreenBomb,
+        ..Fruit::new(x, y, None, &mut rng)
+    });
+    game.update(&[PlayerInput::default()]);
+
+    assert!(game.enemies.values().all(|e| e.trapped));
+    assert_eq!(game.orbs.len(), 3);
+}
+
+#[test]
+fn big_orb_traps_a_robot_that_a_normal_orb_misses() {
+    let mut game = new_game(vec![Player::new(0)], &load_pack(), SEED);
+    game.pending_enemies.clear();
+
+    let robot = game.enemies.insert(Robot::new(
+        WIDTH / 2,
+        200,
+        RobotType::Normal,
+        &mut Rng::new(SEED),
+    ));
+    game.update(&[PlayerInput::default()]);
+
+    // A fully grown orb, already floating, whose centre is just past the side of the robot
+    let (x, y) = {
+        let robot = game.enemies.get(robot).unwrap();
+        (robot.right() + 20, robot.center().1)
+    };
+
+    for (scale, trapped) in [(1, false), (2, true)] {
+        let mut game = game.clone();
+        game.orbs.insert(Orb {
+            timer: 20,
+            floating: true,
+            scale,
+            ..Orb::new(x, y, 1, 0)
+        });
+        // The orb image is only set by its first update, after the robots are checked
+        for _ in 0..2 {
+            game.update(&[PlayerInput::default()]);
+        }
+
+        assert_eq!(game.enemies.get(robot).unwrap().trapped, trapped);
+    }
+}
@@ -0,0 +1,338 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    brain::Brain,
+    components::*,
+    constants::JUMP_VELOCITY,
+    core_plugin::PhysicsSet,
+    resources::Game,
+    setup,
+    states::GameState,
+    systems,
+};
+
+pub const DEFAULT_POPULATION_SIZE: usize = 50;
+pub const DEFAULT_BRAIN_FILE: &str = "best.brain";
+
+// A generation also ends after two minutes of play, once its best birds no longer crash
+const MAX_GENERATION_TICKS: u32 = 120 * 60;
+
+// The fittest birds go to the next generation unchanged; the other birds are children of parents
+// picked by tournaments, each between a few random birds
+const ELITES: usize = 2;
+const TOURNAMENT_SIZE: usize = 3;
+
+pub enum AiMode {
+    // A population of birds learns to fly, in parallel, a generation after the other; the training
+    // stops after the given number of generations, if any, and saves the best network
+    Train {
+        population_size: usize,
+        seed: Option<u64>,
+        generations: Option<u32>,
+    },
+    // The bird of the player is flown by a trained network
+    Replay(Brain),
+}
+
+// Birds flown by neural networks (see brain.rs); like the FlappyCorePlugin, it runs headless, for
+// a training faster than real time
+pub struct AiPlugin {
+    pub mode: AiMode,
+    // Where the best network is saved
+    pub brain_file: PathBuf,
+}
+
+// Where the best network is saved, e.g. when exported during the training
+#[derive(Resource)]
+pub struct BrainFile(pub PathBuf);
+
+#[derive(Resource)]
+struct AutopilotBrain(Brain);
+
+#[derive(Resource)]
+struct TrainingLength(Option<u32>);
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BrainFile(self.brain_file.clone()))
+            .add_systems(
+                FixedUpdate,
+                think.in_set(PhysicsSet).before(systems::jump),
+            );
+
+        match &self.mode {
+            AiMode::Train {
+                population_size,
+                seed,
+                generations,
+            } => {
+                app.insert_resource(Population::new(*population_size, *seed))
+                    .insert_resource(TrainingLength(*generations))
+                    .add_systems(
+                        OnEnter(GameState::Menu),
+                        (spawn_trainees.after(setup::spawn_run), start_training),
+                    )
+                    .add_systems(
+                        OnExit(GameState::GameOver),
+                        spawn_trainees.after(setup::spawn_run),
+                    )
+                    .add_systems(OnEnter(GameState::GameOver), next_generation)
+                    .add_systems(
+                        FixedUpdate,
+                        record_crashes
+                            .in_set(PhysicsSet)
+                            .after(systems::score)
+                            .before(systems::end_game),
+                    )
+                    .add_systems(Update, hide_crashed_trainees);
+            }
+            AiMode::Replay(brain) => {
+                app.insert_resource(AutopilotBrain(brain.clone()))
+                    .add_systems(
+                        OnEnter(GameState::Menu),
+                        take_controls.after(setup::spawn_run),
+                    )
+                    .add_systems(
+                        OnExit(GameState::GameOver),
+                        take_controls.after(setup::spawn_run),
+                    );
+            }
+        }
+    }
+}
+
+// The birds of a generation, and the best network so far
+#[derive(Resource)]
+pub struct Population {
+    // Starting from 1
+    pub generation: u32,
+    brains: Vec<Brain>,
+    // Ticks flown by each bird of the generation
+    fitness: Vec<u32>,
+    // Ticks flown by the generation so far
+    ticks: u32,
+    pub best_score: u32,
+    pub best_fitness: u32,
+    pub best: Option<Brain>,
+    rng: StdRng,
+}
+
+impl Population {
+    pub fn new(size: usize, seed: Option<u64>) -> Self {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Self {
+            generation: 1,
+            brains: (0..size).map(|_| Brain::random(&mut rng)).collect(),
+            fitness: vec![0; size],
+            ticks: 0,
+            best_score: 0,
+            best_fitness: 0,
+            best: None,
+            rng,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.brains.len()
+    }
+
+    fn record(&mut self, index: usize, score: u32) {
+        self.fitness[index] = self.ticks;
+        self.best_score = self.best_score.max(score);
+
+        if self.ticks > self.best_fitness || self.best.is_none() {
+            self.best_fitness = self.ticks;
+            self.best = Some(self.brains[index].clone());
+        }
+    }
+
+    // Replaces the birds by the next generation
+    pub fn evolve(&mut self) {
+        let mut ranking: Vec<usize> = (0..self.size()).collect();
+        ranking.sort_by_key(|&index| std::cmp::Reverse(self.fitness[index]));
+
+        let mut brains: Vec<Brain> = ranking
+            .iter()
+            .take(ELITES)
+            .map(|&index| self.brains[index].clone())
+            .collect();
+
+        while brains.len() < self.size() {
+            let father = self.tournament();
+            let mother = self.tournament();
+            let mut child = self.brains[father].crossover(&self.brains[mother], &mut self.rng);
+            child.mutate(&mut self.rng);
+
+            brains.push(child);
+        }
+
+        self.brains = brains;
+        self.fitness = vec![0; self.size()];
+        self.ticks = 0;
+        self.generation += 1;
+    }
+
+    // Index of the fittest of a few random birds
+    fn tournament(&mut self) -> usize {
+        let size = self.size();
+        let candidates: Vec<usize> = (0..TOURNAMENT_SIZE)
+            .map(|_| self.rng.gen_range(0..size))
+            .collect();
+
+        candidates
+            .into_iter()
+            .max_by_key(|&index| self.fitness[index])
+            .unwrap()
+    }
+}
+
+// The network of the bird, whether trained or being trained
+type Pilot<'a> = AnyOf<(&'a Autopilot, &'a Trainee)>;
+
+// The network sees the middle of the gap of the next pipes, which the bird hasn't passed yet
+fn think(
+    mut bird_query: Query<(&mut Bird, &Position, Pilot), Without<Crashed>>,
+    lower_pipe_query: Query<&Position, With<LowerPipe>>,
+    upper_pipe_query: Query<&Position, With<UpperPipe>>,
+    population: Option<Res<Population>>,
+) {
+    for (mut bird, position, (autopilot, trainee)) in bird_query.iter_mut() {
+        let (Some(lower), Some(upper)) = (
+            next_pipe(lower_pipe_query.iter(), position),
+            next_pipe(upper_pipe_query.iter(), position),
+        ) else {
+            continue;
+        };
+
+        let inputs = [
+            (lower.x - position.current.x) / 200.,
+            ((lower.y + upper.y) / 2. - position.current.y) / 200.,
+            bird.velocity / JUMP_VELOCITY,
+        ];
+        let brain = match (autopilot, trainee, &population) {
+            (Some(Autopilot(brain)), _, _) => brain,
+            (_, Some(Trainee(index)), Some(population)) => &population.brains[*index],
+            _ => continue,
+        };
+
+        if brain.flaps(inputs) {
+            bird.velocity = JUMP_VELOCITY;
+        }
+    }
+}
+
+// Pipes are 52 wide, and the bird 34
+fn next_pipe<'a>(pipes: impl Iterator<Item = &'a Position>, bird: &Position) -> Option<Vec2> {
+    pipes
+        .filter(|pipe| pipe.current.x + 26. > bird.current.x - 17.)
+        .min_by(|a, b| a.current.x.total_cmp(&b.current.x))
+        .map(|pipe| pipe.current)
+}
+
+// Each bird of the population is as fit as the number of ticks it flew
+fn record_crashes(
+    mut commands: Commands,
+    mut population: ResMut<Population>,
+    game: Res<Game>,
+    crashed_query: Query<&Trainee, Added<Crashed>>,
+    flying_query: Query<(Entity, &Trainee), Without<Crashed>>,
+) {
+    population.ticks += 1;
+
+    for Trainee(index) in crashed_query.iter() {
+        population.record(*index, game.score);
+    }
+
+    if population.ticks >= MAX_GENERATION_TICKS {
+        for (entity, Trainee(index)) in flying_query.iter() {
+            population.record(*index, game.score);
+            commands.entity(entity).insert(Crashed);
+        }
+    }
+}
+
+// The population takes the place of the bird of the run
+fn spawn_trainees(
+    mut commands: Commands,
+    player_query: Query<(Entity, &Position), With<Player>>,
+    population: Res<Population>,
+) {
+    for (entity, position) in player_query.iter() {
+        commands.entity(entity).despawn();
+
+        for index in 0..population.size() {
+            commands.spawn((
+                Bird {
+                    timer: Timer::from_seconds(0.2, TimerMode::Repeating),
+                    velocity: 0.,
+                },
+                *position,
+                Trainee(index),
+            ));
+        }
+    }
+}
+
+// There is no one to press the space bar
+fn start_training(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
+
+// The game is over when the whole generation has crashed; the next one starts right away, in a new
+// run, unless the training is over
+fn next_generation(
+    mut population: ResMut<Population>,
+    length: Res<TrainingLength>,
+    brain_file: Res<BrainFile>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    println!(
+        "Generation {}: best score {}, best fitness {}",
+        population.generation, population.best_score, population.best_fitness
+    );
+
+    if length.0 == Some(population.generation) {
+        save_best_brain(&population, &brain_file);
+        exit_events.send(AppExit::Success);
+        return;
+    }
+
+    population.evolve();
+    next_state.set(GameState::Playing);
+}
+
+pub fn save_best_brain(population: &Population, brain_file: &BrainFile) {
+    if let Some(best) = &population.best {
+        match best.save(&brain_file.0) {
+            Ok(()) => println!("Best network saved to {}", brain_file.0.display()),
+            Err(error) => eprintln!("Can't save {}: {error}", brain_file.0.display()),
+        }
+    }
+}
+
+fn hide_crashed_trainees(mut query: Query<&mut Visibility, (With<Trainee>, Added<Crashed>)>) {
+    for mut visibility in query.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn take_controls(
+    mut commands: Commands,
+    player_query: Query<Entity, With<Player>>,
+    brain: Res<AutopilotBrain>,
+) {
+    for entity in player_query.iter() {
+        commands
+            .entity(entity)
+            .remove::<Player>()
+            .insert(Autopilot(brain.0.clone()));
+    }
+}
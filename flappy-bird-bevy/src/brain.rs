@@ -0,0 +1,115 @@
+use std::{fs, io, path::Path};
+
+use rand::Rng;
+
+// What the bird sees: the horizontal and the vertical distance to the middle of the next gap, and
+// its own velocity
+pub const INPUTS: usize = 3;
+pub const HIDDEN: usize = 6;
+
+// Each neuron has a weight per input, then a bias
+const WEIGHTS: usize = HIDDEN * (INPUTS + 1) + (HIDDEN + 1);
+
+// First line of the files, with the size of the network
+const HEADER: &str = "flappy-brain";
+
+// Chance of each weight to be mutated, and largest change
+const MUTATION_RATE: f64 = 0.1;
+const MUTATION_SIZE: f32 = 0.5;
+
+// A small neural network that decides when a bird flaps: the inputs go through a hidden layer of
+// tanh neurons, to a single sigmoid output; the bird flaps when it is above 0.5
+#[derive(Clone, Debug, PartialEq)]
+pub struct Brain {
+    weights: Vec<f32>,
+}
+
+impl Brain {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            weights: (0..WEIGHTS).map(|_| rng.gen_range(-1.0..1.0)).collect(),
+        }
+    }
+
+    pub fn flaps(&self, inputs: [f32; INPUTS]) -> bool {
+        let (hidden_weights, output_weights) = self.weights.split_at(HIDDEN * (INPUTS + 1));
+
+        let hidden = hidden_weights.chunks(INPUTS + 1).map(|neuron| {
+            let sum: f32 = inputs.iter().zip(neuron).map(|(input, weight)| input * weight).sum();
+
+            (sum + neuron[INPUTS]).tanh()
+        });
+        let output = hidden
+            .zip(output_weights)
+            .map(|(value, weight)| value * weight)
+            .sum::<f32>()
+            + output_weights[HIDDEN];
+
+        // The sigmoid is above 0.5 for a positive sum
+        output > 0.
+    }
+
+    // Each weight comes from either parent
+    pub fn crossover(&self, other: &Brain, rng: &mut impl Rng) -> Self {
+        Self {
+            weights: self
+                .weights
+                .iter()
+                .zip(&other.weights)
+                .map(|(&a, &b)| if rng.gen_bool(0.5) { a } else { b })
+                .collect(),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng) {
+        for weight in self.weights.iter_mut() {
+            if rng.gen_bool(MUTATION_RATE) {
+                *weight += rng.gen_range(-MUTATION_SIZE..MUTATION_SIZE);
+            }
+        }
+    }
+
+    // A header line with the size of the network, then a weight per line
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER} {INPUTS} {HIDDEN}\n");
+        for weight in &self.weights {
+            text += &format!("{weight}\n");
+        }
+
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+
+        let header = lines.next().unwrap_or_default();
+        if header != format!("{HEADER} {INPUTS} {HIDDEN}") {
+            return Err(format!("not a brain of {INPUTS} inputs and {HIDDEN} hidden neurons"));
+        }
+
+        let weights = lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.trim()
+                    .parse::<f32>()
+                    .map_err(|_| format!("invalid weight {line:?}"))
+            })
+            .collect::<Result<Vec<f32>, String>>()?;
+
+        if weights.len() != WEIGHTS {
+            return Err(format!("{} weights instead of {WEIGHTS}", weights.len()));
+        }
+
+        Ok(Self { weights })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+
+        Self::from_text(&text).map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+    }
+}
@@ -1,4 +1,6 @@
 use bevy::prelude::*;
+
+use crate::brain::Brain;
  
 #[derive(Component)]
 pub struct Background;
@@ -13,7 +15,11 @@ pub struct GameOverText;
 pub struct PressSpaceBarText(pub Timer); // timer will repeat every 0.5 seconds
 
 #[derive(Component)]
-pub struct ScoreText;
+pub struct PausedText;
+
+// A digit of the score, counting from the left
+#[derive(Component)]
+pub struct ScoreText(pub usize);
 
 #[derive(Component)]
 pub struct Bird {
@@ -27,4 +33,71 @@ pub struct UpperPipe{
 }
  
 #[derive(Component)]
-pub struct LowerPipe;
\ No newline at end of file
+pub struct LowerPipe;
+
+// Height of a pipe, which goes up and down around its base height, unless its amplitude is 0
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Oscillation {
+    pub base_y: f32,
+    pub amplitude: f32,
+    // In radians
+    pub phase: f32,
+}
+
+impl Oscillation {
+    pub fn y(&self) -> f32 {
+        self.base_y + self.amplitude * self.phase.sin()
+    }
+}
+
+// Query filter of both kinds of pipes
+pub type AnyPipe = Or<(With<UpperPipe>, With<LowerPipe>)>;
+
+// Position used by the physics, which runs at a fixed rate (see core_plugin.rs); the Transform is
+// interpolated between the previous and the current position, so that the movement is smooth at any
+// frame rate
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub previous: Vec2,
+    pub current: Vec2,
+}
+
+impl Position {
+    pub fn new(x: f32, y: f32) -> Self {
+        let position = Vec2::new(x, y);
+
+        Self {
+            previous: position,
+            current: position,
+        }
+    }
+
+    // Moves without interpolation, e.g. for the pipes that go back to the right
+    pub fn teleport(&mut self, position: Vec2) {
+        self.previous = position;
+        self.current = position;
+    }
+}
+
+// The bird flown with the space bar
+#[derive(Component)]
+pub struct Player;
+
+// Set on a bird when it hits the ground or a pipe; it no longer moves nor scores
+#[derive(Component)]
+pub struct Crashed;
+
+// Query filter of the birds still flying
+pub type FlyingBird = (With<Bird>, Without<Crashed>);
+
+// A bird flown by a trained network (see ai.rs)
+#[derive(Component)]
+pub struct Autopilot(pub Brain);
+
+// A bird of the population being trained, flown by the network of this index in the Population
+#[derive(Component)]
+pub struct Trainee(pub usize);
+
+// Generation, best score and fitness of the training
+#[derive(Component)]
+pub struct TrainingText;
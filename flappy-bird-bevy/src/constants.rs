@@ -2,4 +2,16 @@
 pub const WINDOW_WIDTH: f32 = 800.0;
 pub const WINDOW_HEIGHT: f32 = 512.0;
 
-pub const BACKGROUNG_IMAGE_WIDTH: f32 = 288.0;
\ No newline at end of file
+pub const BACKGROUNG_IMAGE_WIDTH: f32 = 288.0;
+
+// Upward velocity of the bird when it flaps, and its downward acceleration
+pub const JUMP_VELOCITY: f32 = 400.0;
+pub const GRAVITY: f32 = 9.8 * 150.;
+
+// Speed of the pipes, to the left; the moving pipes also go up and down, at this many radians of
+// their oscillation per second
+pub const PIPE_SPEED: f32 = 150.;
+pub const OSCILLATION_SPEED: f32 = 2.;
+
+// Pairs of pipes on the screen at once, which go back to the right when they leave it
+pub const PIPE_PAIRS: usize = 6;
@@ -0,0 +1,113 @@
+use bevy::{prelude::*, state::app::StatesPlugin};
+
+use crate::{
+    components::Position,
+    events::{Flapped, PipePassed},
+    pipe_generator::PipeGenerator,
+    resources::{BestScore, Game, JumpInput},
+    setup,
+    states::GameState,
+    systems,
+};
+
+pub const DEFAULT_TICK_RATE: f64 = 60.;
+
+// The gameplay without the window, the sprites and the audio, so that it also runs under the
+// MinimalPlugins, e.g. in the tests: the states, the bird and the pipes of each run, and the
+// physics. The jump is requested through the JumpInput resource, and the sounds are played on the
+// Flapped and PipePassed events
+//
+// The bird physics, the pipe movement, the collisions and the scoring run in FixedUpdate, with a
+// fixed time step, so that the game plays the same at any frame rate; the transforms are then
+// interpolated between the last two ticks
+pub struct FlappyCorePlugin {
+    // Ticks per second
+    pub tick_rate: f64,
+    // Seed of the pipes (see PipeGenerator); random if None
+    pub seed: Option<u64>,
+}
+
+impl Default for FlappyCorePlugin {
+    fn default() -> Self {
+        Self {
+            tick_rate: DEFAULT_TICK_RATE,
+            seed: None,
+        }
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhysicsSet;
+
+impl Plugin for FlappyCorePlugin {
+    fn build(&self, app: &mut App) {
+        // The StatesPlugin is part of the DefaultPlugins, but not of the MinimalPlugins
+        if !app.is_plugin_added::<StatesPlugin>() {
+            app.add_plugins(StatesPlugin);
+        }
+
+        app.init_state::<GameState>()
+            .insert_resource(Time::<Fixed>::from_hz(self.tick_rate))
+            .insert_resource(PipeGenerator::new(self.seed))
+            .init_resource::<Game>()
+            .init_resource::<BestScore>()
+            .init_resource::<JumpInput>()
+            .add_event::<Flapped>()
+            .add_event::<PipePassed>()
+            .add_systems(OnEnter(GameState::Menu), setup::spawn_run)
+            .add_systems(OnEnter(GameState::GameOver), systems::update_best_score)
+            .add_systems(
+                OnExit(GameState::GameOver),
+                (setup::despawn_run, setup::spawn_run).chain(),
+            )
+            .configure_sets(
+                FixedUpdate,
+                PhysicsSet.run_if(in_state(GameState::Playing).and_then(not(crash_pending))),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    save_previous_positions,
+                    systems::jump,
+                    systems::gravity,
+                    systems::move_pipes,
+                    systems::check_collisions,
+                    systems::score,
+                    systems::end_game,
+                )
+                    .chain()
+                    .in_set(PhysicsSet),
+            )
+            .add_systems(
+                PostUpdate,
+                interpolate_transforms.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+// The state changes at the next frame, while there may be more ticks in the current one; they're
+// skipped, so that the game ends at the same tick at any frame rate
+fn crash_pending(next_state: Res<NextState<GameState>>) -> bool {
+    matches!(*next_state, NextState::Pending(GameState::GameOver))
+}
+
+fn save_previous_positions(mut query: Query<&mut Position>) {
+    for mut position in query.iter_mut() {
+        position.previous = position.current;
+    }
+}
+
+// Between ticks, the entities are drawn at the fraction of the time step elapsed
+fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&Position, &mut Transform)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+
+    for (position, mut transform) in query.iter_mut() {
+        let interpolated = position.previous.lerp(position.current, alpha);
+
+        transform.translation.x = interpolated.x;
+        transform.translation.y = interpolated.y;
+    }
+}
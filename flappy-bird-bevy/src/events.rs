@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+// Sent by the physics, which doesn't play sounds itself, so that it can run without the audio
+
+#[derive(Event)]
+pub struct Flapped;
+
+#[derive(Event)]
+pub struct PipePassed;
@@ -0,0 +1,13 @@
+pub mod ai;
+pub mod brain;
+pub mod components;
+pub mod constants;
+pub mod core_plugin;
+pub mod events;
+pub mod pipe_generator;
+pub mod plugin;
+pub mod resources;
+pub mod scoreboard;
+pub mod setup;
+pub mod states;
+pub mod systems;
@@ -1,42 +1,196 @@
-use bevy::prelude::*;
-use plugin::MyPlugin;
-use resources::{Game, GameState};
-
-mod components;
-mod constants;
-mod plugin;
-mod resources;
-mod setup;
-mod systems;
-mod utils;
+use std::{path::PathBuf, process, time::Duration};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use rust_flappy_bird::{
+    ai::{AiMode, AiPlugin, Population, DEFAULT_BRAIN_FILE, DEFAULT_POPULATION_SIZE},
+    brain::Brain,
+    core_plugin::{FlappyCorePlugin, DEFAULT_TICK_RATE},
+    pipe_generator::PipeGenerator,
+    plugin::MyPlugin,
+    scoreboard::ScoreboardPlugin,
+    setup,
+    states::GameState,
+    systems,
+};
+
+const USAGE: &str = "Usage:
+  rust-flappy-bird                  play
+  rust-flappy-bird daily            play the pipes of the day, the same for everyone
+  rust-flappy-bird train [options]  watch a population of birds learn to fly; E saves the best one
+      --headless                    train without a window, faster than real time
+      --generations N               stop after N generations, and save the best bird
+      --population N                number of birds of each generation (50 by default)
+      --seed N                      seed of the training
+      --file FILE                   where the best bird is saved (best.brain by default)
+  rust-flappy-bird replay [FILE]    watch the bird saved in FILE (best.brain by default)
+  rust-flappy-bird help             show this help";
+
+enum Command {
+    Play,
+    Daily,
+    Help,
+    Train {
+        headless: bool,
+        generations: Option<u32>,
+        population_size: usize,
+        seed: Option<u64>,
+        brain_file: PathBuf,
+    },
+    Replay(PathBuf),
+}
  
 fn main() {
-    App::new()
-    // Add a global resource that holds the game state
-    .init_resource::<Game>()
+    let command = parse_command(std::env::args().skip(1).collect()).unwrap_or_else(|message| {
+        eprintln!("{message}\n\n{USAGE}");
+        process::exit(2);
+    });
 
+    match command {
+        Command::Play => {
+            game(None).run();
+        }
+        Command::Daily => {
+            game(Some(PipeGenerator::daily_seed())).run();
+        }
+        Command::Help => println!("{USAGE}"),
+        Command::Train {
+            headless,
+            generations,
+            population_size,
+            seed,
+            brain_file,
+        } => {
+            let ai = AiPlugin {
+                mode: AiMode::Train {
+                    population_size,
+                    seed,
+                    generations,
+                },
+                brain_file,
+            };
+
+            if headless {
+                // Each frame is a tick, with no wait between frames
+                App::new()
+                    .add_plugins((MinimalPlugins, FlappyCorePlugin::default(), ai))
+                    .insert_resource(TimeUpdateStrategy::ManualDuration(
+                        Duration::from_secs_f64(1. / DEFAULT_TICK_RATE),
+                    ))
+                    .run();
+            } else {
+                game(None).add_plugins(ai).run();
+            }
+        }
+        Command::Replay(brain_file) => {
+            let brain = Brain::load(&brain_file).unwrap_or_else(|error| {
+                eprintln!("Can't load {}: {error}", brain_file.display());
+                process::exit(1);
+            });
+
+            game(None)
+                .add_plugins(AiPlugin {
+                    mode: AiMode::Replay(brain),
+                    brain_file,
+                })
+                .run();
+        }
+    }
+}
+
+// The seed of the pipes is random if None
+fn game(seed: Option<u64>) -> App {
+    let mut app = App::new();
+
+    app
     // System that runs once at the start of the app
     .add_systems(Startup, setup::setup)
 
-    // Systems that run every frame
-    .add_systems(Update, systems::blink_space_bar_text.run_if(is_game_not_active))
-    .add_systems(Update, systems::move_background.run_if(is_game_active))
-    .add_systems(Update, systems::move_ground.run_if(is_game_active))
-    .add_systems(Update, systems::animate_bird.run_if(is_game_active))
-    .add_systems(Update, systems::start_game.run_if(is_game_not_active))
-    .add_systems(Update, systems::gravity.run_if(is_game_active))
-    .add_systems(Update, systems::jump.run_if(is_game_active))
-    .add_systems(Update, systems::pipes.run_if(is_game_active))
-    .add_systems(Update, systems::score.run_if(is_game_active))
-    .add_systems(Update, systems::render_score.run_if(is_game_active))
-
-    .add_plugins(MyPlugin).run();
+    // Setup and teardown of each screen
+    .add_systems(OnEnter(GameState::Menu), setup::spawn_press_space_bar_text)
+    .add_systems(OnEnter(GameState::Paused), (setup::spawn_paused_text, systems::pause_time))
+    .add_systems(OnExit(GameState::Paused), systems::resume_time)
+    .add_systems(OnEnter(GameState::GameOver), (setup::spawn_game_over_text, setup::spawn_press_space_bar_text, systems::play_hit_sound).run_if(not(is_training)))
+
+    // Systems that run every frame; the bird physics, the pipes and the score are in the
+    // FlappyCorePlugin, and run at a fixed rate
+    .add_systems(Update, (setup::add_bird_sprite, setup::add_pipe_sprites))
+    .add_systems(Update, (systems::blink_space_bar_text, systems::start_game).run_if(is_game_not_active))
+    .add_systems(Update, systems::toggle_pause.run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))))
+    .add_systems(Update, systems::move_background.run_if(in_state(GameState::Playing)))
+    .add_systems(Update, systems::move_ground.run_if(in_state(GameState::Playing)))
+    .add_systems(Update, systems::animate_bird.run_if(in_state(GameState::Playing)))
+    .add_systems(Update, systems::rotate_bird.run_if(in_state(GameState::Playing)))
+    .add_systems(Update, systems::read_jump_input.run_if(in_state(GameState::Playing)))
+    .add_systems(Update, systems::render_score.run_if(in_state(GameState::Playing)))
+    .add_systems(Update, systems::play_sounds)
+
+    // While the AI is trained
+    .add_systems(Startup, setup::spawn_training_text.run_if(is_training))
+    .add_systems(Update, (systems::render_training_text, systems::export_brain).run_if(is_training))
+
+    // The FlappyCorePlugin sets up the states, and the runs of the bird
+    .add_plugins((MyPlugin, FlappyCorePlugin { seed, ..default() }, ScoreboardPlugin))
+    .enable_state_scoped_entities::<GameState>();
+
+    app
 }
 
-fn is_game_active(game: Res<Game>) -> bool {
-    game.state == GameState::Active
+// The title screen and the game over screen both wait for the space bar
+fn is_game_not_active(state: Res<State<GameState>>) -> bool {
+    matches!(state.get(), GameState::Menu | GameState::GameOver)
+}
+
+// A generation follows the other, without game over screen
+fn is_training(population: Option<Res<Population>>) -> bool {
+    population.is_some()
+}
+
+fn parse_command(args: Vec<String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+
+    match args.next().as_deref() {
+        None => Ok(Command::Play),
+        Some("daily") => Ok(Command::Daily),
+        Some("help" | "--help" | "-h") => Ok(Command::Help),
+        Some("train") => {
+            let mut headless = false;
+            let mut generations = None;
+            let mut population_size = DEFAULT_POPULATION_SIZE;
+            let mut seed = None;
+            let mut brain_file = PathBuf::from(DEFAULT_BRAIN_FILE);
+
+            while let Some(arg) = args.next() {
+                let mut value = || args.next().ok_or(format!("Missing value of {arg}"));
+
+                match arg.as_str() {
+                    "--headless" => headless = true,
+                    "--generations" => generations = Some(parse_number(&value()?)?),
+                    "--population" => population_size = parse_number(&value()?)?,
+                    "--seed" => seed = Some(parse_number(&value()?)?),
+                    "--file" => brain_file = PathBuf::from(value()?),
+                    _ => return Err(format!("Unknown option {arg}")),
+                }
+            }
+
+            if population_size == 0 {
+                return Err("The population needs at least a bird".to_string());
+            }
+
+            Ok(Command::Train {
+                headless,
+                generations,
+                population_size,
+                seed,
+                brain_file,
+            })
+        }
+        Some("replay") => Ok(Command::Replay(PathBuf::from(
+            args.next().as_deref().unwrap_or(DEFAULT_BRAIN_FILE),
+        ))),
+        Some(command) => Err(format!("Unknown command {command}")),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid number {value}"))
 }
- 
-fn is_game_not_active(game: Res<Game>) -> bool {
-    game.state != GameState::Active
-}
\ No newline at end of file
@@ -0,0 +1,158 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    components::Oscillation,
+    constants::{GRAVITY, JUMP_VELOCITY, PIPE_SPEED},
+};
+
+// The gap gets smaller, and the pipes closer, by this much per point, down to the minimum
+const START_GAP: f32 = 130.;
+const MIN_GAP: f32 = 100.;
+const GAP_SHRINK: f32 = 1.5;
+const START_SPACING: f32 = 200.;
+const MIN_SPACING: f32 = 160.;
+const SPACING_TIGHTENING: f32 = 2.;
+
+// Range of the middle of the gaps
+const MIN_GAP_Y: f32 = -55.;
+const MAX_GAP_Y: f32 = 155.;
+
+// From this score, some pipes go up and down; more and more of them, up to the maximum chance
+const MOVING_PIPES_SCORE: u32 = 10;
+const MOVING_PIPES_CHANCE: f64 = 0.2;
+const MOVING_PIPES_CHANCE_PER_POINT: f64 = 0.03;
+const MAX_MOVING_PIPES_CHANCE: f64 = 0.5;
+const MIN_AMPLITUDE: f32 = 15.;
+const MAX_AMPLITUDE: f32 = 35.;
+
+// Half of a pipe, which is 320 high; the pipes are 52 wide, and the bird 34 by 24
+const PIPE_HALF_HEIGHT: f32 = 160.;
+const PIPE_WIDTH: f32 = 52.;
+const BIRD_WIDTH: f32 = 34.;
+const BIRD_HEIGHT: f32 = 24.;
+
+// Share of the height that the bird could reach between two gaps that is actually used, as the
+// player doesn't flap perfectly
+const REACH_MARGIN: f32 = 0.8;
+
+// Heights of the pipes, with a gap and a spacing depending on the score, so that the game gets
+// harder; consecutive gaps are never further apart than the bird can fly between them. The
+// generator is seeded, so that a run can be replayed, e.g. for the daily challenge: with a seed,
+// every run has the same pipes
+#[derive(Resource)]
+pub struct PipeGenerator {
+    seed: Option<u64>,
+    rng: StdRng,
+    // Middle of the last gap, with its amplitude, if it moves
+    last_gap: Option<(f32, f32)>,
+}
+
+impl PipeGenerator {
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            rng: Self::rng(seed),
+            last_gap: None,
+        }
+    }
+
+    fn rng(seed: Option<u64>) -> StdRng {
+        match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+
+    // Everyone plays the same pipes on a given day
+    pub fn daily_seed() -> u64 {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        since_epoch.as_secs() / (24 * 60 * 60)
+    }
+
+    // The first gap of a run can be anywhere; a seeded generator starts the sequence again
+    pub fn start_run(&mut self) {
+        if self.seed.is_some() {
+            self.rng = Self::rng(self.seed);
+        }
+        self.last_gap = None;
+    }
+
+    pub fn gap(score: u32) -> f32 {
+        (START_GAP - GAP_SHRINK * score as f32).max(MIN_GAP)
+    }
+
+    // Horizontal distance between the middles of two pipes
+    pub fn spacing(score: u32) -> f32 {
+        (START_SPACING - SPACING_TIGHTENING * score as f32).max(MIN_SPACING)
+    }
+
+    // Highest the bird can climb, and lowest it can drop, from a gap to the next one
+    pub fn reach(score: u32) -> (f32, f32) {
+        // Time to fly from a pipe to the next, between which the bird can go up or down
+        let time = (Self::spacing(score) - PIPE_WIDTH - BIRD_WIDTH) / PIPE_SPEED;
+        // Flapping at the top of each jump, the bird climbs at half the jump velocity
+        let climb = JUMP_VELOCITY / 2. * time;
+        let drop = GRAVITY / 2. * time * time;
+        // The bird can leave a gap at its top, or bottom, and enter the next one at its bottom, or
+        // top
+        let gap_room = Self::gap(score) - BIRD_HEIGHT;
+
+        (
+            (climb + gap_room) * REACH_MARGIN,
+            (drop + gap_room) * REACH_MARGIN,
+        )
+    }
+
+    // The lower and the upper pipes of the next pair
+    pub fn next_pair(&mut self, score: u32) -> (Oscillation, Oscillation) {
+        let amplitude =
+            if score >= MOVING_PIPES_SCORE && self.rng.gen_bool(Self::moving_chance(score)) {
+                self.rng.gen_range(MIN_AMPLITUDE..MAX_AMPLITUDE)
+            } else {
+                0.
+            };
+
+        // A moving gap must be reachable wherever it is
+        let (mut min_y, mut max_y) = (MIN_GAP_Y + amplitude, MAX_GAP_Y - amplitude);
+        if let Some((last_y, last_amplitude)) = self.last_gap {
+            let (climb, drop) = Self::reach(score);
+            let amplitudes = last_amplitude + amplitude;
+
+            min_y = min_y.max(last_y - drop + amplitudes);
+            max_y = max_y.min(last_y + climb - amplitudes);
+        }
+
+        // The range is empty only if the amplitudes were larger than the reach
+        let gap_y = if min_y < max_y {
+            self.rng.gen_range(min_y..max_y)
+        } else {
+            (min_y + max_y) / 2.
+        };
+        self.last_gap = Some((gap_y, amplitude));
+
+        let phase = self.rng.gen_range(0.0..std::f32::consts::TAU);
+        let half_gap = Self::gap(score) / 2.;
+        let pipe = |base_y| Oscillation {
+            base_y,
+            amplitude,
+            phase,
+        };
+
+        (
+            pipe(gap_y - half_gap - PIPE_HALF_HEIGHT),
+            pipe(gap_y + half_gap + PIPE_HALF_HEIGHT),
+        )
+    }
+
+    fn moving_chance(score: u32) -> f64 {
+        let points = (score - MOVING_PIPES_SCORE) as f64;
+
+        (MOVING_PIPES_CHANCE + MOVING_PIPES_CHANCE_PER_POINT * points).min(MAX_MOVING_PIPES_CHANCE)
+    }
+}
@@ -3,19 +3,26 @@ use bevy::prelude::*;
 #[derive(Resource, Default)]
 pub struct Game {
     pub score: u32,
-    pub state: GameState,
 }
- 
 
-#[derive(Debug, PartialEq)]
-pub enum GameState {
-    Active,
-    Inactive,
-    GameOver,
+// Best score of the player, across runs; the scoreboard saves it
+#[derive(Resource, Default)]
+pub struct BestScore {
+    pub score: u32,
+    // Set at the end of a run that beat the best score
+    pub beaten: bool,
+}
+
+// The digits of the numbers image, used for the scores
+#[derive(Resource)]
+pub struct NumberSprites {
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
 }
 
-impl Default for GameState {
-    fn default() -> Self {
-        GameState::Inactive
-    }
-}
\ No newline at end of file
+// Set when the jump key is pressed, and consumed by the next physics tick; the key is read every
+// frame, while there may be no tick in a given frame
+#[derive(Resource, Default)]
+pub struct JumpInput {
+    pub requested: bool,
+}
@@ -0,0 +1,280 @@
+use std::{fs, io, path::PathBuf};
+
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+
+use crate::{
+    ai::Population,
+    resources::{BestScore, Game, NumberSprites},
+    states::GameState,
+    systems,
+};
+
+const BEST_SCORE_FILE: &str = "best_score";
+
+// On game over, the text goes above the panel, and the prompt to play again below it; they're
+// drawn over the panel, which slides up across the prompt
+pub const GAME_OVER_TEXT_Y: f32 = 125.;
+pub const GAME_OVER_PROMPT_Y: f32 = -145.;
+pub const GAME_OVER_TEXT_Z: f32 = 4.;
+const GAME_OVER_TEXT_SIZE: Vec2 = Vec2::new(192., 42.);
+const PROMPT_SIZE: Vec2 = Vec2::new(184., 30.);
+
+// The panel slides up from below the screen, in this many seconds
+const PANEL_START_Y: f32 = -400.;
+const PANEL_Y: f32 = -20.;
+const PANEL_SLIDE_SECONDS: f32 = 0.6;
+const PANEL_SIZE: Vec2 = Vec2::new(240., 130.);
+const PANEL_BORDER: f32 = 4.;
+const PANEL_COLOR: Color = Color::srgb(0.87, 0.85, 0.59);
+const BORDER_COLOR: Color = Color::srgb(0.33, 0.22, 0.27);
+const LABEL_COLOR: Color = Color::srgb(0.91, 0.38, 0.27);
+
+// The digits of the scores on the panel are smaller than those of the score during the run
+const DIGIT_SCALE: f32 = 0.75;
+const DIGIT_SPACING: f32 = 20.;
+const DIGITS_RIGHT_X: f32 = 100.;
+
+const MEDAL_RADIUS: f32 = 26.;
+const NO_MEDAL_COLOR: Color = Color::srgb(0.78, 0.74, 0.45);
+const BADGE_PULSE_SPEED: f32 = 6.;
+
+// Areas of the game over text, of the panel once in place, and of the prompt, from top to bottom
+pub fn game_over_layout() -> [Rect; 3] {
+    [
+        Rect::from_center_size(Vec2::new(0., GAME_OVER_TEXT_Y), GAME_OVER_TEXT_SIZE),
+        Rect::from_center_size(Vec2::new(0., PANEL_Y), PANEL_SIZE + PANEL_BORDER * 2.),
+        Rect::from_center_size(Vec2::new(0., GAME_OVER_PROMPT_Y), PROMPT_SIZE),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+}
+
+impl Medal {
+    // Lowest score of each medal
+    const THRESHOLDS: [(u32, Medal); 4] = [
+        (40, Medal::Platinum),
+        (30, Medal::Gold),
+        (20, Medal::Silver),
+        (10, Medal::Bronze),
+    ];
+
+    pub fn for_score(score: u32) -> Option<Medal> {
+        Self::THRESHOLDS
+            .iter()
+            .find(|(threshold, _)| score >= *threshold)
+            .map(|(_, medal)| *medal)
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Medal::Bronze => Color::srgb(0.80, 0.50, 0.20),
+            Medal::Silver => Color::srgb(0.75, 0.75, 0.78),
+            Medal::Gold => Color::srgb(1.00, 0.84, 0.00),
+            Medal::Platinum => Color::srgb(0.90, 0.96, 1.00),
+        }
+    }
+}
+
+// The best score is saved in the data directory of the user, e.g. ~/.local/share on Linux
+fn best_score_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("rust-flappy-bird").join(BEST_SCORE_FILE))
+}
+
+pub fn load_best_score() -> u32 {
+    best_score_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn save_best_score(score: u32) -> io::Result<()> {
+    let path = best_score_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, format!("{score}\n"))
+}
+
+// The game over panel, with the score, the best score and the medal; there is none while the AI
+// is trained
+pub struct ScoreboardPlugin;
+
+impl Plugin for ScoreboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(BestScore {
+            score: load_best_score(),
+            beaten: false,
+        })
+        .add_systems(
+            OnEnter(GameState::GameOver),
+            (save_new_best_score, spawn_scoreboard)
+                .after(systems::update_best_score)
+                .run_if(not(resource_exists::<Population>)),
+        )
+        .add_systems(
+            Update,
+            (slide_scoreboard, pulse_new_best_badge).run_if(in_state(GameState::GameOver)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Scoreboard(Timer);
+
+#[derive(Component)]
+pub struct NewBestBadge;
+
+fn save_new_best_score(best_score: Res<BestScore>) {
+    if best_score.beaten {
+        if let Err(error) = save_best_score(best_score.score) {
+            eprintln!("Can't save the best score: {error}");
+        }
+    }
+}
+
+fn spawn_scoreboard(
+    mut commands: Commands,
+    game: Res<Game>,
+    best_score: Res<BestScore>,
+    number_sprites: Res<NumberSprites>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let medal_color = Medal::for_score(game.score).map_or(NO_MEDAL_COLOR, Medal::color);
+    let label = |text: &str, x: f32, y: f32| Text2dBundle {
+        text: Text::from_section(
+            text,
+            TextStyle {
+                font_size: 18.,
+                color: LABEL_COLOR,
+                ..default()
+            },
+        ),
+        transform: Transform::from_xyz(x, y, 0.2),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: BORDER_COLOR,
+                    custom_size: Some(PANEL_SIZE + PANEL_BORDER * 2.),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0., PANEL_START_Y, 3.),
+                ..default()
+            },
+            Scoreboard(Timer::from_seconds(PANEL_SLIDE_SECONDS, TimerMode::Once)),
+            StateScoped(GameState::GameOver),
+        ))
+        .with_children(|panel| {
+            panel.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: PANEL_COLOR,
+                    custom_size: Some(PANEL_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0., 0., 0.1),
+                ..default()
+            });
+
+            panel.spawn(label("MEDAL", -70., 42.));
+            panel.spawn(MaterialMesh2dBundle {
+                mesh: Mesh2dHandle(meshes.add(Circle::new(MEDAL_RADIUS))),
+                material: materials.add(medal_color),
+                transform: Transform::from_xyz(-70., -8., 0.2),
+                ..default()
+            });
+
+            panel.spawn(label("SCORE", 60., 48.));
+            spawn_panel_number(panel, &number_sprites, game.score, 22.);
+            panel.spawn(label("BEST", 60., -12.));
+            spawn_panel_number(panel, &number_sprites, best_score.score, -38.);
+
+            if best_score.beaten {
+                panel
+                    .spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: Color::srgb(0.85, 0.15, 0.15),
+                                custom_size: Some(Vec2::new(44., 18.)),
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(8., -12., 0.3),
+                            ..default()
+                        },
+                        NewBestBadge,
+                    ))
+                    .with_children(|badge| {
+                        badge.spawn(Text2dBundle {
+                            text: Text::from_section(
+                                "NEW",
+                                TextStyle {
+                                    font_size: 14.,
+                                    color: Color::WHITE,
+                                    ..default()
+                                },
+                            ),
+                            transform: Transform::from_xyz(0., 0., 0.1),
+                            ..default()
+                        });
+                    });
+            }
+        });
+}
+
+// Right aligned, with as many digits as needed
+fn spawn_panel_number(
+    panel: &mut ChildBuilder,
+    number_sprites: &NumberSprites,
+    number: u32,
+    y: f32,
+) {
+    let number_digits = systems::digits(number, 1);
+
+    for (position, digit) in number_digits.iter().enumerate() {
+        let from_right = (number_digits.len() - 1 - position) as f32;
+        let x = DIGITS_RIGHT_X - from_right * DIGIT_SPACING;
+
+        panel.spawn((
+            SpriteBundle {
+                texture: number_sprites.texture.clone(),
+                transform: Transform::from_xyz(x, y, 0.2).with_scale(Vec3::splat(DIGIT_SCALE)),
+                ..default()
+            },
+            TextureAtlas {
+                index: *digit,
+                layout: number_sprites.layout.clone(),
+            },
+        ));
+    }
+}
+
+// Eases out, slowing down as it reaches its place
+fn slide_scoreboard(time: Res<Time>, mut query: Query<(&mut Scoreboard, &mut Transform)>) {
+    for (mut scoreboard, mut transform) in query.iter_mut() {
+        scoreboard.0.tick(time.delta());
+
+        let progress = 1. - (1. - scoreboard.0.fraction()).powi(3);
+        transform.translation.y = PANEL_START_Y + (PANEL_Y - PANEL_START_Y) * progress;
+    }
+}
+
+fn pulse_new_best_badge(time: Res<Time>, mut query: Query<&mut Transform, With<NewBestBadge>>) {
+    for mut transform in query.iter_mut() {
+        let scale = 1. + 0.15 * (time.elapsed_seconds() * BADGE_PULSE_SPEED).sin();
+        transform.scale = Vec3::splat(scale);
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::{components::*, constants::{BACKGROUNG_IMAGE_WIDTH, WINDOW_HEIGHT, WINDOW_WIDTH}, utils::random_pipe_position};
+use crate::{components::*, constants::{BACKGROUNG_IMAGE_WIDTH, PIPE_PAIRS, WINDOW_HEIGHT, WINDOW_WIDTH}, pipe_generator::PipeGenerator, resources::{Game, JumpInput, NumberSprites}, scoreboard::{GAME_OVER_PROMPT_Y, GAME_OVER_TEXT_Y, GAME_OVER_TEXT_Z}, states::GameState};
  
 pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,) {
     // Spawn a 2D camera
@@ -49,109 +49,208 @@ pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut texture
         Ground,
     ));
 
-        // Game Over Text
-        commands.spawn((
-            SpriteBundle {
-                texture: asset_server.load("texture/game-over.png"),
-                transform: Transform::from_xyz(0., 0., 1.),
-                visibility: Visibility::Hidden,
-                ..default()
-            },
-            GameOverText,
-        ));
-
-
-        // Press Space Bar Text
-        commands.spawn((
-            SpriteBundle {
-                texture: asset_server.load("texture/space.png"),
-                transform: Transform::from_xyz(0.0, -50.0, 1.0),
-                ..default()
-            },
-            PressSpaceBarText(Timer::from_seconds(0.5, TimerMode::Repeating)),
-        ));
-
         // Score Text
         {
             let number_layout: TextureAtlasLayout =
                 TextureAtlasLayout::from_grid(UVec2::new(24, 36), 1, 10, None, None);
-            let number_texture_atlas_layout: Handle<TextureAtlasLayout> = texture_atlas_layouts.add(number_layout);
+            let number_sprites = NumberSprites {
+                texture: asset_server.load("texture/numbers.png"),
+                layout: texture_atlas_layouts.add(number_layout),
+            };
         
-            // We spawn 3 numbers to represent a maximum score of 999
+            // We spawn 3 numbers, and render_score adds more past 999
             for i in 0..3 {
-                let x_starting_point = -350. + (i as f32 * (24. + 2.)); // 24 is the width + 2 is the space between the numbers
-         
-                commands.spawn((
-                    SpriteBundle {
-                        texture: asset_server.load("texture/numbers.png"),
-                        transform: Transform::from_xyz(x_starting_point, 200., 1.),
-                        ..default()
-                    },
-                    TextureAtlas {
-                        index: 0,
-                        layout: number_texture_atlas_layout.clone(),
-                    },
-                    ScoreText,
-                ));
+                spawn_score_digit(&mut commands, &number_sprites, i, 0);
             }
+
+            commands.insert_resource(number_sprites);
         }
+}
+
+pub fn spawn_score_digit(commands: &mut Commands, number_sprites: &NumberSprites, position: usize, digit: usize) {
+    let x_starting_point = -350. + (position as f32 * (24. + 2.)); // 24 is the width + 2 is the space between the numbers
 
-            // Spawn the bird
     commands.spawn((
         SpriteBundle {
-            texture: asset_server.load("texture/bird.png"),
-            transform: Transform::from_xyz(0., 0., 2.),
+            texture: number_sprites.texture.clone(),
+            transform: Transform::from_xyz(x_starting_point, 200., 1.),
             ..default()
         },
         TextureAtlas {
-            index: 1,
-            layout: texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
-                UVec2::new(34, 24),
-                3,
-                1,
-                None,
-                None,
-            )),
+            index: digit,
+            layout: number_sprites.layout.clone(),
         },
+        ScoreText(position),
+    ));
+}
+
+// Spawns the bird and the pipes of a new run, and resets the score; they have no sprite, so that
+// this also runs headless (see FlappyCorePlugin), and the sprites are added by add_bird_sprite and
+// add_pipe_sprites
+pub fn spawn_run(
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut jump_input: ResMut<JumpInput>,
+    mut pipe_generator: ResMut<PipeGenerator>,
+) {
+    game.score = 0;
+    jump_input.requested = false;
+
+    // Spawn the bird
+    commands.spawn((
         Bird {
             timer: Timer::from_seconds(0.2, TimerMode::Repeating),
             velocity: 0.,
         },
+        Position::new(0., 0.),
+        Player,
     ));
 
     // Spawn the pipes
-    for i in 0..5 {
-        let delta_x = i as f32 * 200.;
-        let (lower_y, upper_y) = random_pipe_position();
-        let mut transform = Transform::from_xyz(350. + delta_x, lower_y, 0.5);
-     
-        // Spawn Lower Pipe
-        commands.spawn((
-            SpriteBundle {
-                texture: asset_server.load("texture/pipe.png"),
-                transform,
-                ..default()
-            },
-            LowerPipe,
-        ));
-     
-        // Rotating the upper pipe
-        transform.rotate(Quat::from_rotation_z(std::f32::consts::PI));
-        // Changing the y position of the upper pipe
-        transform.translation.y = upper_y;
-     
-        // Spawn Upper Pipe
-        commands.spawn((
+    pipe_generator.start_run();
+    for i in 0..PIPE_PAIRS {
+        let x = 350. + i as f32 * PipeGenerator::spacing(0);
+        let (lower, upper) = pipe_generator.next_pair(0);
+
+        commands.spawn((LowerPipe, Position::new(x, lower.y()), lower));
+        commands.spawn((UpperPipe { passed: false }, Position::new(x, upper.y()), upper));
+    }
+}
+
+pub fn add_bird_sprite(
+    mut commands: Commands,
+    query: Query<(Entity, &Position), Added<Bird>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    for (entity, position) in query.iter() {
+        commands.entity(entity).insert((
             SpriteBundle {
-                texture: asset_server.load("texture/pipe.png"),
-                transform,
+                texture: asset_server.load("texture/bird.png"),
+                transform: Transform::from_xyz(position.current.x, position.current.y, 2.),
                 ..default()
             },
-            UpperPipe {
-                passed: false,
+            TextureAtlas {
+                index: 1,
+                layout: texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+                    UVec2::new(34, 24),
+                    3,
+                    1,
+                    None,
+                    None,
+                )),
             },
         ));
     }
-    
+}
+
+pub fn add_pipe_sprites(
+    mut commands: Commands,
+    lower_pipe_query: Query<(Entity, &Position), Added<LowerPipe>>,
+    upper_pipe_query: Query<(Entity, &Position), Added<UpperPipe>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, position) in lower_pipe_query.iter() {
+        commands.entity(entity).insert(SpriteBundle {
+            texture: asset_server.load("texture/pipe.png"),
+            transform: Transform::from_xyz(position.current.x, position.current.y, 0.5),
+            ..default()
+        });
+    }
+
+    for (entity, position) in upper_pipe_query.iter() {
+        // The upper pipe is the same image, upside down
+        let transform = Transform::from_xyz(position.current.x, position.current.y, 0.5)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::PI));
+
+        commands.entity(entity).insert(SpriteBundle {
+            texture: asset_server.load("texture/pipe.png"),
+            transform,
+            ..default()
+        });
+    }
+}
+
+type RunEntity = Or<(With<Bird>, AnyPipe)>;
+
+// The bird and the pipes live across the Playing and Paused states, so they can't be state scoped;
+// they are despawned when a new run starts after a game over
+pub fn despawn_run(mut commands: Commands, query: Query<Entity, RunEntity>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Shown, blinking, on the title screen and on the game over screen, below the scoreboard
+pub fn spawn_press_space_bar_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<State<GameState>>,
+) {
+    let (y, z) = match state.get() {
+        GameState::GameOver => (GAME_OVER_PROMPT_Y, GAME_OVER_TEXT_Z),
+        _ => (-50.0, 1.0),
+    };
+
+    commands.spawn((
+        SpriteBundle {
+            texture: asset_server.load("texture/space.png"),
+            transform: Transform::from_xyz(0.0, y, z),
+            ..default()
+        },
+        PressSpaceBarText(Timer::from_seconds(0.5, TimerMode::Repeating)),
+        StateScoped(*state.get()),
+    ));
+}
+
+pub fn spawn_game_over_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        SpriteBundle {
+            texture: asset_server.load("texture/game-over.png"),
+            transform: Transform::from_xyz(0., GAME_OVER_TEXT_Y, GAME_OVER_TEXT_Z),
+            ..default()
+        },
+        GameOverText,
+        StateScoped(GameState::GameOver),
+    ));
+}
+
+// There is no image for the pause text, so it uses the default font
+pub fn spawn_paused_text(mut commands: Commands) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "PAUSED",
+                TextStyle {
+                    font_size: 48.,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_xyz(0., 0., 3.),
+            ..default()
+        },
+        PausedText,
+        StateScoped(GameState::Paused),
+    ));
+}
 
-}
\ No newline at end of file
+// Generation, best score and fitness, at the top right, while the AI is trained
+pub fn spawn_training_text(mut commands: Commands) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            text_anchor: bevy::sprite::Anchor::TopRight,
+            transform: Transform::from_xyz(WINDOW_WIDTH / 2. - 20., WINDOW_HEIGHT / 2. - 20., 3.),
+            ..default()
+        },
+        TrainingText,
+    ));
+}
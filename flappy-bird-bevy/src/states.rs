@@ -0,0 +1,12 @@
+use bevy::prelude::*;
+
+// The screens of the game; the entities that belong to a single screen are spawned with
+// StateScoped, so that Bevy despawns them when the screen is left
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameState {
+    #[default]
+    Menu,
+    Playing,
+    Paused,
+    GameOver,
+}
@@ -1,11 +1,22 @@
 use bevy::prelude::*;
 
-use crate::{components::*, constants::*, resources::*, utils::random_pipe_position};
+use crate::{
+    ai::{self, BrainFile, Population},
+    components::*,
+    constants::*,
+    events::{Flapped, PipePassed},
+    resources::*,
+    pipe_generator::PipeGenerator,
+    setup,
+    states::GameState,
+};
 
 // simple system that makes the Press Space Bar text blink
 pub fn blink_space_bar_text(time: Res<Time>, mut query: Query<(&mut PressSpaceBarText, &mut Visibility)>,) {
 
-    let (mut space, mut visibility) = query.single_mut();
+    let Ok((mut space, mut visibility)) = query.get_single_mut() else {
+        return;
+    };
  
     let timer = &mut space.0;
     timer.tick(time.delta());
@@ -66,87 +77,55 @@ pub fn animate_bird(time: Res<Time>, mut query: Query<(&mut Bird, &mut TextureAt
 
 // simple system that starts the game when the space bar is pressed
 //
-// Using the Without<PressSpaceBarText> in the game_over_query is critical, 
-// because in the previous query we have queried for PressSpaceBarText and Visibility, 
-// so we need to exclude the PressSpaceBarText from this query. 
-// If we don't use the Without<PrestSpaceBarText> to exclude the PressSpaceBarText from this query, 
-// Bevy will panic, if you want to learn more about this error, read https://bevyengine.org/learn/errors/b0001/
+// Hiding the texts and resetting the bird and the pipes is left to the OnExit/OnEnter systems
 pub fn start_game(
-    mut game: ResMut<Game>,
-    mut space_query: Query<(&mut PressSpaceBarText, &mut Visibility)>,
-    mut game_over_query: Query<&mut Visibility, (With<GameOverText>, Without<PressSpaceBarText>)>,
-    mut bird_query: Query<(&mut Bird, &mut Transform)>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut upper_pipe_query: Query<(&mut Transform, &mut UpperPipe), (With<UpperPipe>, Without<Bird>)>,
-    mut lower_pipe_query: Query<
-        &mut Transform,
-        (With<LowerPipe>, Without<Bird>, Without<UpperPipe>),
-    >,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        next_state.set(GameState::Playing);
+    }
+}
+
+// pauses and resumes the game with the P or Escape key
+pub fn toggle_pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
-    if !keyboard_input.just_pressed(KeyCode::Space) {
+    if !keyboard_input.any_just_pressed([KeyCode::KeyP, KeyCode::Escape]) {
         return;
     }
- 
-    // Reset the pipes
-    if game.state == GameState::GameOver {
-        for (i, (mut transform, mut upper_pipe)) in upper_pipe_query.iter_mut().enumerate() {
-            let delta_x = i as f32 * 200.0 + 200.;
- 
-            upper_pipe.passed = false;
-            transform.translation.x = 0.;
-            transform.translation.x += delta_x;
-        }
- 
-        for (i, mut transform) in lower_pipe_query.iter_mut().enumerate() {
-            let delta_x = i as f32 * 200.0 + 200.;
- 
-            transform.translation.x = 0.;
-            transform.translation.x += delta_x;
-        }
-    };
 
-    game.state = GameState::Active;
- 
-    // Reset the bird position
-    for (mut bird, mut transform) in bird_query.iter_mut() {
-        bird.velocity = 0.0;
-        transform.translation.y = 0.0;
-        transform.rotation = Quat::from_rotation_z(0.0);
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => {}
     }
+}
 
-    // Hiding the PressSpaceBarText
-    let (mut space, mut visibility) = space_query.single_mut();
-    space.0.reset();
-    *visibility = Visibility::Hidden;
- 
-    // Hiding the GameOverText
-    let mut game_over_visibility = game_over_query.single_mut();
-    *game_over_visibility = Visibility::Hidden;
+pub fn play_hit_sound(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(AudioBundle {
+        source: asset_server.load("audio/hit.ogg"),
+        settings: PlaybackSettings::DESPAWN,
+    });
 }
 
 // simple system that makes the bird fall down
 pub fn gravity(
-    time: Res<Time>,
-    mut game: ResMut<Game>,
-    mut query: Query<(&mut Bird, &mut Transform)>,
-    mut game_over_query: Query<&mut Visibility, With<GameOverText>>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Bird, &mut Position), Without<Crashed>>,
 ) {
-    for (mut bird, mut transform) in query.iter_mut() {
+    for (entity, mut bird, mut position) in query.iter_mut() {
+        // In FixedUpdate, this is the fixed time step
         let delta = time.delta().as_secs_f32();
-        let gravity = 9.8;
 
-        let delta_v = gravity * 150. * delta;
+        let delta_v = GRAVITY * delta;
         let delta_y = bird.velocity * delta;
         
         bird.velocity -= delta_v;
-        transform.translation.y = (transform.translation.y + delta_y).min(260.0);
- 
-        // Rotate the bird
-        let rotation = bird.velocity / 600.0;
-        let max_rotation = 0.5;
-        transform.rotation = Quat::from_rotation_z(rotation.max(-max_rotation).min(max_rotation));
+        position.current.y = (position.current.y + delta_y).min(260.0);
  
         // Check if the bird hits the ground
         let ground_y = -250.0;
@@ -155,98 +134,122 @@ pub fn gravity(
  
         let collision_point = ground_y + ground_height / 2.0 + bird_height / 2.0;
  
-        if transform.translation.y < collision_point {
-            transform.translation.y = collision_point;
+        if position.current.y < collision_point {
+            position.current.y = collision_point;
             bird.velocity = 0.0;
  
-            game.state = GameState::GameOver;
-            *game_over_query.single_mut() = Visibility::Visible;
- 
-            // play game over sound
-            commands.spawn(AudioBundle {
-                source: asset_server.load("audio/hit.ogg"),
-                settings: PlaybackSettings::DESPAWN,
-                ..default()
-            });
+            commands.entity(entity).insert(Crashed);
         }
     }
 }
 
-// simple system that makes the bird jump
-pub fn jump(
-    mut query: Query<&mut Bird>,
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
+// Reads the jump key every frame, for the next physics tick
+pub fn read_jump_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut jump_input: ResMut<JumpInput>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        jump_input.requested = true;
+    }
+}
+
+// simple system that makes the bird of the player jump
+pub fn jump(
+    mut query: Query<&mut Bird, (With<Player>, Without<Crashed>)>,
+    mut jump_input: ResMut<JumpInput>,
+    mut flapped_events: EventWriter<Flapped>,
 ) {
-    if !keyboard_input.just_pressed(KeyCode::Space) {
+    if !std::mem::take(&mut jump_input.requested) {
         return;
     }
  
-    commands.spawn(AudioBundle {
-        source: asset_server.load("audio/wing.ogg"),
-        settings: PlaybackSettings::DESPAWN,
-        ..default()
-    });
- 
     for mut bird in query.iter_mut() {
-        bird.velocity = 400.0;
+        bird.velocity = JUMP_VELOCITY;
+        flapped_events.send(Flapped);
     }
 }
 
-// simple system that moves the pipes to the left and check for bird collision
-pub fn pipes(
+// Rotate the bird, depending on its velocity; only the looks depend on this
+pub fn rotate_bird(mut query: Query<(&Bird, &mut Transform)>) {
+    for (bird, mut transform) in query.iter_mut() {
+        let rotation = bird.velocity / 600.0;
+        let max_rotation = 0.5;
+        transform.rotation = Quat::from_rotation_z(rotation.max(-max_rotation).min(max_rotation));
+    }
+}
+
+// Disjoint from the upper pipes, which are queried mutably at the same time
+type LowerPipeOnly = (With<LowerPipe>, Without<UpperPipe>);
+
+// simple system that moves the pipes to the left, and the moving pipes up and down
+pub fn move_pipes(
     time: Res<Time>,
-    mut upper_pipe_query: Query<(&mut UpperPipe, &mut Transform)>,
-    mut lower_pipe_query: Query<(&LowerPipe, &mut Transform), Without<UpperPipe>>,
-    mut bird_query: Query<&Transform, (With<Bird>, Without<LowerPipe>, Without<UpperPipe>)>,
-    mut game_over_query: Query<&mut Visibility, With<GameOverText>>,
-    asset_server: Res<AssetServer>,
-    mut game: ResMut<Game>,
-    mut commands: Commands,
+    game: Res<Game>,
+    mut upper_pipe_query: Query<(&mut UpperPipe, &mut Position, &mut Oscillation)>,
+    mut lower_pipe_query: Query<(&mut Position, &mut Oscillation), LowerPipeOnly>,
+    mut pipe_generator: ResMut<PipeGenerator>,
 ) {
     let delta = time.delta().as_secs_f32();
-    let delta_x = 150. * delta;
+    let delta_x = PIPE_SPEED * delta;
  
     let utmost_right_pipe = upper_pipe_query
         .iter()
-        .max_by(|(_, a), (_, b)| a.translation.x.partial_cmp(&b.translation.x).unwrap())
-        .unwrap()
-        .1
-        .translation
-        .x;
+        .map(|(_, position, _)| position.current.x)
+        .fold(f32::MIN, f32::max);
  
-    let new_pipe_position = utmost_right_pipe + 200.0;
-    let (lower_y, upper_y) = random_pipe_position();
+    let new_pipe_position = utmost_right_pipe + PipeGenerator::spacing(game.score);
     let out_of_screen_x = (-WINDOW_WIDTH / 2.) - 26.;
+
+    // Both pipes of a pair leave the screen at the same tick, and come back with the same gap
+    let leaving = upper_pipe_query
+        .iter()
+        .any(|(_, position, _)| position.current.x - delta_x < out_of_screen_x);
+    let (lower, upper) = if leaving {
+        pipe_generator.next_pair(game.score)
+    } else {
+        Default::default()
+    };
  
-    for (mut upper_pipe, mut transform) in upper_pipe_query.iter_mut() {
-        transform.translation.x -= delta_x;
+    for (mut upper_pipe, mut position, mut oscillation) in upper_pipe_query.iter_mut() {
+        position.current.x -= delta_x;
+        oscillation.phase += OSCILLATION_SPEED * delta;
+        position.current.y = oscillation.y();
  
-        if transform.translation.x < out_of_screen_x {
-            transform.translation.x = new_pipe_position;
-            transform.translation.y = upper_y;
+        if position.current.x < out_of_screen_x {
+            *oscillation = upper;
+            position.teleport(Vec2::new(new_pipe_position, upper.y()));
             upper_pipe.passed = false;
         }
     }
  
-    for (_, mut transform) in lower_pipe_query.iter_mut() {
-        transform.translation.x -= delta_x;
+    for (mut position, mut oscillation) in lower_pipe_query.iter_mut() {
+        position.current.x -= delta_x;
+        oscillation.phase += OSCILLATION_SPEED * delta;
+        position.current.y = oscillation.y();
  
-        if transform.translation.x < out_of_screen_x {
-            transform.translation.x = new_pipe_position;
-            transform.translation.y = lower_y;
+        if position.current.x < out_of_screen_x {
+            *oscillation = lower;
+            position.teleport(Vec2::new(new_pipe_position, lower.y()));
         }
     }
+}
  
-    let is_collision = |bird_transform: &Transform, pipe_transform: &Transform| -> bool {
-        let bird_x = bird_transform.translation.x;
-        let bird_y = bird_transform.translation.y;
+// simple system that checks for bird collision with the pipes
+//
+// The pipes move by a few units per tick, so the bird can't go through one between two ticks
+pub fn check_collisions(
+    mut commands: Commands,
+    bird_query: Query<(Entity, &Position), FlyingBird>,
+    pipe_query: Query<&Position, AnyPipe>,
+) {
+    let is_collision = |bird_position: &Position, pipe_position: &Position| -> bool {
+        let bird_x = bird_position.current.x;
+        let bird_y = bird_position.current.y;
         let bird_width = 34.0;
         let bird_height = 24.0;
  
-        let pipe_x = pipe_transform.translation.x;
-        let pipe_y = pipe_transform.translation.y;
+        let pipe_x = pipe_position.current.x;
+        let pipe_y = pipe_position.current.y;
         let pipe_width = 52.0;
         let pipe_height = 320.0;
  
@@ -258,28 +261,12 @@ pub fn pipes(
         collision_x && collision_y
     };
  
-    for bird_transform in bird_query.iter_mut() {
-        let mut game_over = || {
-            game.state = GameState::GameOver;
-            *game_over_query.single_mut() = Visibility::Visible;
- 
-            // Play game over sound
-            commands.spawn(AudioBundle {
-                source: asset_server.load("audio/hit.ogg"),
-                settings: PlaybackSettings::DESPAWN,
-            });
-        };
- 
-        for (_, transform) in upper_pipe_query.iter_mut() {
-            if is_collision(bird_transform, &transform) {
-                game_over();
-            }
-        }
- 
-        for (_, transform) in lower_pipe_query.iter_mut() {
-            if is_collision(bird_transform, &transform) {
-                game_over();
-            }
+    for (entity, bird_position) in bird_query.iter() {
+        if pipe_query
+            .iter()
+            .any(|pipe_position| is_collision(bird_position, pipe_position))
+        {
+            commands.entity(entity).insert(Crashed);
         }
     }
 }
@@ -287,39 +274,138 @@ pub fn pipes(
 // simple system that scores the game
 pub fn score(
     mut game: ResMut<Game>,
-    bird_query: Query<(&Bird, &Transform)>,
-    mut upper_pipe_query: Query<(&mut UpperPipe, &Transform)>,
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    bird_query: Query<&Position, FlyingBird>,
+    mut upper_pipe_query: Query<(&mut UpperPipe, &Position)>,
+    mut pipe_passed_events: EventWriter<PipePassed>,
 ) {
-    for (_, bird_transform) in bird_query.iter() {
-        for (mut upper_pipe, transform) in upper_pipe_query.iter_mut() {
-            let passed = transform.translation.x < bird_transform.translation.x;
+    for bird_position in bird_query.iter() {
+        for (mut upper_pipe, position) in upper_pipe_query.iter_mut() {
+            let passed = position.current.x < bird_position.current.x;
             let passed_state = upper_pipe.passed;
  
             if passed && !passed_state {
                 game.score += 1;
                 upper_pipe.passed = true;
  
-                commands.spawn(AudioBundle {
-                    source: asset_server.load("audio/point.ogg"),
-                    settings: PlaybackSettings::DESPAWN,
-                });
- 
-                println!("Score: {}", game.score);
+                pipe_passed_events.send(PipePassed);
             }
         }
     }
 }
 
-pub fn render_score(game: Res<Game>, mut query: Query<&mut TextureAtlas, With<ScoreText>>) {
-    let score_string = format!("{:03}", game.score); // Ensure at least 3 digits, pad with zeros
-    let score_digits: Vec<usize> = score_string
+// The game is over when every bird has crashed; there are several birds when the AI is trained
+pub fn end_game(
+    bird_query: Query<Has<Crashed>, With<Bird>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !bird_query.is_empty() && bird_query.iter().all(|crashed| crashed) {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+pub fn play_sounds(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut flapped_events: EventReader<Flapped>,
+    mut pipe_passed_events: EventReader<PipePassed>,
+) {
+    for _ in flapped_events.read() {
+        commands.spawn(AudioBundle {
+            source: asset_server.load("audio/wing.ogg"),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+
+    for _ in pipe_passed_events.read() {
+        commands.spawn(AudioBundle {
+            source: asset_server.load("audio/point.ogg"),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+// While paused, the virtual time stands still, so that no physics tick is owed when resuming
+pub fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+pub fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+// The score has as many digits as needed, and at least 3
+pub fn render_score(
+    mut commands: Commands,
+    game: Res<Game>,
+    number_sprites: Res<NumberSprites>,
+    mut query: Query<(Entity, &ScoreText, &mut TextureAtlas)>,
+) {
+    let score_digits = digits(game.score, 3);
+ 
+    for (entity, ScoreText(position), mut texture_atlas) in query.iter_mut() {
+        match score_digits.get(*position) {
+            Some(digit) => texture_atlas.index = *digit,
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (position, digit) in score_digits.iter().enumerate().skip(query.iter().len()) {
+        setup::spawn_score_digit(&mut commands, &number_sprites, position, *digit);
+    }
+}
+
+// Digits of a number, padded with zeros to the minimum number of digits
+pub fn digits(number: u32, min_digits: usize) -> Vec<usize> {
+    format!("{number:0min_digits$}")
         .chars()
         .map(|c| c.to_digit(10).unwrap() as usize)
-        .collect();
- 
-    for (digit, mut texture_atlas) in score_digits.iter().zip(query.iter_mut()) {
-        texture_atlas.index = *digit;
+        .collect()
+}
+
+// At the end of a run of the player, rather than of the AI
+pub fn update_best_score(
+    game: Res<Game>,
+    mut best_score: ResMut<BestScore>,
+    player_query: Query<(), With<Player>>,
+) {
+    if player_query.is_empty() {
+        return;
+    }
+
+    best_score.beaten = game.score > best_score.score;
+    if best_score.beaten {
+        best_score.score = game.score;
+    }
+}
+
+pub fn render_training_text(
+    population: Res<Population>,
+    trainee_query: Query<Has<Crashed>, With<Trainee>>,
+    mut query: Query<&mut Text, With<TrainingText>>,
+) {
+    let flying = trainee_query.iter().filter(|crashed| !crashed).count();
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "Generation {}\nAlive {}/{}\nBest score {}\nBest fitness {}",
+            population.generation,
+            flying,
+            population.size(),
+            population.best_score,
+            population.best_fitness,
+        );
+    }
+}
+
+// Saves the best network so far, to replay it later
+pub fn export_brain(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    population: Res<Population>,
+    brain_file: Res<BrainFile>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyE) {
+        return;
     }
-}
\ No newline at end of file
+
+    ai::save_best_brain(&population, &brain_file);
+}
@@ -0,0 +1,113 @@
+// Headless training of the AI, as with `train --headless`, and its networks.
+
+mod common;
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use common::state;
+use rand::{rngs::StdRng, SeedableRng};
+use rust_flappy_bird::{
+    ai::{AiMode, AiPlugin, Population},
+    brain::Brain,
+    components::{Bird, Trainee},
+    core_plugin::DEFAULT_TICK_RATE,
+    states::GameState,
+};
+
+const POPULATION_SIZE: usize = 10;
+
+// Long enough for any generation of the first ones
+const MAX_FRAMES: u32 = 100_000;
+
+fn training(seed: u64, generations: Option<u32>, brain_file: PathBuf) -> App {
+    let mut app = common::headless_app(seed, DEFAULT_TICK_RATE);
+
+    app.add_plugins(AiPlugin {
+        mode: AiMode::Train {
+            population_size: POPULATION_SIZE,
+            seed: Some(seed),
+            generations,
+        },
+        brain_file,
+    });
+
+    app
+}
+
+fn run_until(app: &mut App, done: impl Fn(&App) -> bool) {
+    for _ in 0..MAX_FRAMES {
+        app.update();
+
+        if done(app) {
+            return;
+        }
+    }
+
+    panic!("still not done after {MAX_FRAMES} frames");
+}
+
+fn generation(app: &App) -> u32 {
+    app.world().resource::<Population>().generation
+}
+
+#[test]
+fn brains_are_saved_as_text() {
+    let brain = Brain::random(&mut StdRng::seed_from_u64(0));
+    let text = brain.to_text();
+
+    assert_eq!(Brain::from_text(&text), Ok(brain));
+
+    assert!(Brain::from_text("flappy-brain 2 6\n0.5\n").is_err());
+    let missing_weight = text.lines().take(5).collect::<Vec<_>>().join("\n");
+    assert!(Brain::from_text(&missing_weight).is_err());
+    assert!(Brain::from_text(&text.replacen("\n", "\nnot a number\n", 1)).is_err());
+}
+
+#[test]
+fn a_generation_follows_the_other() {
+    let mut app = training(1, None, PathBuf::from("unused.brain"));
+
+    run_until(&mut app, |app| generation(app) == 3);
+    app.update();
+
+    assert_eq!(state(&app), GameState::Playing);
+
+    // The whole population flies again, without any bird of the player
+    let world = app.world_mut();
+    let birds = world.query::<&Bird>().iter(world).count();
+    let trainees = world.query::<&Trainee>().iter(world).count();
+    assert_eq!(birds, POPULATION_SIZE);
+    assert_eq!(trainees, POPULATION_SIZE);
+
+    let population = app.world().resource::<Population>();
+    assert!(population.best_fitness > 0);
+    assert!(population.best.is_some());
+}
+
+#[test]
+fn the_same_seed_trains_the_same_birds() {
+    let best = [1, 1].map(|seed| {
+        let mut app = training(seed, None, PathBuf::from("unused.brain"));
+        run_until(&mut app, |app| generation(app) == 4);
+
+        let population = app.world().resource::<Population>();
+        (population.best_fitness, population.best.clone())
+    });
+
+    assert_eq!(best[0], best[1]);
+}
+
+#[test]
+fn the_best_brain_is_saved_at_the_end_of_the_training() {
+    let brain_file = std::env::temp_dir().join("rust-flappy-bird-test.brain");
+    let _ = std::fs::remove_file(&brain_file);
+
+    let mut app = training(2, Some(2), brain_file.clone());
+    run_until(&mut app, |app| app.should_exit().is_some());
+
+    let best = app.world().resource::<Population>().best.clone();
+    assert_eq!(Brain::load(&brain_file).ok(), best);
+
+    std::fs::remove_file(&brain_file).unwrap();
+}
@@ -0,0 +1,95 @@
+// Fixture shared by the headless runs of the game: the FlappyCorePlugin under the MinimalPlugins,
+// with the time advanced by hand, and an autopilot that requests the jumps through the JumpInput
+// resource, as the space bar does. Each test file uses only part of it.
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use rust_flappy_bird::{
+    components::{Bird, LowerPipe, Position},
+    core_plugin::{FlappyCorePlugin, PhysicsSet},
+    resources::JumpInput,
+    states::GameState,
+};
+
+// The autopilot jumps when the bottom of the bird goes below this height above the lower pipe
+const JUMP_MARGIN: f32 = 25.;
+
+// Half of a pipe, which is 320 high
+pub const PIPE_HALF_HEIGHT: f32 = 160.;
+
+#[derive(Resource, Default)]
+pub struct Autopilot {
+    pub enabled: bool,
+}
+
+// The game with the given seed, advanced by one frame at the given rate on each update; nothing
+// has run yet
+pub fn headless_app(seed: u64, frame_rate: f64) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        FlappyCorePlugin {
+            seed: Some(seed),
+            ..default()
+        },
+    ))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1. / frame_rate,
+    )));
+
+    app
+}
+
+// As headless_app(), with the autopilot flying while playing
+pub fn simulation(seed: u64, frame_rate: f64, autopilot: bool) -> App {
+    let mut app = headless_app(seed, frame_rate);
+
+    app.insert_resource(Autopilot { enabled: autopilot })
+        .add_systems(
+            FixedUpdate,
+            fly.before(PhysicsSet).run_if(in_state(GameState::Playing)),
+        );
+
+    app
+}
+
+pub fn start(app: &mut App) {
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+}
+
+pub fn state(app: &App) -> GameState {
+    *app.world().resource::<State<GameState>>().get()
+}
+
+pub fn set_autopilot(app: &mut App, enabled: bool) {
+    app.world_mut().resource_mut::<Autopilot>().enabled = enabled;
+}
+
+// Jumps whenever the bird is low in the gap of the next lower pipe
+fn fly(
+    autopilot: Res<Autopilot>,
+    bird_query: Query<&Position, With<Bird>>,
+    pipe_query: Query<&Position, With<LowerPipe>>,
+    mut jump_input: ResMut<JumpInput>,
+) {
+    let bird = bird_query.single();
+    // Pipes are 52 wide and the bird 34
+    let next_pipe = pipe_query
+        .iter()
+        .filter(|pipe| pipe.current.x + 26. > bird.current.x - 17.)
+        .min_by(|a, b| a.current.x.total_cmp(&b.current.x));
+
+    if let Some(pipe) = next_pipe {
+        let gap_bottom = pipe.current.y + PIPE_HALF_HEIGHT;
+
+        // The bird is 24 high
+        if autopilot.enabled && bird.current.y - 12. < gap_bottom + JUMP_MARGIN {
+            jump_input.requested = true;
+        }
+    }
+}
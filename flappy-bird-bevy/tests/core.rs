@@ -0,0 +1,222 @@
+// Runs of the FlappyCorePlugin, headless, at one tick per frame, flown by the autopilot of the
+// common fixture.
+
+mod common;
+
+use bevy::prelude::*;
+use common::{set_autopilot, start, state};
+use rust_flappy_bird::{
+    components::{Bird, LowerPipe, Position, UpperPipe},
+    constants::PIPE_PAIRS,
+    core_plugin::{PhysicsSet, DEFAULT_TICK_RATE},
+    events::PipePassed,
+    resources::{BestScore, Game},
+    states::GameState,
+};
+
+const SEED: u64 = 42;
+
+#[derive(Resource, Default)]
+struct Counters {
+    // Upper pipes that went from the right to the left of the bird
+    crossings: u32,
+    pipe_passed_events: u32,
+}
+
+// Starts a run of one tick per frame, and waits on the title screen for the game to start
+fn simulation(seed: u64, autopilot: bool) -> App {
+    let mut app = common::simulation(seed, DEFAULT_TICK_RATE, autopilot);
+
+    app.init_resource::<Counters>().add_systems(
+        FixedUpdate,
+        count.after(PhysicsSet).run_if(in_state(GameState::Playing)),
+    );
+
+    app.update();
+
+    app
+}
+
+// Velocity and position of the bird
+fn bird(app: &mut App) -> (f32, Position) {
+    let world = app.world_mut();
+    let (bird, position) = world.query::<(&Bird, &Position)>().single(world);
+
+    (bird.velocity, *position)
+}
+
+fn pipe_positions(app: &mut App) -> Vec<Vec2> {
+    let world = app.world_mut();
+    let mut positions: Vec<Vec2> = world
+        .query_filtered::<&Position, Or<(With<LowerPipe>, With<UpperPipe>)>>()
+        .iter(world)
+        .map(|position| position.current)
+        .collect();
+    positions.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+
+    positions
+}
+
+fn count(
+    mut counters: ResMut<Counters>,
+    bird_query: Query<&Position, With<Bird>>,
+    pipe_query: Query<&Position, With<UpperPipe>>,
+    mut pipe_passed_events: EventReader<PipePassed>,
+) {
+    let bird_x = bird_query.single().current.x;
+
+    for pipe in pipe_query.iter() {
+        if pipe.previous.x >= bird_x && pipe.current.x < bird_x {
+            counters.crossings += 1;
+        }
+    }
+
+    counters.pipe_passed_events += pipe_passed_events.read().count() as u32;
+}
+
+#[test]
+fn score_increments_once_per_upper_pipe_passed() {
+    let mut app = simulation(SEED, true);
+    start(&mut app);
+
+    // 20 seconds, long enough for a dozen pipes
+    for _ in 0..1200 {
+        app.update();
+    }
+
+    let score = app.world().resource::<Game>().score;
+    let counters = app.world().resource::<Counters>();
+
+    assert!(score >= 10, "the autopilot only scored {score}");
+    assert_eq!(score, counters.crossings);
+    assert_eq!(score, counters.pipe_passed_events);
+}
+
+#[test]
+fn hitting_the_ground_sets_game_over() {
+    let mut app = simulation(SEED, false);
+    start(&mut app);
+
+    let mut frames = 0;
+    while state(&app) != GameState::GameOver {
+        app.update();
+        frames += 1;
+        assert!(frames < 200, "the bird never hit the ground");
+    }
+
+    // On the ground, which is 112 high at -250, before reaching the first pipe
+    let (velocity, position) = bird(&mut app);
+    assert_eq!(position.current.y, -250. + 112. / 2. + 24. / 2.);
+    assert_eq!(velocity, 0.);
+    assert_eq!(app.world().resource::<Game>().score, 0);
+
+    // Nothing moves anymore
+    let pipes = pipe_positions(&mut app);
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(bird(&mut app).1, position);
+    assert_eq!(pipe_positions(&mut app), pipes);
+}
+
+#[test]
+fn a_new_run_starts_after_game_over() {
+    let mut app = simulation(SEED, true);
+    start(&mut app);
+
+    for _ in 0..300 {
+        app.update();
+    }
+
+    // Without the autopilot, the bird falls to the ground
+    set_autopilot(&mut app, false);
+    while state(&app) != GameState::GameOver {
+        app.update();
+    }
+    assert!(app.world().resource::<Game>().score > 0);
+
+    start(&mut app);
+    app.update();
+
+    assert_eq!(state(&app), GameState::Playing);
+    assert_eq!(app.world().resource::<Game>().score, 0);
+    assert_eq!(pipe_positions(&mut app).len(), PIPE_PAIRS * 2);
+    // The bird has only fallen for a tick
+    assert!(bird(&mut app).1.current.y > -1.);
+}
+
+#[test]
+fn the_best_score_is_kept_across_runs() {
+    let mut app = simulation(SEED, true);
+    app.world_mut().resource_mut::<BestScore>().score = 3;
+    start(&mut app);
+
+    for _ in 0..600 {
+        app.update();
+    }
+
+    set_autopilot(&mut app, false);
+    while state(&app) != GameState::GameOver {
+        app.update();
+    }
+    let score = app.world().resource::<Game>().score;
+    assert!(score > 3, "the autopilot only scored {score}");
+    let best_score = app.world().resource::<BestScore>();
+    assert_eq!(best_score.score, score);
+    assert!(best_score.beaten);
+
+    // A worse run doesn't beat it
+    start(&mut app);
+    app.update();
+    while state(&app) != GameState::GameOver {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<Game>().score, 0);
+    let best_score = app.world().resource::<BestScore>();
+    assert_eq!(best_score.score, score);
+    assert!(!best_score.beaten);
+}
+
+#[test]
+fn the_same_seed_gives_the_same_pipes() {
+    let pipes = [SEED, SEED, SEED + 1].map(|seed| {
+        let mut app = simulation(seed, true);
+        start(&mut app);
+
+        // Long enough for the first pipes to go back to the right, at new positions
+        for _ in 0..600 {
+            app.update();
+        }
+
+        pipe_positions(&mut app)
+    });
+
+    assert_eq!(pipes[0], pipes[1]);
+    assert_ne!(pipes[0], pipes[2]);
+}
+
+#[test]
+fn runs_in_a_row_get_the_same_pipes() {
+    let mut app = simulation(SEED, true);
+
+    let runs = [0, 1].map(|_| {
+        start(&mut app);
+        app.update();
+        let first_pipes = pipe_positions(&mut app);
+
+        set_autopilot(&mut app, true);
+        for _ in 0..600 {
+            app.update();
+        }
+        let later_pipes = pipe_positions(&mut app);
+
+        set_autopilot(&mut app, false);
+        while state(&app) != GameState::GameOver {
+            app.update();
+        }
+
+        (first_pipes, later_pipes)
+    });
+
+    assert_eq!(runs[0], runs[1]);
+}
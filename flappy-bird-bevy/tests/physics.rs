@@ -0,0 +1,156 @@
+// The physics run headless (see the common fixture) at different frame rates; the outcome must
+// not depend on the frame rate.
+
+mod common;
+
+use bevy::prelude::*;
+use common::{start, state};
+use rust_flappy_bird::{
+    components::{Bird, Oscillation, Position, UpperPipe},
+    core_plugin::{PhysicsSet, DEFAULT_TICK_RATE},
+    resources::Game,
+    states::GameState,
+};
+
+const FRAME_RATES: [f64; 3] = [30., 60., 144.];
+
+// Within a few seconds, before the first pipe goes back to the right (with a random gap)
+const TICKS: u32 = 300;
+
+// Lower and upper pipe of each pair; the gap is between 10 and 140
+const PIPE_Y: (f32, f32) = (-150., 300.);
+
+#[derive(Resource, Default)]
+struct Recorder {
+    ticks: u32,
+    // Tick at which the game over has been detected
+    crash_tick: Option<u32>,
+    // State of the bird and score at the last tick
+    snapshot: Option<Snapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Snapshot {
+    bird: Vec2,
+    velocity: f32,
+    score: u32,
+}
+
+fn simulation(frame_rate: f64, autopilot: bool) -> App {
+    let mut app = common::simulation(0, frame_rate, autopilot);
+
+    app.init_resource::<Recorder>().add_systems(
+        FixedUpdate,
+        record
+            .after(PhysicsSet)
+            .run_if(in_state(GameState::Playing)),
+    );
+
+    // The run is spawned when entering the title screen, then all the gaps are set at the same
+    // height before the game starts
+    app.update();
+    let world = app.world_mut();
+    for (mut position, mut oscillation, upper) in world
+        .query::<(&mut Position, &mut Oscillation, Has<UpperPipe>)>()
+        .iter_mut(world)
+    {
+        oscillation.base_y = if upper { PIPE_Y.1 } else { PIPE_Y.0 };
+        let x = position.current.x;
+        position.teleport(Vec2::new(x, oscillation.y()));
+    }
+    start(&mut app);
+
+    app
+}
+
+fn record(
+    mut recorder: ResMut<Recorder>,
+    next_state: Res<NextState<GameState>>,
+    bird_query: Query<(&Bird, &Position)>,
+    game: Res<Game>,
+) {
+    recorder.ticks += 1;
+
+    if recorder.crash_tick.is_none()
+        && matches!(*next_state, NextState::Pending(GameState::GameOver))
+    {
+        recorder.crash_tick = Some(recorder.ticks);
+    }
+
+    if recorder.ticks == TICKS {
+        let (bird, position) = bird_query.single();
+        recorder.snapshot = Some(Snapshot {
+            bird: position.current,
+            velocity: bird.velocity,
+            score: game.score,
+        });
+    }
+}
+
+fn run(frame_rate: f64, autopilot: bool) -> App {
+    let mut app = simulation(frame_rate, autopilot);
+
+    // A few more frames than needed; the ticks after the last one don't matter
+    let frames = (TICKS as f64 / DEFAULT_TICK_RATE * frame_rate) as u32 + 10;
+    for _ in 0..frames {
+        app.update();
+    }
+
+    app
+}
+
+#[test]
+fn falling_bird_hits_the_ground_at_the_same_tick_at_any_frame_rate() {
+    let crash_ticks = FRAME_RATES.map(|frame_rate| {
+        let app = run(frame_rate, false);
+
+        assert_eq!(state(&app), GameState::GameOver);
+
+        app.world().resource::<Recorder>().crash_tick.unwrap()
+    });
+
+    assert!(crash_ticks.iter().all(|&tick| tick == crash_ticks[0]));
+}
+
+#[test]
+fn flight_is_the_same_at_any_frame_rate() {
+    let snapshots = FRAME_RATES.map(|frame_rate| {
+        let app = run(frame_rate, true);
+        let recorder = app.world().resource::<Recorder>();
+
+        assert_eq!(recorder.crash_tick, None);
+
+        recorder.snapshot.clone().unwrap()
+    });
+
+    assert!(snapshots[0].score >= 2);
+    assert_eq!(snapshots[0], snapshots[1]);
+    assert_eq!(snapshots[0], snapshots[2]);
+}
+
+#[test]
+fn transforms_are_interpolated_between_ticks() {
+    // Two frames per tick: the second frame of each tick is half way
+    let mut app = simulation(DEFAULT_TICK_RATE * 2., false);
+    let bird = app
+        .world_mut()
+        .query_filtered::<Entity, With<Bird>>()
+        .single(app.world());
+    app.world_mut()
+        .entity_mut(bird)
+        .insert(Transform::default());
+
+    for _ in 0..20 {
+        app.update();
+
+        let position = *app.world().get::<Position>(bird).unwrap();
+        let translation = app.world().get::<Transform>(bird).unwrap().translation;
+        let alpha = app.world().resource::<Time<Fixed>>().overstep_fraction();
+
+        assert!(position.current.y <= translation.y && translation.y <= position.previous.y);
+        assert_eq!(
+            translation.y,
+            position.previous.lerp(position.current, alpha).y
+        );
+    }
+}
@@ -0,0 +1,105 @@
+// The pipes of the PipeGenerator, over many pairs and scores.
+
+use rust_flappy_bird::{components::Oscillation, pipe_generator::PipeGenerator};
+
+const PAIRS: usize = 500;
+
+// Middle of the gap of a pair, and its amplitude
+fn gap(pair: &(Oscillation, Oscillation)) -> (f32, f32) {
+    let (lower, upper) = pair;
+    assert_eq!(lower.amplitude, upper.amplitude);
+    assert_eq!(lower.phase, upper.phase);
+
+    ((lower.base_y + upper.base_y) / 2., lower.amplitude)
+}
+
+fn pairs(seed: u64, score: u32) -> Vec<(Oscillation, Oscillation)> {
+    let mut generator = PipeGenerator::new(Some(seed));
+
+    (0..PAIRS).map(|_| generator.next_pair(score)).collect()
+}
+
+#[test]
+fn the_same_seed_gives_the_same_pipes() {
+    assert_eq!(pairs(1, 20), pairs(1, 20));
+    assert_ne!(pairs(1, 20), pairs(2, 20));
+}
+
+#[test]
+fn gaps_shrink_and_pipes_get_closer_with_the_score() {
+    for score in 0..100 {
+        assert!(PipeGenerator::gap(score + 1) <= PipeGenerator::gap(score));
+        assert!(PipeGenerator::spacing(score + 1) <= PipeGenerator::spacing(score));
+
+        // The pipes are 320 high
+        for (lower, upper) in pairs(0, score).iter().take(10) {
+            let gap = (upper.base_y - 160.) - (lower.base_y + 160.);
+            assert!((gap - PipeGenerator::gap(score)).abs() < 0.001);
+        }
+    }
+
+    assert!(PipeGenerator::gap(0) > PipeGenerator::gap(100));
+    assert!(PipeGenerator::spacing(0) > PipeGenerator::spacing(100));
+    // Down to a minimum
+    assert_eq!(PipeGenerator::gap(100), PipeGenerator::gap(1000));
+    assert_eq!(PipeGenerator::spacing(100), PipeGenerator::spacing(1000));
+}
+
+#[test]
+fn pipes_move_only_from_10_points() {
+    let moving = |score| {
+        pairs(0, score)
+            .iter()
+            .filter(|pair| gap(pair).1 > 0.)
+            .count()
+    };
+
+    assert_eq!(moving(0), 0);
+    assert_eq!(moving(9), 0);
+    assert!(moving(10) > 0);
+    assert!(moving(30) > moving(10));
+    assert!(moving(30) < PAIRS);
+}
+
+#[test]
+fn consecutive_gaps_are_reachable() {
+    for score in 0..60 {
+        let (climb, drop) = PipeGenerator::reach(score);
+        // Even where the gaps move, the bird can pass them, between the top of the screen and the
+        // ground
+        assert!(climb > 70. && drop > 70.);
+
+        let gaps: Vec<(f32, f32)> = pairs(score as u64, score).iter().map(gap).collect();
+
+        for window in gaps.windows(2) {
+            let ((last_y, last_amplitude), (y, amplitude)) = (window[0], window[1]);
+            let amplitudes = last_amplitude + amplitude;
+
+            assert!(y - last_y + amplitudes <= climb, "{last_y} to {y} at {score}");
+            assert!(last_y - y + amplitudes <= drop, "{last_y} to {y} at {score}");
+        }
+
+        for (y, amplitude) in gaps {
+            assert!(y - amplitude >= -55. && y + amplitude <= 155.);
+        }
+    }
+}
+
+#[test]
+fn seeded_runs_start_the_same_pipes_again() {
+    let mut generator = PipeGenerator::new(Some(7));
+    let run = |generator: &mut PipeGenerator| {
+        generator.start_run();
+        (0..PAIRS)
+            .map(|pair| generator.next_pair(pair as u32 / 10))
+            .map(|pair| gap(&pair))
+            .collect::<Vec<_>>()
+    };
+
+    let first = run(&mut generator);
+    assert_eq!(run(&mut generator), first);
+
+    // Without a seed, every run is different
+    let mut generator = PipeGenerator::new(None);
+    assert_ne!(run(&mut generator), run(&mut generator));
+}
@@ -0,0 +1,41 @@
+// The medals of the game over scoreboard, the digits of the scores, and the layout of the screen.
+
+use rust_flappy_bird::{
+    constants::WINDOW_HEIGHT,
+    scoreboard::{game_over_layout, Medal},
+    systems::digits,
+};
+
+#[test]
+fn medals_are_won_from_10_points() {
+    assert_eq!(Medal::for_score(0), None);
+    assert_eq!(Medal::for_score(9), None);
+    assert_eq!(Medal::for_score(10), Some(Medal::Bronze));
+    assert_eq!(Medal::for_score(19), Some(Medal::Bronze));
+    assert_eq!(Medal::for_score(20), Some(Medal::Silver));
+    assert_eq!(Medal::for_score(30), Some(Medal::Gold));
+    assert_eq!(Medal::for_score(40), Some(Medal::Platinum));
+    assert_eq!(Medal::for_score(1000), Some(Medal::Platinum));
+}
+
+#[test]
+fn scores_have_as_many_digits_as_needed() {
+    assert_eq!(digits(7, 3), vec![0, 0, 7]);
+    assert_eq!(digits(0, 1), vec![0]);
+    assert_eq!(digits(1234, 3), vec![1, 2, 3, 4]);
+    assert_eq!(digits(u32::MAX, 3).len(), 10);
+}
+
+#[test]
+fn game_over_texts_are_clear_of_the_panel() {
+    // The ground is 112 high at y -250, and comes over the bottom of the screen
+    let ground_top = -250. + 112. / 2.;
+    let layout = game_over_layout();
+    for (i, rect) in layout.iter().enumerate() {
+        assert!(rect.min.y > ground_top, "{i}: {rect:?}");
+        assert!(rect.max.y < WINDOW_HEIGHT / 2., "{i}: {rect:?}");
+        for other in &layout[i + 1..] {
+            assert!(rect.intersect(*other).is_empty(), "{rect:?} and {other:?}");
+        }
+    }
+}